@@ -4,6 +4,7 @@
 //! Post-adjust moves define symmetry for ending positions.
 
 use super::equivalence::EquivalenceHandler;
+use super::parser::ScrambleParser;
 use super::types::{BatchError, GeneratedState, NormalizedState};
 use crate::minx::{LLMinx, Move};
 use std::collections::HashSet;
@@ -53,7 +54,7 @@ impl AdjustHandler {
                 }
             } else {
                 // Parse as explicit move sequence
-                let moves = Self::parse_moves(trimmed)?;
+                let moves = ScrambleParser::parse_moves(trimmed)?;
                 if !moves.is_empty() {
                     result.push(moves);
                 }
@@ -190,7 +191,7 @@ impl AdjustHandler {
         }
 
         // Apply the original setup moves
-        if let Ok(moves) = Self::parse_moves(setup_moves) {
+        if let Ok(moves) = ScrambleParser::parse_moves(setup_moves) {
             for mv in moves {
                 result.apply_move(mv);
             }
@@ -222,66 +223,6 @@ impl AdjustHandler {
     pub fn is_solved_with_post_adjust(&self, state: &LLMinx, goal: &LLMinx) -> bool {
         self.find_post_adjust_solution(state, goal).is_some()
     }
-
-    /// Parse a move string into Move enums
-    fn parse_moves(input: &str) -> Result<Vec<Move>, BatchError> {
-        let move_strs: Vec<&str> = input.split_whitespace().collect();
-        let mut moves = Vec::with_capacity(move_strs.len());
-
-        for s in move_strs {
-            let mv = Self::parse_single_move(s.trim())?;
-            moves.push(mv);
-        }
-
-        Ok(moves)
-    }
-
-    /// Parse a single move string
-    fn parse_single_move(input: &str) -> Result<Move, BatchError> {
-        let input = input.trim();
-
-        match input {
-            "R" => Ok(Move::R),
-            "R'" | "Ri" => Ok(Move::Ri),
-            "R2" => Ok(Move::R2),
-            "R2'" | "R2i" => Ok(Move::R2i),
-
-            "L" => Ok(Move::L),
-            "L'" | "Li" => Ok(Move::Li),
-            "L2" => Ok(Move::L2),
-            "L2'" | "L2i" => Ok(Move::L2i),
-
-            "U" => Ok(Move::U),
-            "U'" | "Ui" => Ok(Move::Ui),
-            "U2" => Ok(Move::U2),
-            "U2'" | "U2i" => Ok(Move::U2i),
-
-            "F" => Ok(Move::F),
-            "F'" | "Fi" => Ok(Move::Fi),
-            "F2" => Ok(Move::F2),
-            "F2'" | "F2i" => Ok(Move::F2i),
-
-            "bL" => Ok(Move::bL),
-            "bL'" | "bLi" => Ok(Move::bLi),
-            "bL2" => Ok(Move::bL2),
-            "bL2'" | "bL2i" => Ok(Move::bL2i),
-
-            "bR" => Ok(Move::bR),
-            "bR'" | "bRi" => Ok(Move::bRi),
-            "bR2" => Ok(Move::bR2),
-            "bR2'" | "bR2i" => Ok(Move::bR2i),
-
-            "D" => Ok(Move::D),
-            "D'" | "Di" => Ok(Move::Di),
-            "D2" => Ok(Move::D2),
-            "D2'" | "D2i" => Ok(Move::D2i),
-
-            _ => Err(BatchError::InvalidMove(format!(
-                "Unrecognized move: '{}'",
-                input
-            ))),
-        }
-    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,142 @@
+//! Coverage of a generated batch by a set of known algorithms
+//!
+//! Splits generated cases into those an existing algorithm already solves,
+//! up to pre/post adjust and piece equivalences, and those still missing, so
+//! only the missing ones need to go through `solve_batch_states`.
+
+use super::recognition::{CaseIndex, Recognition};
+use super::types::{BatchError, GeneratedState};
+use std::path::Path;
+
+/// Parse a list of known algorithms, one per line
+///
+/// Lines are either `name: alg` or a bare algorithm, which is then named by
+/// itself. Blank lines and lines starting with `#` are skipped.
+pub fn parse_alg_list(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| match line.split_once(':') {
+            Some((name, alg)) => (name.trim().to_string(), alg.trim().to_string()),
+            None => (line.to_string(), line.to_string()),
+        })
+        .collect()
+}
+
+/// Read a file of known algorithms in the `parse_alg_list` format
+pub fn read_alg_file(path: &Path) -> Result<Vec<(String, String)>, BatchError> {
+    let text = std::fs::read_to_string(path).map_err(|e| {
+        BatchError::ParseError(format!("Cannot read alg file {}: {}", path.display(), e))
+    })?;
+    Ok(parse_alg_list(&text))
+}
+
+/// A generated case an existing algorithm already solves
+#[derive(Debug, Clone)]
+pub struct CoveredCase {
+    pub state: GeneratedState,
+    pub recognition: Recognition,
+}
+
+/// Generated cases split by whether a known algorithm covers them
+#[derive(Debug, Clone, Default)]
+pub struct CoverageReport {
+    /// Cases with a known algorithm, in generation order
+    pub covered: Vec<CoveredCase>,
+    /// Cases still to be solved, in generation order
+    pub missing: Vec<GeneratedState>,
+}
+
+impl CoverageReport {
+    /// Look up every generated state in `index`
+    pub fn new(states: Vec<GeneratedState>, index: &CaseIndex) -> Self {
+        let mut report = Self::default();
+        for state in states {
+            match index.recognize(&state.state) {
+                Some(recognition) => report.covered.push(CoveredCase { state, recognition }),
+                None => report.missing.push(state),
+            }
+        }
+        report
+    }
+
+    /// Total number of cases in the report
+    pub fn total_cases(&self) -> usize {
+        self.covered.len() + self.missing.len()
+    }
+
+    /// Get the covered share as a percentage
+    pub fn coverage_rate(&self) -> f64 {
+        if self.total_cases() == 0 {
+            0.0
+        } else {
+            (self.covered.len() as f64 / self.total_cases() as f64) * 100.0
+        }
+    }
+
+    /// Case numbers still to be solved
+    pub fn missing_case_numbers(&self) -> Vec<usize> {
+        self.missing.iter().map(|s| s.case_number).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::batch_solver::adjust::AdjustHandler;
+    use crate::batch_solver::generator::{GeneratorConfig, generate_batch_states};
+    use crate::minx::LLMinx;
+
+    #[test]
+    fn test_parse_alg_list() {
+        let algs =
+            parse_alg_list("# sune family\nSune: R U R' U R U2' R'\n\n  R U2 R' U' R U' R'  \n");
+        assert_eq!(
+            algs,
+            vec![
+                ("Sune".to_string(), "R U R' U R U2' R'".to_string()),
+                (
+                    "R U2 R' U' R U' R'".to_string(),
+                    "R U2 R' U' R U' R'".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_alg_file_missing() {
+        let path = std::env::temp_dir().join("llminx_no_such_alg_file.txt");
+        assert!(matches!(
+            read_alg_file(&path),
+            Err(BatchError::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn test_coverage_splits_generated_cases() {
+        let config = GeneratorConfig {
+            scramble: "[R U R' U R U2' R', R U2 R' U' R U' R', R U R' U']".to_string(),
+            pre_adjust: vec!["U".to_string()],
+            post_adjust: vec!["U".to_string()],
+            num_threads: 1,
+            ..GeneratorConfig::default()
+        };
+        let (states, equivalence) = generate_batch_states(&config, None, None).unwrap();
+        assert_eq!(states.len(), 3);
+
+        let adjust = AdjustHandler::new(&config.pre_adjust, &config.post_adjust).unwrap();
+        let algs = parse_alg_list("Sune: U R U R' U R U2' R' U2\nAntisune: R U2 R' U' R U' R'");
+        let index = CaseIndex::new(&algs, Some(&adjust), equivalence).unwrap();
+
+        let report = CoverageReport::new(states, &index);
+        assert_eq!(report.covered.len(), 2);
+        assert_eq!(report.missing.len(), 1);
+        assert_eq!(report.missing[0].setup_moves.trim(), "R U R' U'");
+        assert!((report.coverage_rate() - 200.0 / 3.0).abs() < 1e-9);
+
+        for case in &report.covered {
+            let solution = case.recognition.solution();
+            assert!(crate::minx::verify(&case.state.state, &solution, &LLMinx::new()).is_ok());
+        }
+    }
+}
@@ -45,6 +45,16 @@ impl EquivalenceHandler {
         Ok(handler)
     }
 
+    /// Equivalence sets the handler was built from
+    pub fn equivalences(&self) -> &[EquivalenceSet] {
+        &self.equivalences
+    }
+
+    /// Orientation groups the handler was built from
+    pub fn orientation_groups(&self) -> &[OrientationGroup] {
+        &self.orientation_groups
+    }
+
     /// Get the corner equivalence map (for debugging)
     pub fn corner_map(&self) -> &HashMap<usize, usize> {
         &self.corner_equivalence_map
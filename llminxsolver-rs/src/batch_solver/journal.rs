@@ -0,0 +1,412 @@
+//! Checkpoint journal for resumable batch solves
+//!
+//! The journal is an append-only file of JSON lines. It starts with a header
+//! holding the hash of the solver configuration and the cases, followed by
+//! every solution found, the cases searched through each depth and the cases
+//! that are finished. Each line is flushed as it is written, so a killed process loses
+//! at most the depth it was searching.
+
+use super::equivalence::EquivalenceHandler;
+use super::solver::BatchSolverConfig;
+use super::types::GeneratedState;
+use crate::solution::Solution;
+use crate::table_file::checksum;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+const JOURNAL_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "entry", rename_all = "snake_case")]
+enum JournalEntry {
+    Header {
+        version: u32,
+        config_hash: u64,
+    },
+    Solution {
+        case_number: usize,
+        solution: Solution,
+    },
+    Depth {
+        depth: usize,
+        cases: Vec<usize>,
+    },
+    Complete {
+        case_number: usize,
+    },
+}
+
+/// What the journal knows about one case.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CaseProgress {
+    /// Deepest depth the case was fully searched through.
+    pub searched_depth: usize,
+    /// Solutions found up to `searched_depth`.
+    pub solutions: Vec<Solution>,
+    /// Whether the case needs no further searching.
+    pub complete: bool,
+}
+
+pub struct BatchJournal {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    config_hash: u64,
+    progress: HashMap<usize, CaseProgress>,
+}
+
+impl BatchJournal {
+    /// Start a new journal, replacing any existing file at `path`.
+    pub fn create(path: &Path, config_hash: u64) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("Failed to create journal: {}", e))?;
+        let mut journal = Self {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(file),
+            config_hash,
+            progress: HashMap::new(),
+        };
+        journal.write(&JournalEntry::Header {
+            version: JOURNAL_VERSION,
+            config_hash,
+        })?;
+        Ok(journal)
+    }
+
+    /// Open an existing journal to continue it, or create one if there is
+    /// none. Fails if the journal was written for a different configuration.
+    pub fn open(path: &Path, config_hash: u64) -> Result<Self, String> {
+        if !path.exists() {
+            return Self::create(path, config_hash);
+        }
+
+        let file = File::open(path).map_err(|e| format!("Failed to open journal: {}", e))?;
+        let mut lines = BufReader::new(file).lines().map_while(Result::ok);
+
+        match lines
+            .next()
+            .and_then(|line| serde_json::from_str(&line).ok())
+        {
+            Some(JournalEntry::Header {
+                version,
+                config_hash: stored,
+            }) => {
+                if version != JOURNAL_VERSION {
+                    return Err(format!("Unsupported journal version {}", version));
+                }
+                if stored != config_hash {
+                    return Err(
+                        "Journal was written for a different batch configuration".to_string()
+                    );
+                }
+            }
+            _ => return Err("File is not a batch journal".to_string()),
+        }
+
+        let mut progress: HashMap<usize, CaseProgress> = HashMap::new();
+        // A line cut short by a kill fails to parse and is dropped.
+        for entry in lines.filter_map(|line| serde_json::from_str::<JournalEntry>(&line).ok()) {
+            match entry {
+                JournalEntry::Header { .. } => {}
+                JournalEntry::Solution {
+                    case_number,
+                    solution,
+                } => progress
+                    .entry(case_number)
+                    .or_default()
+                    .solutions
+                    .push(solution),
+                JournalEntry::Depth { depth, cases } => {
+                    for case_number in cases {
+                        let case = progress.entry(case_number).or_default();
+                        case.searched_depth = case.searched_depth.max(depth);
+                    }
+                }
+                JournalEntry::Complete { case_number } => {
+                    progress.entry(case_number).or_default().complete = true;
+                }
+            }
+        }
+
+        // Solutions from a depth that never finished are found again on resume.
+        for case in progress.values_mut() {
+            let searched_depth = case.searched_depth;
+            case.solutions.retain(|s| s.depth <= searched_depth);
+        }
+
+        let file = OpenOptions::new()
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open journal: {}", e))?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(file),
+            config_hash,
+            progress,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn config_hash(&self) -> u64 {
+        self.config_hash
+    }
+
+    /// Progress recorded for a case when the journal was opened.
+    pub fn progress(&self, case_number: usize) -> Option<&CaseProgress> {
+        self.progress.get(&case_number)
+    }
+
+    /// Case numbers of the finished cases, in ascending order.
+    pub fn completed_cases(&self) -> Vec<usize> {
+        let mut cases: Vec<usize> = self
+            .progress
+            .iter()
+            .filter(|(_, p)| p.complete)
+            .map(|(&case_number, _)| case_number)
+            .collect();
+        cases.sort_unstable();
+        cases
+    }
+
+    pub fn record_solution(
+        &mut self,
+        case_number: usize,
+        solution: &Solution,
+    ) -> Result<(), String> {
+        self.write(&JournalEntry::Solution {
+            case_number,
+            solution: solution.clone(),
+        })
+    }
+
+    /// Record that `cases` were searched through `depth`.
+    pub fn record_depth(&mut self, depth: usize, cases: &[usize]) -> Result<(), String> {
+        self.write(&JournalEntry::Depth {
+            depth,
+            cases: cases.to_vec(),
+        })
+    }
+
+    pub fn record_complete(&mut self, case_number: usize) -> Result<(), String> {
+        self.write(&JournalEntry::Complete { case_number })
+    }
+
+    fn write(&mut self, entry: &JournalEntry) -> Result<(), String> {
+        let line = serde_json::to_string(entry)
+            .map_err(|e| format!("Failed to encode journal entry: {}", e))?;
+        writeln!(self.writer, "{}", line)
+            .and_then(|_| self.writer.flush())
+            .map_err(|e| format!("Failed to write journal: {}", e))
+    }
+}
+
+impl BatchSolverConfig {
+    /// Hash of everything that decides which solutions a batch reports: the
+    /// search and output settings, the equivalences and every generated case
+    /// with its setup moves. The cases carry the scramble and the pre and
+    /// post adjusts they were generated from. A journal can only be resumed
+    /// with a batch of the same hash.
+    pub fn journal_hash(
+        &self,
+        states: &[GeneratedState],
+        equivalence: Option<&EquivalenceHandler>,
+    ) -> u64 {
+        let mut key = format!(
+            "{:?}|{:?}|{}|{}|{:?}|{}|{}|{}|{}|{:?}|{:?}|{:?}|{:?}",
+            self.search_mode,
+            self.metric,
+            self.max_search_depth,
+            self.stop_after_first,
+            self.depth_slack,
+            self.ignore_corner_permutation,
+            self.ignore_edge_permutation,
+            self.ignore_corner_orientation,
+            self.ignore_edge_orientation,
+            self.mcc_params,
+            self.ranking,
+            self.dedup,
+            self.filters,
+        );
+        if let Some(equivalence) = equivalence {
+            key.push_str(&format!(
+                "|{:?}|{:?}",
+                equivalence.equivalences(),
+                equivalence.orientation_groups()
+            ));
+        }
+        for generated in states {
+            let state = &generated.state;
+            key.push_str(&format!(
+                "|{}:{}:{:?}:{:?}:{:x}:{:x}",
+                generated.case_number,
+                generated.setup_moves,
+                state.corner_positions(),
+                state.edge_positions(),
+                state.corner_orientations(),
+                state.edge_orientations(),
+            ));
+        }
+        checksum(key.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::batch_solver::types::{EquivalenceSet, PieceMap};
+    use crate::canonical::DedupMode;
+    use crate::filter::NoAuf;
+    use crate::mcc::MCCParams;
+    use crate::minx::{LLMinx, Move};
+    use crate::ranking::{RankScore, SolutionRanking};
+    use crate::search_mode::SearchMode;
+    use std::sync::Arc;
+
+    fn temp_journal(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "llminx_journal_{}_{}.jsonl",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_journal_round_trip_keeps_searched_solutions() {
+        let path = temp_journal("round_trip");
+        let found = Solution::from_moves(&[Move::R; 2], SearchMode::RU);
+        {
+            let mut journal = BatchJournal::create(&path, 42).unwrap();
+            journal.record_solution(1, &found).unwrap();
+            journal.record_depth(2, &[1, 2]).unwrap();
+            journal.record_complete(1).unwrap();
+            journal.record_depth(3, &[2]).unwrap();
+            journal
+                .record_solution(2, &Solution::from_moves(&[Move::R; 4], SearchMode::RU))
+                .unwrap();
+        }
+
+        let journal = BatchJournal::open(&path, 42).unwrap();
+        assert_eq!(journal.completed_cases(), vec![1]);
+
+        let first = journal.progress(1).unwrap();
+        assert_eq!(first.searched_depth, 2);
+        assert_eq!(first.solutions, vec![found]);
+
+        let second = journal.progress(2).unwrap();
+        assert_eq!(second.searched_depth, 3);
+        assert!(second.solutions.is_empty());
+        assert!(!second.complete);
+        assert!(journal.progress(3).is_none());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_journal_rejects_other_config_and_ignores_torn_line() {
+        let path = temp_journal("mismatch");
+        {
+            let mut journal = BatchJournal::create(&path, 7).unwrap();
+            journal.record_depth(1, &[5]).unwrap();
+        }
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "{{\"entry\":\"depth\",\"depth\":2,\"ca").unwrap();
+        drop(file);
+
+        assert!(BatchJournal::open(&path, 8).is_err());
+        let journal = BatchJournal::open(&path, 7).unwrap();
+        assert_eq!(journal.progress(5).unwrap().searched_depth, 1);
+
+        std::fs::write(&path, "not a journal\n").unwrap();
+        assert!(BatchJournal::open(&path, 7).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_journal_hash_tracks_search_settings() {
+        let config = BatchSolverConfig::default();
+        let same = BatchSolverConfig {
+            pruning_depth: config.pruning_depth + 1,
+            verify_solutions: true,
+            ..config.clone()
+        };
+        let changed = [
+            BatchSolverConfig {
+                max_search_depth: config.max_search_depth + 1,
+                ..config.clone()
+            },
+            BatchSolverConfig {
+                mcc_params: Some(MCCParams::default()),
+                ..config.clone()
+            },
+            BatchSolverConfig {
+                ranking: Some(SolutionRanking::new(RankScore::Ftm, 5)),
+                ..config.clone()
+            },
+            BatchSolverConfig {
+                dedup: DedupMode::Commuting,
+                ..config.clone()
+            },
+            BatchSolverConfig {
+                filters: vec![Arc::new(NoAuf::both())],
+                ..config.clone()
+            },
+        ];
+        assert_eq!(config.journal_hash(&[], None), same.journal_hash(&[], None));
+        for other in &changed {
+            assert_ne!(
+                config.journal_hash(&[], None),
+                other.journal_hash(&[], None)
+            );
+        }
+    }
+
+    #[test]
+    fn test_journal_hash_tracks_cases() {
+        let config = BatchSolverConfig::default();
+        let case = |m: Move, setup_moves: &str, case_number: usize| {
+            let mut state = LLMinx::new();
+            state.apply_move(m);
+            GeneratedState {
+                state,
+                setup_moves: setup_moves.to_string(),
+                case_number,
+            }
+        };
+        let hash = |states: &[GeneratedState], equivalence: Option<&EquivalenceHandler>| {
+            config.journal_hash(states, equivalence)
+        };
+
+        let states = [case(Move::R, "R", 1), case(Move::U, "U", 2)];
+        assert_eq!(hash(&states, None), hash(&states.clone(), None));
+        assert_ne!(hash(&states, None), hash(&states[..1], None));
+        assert_ne!(
+            hash(&states, None),
+            hash(&[case(Move::R, "R", 1), case(Move::Ui, "U'", 2)], None)
+        );
+        assert_ne!(
+            hash(&states, None),
+            hash(&[case(Move::R, "R", 1), case(Move::U, "U", 3)], None)
+        );
+        assert_ne!(
+            hash(&states, None),
+            hash(&[case(Move::R, "R", 1), case(Move::U, "U2 U4", 2)], None)
+        );
+
+        let equivalence = EquivalenceHandler::new(
+            vec![EquivalenceSet {
+                pieces: vec!["UC1".to_string(), "UC2".to_string()],
+            }],
+            vec![],
+            PieceMap::default_megaminx(),
+        )
+        .unwrap();
+        assert_ne!(hash(&states, None), hash(&states, Some(&equivalence)));
+    }
+}
@@ -4,18 +4,24 @@
 //! It allows defining multiple equivalent states using scramble syntax and solving them in a batched manner.
 
 pub mod adjust;
+pub mod coverage;
 pub mod equivalence;
 pub mod generator;
+pub mod journal;
 pub mod parser;
+pub mod recognition;
 pub mod solver;
 pub mod sorting;
 pub mod types;
 
 pub use adjust::AdjustHandler;
+pub use coverage::{CoverageReport, CoveredCase, parse_alg_list, read_alg_file};
 pub use equivalence::EquivalenceHandler;
 pub use generator::{GeneratorCallback, GeneratorConfig, StateGenerator, generate_batch_states};
+pub use journal::{BatchJournal, CaseProgress};
 pub use parser::ScrambleParser;
-pub use solver::{BatchSolverConfig, CaseSolvedCallback, solve_batch_states};
+pub use recognition::{CaseIndex, Recognition};
+pub use solver::{BatchSolverConfig, CaseSolvedCallback, resume_batch_states, solve_batch_states};
 pub use sorting::CaseSorter;
 pub use types::{
     BatchCaseResult, BatchConfig, BatchError, BatchResults, CaseModifiers, EquivalenceSet,
@@ -7,7 +7,7 @@
 //! - Modifiers: `#1,3,5-10,15+` for selective case solving
 
 use super::types::{BatchError, CaseModifiers, ParsedScramble, ScrambleSegment};
-use crate::minx::Move;
+use crate::minx::{Move, parse_alg, parse_move};
 
 /// Parser for scramble syntax
 pub struct ScrambleParser;
@@ -230,66 +230,12 @@ impl ScrambleParser {
 
     /// Parse a move string into Move enum values
     pub fn parse_moves(input: &str) -> Result<Vec<Move>, BatchError> {
-        let move_strs: Vec<&str> = input.split_whitespace().collect();
-        let mut moves = Vec::with_capacity(move_strs.len());
-
-        for s in move_strs {
-            let mv = Self::parse_single_move(s.trim())?;
-            moves.push(mv);
-        }
-
-        Ok(moves)
+        parse_alg(input).map_err(|e| BatchError::InvalidMove(e.to_string()))
     }
 
     /// Parse a single move string
     pub fn parse_single_move(input: &str) -> Result<Move, BatchError> {
-        // Handle common move formats
-        let input = input.trim();
-
-        // Standard face turns
-        match input {
-            "R" => return Ok(Move::R),
-            "R'" | "Ri" => return Ok(Move::Ri),
-            "R2" => return Ok(Move::R2),
-            "R2'" | "R2i" => return Ok(Move::R2i),
-
-            "L" => return Ok(Move::L),
-            "L'" | "Li" => return Ok(Move::Li),
-            "L2" => return Ok(Move::L2),
-            "L2'" | "L2i" => return Ok(Move::L2i),
-
-            "U" => return Ok(Move::U),
-            "U'" | "Ui" => return Ok(Move::Ui),
-            "U2" => return Ok(Move::U2),
-            "U2'" | "U2i" => return Ok(Move::U2i),
-
-            "F" => return Ok(Move::F),
-            "F'" | "Fi" => return Ok(Move::Fi),
-            "F2" => return Ok(Move::F2),
-            "F2'" | "F2i" => return Ok(Move::F2i),
-
-            "bL" => return Ok(Move::bL),
-            "bL'" | "bLi" => return Ok(Move::bLi),
-            "bL2" => return Ok(Move::bL2),
-            "bL2'" | "bL2i" => return Ok(Move::bL2i),
-
-            "bR" => return Ok(Move::bR),
-            "bR'" | "bRi" => return Ok(Move::bRi),
-            "bR2" => return Ok(Move::bR2),
-            "bR2'" | "bR2i" => return Ok(Move::bR2i),
-
-            "D" => return Ok(Move::D),
-            "D'" | "Di" => return Ok(Move::Di),
-            "D2" => return Ok(Move::D2),
-            "D2'" | "D2i" => return Ok(Move::D2i),
-
-            _ => {}
-        }
-
-        Err(BatchError::InvalidMove(format!(
-            "Unrecognized move: '{}'",
-            input
-        )))
+        parse_move(input).map_err(|e| BatchError::InvalidMove(e.to_string()))
     }
 
     /// Parse equivalence definition string
@@ -0,0 +1,248 @@
+//! Case recognition against a set of named algorithms
+//!
+//! Every algorithm is indexed by the state it solves, under each pre-adjust
+//! and post-adjust the `AdjustHandler` allows and normalized through the
+//! `EquivalenceHandler`, so a state is recognized whatever AUF it was set up
+//! with.
+
+use super::adjust::AdjustHandler;
+use super::equivalence::EquivalenceHandler;
+use super::parser::ScrambleParser;
+use super::types::{BatchError, NormalizedState};
+use crate::minx::{LLMinx, Move, invert_alg};
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::sync::Arc;
+
+/// A recognized case and the adjustments that solve it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recognition {
+    /// Name of the matching algorithm
+    pub name: String,
+    /// Moves to do before the algorithm
+    pub pre_auf: Vec<Move>,
+    /// The matching algorithm
+    pub alg: Vec<Move>,
+    /// Moves to do after the algorithm
+    pub post_auf: Vec<Move>,
+}
+
+impl Recognition {
+    /// Full solving sequence: pre-AUF, algorithm, post-AUF
+    pub fn solution(&self) -> Vec<Move> {
+        let mut moves = self.pre_auf.clone();
+        moves.extend_from_slice(&self.alg);
+        moves.extend_from_slice(&self.post_auf);
+        moves
+    }
+}
+
+struct IndexedCase {
+    alg_index: usize,
+    pre_auf: Vec<Move>,
+    post_auf: Vec<Move>,
+}
+
+/// Index of case signatures built from a list of named algorithms
+pub struct CaseIndex {
+    algs: Vec<(String, Vec<Move>)>,
+    cases: HashMap<NormalizedState, IndexedCase>,
+    equivalence: Option<Arc<EquivalenceHandler>>,
+    /// Pairs of algorithm names that solve the same case
+    duplicates: Vec<(String, String)>,
+}
+
+impl CaseIndex {
+    /// Build an index from `(name, algorithm)` pairs
+    ///
+    /// # Errors
+    /// Returns `BatchError::InvalidMove` if an algorithm cannot be parsed
+    pub fn new(
+        algs: &[(String, String)],
+        adjust: Option<&AdjustHandler>,
+        equivalence: Option<Arc<EquivalenceHandler>>,
+    ) -> Result<Self, BatchError> {
+        // Identity first, so a case that needs no AUF is indexed without one
+        let mut pre_sequences: Vec<Vec<Move>> = vec![vec![]];
+        let mut post_sequences: Vec<Vec<Move>> = vec![vec![]];
+        if let Some(adjust) = adjust {
+            pre_sequences.extend_from_slice(adjust.pre_adjust_sequences());
+            post_sequences.extend_from_slice(adjust.post_adjust_sequences());
+        }
+
+        let mut index = Self {
+            algs: Vec::with_capacity(algs.len()),
+            cases: HashMap::new(),
+            equivalence,
+            duplicates: Vec::new(),
+        };
+
+        for (alg_index, (name, alg)) in algs.iter().enumerate() {
+            let moves = ScrambleParser::parse_moves(alg)
+                .map_err(|e| BatchError::InvalidMove(format!("{}: {}", name, e)))?;
+            let setup = invert_alg(&moves);
+
+            for pre_seq in &pre_sequences {
+                for post_seq in &post_sequences {
+                    // The state solved by pre_seq, the algorithm, then post_seq
+                    let mut state = LLMinx::new();
+                    for &mv in invert_alg(post_seq).iter().chain(&setup) {
+                        state.apply_move(mv);
+                    }
+                    for &mv in &invert_alg(pre_seq) {
+                        state.apply_move(mv);
+                    }
+
+                    match index.cases.entry(index.normalize(&state)) {
+                        Entry::Vacant(entry) => {
+                            entry.insert(IndexedCase {
+                                alg_index,
+                                pre_auf: pre_seq.clone(),
+                                post_auf: post_seq.clone(),
+                            });
+                        }
+                        Entry::Occupied(entry) => {
+                            let existing = &algs[entry.get().alg_index].0;
+                            let pair = (existing.clone(), name.clone());
+                            if existing != name && !index.duplicates.contains(&pair) {
+                                index.duplicates.push(pair);
+                            }
+                        }
+                    }
+                }
+            }
+
+            index.algs.push((name.clone(), moves));
+        }
+
+        Ok(index)
+    }
+
+    /// Number of indexed algorithms
+    pub fn len(&self) -> usize {
+        self.algs.len()
+    }
+
+    /// Returns true if no algorithms are indexed
+    pub fn is_empty(&self) -> bool {
+        self.algs.is_empty()
+    }
+
+    /// Pairs of algorithm names where the second solves a case already
+    /// covered by the first; only the first is ever recognized
+    pub fn duplicates(&self) -> &[(String, String)] {
+        &self.duplicates
+    }
+
+    /// Find the case `state` belongs to
+    pub fn recognize(&self, state: &LLMinx) -> Option<Recognition> {
+        let case = self.cases.get(&self.normalize(state))?;
+        let (name, alg) = &self.algs[case.alg_index];
+        Some(Recognition {
+            name: name.clone(),
+            pre_auf: case.pre_auf.clone(),
+            alg: alg.clone(),
+            post_auf: case.post_auf.clone(),
+        })
+    }
+
+    fn normalize(&self, state: &LLMinx) -> NormalizedState {
+        match self.equivalence {
+            Some(ref equiv) => equiv.normalize(state),
+            None => NormalizedState::from_minx(state),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::batch_solver::types::{EquivalenceSet, PieceMap};
+    use crate::minx::{parse_alg, verify};
+
+    const SUNE: &str = "R U R' U R U2' R'";
+    const ANTISUNE: &str = "R U2 R' U' R U' R'";
+
+    fn alg_set() -> Vec<(String, String)> {
+        vec![
+            ("Sune".to_string(), SUNE.to_string()),
+            ("Antisune".to_string(), ANTISUNE.to_string()),
+        ]
+    }
+
+    fn u_adjust() -> AdjustHandler {
+        AdjustHandler::new(&["U".to_string()], &["U".to_string()]).unwrap()
+    }
+
+    #[test]
+    fn test_recognize_exact_case() {
+        let index = CaseIndex::new(&alg_set(), None, None).unwrap();
+        assert_eq!(index.len(), 2);
+
+        let state = LLMinx::from_alg_inverse(ANTISUNE).unwrap();
+        let found = index.recognize(&state).unwrap();
+        assert_eq!(found.name, "Antisune");
+        assert!(found.pre_auf.is_empty());
+        assert!(found.post_auf.is_empty());
+
+        assert!(index.recognize(&LLMinx::from_alg("U").unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_recognize_returns_auf_that_solves_the_state() {
+        let index = CaseIndex::new(&alg_set(), Some(&u_adjust()), None).unwrap();
+
+        let mut state = LLMinx::new();
+        state.apply_alg("U2").unwrap();
+        for mv in invert_alg(&parse_alg(SUNE).unwrap()) {
+            state.apply_move(mv);
+        }
+        state.apply_alg("U'").unwrap();
+        state.clear_moves();
+
+        let found = index.recognize(&state).unwrap();
+        assert_eq!(found.alg, parse_alg(SUNE).unwrap());
+        assert!(verify(&state, &found.solution(), &LLMinx::new()).is_ok());
+    }
+
+    #[test]
+    fn test_recognize_with_equivalences() {
+        let piece_map = PieceMap::default_megaminx();
+        let edges = EquivalenceSet {
+            pieces: ["UE1", "UE2", "UE3", "UE4", "UE5"]
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+        };
+        let equiv = Arc::new(EquivalenceHandler::new(vec![edges], vec![], piece_map).unwrap());
+
+        // A Sune case with two edges swapped only matches once edges are equivalent
+        let mut state = LLMinx::from_alg_inverse(SUNE).unwrap();
+        state.edge_positions_mut().swap(0, 1);
+
+        let index = CaseIndex::new(&alg_set(), None, None).unwrap();
+        assert!(index.recognize(&state).is_none());
+
+        let index = CaseIndex::new(&alg_set(), None, Some(equiv)).unwrap();
+        assert_eq!(index.recognize(&state).unwrap().name, "Sune");
+    }
+
+    #[test]
+    fn test_duplicates_and_invalid_algs() {
+        let algs = vec![
+            ("Sune".to_string(), SUNE.to_string()),
+            ("Sune again".to_string(), format!("U {} U'", SUNE)),
+        ];
+        let index = CaseIndex::new(&algs, Some(&u_adjust()), None).unwrap();
+        assert_eq!(
+            index.duplicates(),
+            &[("Sune".to_string(), "Sune again".to_string())]
+        );
+
+        let bad = vec![("Broken".to_string(), "R Q".to_string())];
+        assert!(matches!(
+            CaseIndex::new(&bad, None, None),
+            Err(BatchError::InvalidMove(_))
+        ));
+    }
+}
@@ -1,13 +1,23 @@
 use super::equivalence::EquivalenceHandler;
+use super::journal::BatchJournal;
 use super::types::{BatchCaseResult, BatchResults, GeneratedState};
+use crate::canonical::{DedupMode, Deduplicator};
+use crate::filter::{SharedFilter, accepts_all};
+use crate::mcc::MCCParams;
 use crate::memory_config::{MemoryConfig, get_current_rss_bytes};
 use crate::minx::{LLMinx, Move};
 use crate::pruner::Pruner;
+use crate::ranking::{SolutionRanking, TopSolutions};
+use crate::result_cache::{CacheKey, CachedResult, ResultCache};
 use crate::search_mode::{Metric, SearchMode};
+use crate::solution::Solution;
 use crate::solver::{
     IGNORE_CORNER_5, IGNORE_EDGE_5, Solver, StatusCallback, StatusEvent, StatusEventType,
 };
+use crate::table_registry::PruningTable;
 use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
@@ -26,11 +36,28 @@ pub struct BatchSolverConfig {
     pub pruning_depth: u8,
     pub max_search_depth: usize,
     pub stop_after_first: bool,
+    /// Stop each case `depth_slack` moves past its shortest solution.
+    pub depth_slack: Option<usize>,
     pub memory_config: MemoryConfig,
     pub ignore_corner_permutation: bool,
     pub ignore_edge_permutation: bool,
     pub ignore_corner_orientation: bool,
     pub ignore_edge_orientation: bool,
+    /// Score each solution with these MCC params as it is found.
+    pub mcc_params: Option<MCCParams>,
+    /// Keep only each case's best solutions under this ranking.
+    pub ranking: Option<SolutionRanking>,
+    /// Keep one solution per class of equivalent reorderings in each case.
+    pub dedup: DedupMode,
+    /// Drop solutions that any of these filters rejects.
+    pub filters: Vec<SharedFilter>,
+    /// Debug check: replay each solution against its case and report any
+    /// that miss the goal. Solutions are marked through `Solution::verified`.
+    pub verify_solutions: bool,
+    /// Answer cases from this cache as far as it has them, and store what
+    /// is searched past that. Cases a journal already has progress for
+    /// continue from the journal instead.
+    pub result_cache: Option<Arc<ResultCache>>,
 }
 
 impl Default for BatchSolverConfig {
@@ -41,11 +68,18 @@ impl Default for BatchSolverConfig {
             pruning_depth: 6,
             max_search_depth: 12,
             stop_after_first: false,
+            depth_slack: None,
             memory_config: MemoryConfig::default(),
             ignore_corner_permutation: false,
             ignore_edge_permutation: false,
             ignore_corner_orientation: false,
             ignore_edge_orientation: false,
+            mcc_params: None,
+            ranking: None,
+            dedup: DedupMode::Off,
+            filters: Vec::new(),
+            verify_solutions: false,
+            result_cache: None,
         }
     }
 }
@@ -60,17 +94,445 @@ struct BatchCase {
 
 unsafe impl Sync for BatchCase {}
 
+/// Solutions kept for one case.
+struct CaseSolutions {
+    top: TopSolutions,
+    dedup: Deduplicator,
+}
+
+impl CaseSolutions {
+    fn new(ranking: Option<SolutionRanking>, dedup: DedupMode) -> Self {
+        Self {
+            top: TopSolutions::new(ranking),
+            dedup: Deduplicator::new(dedup),
+        }
+    }
+
+    /// Offer a solution, returning whether it was kept.
+    fn push(&mut self, solution: Solution) -> bool {
+        self.dedup.insert(&solution) && self.top.push(solution)
+    }
+
+    fn solutions(&self) -> Vec<Solution> {
+        let mut solutions = self.top.to_sorted_vec();
+        for solution in &mut solutions {
+            self.dedup.annotate(solution);
+        }
+        solutions
+    }
+}
+
+/// Solutions each case keeps under the batch ranking and dedup settings.
+#[derive(Clone)]
+struct KeptSolutions {
+    cases: Arc<Mutex<HashMap<usize, CaseSolutions>>>,
+    ranking: Option<SolutionRanking>,
+    dedup: DedupMode,
+}
+
+impl KeptSolutions {
+    fn new(config: &BatchSolverConfig) -> Self {
+        Self {
+            cases: Arc::new(Mutex::new(HashMap::new())),
+            ranking: config.ranking,
+            dedup: config.dedup,
+        }
+    }
+
+    /// Offer a solution to its case, returning whether the case kept it.
+    fn push(&self, case_number: usize, solution: Solution) -> bool {
+        self.cases
+            .lock()
+            .unwrap()
+            .entry(case_number)
+            .or_insert_with(|| CaseSolutions::new(self.ranking, self.dedup))
+            .push(solution)
+    }
+
+    fn solutions(&self, case_number: usize) -> Vec<Solution> {
+        self.cases
+            .lock()
+            .unwrap()
+            .get(&case_number)
+            .map(CaseSolutions::solutions)
+            .unwrap_or_default()
+    }
+}
+
+/// The cases of one batch solve and what is known about each, shared by the
+/// journal, result cache, search and result stages.
+struct BatchRun<'a> {
+    config: &'a BatchSolverConfig,
+    status_callback: Option<StatusCallback>,
+    cases: Vec<BatchCase>,
+    /// Depth each case was already searched through.
+    searched_depths: Vec<usize>,
+    /// Cases that need no further searching.
+    finished: Vec<bool>,
+    /// Depth of each case's first solution, 0 while it has none.
+    optimal_depths: Arc<Vec<AtomicUsize>>,
+    kept: KeptSolutions,
+}
+
+impl<'a> BatchRun<'a> {
+    fn new(
+        config: &'a BatchSolverConfig,
+        cases: Vec<BatchCase>,
+        status_callback: Option<StatusCallback>,
+    ) -> Self {
+        Self {
+            config,
+            status_callback,
+            searched_depths: vec![0; cases.len()],
+            finished: vec![false; cases.len()],
+            optimal_depths: Arc::new(cases.iter().map(|_| AtomicUsize::new(0)).collect()),
+            kept: KeptSolutions::new(config),
+            cases,
+        }
+    }
+
+    fn event(&self, event: StatusEvent) {
+        fire_event(&self.status_callback, event);
+    }
+
+    /// Tables are only needed if some case has depths left to search.
+    fn needs_tables(
+        &self,
+        cached: &[Option<CachedResult>],
+        journal: Option<&BatchJournal>,
+    ) -> bool {
+        self.cases.iter().zip(cached).any(|(case, cached)| {
+            let complete = journal
+                .and_then(|j| j.progress(case.case_number))
+                .is_some_and(|p| p.complete);
+            !complete
+                && !cached
+                    .as_ref()
+                    .is_some_and(|c| cache_finishes(c, self.config))
+        })
+    }
+
+    fn mark_solved(&self, case_idx: usize, optimal_depth: usize) {
+        self.optimal_depths[case_idx].store(optimal_depth, Ordering::SeqCst);
+        self.cases[case_idx].solved.store(true, Ordering::SeqCst);
+    }
+
+    /// Continue each case from the depth and solutions the journal has for it.
+    fn restore_journal(&mut self, journal: &BatchJournal) {
+        for case_idx in 0..self.cases.len() {
+            let case_number = self.cases[case_idx].case_number;
+            let Some(progress) = journal.progress(case_number) else {
+                continue;
+            };
+            self.searched_depths[case_idx] = progress.searched_depth;
+            self.finished[case_idx] = progress.complete;
+            if let Some(depth) = progress.solutions.iter().map(|s| s.depth).min() {
+                self.mark_solved(case_idx, depth);
+                for solution in &progress.solutions {
+                    self.kept.push(case_number, solution.clone());
+                }
+            }
+        }
+
+        self.event(StatusEvent::new(
+            StatusEventType::Message,
+            &format!(
+                "Resuming batch: {} of {} cases already finished",
+                self.finished.iter().filter(|&&f| f).count(),
+                self.cases.len()
+            ),
+            0.05,
+        ));
+    }
+
+    /// Answer cases from their cached results, journaling what they add.
+    fn replay_result_cache(
+        &mut self,
+        cached: &mut [Option<CachedResult>],
+        mut journal: Option<&mut BatchJournal>,
+    ) {
+        let config = self.config;
+        let mut loaded = 0;
+        for (case_idx, entry) in cached.iter_mut().enumerate() {
+            let Some(entry) = entry else {
+                continue;
+            };
+            let depth = entry.searched_depth.min(config.max_search_depth);
+            entry.solutions.retain(|s| s.depth <= depth);
+            self.searched_depths[case_idx] = depth;
+            self.finished[case_idx] = cache_finishes(entry, config);
+            loaded += 1;
+
+            let case = &self.cases[case_idx];
+            let optimal = entry.optimal_depth(&config.filters);
+            for solution in accepted_cached_solutions(entry, optimal, config) {
+                let mut solution = solution.clone();
+                if let Some(event) = score_and_verify(
+                    &mut solution,
+                    config.mcc_params.as_ref(),
+                    config.verify_solutions.then_some(&case.start),
+                    &case.goal,
+                    case.case_number,
+                ) {
+                    self.event(event);
+                }
+                if let Some(journal) = journal.as_deref_mut() {
+                    let _ = journal.record_solution(case.case_number, &solution);
+                }
+                self.kept.push(case.case_number, solution);
+            }
+            if let Some(optimal) = optimal {
+                self.mark_solved(case_idx, optimal);
+            }
+
+            if let Some(journal) = journal.as_deref_mut() {
+                if depth > 0 {
+                    let _ = journal.record_depth(depth, &[case.case_number]);
+                }
+                if self.finished[case_idx] {
+                    let _ = journal.record_complete(case.case_number);
+                }
+            }
+        }
+
+        if loaded > 0 {
+            self.event(StatusEvent::new(
+                StatusEventType::Message,
+                &format!(
+                    "Loaded cached results for {} of {} cases",
+                    loaded,
+                    self.cases.len()
+                ),
+                0.05,
+            ));
+        }
+    }
+
+    /// Cases to search at `depth`.
+    fn active_cases(&self, depth: usize) -> Vec<usize> {
+        let config = self.config;
+        self.cases
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !self.finished[*i] && depth > self.searched_depths[*i])
+            .filter(|(_, c)| !config.stop_after_first || !c.solved.load(Ordering::Relaxed))
+            .filter(|(i, _)| {
+                within_depth_slack(&self.optimal_depths[*i], depth, config.depth_slack)
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Journal that the active cases were searched through `depth`, and
+    /// finish those that need no deeper search.
+    fn journal_depth(&mut self, journal: &mut BatchJournal, depth: usize, active: &[usize]) {
+        let config = self.config;
+        let case_numbers: Vec<usize> = active.iter().map(|&i| self.cases[i].case_number).collect();
+        let _ = journal.record_depth(depth, &case_numbers);
+
+        for &i in active {
+            let done = depth == config.max_search_depth
+                || (config.stop_after_first && self.cases[i].solved.load(Ordering::SeqCst))
+                || !within_depth_slack(&self.optimal_depths[i], depth + 1, config.depth_slack);
+            if done {
+                self.finished[i] = true;
+                let _ = journal.record_complete(self.cases[i].case_number);
+            }
+        }
+    }
+
+    /// Concurrency for the next depth, following the RSS the last one left.
+    fn adjust_concurrency(&self, current: usize, rss_before: usize) -> usize {
+        let rss_after = get_current_rss_bytes();
+        if rss_before == 0 || rss_after == 0 {
+            return current;
+        }
+        let budget_bytes =
+            (self.config.memory_config.total_budget_bytes as f64 * UPPER_BOUND) as usize;
+        let total_cases = self.cases.len();
+
+        if rss_after > budget_bytes && current > MIN_CONCURRENT_CASES {
+            let ratio = budget_bytes as f64 / rss_after as f64;
+            let adjusted = ((current as f64 * ratio) as usize).max(MIN_CONCURRENT_CASES);
+            if adjusted != current {
+                self.event(StatusEvent::new(
+                    StatusEventType::MemoryWarning,
+                    &format!(
+                        "Reducing concurrency {} -> {} (RSS: {} MB, budget: {} MB)",
+                        current,
+                        adjusted,
+                        rss_after / (1024 * 1024),
+                        budget_bytes / (1024 * 1024),
+                    ),
+                    0.0,
+                ));
+                return adjusted;
+            }
+        } else if rss_after < budget_bytes / 2 && current < total_cases {
+            let headroom = budget_bytes as f64 / rss_after.max(1) as f64;
+            let adjusted = ((current as f64 * headroom * 0.8) as usize)
+                .min(total_cases)
+                .max(current);
+            if adjusted > current {
+                self.event(StatusEvent::new(
+                    StatusEventType::Message,
+                    &format!(
+                        "Increasing concurrency {} -> {} (RSS: {} MB, budget: {} MB)",
+                        current,
+                        adjusted,
+                        rss_after / (1024 * 1024),
+                        budget_bytes / (1024 * 1024),
+                    ),
+                    0.0,
+                ));
+                return adjusted;
+            }
+        }
+        current
+    }
+
+    /// Store what each case was searched through past its cached depths.
+    fn store_result_cache(
+        &self,
+        cache: &ResultCache,
+        cache_keys: &[Option<CacheKey>],
+        cached: Vec<Option<CachedResult>>,
+        found: Vec<Option<Mutex<Vec<Solution>>>>,
+        cache_depths: &[usize],
+    ) {
+        let entries = cache_keys.iter().zip(cached).zip(found).enumerate();
+        for (case_idx, ((key, cached), found)) in entries {
+            let Some(key) = key else {
+                continue;
+            };
+            let searched_through = cache_depths[case_idx];
+            if searched_through <= self.searched_depths[case_idx] {
+                continue;
+            }
+            let mut solutions = cached.map(|c| c.solutions).unwrap_or_default();
+            let found = found.map(|f| f.into_inner().unwrap()).unwrap_or_default();
+            solutions.extend(found.into_iter().filter(|s| s.depth <= searched_through));
+            if let Err(err) = cache.put(key, searched_through, &solutions) {
+                self.event(StatusEvent::new(StatusEventType::Message, &err, 1.0));
+            }
+        }
+    }
+
+    /// Results of every case. Cases the solution collector never reported
+    /// are passed to `case_solved_callback` here.
+    fn results(
+        &self,
+        case_solved_callback: Option<&CaseSolvedCallback>,
+        notified_cases: &HashSet<usize>,
+        elapsed: f64,
+    ) -> BatchResults {
+        let total_cases = self.cases.len();
+        let mut results = BatchResults::new(total_cases);
+        for (case_idx, case) in self.cases.iter().enumerate() {
+            let solutions = self.kept.solutions(case.case_number);
+            let mut result = BatchCaseResult::new(case.case_number, case.setup_moves.clone());
+            result.optimal_length = optimal_length(&self.optimal_depths[case_idx]);
+            result.best_solution = solutions.first().cloned();
+            result.solutions = solutions;
+            result.solve_time = elapsed;
+
+            if !notified_cases.contains(&case.case_number)
+                && let Some(callback) = case_solved_callback
+            {
+                callback(result.clone());
+            }
+            results.add_result(result);
+        }
+
+        results.total_time = elapsed;
+        results.average_time_per_case = if results.case_results.is_empty() {
+            0.0
+        } else {
+            elapsed / results.case_results.len() as f64
+        };
+
+        self.event(StatusEvent::new(
+            StatusEventType::FinishSearch,
+            &format!(
+                "Batch solve complete. Solved {}/{} cases in {:.2}s",
+                results.solved_cases, total_cases, elapsed
+            ),
+            1.0,
+        ));
+        results
+    }
+}
+
+/// Tables, pruners and move lists every case of a batch searches with.
+struct SearchSetup {
+    tables: Vec<Arc<PruningTable>>,
+    pruner_indices: Vec<usize>,
+    moves: Vec<Move>,
+    first_moves: Vec<Move>,
+    next_siblings: Vec<Vec<Option<Move>>>,
+    /// Heap the tables hold. Mapped tables live in the page cache instead.
+    table_memory_bytes: usize,
+}
+
+impl SearchSetup {
+    fn new(solver: &Solver, config: &BatchSolverConfig) -> Self {
+        let used_pruners = filter_pruning_tables(solver, config);
+        Self {
+            table_memory_bytes: used_pruners
+                .iter()
+                .filter(|(t, _)| !t.is_mapped())
+                .map(|(t, _)| t.memory_bytes())
+                .sum(),
+            tables: used_pruners.iter().map(|(t, _)| Arc::clone(t)).collect(),
+            pruner_indices: solver
+                .get_pruners()
+                .iter()
+                .enumerate()
+                .filter(|(_, pruner)| !is_dominated(pruner.as_ref(), config))
+                .map(|(i, _)| i)
+                .collect(),
+            moves: solver.get_moves().to_vec(),
+            first_moves: solver.get_first_moves().to_vec(),
+            next_siblings: solver.get_next_siblings().to_vec(),
+        }
+    }
+}
+
+/// Where the searches of one depth report to.
+struct SearchSinks<'a> {
+    interrupt: &'a Arc<AtomicBool>,
+    solution_tx: crossbeam_channel::Sender<(usize, Solution)>,
+    status_tx: crossbeam_channel::Sender<StatusEvent>,
+    journal: Option<&'a Mutex<BatchJournal>>,
+    /// Every solution found per case, for the cases the result cache stores.
+    found: &'a [Option<Mutex<Vec<Solution>>>],
+}
+
 struct SearchContext<'a> {
-    tables: &'a [Arc<Vec<u8>>],
+    tables: &'a [Arc<PruningTable>],
     pruners: &'a [&'a dyn Pruner],
     first_moves: &'a [Move],
     next_siblings: &'a [Vec<Option<Move>>],
     interrupted: &'a Arc<AtomicBool>,
-    solution_tx: &'a crossbeam_channel::Sender<(usize, String)>,
+    solution_tx: &'a crossbeam_channel::Sender<(usize, Solution)>,
     status_tx: &'a crossbeam_channel::Sender<StatusEvent>,
     case_number: usize,
     case_solved: &'a AtomicBool,
+    /// Depth of the case's first solution, 0 while it has none.
+    case_optimal_depth: &'a AtomicUsize,
     stop_after_first: bool,
+    search_mode: SearchMode,
+    /// Solutions are journaled as they are found, before the depth that found
+    /// them can be recorded as searched.
+    journal: Option<&'a Mutex<BatchJournal>>,
+    mcc_params: Option<&'a MCCParams>,
+    /// Ranked or deduplicated batches report solutions once the case keeps them.
+    report_solutions: bool,
+    filters: &'a [SharedFilter],
+    /// Case start to replay solutions from when verifying them.
+    verify_start: Option<&'a LLMinx>,
+    /// Every solution of the case found, before filtering, for the result cache.
+    found: Option<&'a Mutex<Vec<Solution>>>,
 }
 
 pub fn solve_batch_states(
@@ -80,6 +542,53 @@ pub fn solve_batch_states(
     interrupt: Arc<AtomicBool>,
     status_callback: Option<StatusCallback>,
     case_solved_callback: Option<CaseSolvedCallback>,
+) -> BatchResults {
+    solve_batch(
+        states,
+        config,
+        equivalence,
+        None,
+        interrupt,
+        status_callback,
+        case_solved_callback,
+    )
+}
+
+/// Solve a batch while checkpointing progress to the journal at
+/// `journal_path`. Cases the journal marks finished are not searched again,
+/// and unfinished cases continue from the last depth they completed.
+pub fn resume_batch_states(
+    states: Vec<GeneratedState>,
+    config: &BatchSolverConfig,
+    equivalence: Option<&Arc<EquivalenceHandler>>,
+    journal_path: &Path,
+    interrupt: Arc<AtomicBool>,
+    status_callback: Option<StatusCallback>,
+    case_solved_callback: Option<CaseSolvedCallback>,
+) -> Result<BatchResults, String> {
+    let journal = BatchJournal::open(
+        journal_path,
+        config.journal_hash(&states, equivalence.map(Arc::as_ref)),
+    )?;
+    Ok(solve_batch(
+        states,
+        config,
+        equivalence,
+        Some(journal),
+        interrupt,
+        status_callback,
+        case_solved_callback,
+    ))
+}
+
+fn solve_batch(
+    states: Vec<GeneratedState>,
+    config: &BatchSolverConfig,
+    equivalence: Option<&Arc<EquivalenceHandler>>,
+    mut journal: Option<BatchJournal>,
+    interrupt: Arc<AtomicBool>,
+    status_callback: Option<StatusCallback>,
+    case_solved_callback: Option<CaseSolvedCallback>,
 ) -> BatchResults {
     let start_time = std::time::Instant::now();
     interrupt.store(false, Ordering::SeqCst);
@@ -106,138 +615,59 @@ pub fn solve_batch_states(
         ),
     );
 
-    let mut master_solver = Solver::with_parallel_config(
-        config.search_mode,
-        config.max_search_depth,
-        config.memory_config,
-    );
-    master_solver.set_metric(config.metric);
-    master_solver.set_pruning_depth(config.pruning_depth);
-    master_solver.set_limit_search_depth(true);
-    master_solver.set_ignore_corner_positions(config.ignore_corner_permutation);
-    master_solver.set_ignore_edge_positions(config.ignore_edge_permutation);
-    master_solver.set_ignore_corner_orientations(config.ignore_corner_orientation);
-    master_solver.set_ignore_edge_orientations(config.ignore_edge_orientation);
-
-    if let Some(ref cb) = status_callback {
-        let cb_clone = Arc::clone(cb);
-        master_solver.set_status_callback(move |event| {
-            cb_clone(event);
-        });
-    }
+    let mut master_solver = master_solver(config, &status_callback);
+    let cases = build_cases(&states, config, equivalence);
+    let mut run = BatchRun::new(config, cases, status_callback.clone());
 
-    master_solver.set_start(states[0].state.clone());
-    master_solver.prepare_tables();
+    let (cache_keys, mut cached) = lookup_result_cache(&run.cases, config, journal.as_ref());
+    if run.needs_tables(&cached, journal.as_ref()) {
+        master_solver.set_start(states[0].state.clone());
+        master_solver.prepare_tables();
+    }
 
     if interrupt.load(Ordering::SeqCst) {
         return BatchResults::new(total_cases);
     }
 
-    let cases = build_cases(&states, config, equivalence);
-
-    let used_pruners = filter_pruning_tables(&master_solver, config);
-    let table_memory_bytes: usize = used_pruners.iter().map(|(t, _)| t.len()).sum();
-    let tables: Vec<Arc<Vec<u8>>> = used_pruners.iter().map(|(t, _)| Arc::clone(t)).collect();
-    let pruner_indices: Vec<usize> = master_solver
-        .get_pruners()
-        .iter()
-        .enumerate()
-        .filter(|(_, pruner)| {
-            let dominated = (pruner.uses_corner_permutation() && config.ignore_corner_permutation)
-                || (pruner.uses_edge_permutation() && config.ignore_edge_permutation)
-                || (pruner.uses_corner_orientation() && config.ignore_corner_orientation)
-                || (pruner.uses_edge_orientation() && config.ignore_edge_orientation);
-            !dominated
-        })
-        .map(|(i, _)| i)
-        .collect();
-
-    let moves = master_solver.get_moves().to_vec();
-    let first_moves = master_solver.get_first_moves().to_vec();
-    let next_siblings = master_solver.get_next_siblings().to_vec();
-    let num_threads = config.memory_config.search_threads;
-    let search_mode = config.search_mode;
-
-    let max_concurrent = calculate_max_concurrent(
+    let setup = SearchSetup::new(&master_solver, config);
+    let mut max_concurrent = calculate_max_concurrent(
         config,
-        table_memory_bytes,
-        num_threads,
-        moves.len(),
+        setup.table_memory_bytes,
+        config.memory_config.search_threads,
+        setup.moves.len(),
         total_cases,
     );
 
-    fire_event(
-        &status_callback,
-        StatusEvent::new(
-            StatusEventType::Message,
-            &format!(
-                "Starting batch search: {} cases, {} concurrent (tables: {} MB)",
-                cases.len(),
-                max_concurrent,
-                table_memory_bytes / (1024 * 1024),
-            ),
-            0.05,
+    run.event(StatusEvent::new(
+        StatusEventType::Message,
+        &format!(
+            "Starting batch search: {} cases, {} concurrent (tables: {} MB)",
+            total_cases,
+            max_concurrent,
+            setup.table_memory_bytes / (1024 * 1024),
         ),
-    );
-
-    let (solution_tx, solution_rx) = crossbeam_channel::unbounded::<(usize, String)>();
-
-    let case_solutions: Arc<Mutex<std::collections::HashMap<usize, Vec<String>>>> =
-        Arc::new(Mutex::new(std::collections::HashMap::new()));
-    let case_solutions_for_thread = Arc::clone(&case_solutions);
-    let case_solved_cb_clone = case_solved_callback.clone();
-    let status_callback_for_sol = status_callback.clone();
-    let start_time_for_sol = start_time;
-
-    let cases_for_sol: Arc<Vec<(usize, String)>> = Arc::new(
-        cases
-            .iter()
-            .map(|c| (c.case_number, c.setup_moves.clone()))
-            .collect(),
-    );
-
-    let notified_cases: Arc<Mutex<std::collections::HashSet<usize>>> =
-        Arc::new(Mutex::new(std::collections::HashSet::new()));
-    let notified_for_thread = Arc::clone(&notified_cases);
-
-    let solution_thread = std::thread::spawn(move || {
-        for (case_number, solution) in solution_rx.iter() {
-            let elapsed = start_time_for_sol.elapsed().as_secs_f64();
-
-            {
-                let mut sols = case_solutions_for_thread.lock().unwrap();
-                sols.entry(case_number).or_default().push(solution.clone());
-            }
+        0.05,
+    ));
 
-            fire_event(
-                &status_callback_for_sol,
-                StatusEvent::new(
-                    StatusEventType::SolutionFound,
-                    &format!("Case {}: {}", case_number, solution),
-                    0.0,
-                ),
-            );
-
-            if let Some(ref cb) = case_solved_cb_clone {
-                let sols = case_solutions_for_thread.lock().unwrap();
-                let solutions = sols.get(&case_number).cloned().unwrap_or_default();
-                let setup_moves = cases_for_sol
-                    .iter()
-                    .find(|(cn, _)| *cn == case_number)
-                    .map(|(_, s)| s.clone())
-                    .unwrap_or_default();
-                drop(sols);
+    if let Some(ref journal) = journal {
+        run.restore_journal(journal);
+    }
+    if config.result_cache.is_some() {
+        run.replay_result_cache(&mut cached, journal.as_mut());
+    }
+    let journal = journal.map(Mutex::new);
 
-                let mut result = BatchCaseResult::new(case_number, setup_moves);
-                result.best_solution = solutions.first().cloned();
-                result.solutions = solutions;
-                result.solve_time = elapsed;
+    // Solutions found per case before filtering, and the depth each case has
+    // been searched through without a gap since its cached depths
+    let found: Vec<Option<Mutex<Vec<Solution>>>> = cache_keys
+        .iter()
+        .map(|key| key.as_ref().map(|_| Mutex::new(Vec::new())))
+        .collect();
+    let mut cache_depths = run.searched_depths.clone();
 
-                notified_for_thread.lock().unwrap().insert(case_number);
-                cb(result);
-            }
-        }
-    });
+    let (solution_tx, solution_rx) = crossbeam_channel::unbounded::<(usize, Solution)>();
+    let solution_thread =
+        spawn_solution_collector(&run, solution_rx, case_solved_callback.clone(), start_time);
 
     let (status_tx, status_rx) = crossbeam_channel::unbounded::<StatusEvent>();
     let status_callback_clone = status_callback.clone();
@@ -249,302 +679,406 @@ pub fn solve_batch_states(
         }
     });
 
-    let max_search_depth = config.max_search_depth;
-    let mut current_max_concurrent = max_concurrent;
-    let mut results = BatchResults::new(total_cases);
+    let sinks = SearchSinks {
+        interrupt: &interrupt,
+        solution_tx,
+        status_tx,
+        journal: journal.as_ref(),
+        found: &found,
+    };
 
-    for depth in 1..=max_search_depth {
+    for depth in 1..=config.max_search_depth {
         if interrupt.load(Ordering::SeqCst) {
             break;
         }
 
-        if config.stop_after_first && cases.iter().all(|c| c.solved.load(Ordering::Relaxed)) {
+        if config.stop_after_first && run.cases.iter().all(|c| c.solved.load(Ordering::Relaxed)) {
             break;
         }
 
-        let active_case_indices: Vec<usize> = cases
-            .iter()
-            .enumerate()
-            .filter(|(_, c)| !config.stop_after_first || !c.solved.load(Ordering::Relaxed))
-            .map(|(i, _)| i)
-            .collect();
-
+        let active_case_indices = run.active_cases(depth);
         if active_case_indices.is_empty() {
+            // Resumed cases may still be waiting for a depth past this one.
+            let pending =
+                (0..run.cases.len()).any(|i| !run.finished[i] && run.searched_depths[i] >= depth);
+            if pending {
+                continue;
+            }
             break;
         }
 
-        let num_batchs = active_case_indices.len().div_ceil(current_max_concurrent);
+        let rss_before = get_current_rss_bytes();
+        let depth_start_time = std::time::Instant::now();
 
-        fire_event(
-            &status_callback,
-            StatusEvent::with_context(
-                StatusEventType::StartDepth,
-                &format!(
-                    "Depth {}: {} active cases, {} concurrent ({} batchs)",
-                    depth,
-                    active_case_indices.len(),
-                    current_max_concurrent,
-                    num_batchs,
-                ),
-                0.0,
-                None,
-                depth as u32,
-            ),
+        search_depth(
+            &run,
+            &setup,
+            &sinks,
+            depth,
+            &active_case_indices,
+            max_concurrent,
         );
 
-        let rss_before = get_current_rss_bytes();
-        let depth_start_time = std::time::Instant::now();
+        if !interrupt.load(Ordering::SeqCst) {
+            if let Some(journal) = sinks.journal {
+                run.journal_depth(&mut journal.lock().unwrap(), depth, &active_case_indices);
+            }
 
-        for (batch_idx, batch_chunk) in active_case_indices
-            .chunks(current_max_concurrent)
-            .enumerate()
-        {
-            if interrupt.load(Ordering::SeqCst) {
-                break;
+            // A case that stopped at its first solution wasn't searched through
+            // the whole depth
+            for &i in &active_case_indices {
+                let stopped = config.stop_after_first && run.cases[i].solved.load(Ordering::SeqCst);
+                if cache_depths[i] + 1 == depth && !stopped {
+                    cache_depths[i] = depth;
+                }
             }
+        }
 
-            let batch_active: Vec<usize> = batch_chunk
-                .iter()
-                .copied()
-                .filter(|&i| !config.stop_after_first || !cases[i].solved.load(Ordering::Relaxed))
-                .collect();
+        run.event(StatusEvent::with_context(
+            StatusEventType::EndDepth,
+            &format!(
+                "Finished depth {} in {:.1}s",
+                depth,
+                depth_start_time.elapsed().as_secs_f64()
+            ),
+            1.0,
+            None,
+            depth as u32,
+        ));
+
+        max_concurrent = run.adjust_concurrency(max_concurrent, rss_before);
+    }
+
+    drop(sinks);
+    let notified_cases = solution_thread.join().unwrap_or_default();
+    let _ = status_thread.join();
 
-            if batch_active.is_empty() {
+    if let Some(ref cache) = config.result_cache {
+        run.store_result_cache(cache, &cache_keys, cached, found, &cache_depths);
+    }
+
+    run.results(
+        case_solved_callback.as_ref(),
+        &notified_cases,
+        start_time.elapsed().as_secs_f64(),
+    )
+}
+
+/// Solver that builds the tables the batch searches with.
+fn master_solver(config: &BatchSolverConfig, status_callback: &Option<StatusCallback>) -> Solver {
+    let mut solver = Solver::with_parallel_config(
+        config.search_mode,
+        config.max_search_depth,
+        config.memory_config,
+    );
+    solver.set_metric(config.metric);
+    solver.set_pruning_depth(config.pruning_depth);
+    solver.set_limit_search_depth(true);
+    solver.set_ignore_corner_positions(config.ignore_corner_permutation);
+    solver.set_ignore_edge_positions(config.ignore_edge_permutation);
+    solver.set_ignore_corner_orientations(config.ignore_corner_orientation);
+    solver.set_ignore_edge_orientations(config.ignore_edge_orientation);
+
+    if let Some(cb) = status_callback {
+        let cb_clone = Arc::clone(cb);
+        solver.set_status_callback(move |event| {
+            cb_clone(event);
+        });
+    }
+    solver
+}
+
+/// Result cache keys and entries per case. Cases the journal has progress
+/// for continue from it, the rest from the cache.
+fn lookup_result_cache(
+    cases: &[BatchCase],
+    config: &BatchSolverConfig,
+    journal: Option<&BatchJournal>,
+) -> (Vec<Option<CacheKey>>, Vec<Option<CachedResult>>) {
+    let keys: Vec<Option<CacheKey>> = cases
+        .iter()
+        .map(|case| {
+            let journaled = journal.is_some_and(|j| j.progress(case.case_number).is_some());
+            (config.result_cache.is_some() && !journaled)
+                .then(|| CacheKey::new(&case.start, config.search_mode, config.metric))
+        })
+        .collect();
+    let cached = keys
+        .iter()
+        .map(|key| config.result_cache.as_ref().zip(key.as_ref()))
+        .map(|entry| entry.and_then(|(cache, key)| cache.get(key)))
+        .collect();
+    (keys, cached)
+}
+
+/// Cached solutions the batch reports: those the filters accept, within the
+/// depth slack of the optimal length, and only the first when the batch
+/// stops at one.
+fn accepted_cached_solutions<'c>(
+    cached: &'c CachedResult,
+    optimal: Option<usize>,
+    config: &'c BatchSolverConfig,
+) -> impl Iterator<Item = &'c Solution> {
+    cached
+        .solutions
+        .iter()
+        .filter(|s| accepts_all(&config.filters, s))
+        .filter(move |s| match (optimal, config.depth_slack) {
+            (Some(optimal), Some(slack)) => s.depth <= optimal + slack,
+            _ => true,
+        })
+        .take(if config.stop_after_first {
+            1
+        } else {
+            usize::MAX
+        })
+}
+
+/// Score a solution with the batch MCC params and, when verifying, replay it
+/// from `verify_start`. Returns the event reporting a failed verification.
+fn score_and_verify(
+    solution: &mut Solution,
+    mcc_params: Option<&MCCParams>,
+    verify_start: Option<&LLMinx>,
+    goal: &LLMinx,
+    case_number: usize,
+) -> Option<StatusEvent> {
+    if let Some(params) = mcc_params {
+        solution.score_mcc(params);
+    }
+    let err = verify_start.and_then(|start| solution.verify(start, goal).err())?;
+    Some(StatusEvent::new(
+        StatusEventType::Message,
+        &format!(
+            "Case {}: {} failed verification, {}",
+            case_number, solution, err
+        ),
+        0.0,
+    ))
+}
+
+/// Keep the solutions the searches send and report each one a case keeps.
+/// The thread returns the cases it reported to `case_solved_callback`.
+fn spawn_solution_collector(
+    run: &BatchRun,
+    solution_rx: crossbeam_channel::Receiver<(usize, Solution)>,
+    case_solved_callback: Option<CaseSolvedCallback>,
+    start_time: std::time::Instant,
+) -> std::thread::JoinHandle<HashSet<usize>> {
+    let kept = run.kept.clone();
+    let status_callback = run.status_callback.clone();
+    let optimal_depths = Arc::clone(&run.optimal_depths);
+    let cases: Vec<(usize, String)> = run
+        .cases
+        .iter()
+        .map(|c| (c.case_number, c.setup_moves.clone()))
+        .collect();
+
+    std::thread::spawn(move || {
+        let mut notified = HashSet::new();
+        for (case_number, solution) in solution_rx.iter() {
+            let elapsed = start_time.elapsed().as_secs_f64();
+            if !kept.push(case_number, solution.clone()) {
                 continue;
             }
 
-            let pool = rayon::ThreadPoolBuilder::new()
-                .num_threads(num_threads)
-                .build()
-                .unwrap();
-
-            let moves_clone = moves.clone();
-            let first_moves_clone = first_moves.clone();
-            let next_siblings_clone = next_siblings.clone();
-            let tables_clone = tables.clone();
-            let pruner_indices_clone = pruner_indices.clone();
-            let interrupted_clone = Arc::clone(&interrupt);
-            let solution_tx_clone = solution_tx.clone();
-            let status_tx_clone = status_tx.clone();
-            let stop_after_first = config.stop_after_first;
-
-            let total_work = batch_active.len() * moves_clone.len();
-            let completed_work = Arc::new(AtomicUsize::new(0));
-            let batch_start_time = Arc::new(std::time::Instant::now());
-
-            let _ = status_tx_clone.send(StatusEvent::with_context(
-                StatusEventType::Message,
-                &format!(
-                    "Depth {} - batch {}/{} ({} cases)",
-                    depth,
-                    batch_idx + 1,
-                    num_batchs,
-                    batch_active.len(),
+            fire_event(
+                &status_callback,
+                StatusEvent::solution_found(
+                    &format!("Case {}: {}", case_number, solution),
+                    solution,
                 ),
-                0.0,
-                None,
-                depth as u32,
-            ));
+            );
 
-            pool.install(|| {
-                batch_active.par_iter().for_each(|&case_idx| {
-                    let case = &cases[case_idx];
-
-                    for &first_move in &moves_clone {
-                        if interrupted_clone.load(Ordering::Relaxed) {
-                            return;
-                        }
-                        if stop_after_first && case.solved.load(Ordering::Relaxed) {
-                            completed_work.fetch_add(1, Ordering::Relaxed);
-                            continue;
-                        }
-
-                        let mut minx = case.start.clone();
-                        minx.apply_move(first_move);
-
-                        let all_pruners = search_mode.create_pruners();
-                        let local_pruners: Vec<&dyn Pruner> = pruner_indices_clone
-                            .iter()
-                            .filter_map(|&i| all_pruners.get(i).map(|p| p.as_ref()))
-                            .collect();
-
-                        let ctx = SearchContext {
-                            tables: &tables_clone,
-                            pruners: &local_pruners,
-                            first_moves: &first_moves_clone,
-                            next_siblings: &next_siblings_clone,
-                            interrupted: &interrupted_clone,
-                            solution_tx: &solution_tx_clone,
-                            status_tx: &status_tx_clone,
-                            case_number: case.case_number,
-                            case_solved: &case.solved,
-                            stop_after_first,
-                        };
+            if let Some(ref cb) = case_solved_callback {
+                let solutions = kept.solutions(case_number);
+                let case_idx = cases.iter().position(|(cn, _)| *cn == case_number);
+                let setup_moves = case_idx.map(|i| cases[i].1.clone()).unwrap_or_default();
 
-                        search_branch(&mut minx, &case.goal, depth, &ctx);
-
-                        let done = completed_work.fetch_add(1, Ordering::Relaxed) + 1;
-                        let progress = done as f64 / total_work as f64;
-                        let elapsed = batch_start_time.elapsed().as_secs_f64();
-
-                        if done.is_multiple_of(50) || done == total_work {
-                            let etr_str = if progress > 0.005 && elapsed > 0.5 {
-                                let total_estimated = elapsed / progress;
-                                let remaining = total_estimated - elapsed;
-                                if remaining < 60.0 {
-                                    format!("ETR: {:.1}s", remaining)
-                                } else if remaining < 3600.0 {
-                                    format!("ETR: {:.1}m", remaining / 60.0)
-                                } else {
-                                    format!("ETR: {:.1}h", remaining / 3600.0)
-                                }
-                            } else {
-                                "ETR: --".to_string()
-                            };
-
-                            let _ = status_tx_clone.send(StatusEvent::with_context(
-                                StatusEventType::Message,
-                                &format!(
-                                    "Depth {} - batch {}/{}... ({})",
-                                    depth,
-                                    batch_idx + 1,
-                                    num_batchs,
-                                    etr_str
-                                ),
-                                progress,
-                                None,
-                                depth as u32,
-                            ));
-                        }
-                    }
-                });
-            });
+                let mut result = BatchCaseResult::new(case_number, setup_moves);
+                result.optimal_length = case_idx.and_then(|i| optimal_length(&optimal_depths[i]));
+                result.best_solution = solutions.first().cloned();
+                result.solutions = solutions;
+                result.solve_time = elapsed;
+
+                notified.insert(case_number);
+                cb(result);
+            }
         }
+        notified
+    })
+}
 
-        let depth_elapsed = depth_start_time.elapsed().as_secs_f64();
+/// Search the active cases through `depth`, at most `max_concurrent` at a time.
+fn search_depth(
+    run: &BatchRun,
+    setup: &SearchSetup,
+    sinks: &SearchSinks,
+    depth: usize,
+    active_case_indices: &[usize],
+    max_concurrent: usize,
+) {
+    let config = run.config;
+    let num_batchs = active_case_indices.len().div_ceil(max_concurrent);
+
+    run.event(StatusEvent::with_context(
+        StatusEventType::StartDepth,
+        &format!(
+            "Depth {}: {} active cases, {} concurrent ({} batchs)",
+            depth,
+            active_case_indices.len(),
+            max_concurrent,
+            num_batchs,
+        ),
+        0.0,
+        None,
+        depth as u32,
+    ));
 
-        fire_event(
-            &status_callback,
-            StatusEvent::with_context(
-                StatusEventType::EndDepth,
-                &format!("Finished depth {} in {:.1}s", depth, depth_elapsed),
-                1.0,
-                None,
-                depth as u32,
+    let report_solutions = config.ranking.is_none() && config.dedup == DedupMode::Off;
+
+    for (batch_idx, batch_chunk) in active_case_indices.chunks(max_concurrent).enumerate() {
+        if sinks.interrupt.load(Ordering::SeqCst) {
+            break;
+        }
+
+        let batch_active: Vec<usize> = batch_chunk
+            .iter()
+            .copied()
+            .filter(|&i| !config.stop_after_first || !run.cases[i].solved.load(Ordering::Relaxed))
+            .collect();
+
+        if batch_active.is_empty() {
+            continue;
+        }
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(config.memory_config.search_threads)
+            .build()
+            .unwrap();
+
+        let total_work = batch_active.len() * setup.moves.len();
+        let completed_work = AtomicUsize::new(0);
+        let batch_start_time = std::time::Instant::now();
+
+        let _ = sinks.status_tx.send(StatusEvent::with_context(
+            StatusEventType::Message,
+            &format!(
+                "Depth {} - batch {}/{} ({} cases)",
+                depth,
+                batch_idx + 1,
+                num_batchs,
+                batch_active.len(),
             ),
-        );
+            0.0,
+            None,
+            depth as u32,
+        ));
 
-        let rss_after = get_current_rss_bytes();
-        if rss_before > 0 && rss_after > 0 {
-            let budget_bytes =
-                (config.memory_config.total_budget_bytes as f64 * UPPER_BOUND) as usize;
-
-            if rss_after > budget_bytes && current_max_concurrent > MIN_CONCURRENT_CASES {
-                let ratio = budget_bytes as f64 / rss_after as f64;
-                let adjusted =
-                    ((current_max_concurrent as f64 * ratio) as usize).max(MIN_CONCURRENT_CASES);
-                if adjusted != current_max_concurrent {
-                    fire_event(
-                        &status_callback,
-                        StatusEvent::new(
-                            StatusEventType::MemoryWarning,
-                            &format!(
-                                "Reducing concurrency {} -> {} (RSS: {} MB, budget: {} MB)",
-                                current_max_concurrent,
-                                adjusted,
-                                rss_after / (1024 * 1024),
-                                budget_bytes / (1024 * 1024),
-                            ),
-                            0.0,
-                        ),
-                    );
-                    current_max_concurrent = adjusted;
-                }
-            } else if rss_after < budget_bytes / 2 && current_max_concurrent < total_cases {
-                let headroom = budget_bytes as f64 / rss_after.max(1) as f64;
-                let adjusted = ((current_max_concurrent as f64 * headroom * 0.8) as usize)
-                    .min(total_cases)
-                    .max(current_max_concurrent);
-                if adjusted > current_max_concurrent {
-                    fire_event(
-                        &status_callback,
-                        StatusEvent::new(
+        pool.install(|| {
+            batch_active.par_iter().for_each(|&case_idx| {
+                let case = &run.cases[case_idx];
+
+                for &first_move in &setup.moves {
+                    if sinks.interrupt.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    if config.stop_after_first && case.solved.load(Ordering::Relaxed) {
+                        completed_work.fetch_add(1, Ordering::Relaxed);
+                        continue;
+                    }
+
+                    let mut minx = case.start.clone();
+                    minx.apply_move(first_move);
+
+                    let all_pruners = config.search_mode.create_pruners();
+                    let local_pruners: Vec<&dyn Pruner> = setup
+                        .pruner_indices
+                        .iter()
+                        .filter_map(|&i| all_pruners.get(i).map(|p| p.as_ref()))
+                        .collect();
+
+                    let ctx = SearchContext {
+                        tables: &setup.tables,
+                        pruners: &local_pruners,
+                        first_moves: &setup.first_moves,
+                        next_siblings: &setup.next_siblings,
+                        interrupted: sinks.interrupt,
+                        solution_tx: &sinks.solution_tx,
+                        status_tx: &sinks.status_tx,
+                        case_number: case.case_number,
+                        case_solved: &case.solved,
+                        case_optimal_depth: &run.optimal_depths[case_idx],
+                        stop_after_first: config.stop_after_first,
+                        search_mode: config.search_mode,
+                        journal: sinks.journal,
+                        mcc_params: config.mcc_params.as_ref(),
+                        report_solutions,
+                        filters: &config.filters,
+                        verify_start: config.verify_solutions.then_some(&case.start),
+                        found: sinks.found[case_idx].as_ref(),
+                    };
+
+                    search_branch(&mut minx, &case.goal, depth, &ctx);
+
+                    let done = completed_work.fetch_add(1, Ordering::Relaxed) + 1;
+                    let progress = done as f64 / total_work as f64;
+                    let elapsed = batch_start_time.elapsed().as_secs_f64();
+
+                    if done.is_multiple_of(50) || done == total_work {
+                        let etr_str = if progress > 0.005 && elapsed > 0.5 {
+                            let total_estimated = elapsed / progress;
+                            let remaining = total_estimated - elapsed;
+                            if remaining < 60.0 {
+                                format!("ETR: {:.1}s", remaining)
+                            } else if remaining < 3600.0 {
+                                format!("ETR: {:.1}m", remaining / 60.0)
+                            } else {
+                                format!("ETR: {:.1}h", remaining / 3600.0)
+                            }
+                        } else {
+                            "ETR: --".to_string()
+                        };
+
+                        let _ = sinks.status_tx.send(StatusEvent::with_context(
                             StatusEventType::Message,
                             &format!(
-                                "Increasing concurrency {} -> {} (RSS: {} MB, budget: {} MB)",
-                                current_max_concurrent,
-                                adjusted,
-                                rss_after / (1024 * 1024),
-                                budget_bytes / (1024 * 1024),
+                                "Depth {} - batch {}/{}... ({})",
+                                depth,
+                                batch_idx + 1,
+                                num_batchs,
+                                etr_str
                             ),
-                            0.0,
-                        ),
-                    );
-                    current_max_concurrent = adjusted;
+                            progress,
+                            None,
+                            depth as u32,
+                        ));
+                    }
                 }
-            }
-        }
+            });
+        });
     }
+}
 
-    drop(solution_tx);
-    drop(status_tx);
-    let _ = solution_thread.join();
-    let _ = status_thread.join();
-
-    let elapsed = start_time.elapsed().as_secs_f64();
-    let already_notified = notified_cases.lock().unwrap().clone();
-    let final_solutions = case_solutions.lock().unwrap();
-
-    for case in &cases {
-        if already_notified.contains(&case.case_number) {
-            let solutions = final_solutions
-                .get(&case.case_number)
-                .cloned()
-                .unwrap_or_default();
-            let mut result = BatchCaseResult::new(case.case_number, case.setup_moves.clone());
-            result.best_solution = solutions.first().cloned();
-            result.solutions = solutions;
-            result.solve_time = elapsed;
-            results.add_result(result);
-            continue;
-        }
-
-        let solutions = final_solutions
-            .get(&case.case_number)
-            .cloned()
-            .unwrap_or_default();
-        let mut result = BatchCaseResult::new(case.case_number, case.setup_moves.clone());
-        result.best_solution = solutions.first().cloned();
-        result.solutions = solutions;
-        result.solve_time = elapsed;
-
-        if let Some(ref callback) = case_solved_callback {
-            callback(result.clone());
-        }
-
-        results.add_result(result);
+fn optimal_length(depth: &AtomicUsize) -> Option<usize> {
+    match depth.load(Ordering::SeqCst) {
+        0 => None,
+        d => Some(d),
     }
+}
 
-    results.total_time = elapsed;
-    results.average_time_per_case = if results.case_results.is_empty() {
-        0.0
-    } else {
-        elapsed / results.case_results.len() as f64
-    };
-
-    fire_event(
-        &status_callback,
-        StatusEvent::new(
-            StatusEventType::FinishSearch,
-            &format!(
-                "Batch solve complete. Solved {}/{} cases in {:.2}s",
-                results.solved_cases, total_cases, elapsed
-            ),
-            1.0,
-        ),
-    );
+/// Whether a cached entry leaves nothing to search for its case.
+fn cache_finishes(cached: &CachedResult, config: &BatchSolverConfig) -> bool {
+    let max_depth = config.max_search_depth;
+    cached.covers(max_depth, config.depth_slack, &config.filters)
+        || (config.stop_after_first && cached.optimal_depth(&config.filters).is_some())
+}
 
-    results
+fn within_depth_slack(optimal: &AtomicUsize, depth: usize, slack: Option<usize>) -> bool {
+    match (optimal_length(optimal), slack) {
+        (Some(optimal), Some(slack)) => depth <= optimal + slack,
+        _ => true,
+    }
 }
 
 fn calculate_max_concurrent(
@@ -569,11 +1103,9 @@ fn calculate_max_concurrent(
     let thread_overhead = PER_THREAD_STACK_BYTES * num_threads;
     let effective_budget = search_budget.saturating_sub(thread_overhead);
 
-    let max_concurrent = if per_case_bytes > 0 {
-        (effective_budget / per_case_bytes).max(MIN_CONCURRENT_CASES)
-    } else {
-        total_cases
-    };
+    let max_concurrent = effective_budget
+        .checked_div(per_case_bytes)
+        .map_or(total_cases, |n| n.max(MIN_CONCURRENT_CASES));
 
     max_concurrent.min(total_cases).max(MIN_CONCURRENT_CASES)
 }
@@ -630,21 +1162,23 @@ fn build_cases(
         .collect()
 }
 
+/// Whether the pruner only tracks pieces the batch ignores.
+fn is_dominated(pruner: &dyn Pruner, config: &BatchSolverConfig) -> bool {
+    (pruner.uses_corner_permutation() && config.ignore_corner_permutation)
+        || (pruner.uses_edge_permutation() && config.ignore_edge_permutation)
+        || (pruner.uses_corner_orientation() && config.ignore_corner_orientation)
+        || (pruner.uses_edge_orientation() && config.ignore_edge_orientation)
+}
+
 fn filter_pruning_tables<'a>(
     solver: &'a Solver,
     config: &BatchSolverConfig,
-) -> Vec<(Arc<Vec<u8>>, &'a dyn Pruner)> {
+) -> Vec<(Arc<PruningTable>, &'a dyn Pruner)> {
     solver
         .get_pruners()
         .iter()
         .enumerate()
-        .filter(|(_, pruner)| {
-            let dominated = (pruner.uses_corner_permutation() && config.ignore_corner_permutation)
-                || (pruner.uses_edge_permutation() && config.ignore_edge_permutation)
-                || (pruner.uses_corner_orientation() && config.ignore_corner_orientation)
-                || (pruner.uses_edge_orientation() && config.ignore_edge_orientation);
-            !dominated
-        })
+        .filter(|(_, pruner)| !is_dominated(pruner.as_ref(), config))
         .filter_map(|(i, pruner)| {
             solver
                 .get_tables()
@@ -667,19 +1201,13 @@ fn search_branch(minx: &mut LLMinx, goal: &LLMinx, target_depth: usize, ctx: &Se
 
         if minx.state_equals(goal) {
             if levels_left == 0 && Solver::check_optimal(minx) {
-                let msg = format!(
-                    "{} ({},{})",
-                    minx.get_generating_moves(),
-                    minx.get_ftm_length(),
-                    minx.get_fftm_length()
-                );
-                let _ = ctx.solution_tx.send((ctx.case_number, msg.clone()));
-                let _ = ctx.status_tx.send(StatusEvent::new(
-                    StatusEventType::SolutionFound,
-                    &format!("Case {}: {}", ctx.case_number, msg),
-                    0.0,
-                ));
-                ctx.case_solved.store(true, Ordering::SeqCst);
+                let solution = Solution::from_minx(minx, ctx.search_mode);
+                if let Some(found) = ctx.found {
+                    found.lock().unwrap().push(solution.clone());
+                }
+                if accepts_all(ctx.filters, &solution) {
+                    deliver(solution, goal, target_depth, ctx);
+                }
             }
             stop = Solver::back_track(minx, ctx.next_siblings);
         } else if levels_left > 0 {
@@ -687,7 +1215,7 @@ fn search_branch(minx: &mut LLMinx, goal: &LLMinx, target_depth: usize, ctx: &Se
             for (table_idx, pruner) in ctx.pruners.iter().enumerate() {
                 if let Some(table) = ctx.tables.get(table_idx) {
                     let coord = pruner.get_coordinate(minx);
-                    if coord < table.len() && table[coord] as usize > levels_left {
+                    if coord < table.len() && table.get(coord) as usize > levels_left {
                         pruned = true;
                         break;
                     }
@@ -705,6 +1233,52 @@ fn search_branch(minx: &mut LLMinx, goal: &LLMinx, target_depth: usize, ctx: &Se
     }
 }
 
+/// Score, verify and report a solution the filters accept.
+fn deliver(mut solution: Solution, goal: &LLMinx, target_depth: usize, ctx: &SearchContext) {
+    if let Some(event) = score_and_verify(
+        &mut solution,
+        ctx.mcc_params,
+        ctx.verify_start,
+        goal,
+        ctx.case_number,
+    ) {
+        let _ = ctx.status_tx.send(event);
+    }
+    if ctx
+        .case_optimal_depth
+        .compare_exchange(0, target_depth, Ordering::SeqCst, Ordering::SeqCst)
+        .is_ok()
+    {
+        let _ = ctx.status_tx.send(StatusEvent::with_context(
+            StatusEventType::OptimalDepthFound,
+            &format!(
+                "Case {}: optimal solution length {}",
+                ctx.case_number, target_depth
+            ),
+            0.0,
+            None,
+            target_depth as u32,
+        ));
+    }
+    let event = ctx.report_solutions.then(|| {
+        StatusEvent::solution_found(
+            &format!("Case {}: {}", ctx.case_number, solution),
+            solution.clone(),
+        )
+    });
+    if let Some(journal) = ctx.journal {
+        let _ = journal
+            .lock()
+            .unwrap()
+            .record_solution(ctx.case_number, &solution);
+    }
+    let _ = ctx.solution_tx.send((ctx.case_number, solution));
+    if let Some(event) = event {
+        let _ = ctx.status_tx.send(event);
+    }
+    ctx.case_solved.store(true, Ordering::SeqCst);
+}
+
 fn fire_event(callback: &Option<StatusCallback>, event: StatusEvent) {
     if let Some(cb) = callback {
         cb(event);
@@ -913,12 +1487,420 @@ mod tests {
         assert!(solved_count.load(Ordering::Relaxed) > 0);
     }
 
+    #[test]
+    fn test_solve_batch_scores_solutions_with_mcc_params() {
+        let mut one_move = LLMinx::new();
+        one_move.apply_move(Move::R);
+        let states = vec![GeneratedState {
+            state: one_move,
+            setup_moves: "R".to_string(),
+            case_number: 1,
+        }];
+        let params = crate::mcc::MCCPreset::BigCube.params();
+        let config = BatchSolverConfig {
+            max_search_depth: 2,
+            stop_after_first: true,
+            memory_config: MemoryConfig::new(128, 1, 1),
+            mcc_params: Some(params),
+            ..BatchSolverConfig::default()
+        };
+
+        let interrupt = Arc::new(AtomicBool::new(false));
+        let results = solve_batch_states(states, &config, None, interrupt, None, None);
+        let solution = &results.case_results[0].solutions[0];
+        assert_eq!(
+            solution.mcc,
+            Some(crate::mcc::calculate_mcc_with_params(
+                &solution.algorithm(),
+                &params
+            ))
+        );
+    }
+
+    #[test]
+    fn test_solve_batch_keeps_top_ranked_solutions() {
+        let states = vec![GeneratedState {
+            state: LLMinx::new(),
+            setup_moves: String::new(),
+            case_number: 1,
+        }];
+        // Ignoring the whole last layer makes every U turn a solution.
+        let config = BatchSolverConfig {
+            max_search_depth: 1,
+            memory_config: MemoryConfig::new(128, 1, 1),
+            ignore_corner_permutation: true,
+            ignore_edge_permutation: true,
+            ignore_corner_orientation: true,
+            ignore_edge_orientation: true,
+            ranking: Some(SolutionRanking::new(crate::ranking::RankScore::Fftm, 2)),
+            ..BatchSolverConfig::default()
+        };
+
+        let interrupt = Arc::new(AtomicBool::new(false));
+        let results = solve_batch_states(states, &config, None, interrupt, None, None);
+        let case = &results.case_results[0];
+        assert_eq!(case.solutions.len(), 2);
+        assert!(case.solutions.iter().all(|s| s.fftm == 1));
+        assert_eq!(case.best_solution.as_ref(), case.solutions.first());
+    }
+
+    #[test]
+    fn test_solve_batch_verifies_solutions() {
+        let states = vec![GeneratedState {
+            state: LLMinx::from_alg("U").unwrap(),
+            setup_moves: "U".to_string(),
+            case_number: 1,
+        }];
+        let config = BatchSolverConfig {
+            max_search_depth: 1,
+            memory_config: MemoryConfig::new(128, 1, 1),
+            verify_solutions: true,
+            ..BatchSolverConfig::default()
+        };
+
+        let interrupt = Arc::new(AtomicBool::new(false));
+        let results = solve_batch_states(states, &config, None, interrupt, None, None);
+        let case = &results.case_results[0];
+        assert_eq!(case.solutions.len(), 1);
+        assert_eq!(case.solutions[0].moves, vec![Move::Ui]);
+        assert_eq!(case.solutions[0].verified, Some(true));
+    }
+
+    #[test]
+    fn test_solve_batch_dedups_auf_variants() {
+        let states = vec![GeneratedState {
+            state: LLMinx::new(),
+            setup_moves: String::new(),
+            case_number: 1,
+        }];
+        // Ignoring the whole last layer makes every U turn a solution, and
+        // those differ only in AUF.
+        let config = BatchSolverConfig {
+            max_search_depth: 1,
+            memory_config: MemoryConfig::new(128, 1, 1),
+            ignore_corner_permutation: true,
+            ignore_edge_permutation: true,
+            ignore_corner_orientation: true,
+            ignore_edge_orientation: true,
+            dedup: DedupMode::CommutingAndAuf,
+            ..BatchSolverConfig::default()
+        };
+
+        let interrupt = Arc::new(AtomicBool::new(false));
+        let all = solve_batch_states(
+            states.clone(),
+            &BatchSolverConfig {
+                dedup: DedupMode::Off,
+                ..config.clone()
+            },
+            None,
+            Arc::clone(&interrupt),
+            None,
+            None,
+        );
+        let results = solve_batch_states(states, &config, None, interrupt, None, None);
+        let case = &results.case_results[0];
+        assert!(all.case_results[0].solutions.len() > 1);
+        assert_eq!(case.solutions.len(), 1);
+        assert_eq!(
+            case.solutions[0].merged_variants,
+            all.case_results[0].solutions.len() - 1
+        );
+    }
+
+    #[test]
+    fn test_solve_batch_applies_filters() {
+        let states = vec![GeneratedState {
+            state: LLMinx::new(),
+            setup_moves: String::new(),
+            case_number: 1,
+        }];
+        // Ignoring the whole last layer makes every U turn a solution.
+        let config = BatchSolverConfig {
+            max_search_depth: 1,
+            memory_config: MemoryConfig::new(128, 1, 1),
+            ignore_corner_permutation: true,
+            ignore_edge_permutation: true,
+            ignore_corner_orientation: true,
+            ignore_edge_orientation: true,
+            filters: vec![Arc::new(
+                crate::filter::ForbiddenSequence::new(vec![Move::U]).unwrap(),
+            )],
+            ..BatchSolverConfig::default()
+        };
+
+        let interrupt = Arc::new(AtomicBool::new(false));
+        let results = solve_batch_states(states, &config, None, interrupt, None, None);
+        let case = &results.case_results[0];
+        assert!(!case.solutions.is_empty());
+        assert!(case.solutions.iter().all(|s| s.moves != [Move::U]));
+    }
+
+    #[test]
+    fn test_resume_batch_skips_journaled_cases() {
+        let state = |m: Move, case_number: usize| {
+            let mut minx = LLMinx::new();
+            minx.apply_move(m);
+            GeneratedState {
+                state: minx,
+                setup_moves: String::from(m.to_string()),
+                case_number,
+            }
+        };
+        let config = BatchSolverConfig {
+            max_search_depth: 2,
+            stop_after_first: true,
+            memory_config: MemoryConfig::new(128, 1, 1),
+            ..BatchSolverConfig::default()
+        };
+        let path =
+            std::env::temp_dir().join(format!("llminx_resume_batch_{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let states = || vec![state(Move::R, 1), state(Move::U, 2)];
+
+        // A killed run that finished case 1 only.
+        let journaled = vec![Solution::from_moves(&[Move::Ri], SearchMode::RU)];
+        {
+            let mut journal =
+                BatchJournal::create(&path, config.journal_hash(&states(), None)).unwrap();
+            journal.record_solution(1, &journaled[0]).unwrap();
+            journal.record_depth(1, &[1]).unwrap();
+            journal.record_complete(1).unwrap();
+        }
+
+        let searched = Arc::new(Mutex::new(Vec::new()));
+        let searched_clone = Arc::clone(&searched);
+        let status_cb: Option<StatusCallback> = Some(Arc::new(move |event| {
+            if event.event_type == StatusEventType::SolutionFound {
+                searched_clone.lock().unwrap().push(event.message);
+            }
+        }));
+        let second = resume_batch_states(
+            states(),
+            &config,
+            None,
+            &path,
+            Arc::new(AtomicBool::new(false)),
+            status_cb,
+            None,
+        )
+        .unwrap();
+
+        let searched = searched.lock().unwrap();
+        assert!(!searched.is_empty());
+        assert!(
+            searched
+                .iter()
+                .all(|message| message.starts_with("Case 2:"))
+        );
+        assert_eq!(second.solved_cases, 2);
+        let case_one = second.case_results.iter().find(|r| r.case_number == 1);
+        assert_eq!(case_one.unwrap().solutions, journaled);
+
+        let journal = BatchJournal::open(&path, config.journal_hash(&states(), None)).unwrap();
+        assert_eq!(journal.completed_cases(), vec![1, 2]);
+
+        let other = BatchSolverConfig {
+            stop_after_first: false,
+            ..config.clone()
+        };
+        assert!(
+            resume_batch_states(
+                states(),
+                &other,
+                None,
+                &path,
+                Arc::new(AtomicBool::new(false)),
+                None,
+                None,
+            )
+            .is_err()
+        );
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_resume_batch_rejects_other_scramble() {
+        use crate::batch_solver::generator::{GeneratorConfig, generate_batch_states};
+
+        let generate = |scramble: &str| {
+            let config = GeneratorConfig {
+                scramble: scramble.to_string(),
+                num_threads: 1,
+                ..GeneratorConfig::default()
+            };
+            generate_batch_states(&config, None, None).unwrap()
+        };
+        let config = BatchSolverConfig {
+            max_search_depth: 2,
+            stop_after_first: true,
+            memory_config: MemoryConfig::new(128, 1, 1),
+            ..BatchSolverConfig::default()
+        };
+        let path = std::env::temp_dir().join(format!(
+            "llminx_resume_scramble_{}.jsonl",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        let (states, equivalence) = generate("[R U R' U R U2' R', R U2 R' U' R U' R']");
+        BatchJournal::create(&path, config.journal_hash(&states, equivalence.as_deref())).unwrap();
+
+        let (other, other_equivalence) = generate("[R U R' U R U2' R', R U R' U']");
+        let resumed = resume_batch_states(
+            other,
+            &config,
+            other_equivalence.as_ref(),
+            &path,
+            Arc::new(AtomicBool::new(false)),
+            None,
+            None,
+        );
+        assert!(resumed.is_err());
+
+        let resumed = resume_batch_states(
+            states,
+            &config,
+            equivalence.as_ref(),
+            &path,
+            Arc::new(AtomicBool::new(false)),
+            None,
+            None,
+        );
+        assert_eq!(resumed.unwrap().total_cases, 2);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_solve_batch_replays_and_extends_result_cache() {
+        use crate::result_cache::CachePolicy;
+
+        let dir =
+            std::env::temp_dir().join(format!("llminx_batch_result_cache_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = Arc::new(ResultCache::open(&dir, CachePolicy::default()).unwrap());
+        let states = || {
+            [Move::R, Move::U]
+                .into_iter()
+                .enumerate()
+                .map(|(i, m)| {
+                    let mut minx = LLMinx::new();
+                    minx.apply_move(m);
+                    GeneratedState {
+                        state: minx,
+                        setup_moves: String::from(m.to_string()),
+                        case_number: i + 1,
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+        let config = BatchSolverConfig {
+            max_search_depth: 2,
+            memory_config: MemoryConfig::new(128, 1, 1),
+            result_cache: Some(Arc::clone(&cache)),
+            ..BatchSolverConfig::default()
+        };
+        let solve = |config: &BatchSolverConfig| {
+            let depths = Arc::new(Mutex::new(Vec::new()));
+            let depths_clone = Arc::clone(&depths);
+            let status_cb: Option<StatusCallback> = Some(Arc::new(move |event| {
+                if event.event_type == StatusEventType::StartDepth {
+                    depths_clone.lock().unwrap().push(event.current_depth);
+                }
+            }));
+            let interrupt = Arc::new(AtomicBool::new(false));
+            let results = solve_batch_states(states(), config, None, interrupt, status_cb, None);
+            let depths = depths.lock().unwrap().clone();
+            (results, depths)
+        };
+        let solutions = |results: &BatchResults| {
+            results
+                .case_results
+                .iter()
+                .map(|r| r.solutions.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let (first, depths) = solve(&config);
+        assert_eq!(depths, vec![1, 2]);
+        assert_eq!(first.solved_cases, 2);
+        assert_eq!(cache.len(), 2);
+
+        let (replayed, depths) = solve(&config);
+        assert!(depths.is_empty());
+        assert_eq!(solutions(&replayed), solutions(&first));
+
+        let deeper = BatchSolverConfig {
+            max_search_depth: 3,
+            ..config.clone()
+        };
+        let (extended, depths) = solve(&deeper);
+        assert_eq!(depths, vec![3]);
+        assert_eq!(extended.solved_cases, 2);
+        let key = CacheKey::new(&states()[0].state, SearchMode::RU, Metric::Fifth);
+        assert_eq!(cache.get(&key).unwrap().searched_depth, 3);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_solve_batch_states_depth_slack_reports_optimal_length() {
+        let mut one_move = LLMinx::new();
+        one_move.apply_move(Move::R);
+
+        let states = vec![GeneratedState {
+            state: one_move,
+            setup_moves: "R".to_string(),
+            case_number: 1,
+        }];
+
+        let config = BatchSolverConfig {
+            max_search_depth: 4,
+            depth_slack: Some(0),
+            memory_config: MemoryConfig::new(128, 1, 1),
+            ..BatchSolverConfig::default()
+        };
+
+        let last_depth = Arc::new(AtomicUsize::new(0));
+        let optimal_events = Arc::new(AtomicUsize::new(0));
+        let last_depth_clone = Arc::clone(&last_depth);
+        let optimal_events_clone = Arc::clone(&optimal_events);
+        let status_cb: Option<StatusCallback> =
+            Some(Arc::new(move |event| match event.event_type {
+                StatusEventType::EndDepth => {
+                    last_depth_clone.store(event.current_depth as usize, Ordering::SeqCst)
+                }
+                StatusEventType::OptimalDepthFound => {
+                    optimal_events_clone.fetch_add(1, Ordering::SeqCst);
+                }
+                _ => {}
+            }));
+
+        let interrupt = Arc::new(AtomicBool::new(false));
+        let results = solve_batch_states(states, &config, None, interrupt, status_cb, None);
+        assert_eq!(results.case_results[0].optimal_length, Some(1));
+        assert_eq!(last_depth.load(Ordering::SeqCst), 1);
+        assert_eq!(optimal_events.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_within_depth_slack() {
+        let unsolved = AtomicUsize::new(0);
+        assert!(within_depth_slack(&unsolved, 9, Some(0)));
+
+        let optimal = AtomicUsize::new(3);
+        assert!(within_depth_slack(&optimal, 5, Some(2)));
+        assert!(!within_depth_slack(&optimal, 6, Some(2)));
+        assert!(within_depth_slack(&optimal, 6, None));
+    }
+
     #[test]
     fn test_search_branch_stop_after_first_early_return() {
-        let (solution_tx, _solution_rx) = crossbeam_channel::unbounded::<(usize, String)>();
+        let (solution_tx, _solution_rx) = crossbeam_channel::unbounded::<(usize, Solution)>();
         let (status_tx, _status_rx) = crossbeam_channel::unbounded::<StatusEvent>();
         let interrupted = Arc::new(AtomicBool::new(false));
         let solved = AtomicBool::new(true);
+        let optimal = AtomicUsize::new(0);
 
         let ctx = SearchContext {
             tables: &[],
@@ -930,7 +1912,15 @@ mod tests {
             status_tx: &status_tx,
             case_number: 1,
             case_solved: &solved,
+            case_optimal_depth: &optimal,
             stop_after_first: true,
+            search_mode: SearchMode::RU,
+            journal: None,
+            mcc_params: None,
+            report_solutions: true,
+            filters: &[],
+            verify_start: None,
+            found: None,
         };
 
         let mut minx = LLMinx::new();
@@ -940,10 +1930,11 @@ mod tests {
 
     #[test]
     fn test_search_branch_solution_found_sets_flag() {
-        let (solution_tx, solution_rx) = crossbeam_channel::unbounded::<(usize, String)>();
+        let (solution_tx, solution_rx) = crossbeam_channel::unbounded::<(usize, Solution)>();
         let (status_tx, status_rx) = crossbeam_channel::unbounded::<StatusEvent>();
         let interrupted = Arc::new(AtomicBool::new(false));
         let solved = AtomicBool::new(false);
+        let optimal = AtomicUsize::new(0);
 
         let first_moves = [Move::R];
         let next_siblings = vec![vec![None; Move::D2i as usize + 1]; Move::D2i as usize + 2];
@@ -958,7 +1949,15 @@ mod tests {
             status_tx: &status_tx,
             case_number: 42,
             case_solved: &solved,
+            case_optimal_depth: &optimal,
             stop_after_first: false,
+            search_mode: SearchMode::RU,
+            journal: None,
+            mcc_params: None,
+            report_solutions: true,
+            filters: &[],
+            verify_start: None,
+            found: None,
         };
 
         let mut minx = LLMinx::new();
@@ -971,13 +1970,14 @@ mod tests {
 
     #[test]
     fn test_search_branch_pruned_path_backtracks() {
-        let (solution_tx, _solution_rx) = crossbeam_channel::unbounded::<(usize, String)>();
+        let (solution_tx, _solution_rx) = crossbeam_channel::unbounded::<(usize, Solution)>();
         let (status_tx, _status_rx) = crossbeam_channel::unbounded::<StatusEvent>();
         let interrupted = Arc::new(AtomicBool::new(false));
         let solved = AtomicBool::new(false);
+        let optimal = AtomicUsize::new(0);
         let dummy: &dyn Pruner = &DummyPruner;
         let pruners = vec![dummy];
-        let table = Arc::new(vec![2u8]);
+        let table = Arc::new(PruningTable::from(vec![2u8]));
         let tables = vec![table];
 
         let first_moves = [Move::R];
@@ -993,7 +1993,15 @@ mod tests {
             status_tx: &status_tx,
             case_number: 1,
             case_solved: &solved,
+            case_optimal_depth: &optimal,
             stop_after_first: false,
+            search_mode: SearchMode::RU,
+            journal: None,
+            mcc_params: None,
+            report_solutions: true,
+            filters: &[],
+            verify_start: None,
+            found: None,
         };
 
         let mut minx = LLMinx::new();
@@ -1,6 +1,7 @@
 //! Core types for batch solving functionality
 
 use crate::minx::LLMinx;
+use crate::solution::Solution;
 use std::collections::HashMap;
 use std::fmt;
 
@@ -277,9 +278,11 @@ pub struct BatchCaseResult {
     /// Setup moves for this case
     pub setup_moves: String,
     /// All solutions found for this case
-    pub solutions: Vec<String>,
+    pub solutions: Vec<Solution>,
     /// Best solution (first one, or shortest if sorted)
-    pub best_solution: Option<String>,
+    pub best_solution: Option<Solution>,
+    /// Search depth of the shortest solution found, if any
+    pub optimal_length: Option<usize>,
     /// Time taken to solve this case (in seconds)
     pub solve_time: f64,
 }
@@ -292,6 +295,7 @@ impl BatchCaseResult {
             setup_moves,
             solutions: Vec::new(),
             best_solution: None,
+            optimal_length: None,
             solve_time: 0.0,
         }
     }
@@ -457,6 +461,8 @@ impl NormalizedState {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::minx::Move;
+    use crate::search_mode::SearchMode;
 
     #[test]
     fn test_case_modifiers() {
@@ -670,7 +676,9 @@ mod tests {
         assert!(!result.is_solved());
         assert_eq!(result.case_number, 1);
 
-        result.solutions.push("R' U'".to_string());
+        result
+            .solutions
+            .push(Solution::from_moves(&[Move::Ri, Move::Ui], SearchMode::RU));
         assert!(result.is_solved());
     }
 
@@ -681,7 +689,9 @@ mod tests {
         assert_eq!(results.solved_cases, 0);
 
         let mut case1 = BatchCaseResult::new(1, "R".to_string());
-        case1.solutions.push("R'".to_string());
+        case1
+            .solutions
+            .push(Solution::from_moves(&[Move::Ri], SearchMode::RU));
         case1.solve_time = 1.0;
         results.add_result(case1);
 
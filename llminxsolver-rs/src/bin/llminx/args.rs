@@ -0,0 +1,729 @@
+use llminxsolver_rs::{
+    CachePolicy, DEFAULT_PRUNING_DEPTH, DedupMode, ForbiddenSequence, LLMinx, MAX_PRUNING_DEPTH,
+    MCCParams, MCCPreset, MIN_PRUNING_DEPTH, MaxFaceTurns, MegaminxState, MemoryConfig, Metric,
+    NoAuf, NotationRegex, RankScore, ResultCache, SearchMode, SharedFilter, SolutionRanking,
+    parse_alg, validate_last_layer_state,
+};
+use std::sync::Arc;
+
+pub const USAGE: &str = "\
+Usage: llminx <COMMAND> [OPTIONS]
+
+Commands:
+  solve      Solve a single state with one search mode
+  parallel   Solve a single state with several search modes at once
+  batch      Generate and solve every case of a batch scramble
+  prebuild   Build the pruning tables for one or more search modes
+  help       Print this message
+
+State options (solve, parallel):
+  --scramble <MOVES>              Moves applied to a solved last layer, e.g. \"R U R' U'\"
+  --alg <MOVES>                   Solve the case this algorithm solves (its inverse)
+  --corners <P,P,P,P,P>           Last layer corner positions
+  --corner-orientations <O,...>   Last layer corner orientations
+  --edges <P,P,P,P,P>             Last layer edge positions
+  --edge-orientations <O,...>     Last layer edge orientations
+
+Batch options:
+  --scramble <SCRAMBLE>           Batch scramble, e.g. \"[U] R U R' [U]\"
+  --equivalences <SPEC>           Piece equivalences, e.g. \"{UC1 UC2}\"
+  --pre-adjust <MOVES,...>        Comma-separated pre-adjust sequences
+  --post-adjust <MOVES,...>       Comma-separated post-adjust sequences
+  --stop-after-first              Stop each case after its first solution
+  --journal <PATH>                Checkpoint progress to PATH and resume from it if it exists
+  --per-case                      Export every solution of every case, not just the best
+  --verify-solutions              Replay every solution and report any that miss the goal
+  --known-algs <PATH>             Skip cases already solved by an alg in PATH, one
+                                  \"name: alg\" or bare alg per line
+
+Search options:
+  -m, --mode <MODE[,MODE...]>     RU, RUF, RUL, RUFL, RUFLbL, RUbL, RUbR, RUD (default: RU),
+                                  or any other face set such as RUFbR
+  --metric <fifth|face>           Search metric (default: fifth)
+  -d, --depth <N>                 Maximum search depth (default: 12)
+  --slack <N>                     Stop N moves past the optimal solution length
+  -p, --pruning-depth <N>         Pruning table depth (default: 12)
+  --ignore-corner-positions       Ignore last layer corner positions
+  --ignore-edge-positions         Ignore last layer edge positions
+  --ignore-corner-orientations    Ignore last layer corner orientations
+  --ignore-edge-orientations      Ignore last layer edge orientations
+
+Filter options (repeatable):
+  --no-auf                        Drop solutions that start or end with a U turn
+  --max-face <FACE=N>             Drop solutions turning FACE more than N times, e.g. F=1
+  --forbid <MOVES>                Drop solutions containing MOVES in a row
+  --reject-regex <REGEX>          Drop solutions whose notation matches REGEX
+  --require-regex <REGEX>         Keep only solutions whose notation matches REGEX
+  --dedup <off|commuting|auf>     Merge solutions that only reorder commuting moves,
+                                  or that also differ in AUF (default: off)
+
+Scoring options:
+  --mcc-preset <NAME>             default, one-handed or big-cube (default: default)
+  --mcc-params <PATH>             JSON file of MCC multipliers, overrides --mcc-preset
+  --top <K>                       Keep only the K best solutions (per case for batch)
+  --rank-by <SCORE>               mcc, ftm, fftm or blend:MCC,FTM,FFTM weights (default: mcc)
+
+General options:
+  --data-dir <DIR>                Directory for pruning tables (default: current directory)
+  --cache                         Reuse search results cached in the data directory and
+                                  cache new ones
+  --memory-mb <N>                 Memory budget in MB
+  --threads <N>                   Number of worker threads
+  -f, --format <FORMAT>           text, json, xlsx, or csv for batch (default: text)
+  -o, --output <PATH>             Write output to a file (required for xlsx)
+  -q, --quiet                     Do not print progress to stderr
+";
+
+const DEFAULT_MAX_SEARCH_DEPTH: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandKind {
+    Solve,
+    Parallel,
+    Batch,
+    Prebuild,
+    Help,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Xlsx,
+    Csv,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "xlsx" => Ok(OutputFormat::Xlsx),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => Err(format!("Unknown output format: '{}'", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Args {
+    pub command: CommandKind,
+    pub modes: Vec<SearchMode>,
+    pub metric: Metric,
+    pub max_search_depth: usize,
+    pub depth_slack: Option<usize>,
+    pub pruning_depth: u8,
+    pub ignore_corner_positions: bool,
+    pub ignore_edge_positions: bool,
+    pub ignore_corner_orientations: bool,
+    pub ignore_edge_orientations: bool,
+    pub scramble: Option<String>,
+    pub alg: Option<String>,
+    pub corners: Option<Vec<u8>>,
+    pub corner_orientations: Option<Vec<u8>>,
+    pub edges: Option<Vec<u8>>,
+    pub edge_orientations: Option<Vec<u8>>,
+    pub equivalences: String,
+    pub pre_adjust: Vec<String>,
+    pub post_adjust: Vec<String>,
+    pub stop_after_first: bool,
+    pub journal: Option<String>,
+    pub per_case: bool,
+    pub verify_solutions: bool,
+    pub known_algs: Option<String>,
+    pub mcc_preset: MCCPreset,
+    pub mcc_params_file: Option<String>,
+    pub top: Option<usize>,
+    pub rank_by: RankScore,
+    pub filters: Vec<SharedFilter>,
+    pub dedup: DedupMode,
+    pub data_dir: Option<String>,
+    pub use_cache: bool,
+    pub memory_mb: Option<usize>,
+    pub threads: Option<usize>,
+    pub format: OutputFormat,
+    pub output: Option<String>,
+    pub quiet: bool,
+}
+
+impl Args {
+    fn new(command: CommandKind) -> Self {
+        Self {
+            command,
+            modes: vec![SearchMode::RU],
+            metric: Metric::Fifth,
+            max_search_depth: DEFAULT_MAX_SEARCH_DEPTH,
+            depth_slack: None,
+            pruning_depth: DEFAULT_PRUNING_DEPTH,
+            ignore_corner_positions: false,
+            ignore_edge_positions: false,
+            ignore_corner_orientations: false,
+            ignore_edge_orientations: false,
+            scramble: None,
+            alg: None,
+            corners: None,
+            corner_orientations: None,
+            edges: None,
+            edge_orientations: None,
+            equivalences: String::new(),
+            pre_adjust: Vec::new(),
+            post_adjust: Vec::new(),
+            stop_after_first: false,
+            journal: None,
+            per_case: false,
+            verify_solutions: false,
+            known_algs: None,
+            mcc_preset: MCCPreset::Default,
+            mcc_params_file: None,
+            top: None,
+            rank_by: RankScore::Mcc,
+            filters: Vec::new(),
+            dedup: DedupMode::Off,
+            data_dir: None,
+            use_cache: false,
+            memory_mb: None,
+            threads: None,
+            format: OutputFormat::Text,
+            output: None,
+            quiet: false,
+        }
+    }
+
+    /// Parse the command line, excluding the program name.
+    pub fn parse<I, S>(args: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut iter = args.into_iter().map(Into::into);
+
+        let command = match iter.next().as_deref() {
+            Some("solve") => CommandKind::Solve,
+            Some("parallel") => CommandKind::Parallel,
+            Some("batch") => CommandKind::Batch,
+            Some("prebuild") => CommandKind::Prebuild,
+            Some("help") | Some("-h") | Some("--help") | None => CommandKind::Help,
+            Some(other) => return Err(format!("Unknown command: '{}'", other)),
+        };
+
+        let mut args = Self::new(command);
+
+        while let Some(flag) = iter.next() {
+            let mut value = |name: &str| {
+                iter.next()
+                    .ok_or_else(|| format!("Missing value for {}", name))
+            };
+
+            match flag.as_str() {
+                "-m" | "--mode" | "--modes" => args.modes = parse_modes(&value(&flag)?)?,
+                "--metric" => args.metric = value(&flag)?.parse()?,
+                "-d" | "--depth" => args.max_search_depth = parse_number(&flag, &value(&flag)?)?,
+                "--slack" => args.depth_slack = Some(parse_number(&flag, &value(&flag)?)?),
+                "-p" | "--pruning-depth" => {
+                    args.pruning_depth = parse_number(&flag, &value(&flag)?)?
+                }
+                "--ignore-corner-positions" => args.ignore_corner_positions = true,
+                "--ignore-edge-positions" => args.ignore_edge_positions = true,
+                "--ignore-corner-orientations" => args.ignore_corner_orientations = true,
+                "--ignore-edge-orientations" => args.ignore_edge_orientations = true,
+                "--scramble" => args.scramble = Some(value(&flag)?),
+                "--alg" => args.alg = Some(value(&flag)?),
+                "--corners" => args.corners = Some(parse_list(&flag, &value(&flag)?)?),
+                "--corner-orientations" => {
+                    args.corner_orientations = Some(parse_list(&flag, &value(&flag)?)?)
+                }
+                "--edges" => args.edges = Some(parse_list(&flag, &value(&flag)?)?),
+                "--edge-orientations" => {
+                    args.edge_orientations = Some(parse_list(&flag, &value(&flag)?)?)
+                }
+                "--equivalences" => args.equivalences = value(&flag)?,
+                "--pre-adjust" => args.pre_adjust = split_sequences(&value(&flag)?),
+                "--post-adjust" => args.post_adjust = split_sequences(&value(&flag)?),
+                "--stop-after-first" => args.stop_after_first = true,
+                "--journal" => args.journal = Some(value(&flag)?),
+                "--per-case" => args.per_case = true,
+                "--verify-solutions" => args.verify_solutions = true,
+                "--known-algs" => args.known_algs = Some(value(&flag)?),
+                "--mcc-preset" => args.mcc_preset = value(&flag)?.parse()?,
+                "--mcc-params" => args.mcc_params_file = Some(value(&flag)?),
+                "--top" => args.top = Some(parse_number(&flag, &value(&flag)?)?),
+                "--rank-by" => args.rank_by = value(&flag)?.parse()?,
+                "--dedup" => args.dedup = value(&flag)?.parse()?,
+                "--no-auf" => args.filters.push(Arc::new(NoAuf::both())),
+                "--max-face" => args
+                    .filters
+                    .push(Arc::new(value(&flag)?.parse::<MaxFaceTurns>()?)),
+                "--forbid" => {
+                    let moves = parse_alg(&value(&flag)?).map_err(|e| e.to_string())?;
+                    args.filters.push(Arc::new(ForbiddenSequence::new(moves)?))
+                }
+                "--reject-regex" => args
+                    .filters
+                    .push(Arc::new(NotationRegex::reject(&value(&flag)?)?)),
+                "--require-regex" => args
+                    .filters
+                    .push(Arc::new(NotationRegex::require(&value(&flag)?)?)),
+                "--data-dir" => args.data_dir = Some(value(&flag)?),
+                "--cache" => args.use_cache = true,
+                "--memory-mb" => args.memory_mb = Some(parse_number(&flag, &value(&flag)?)?),
+                "--threads" => args.threads = Some(parse_number(&flag, &value(&flag)?)?),
+                "-f" | "--format" => args.format = value(&flag)?.parse()?,
+                "-o" | "--output" => args.output = Some(value(&flag)?),
+                "-q" | "--quiet" => args.quiet = true,
+                "-h" | "--help" => args.command = CommandKind::Help,
+                _ => return Err(format!("Unknown option: '{}'", flag)),
+            }
+        }
+
+        args.validate()?;
+        Ok(args)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.command == CommandKind::Help {
+            return Ok(());
+        }
+
+        if !(MIN_PRUNING_DEPTH..=MAX_PRUNING_DEPTH).contains(&self.pruning_depth) {
+            return Err(format!(
+                "Pruning depth must be between {} and {}",
+                MIN_PRUNING_DEPTH, MAX_PRUNING_DEPTH
+            ));
+        }
+
+        if self.max_search_depth == 0 {
+            return Err("Search depth must be at least 1".to_string());
+        }
+
+        if matches!(self.command, CommandKind::Solve | CommandKind::Batch) && self.modes.len() != 1
+        {
+            return Err(format!(
+                "The {} command takes exactly one search mode",
+                self.command_name()
+            ));
+        }
+
+        if self.top == Some(0) {
+            return Err("--top must be at least 1".to_string());
+        }
+
+        if self.command == CommandKind::Batch && self.scramble.is_none() {
+            return Err("The batch command requires --scramble".to_string());
+        }
+
+        if self.format == OutputFormat::Csv && self.command != CommandKind::Batch {
+            return Err("csv output is only supported by the batch command".to_string());
+        }
+
+        if self.format == OutputFormat::Xlsx && self.output.is_none() {
+            return Err("xlsx output requires --output".to_string());
+        }
+
+        if self.format != OutputFormat::Text && self.command == CommandKind::Prebuild {
+            return Err("The prebuild command only supports text output".to_string());
+        }
+
+        Ok(())
+    }
+
+    fn command_name(&self) -> &'static str {
+        match self.command {
+            CommandKind::Solve => "solve",
+            CommandKind::Parallel => "parallel",
+            CommandKind::Batch => "batch",
+            CommandKind::Prebuild => "prebuild",
+            CommandKind::Help => "help",
+        }
+    }
+
+    pub fn memory_config(&self) -> MemoryConfig {
+        let mut config = MemoryConfig::default();
+        if let Some(budget_mb) = self.memory_mb {
+            config.set_budget_mb(budget_mb);
+        }
+        if let Some(threads) = self.threads {
+            config.set_table_generation_threads(threads);
+            config.set_search_threads(threads);
+        }
+        config
+    }
+
+    /// The result cache to search with, if `--cache` was given.
+    pub fn result_cache(&self) -> Result<Option<Arc<ResultCache>>, String> {
+        if !self.use_cache {
+            return Ok(None);
+        }
+        ResultCache::in_data_directory(CachePolicy::default()).map(|cache| Some(Arc::new(cache)))
+    }
+
+    fn has_explicit_state(&self) -> bool {
+        self.corners.is_some()
+            || self.corner_orientations.is_some()
+            || self.edges.is_some()
+            || self.edge_orientations.is_some()
+    }
+
+    /// MCC params from `--mcc-params` if given, otherwise from `--mcc-preset`.
+    pub fn mcc_params(&self) -> Result<MCCParams, String> {
+        let Some(ref path) = self.mcc_params_file else {
+            return Ok(self.mcc_preset.params());
+        };
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read MCC params '{}': {}", path, e))?;
+        serde_json::from_str(&json).map_err(|e| format!("Invalid MCC params '{}': {}", path, e))
+    }
+
+    /// Ranking from `--top` and `--rank-by`, or `None` to keep every solution.
+    pub fn ranking(&self) -> Result<Option<SolutionRanking>, String> {
+        let Some(top_k) = self.top else {
+            return Ok(None);
+        };
+        Ok(Some(SolutionRanking {
+            score: self.rank_by,
+            top_k,
+            mcc_params: self.mcc_params()?,
+        }))
+    }
+
+    /// Build the start position from `--scramble`, `--alg` or the explicit state options.
+    pub fn start_state(&self) -> Result<LLMinx, String> {
+        let sources = [
+            self.scramble.is_some(),
+            self.alg.is_some(),
+            self.has_explicit_state(),
+        ];
+        match sources.iter().filter(|&&given| given).count() {
+            0 => {
+                return Err(
+                    "No state given; pass --scramble, --alg or the explicit state options"
+                        .to_string(),
+                );
+            }
+            1 => {}
+            _ => {
+                return Err(
+                    "Use only one of --scramble, --alg or the explicit state options".to_string(),
+                );
+            }
+        }
+
+        if let Some(ref scramble) = self.scramble {
+            return LLMinx::from_alg(scramble).map_err(|e| e.to_string());
+        }
+        if let Some(ref alg) = self.alg {
+            return LLMinx::from_alg_inverse(alg).map_err(|e| e.to_string());
+        }
+
+        let mut minx = LLMinx::new();
+        let identity: Vec<u8> = (0..5).collect();
+        let state = MegaminxState {
+            corner_positions: self.corners.clone().unwrap_or_else(|| identity.clone()),
+            corner_orientations: self.corner_orientations.clone().unwrap_or(vec![0; 5]),
+            edge_positions: self.edges.clone().unwrap_or(identity),
+            edge_orientations: self.edge_orientations.clone().unwrap_or(vec![0; 5]),
+        };
+        if state.corner_orientations.len() != 5 || state.edge_orientations.len() != 5 {
+            return Err("Orientation lists must have exactly 5 entries".to_string());
+        }
+        validate_last_layer_state(&state).map_err(|e| e.to_string())?;
+
+        for i in 0..5 {
+            minx.corner_positions_mut()[i] = state.corner_positions[i];
+            minx.edge_positions_mut()[i] = state.edge_positions[i];
+            minx.set_corner_orientation(i as u8, state.corner_orientations[i]);
+            minx.set_edge_orientation(i as u8, state.edge_orientations[i]);
+        }
+
+        Ok(minx)
+    }
+}
+
+fn parse_modes(input: &str) -> Result<Vec<SearchMode>, String> {
+    let mut modes = Vec::new();
+    for part in input.split(',').filter(|s| !s.trim().is_empty()) {
+        let mode: SearchMode = part.parse()?;
+        if !modes.contains(&mode) {
+            modes.push(mode);
+        }
+    }
+    if modes.is_empty() {
+        return Err("At least one search mode is required".to_string());
+    }
+    Ok(modes)
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, input: &str) -> Result<T, String> {
+    input
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid number for {}: '{}'", flag, input))
+}
+
+fn parse_list(flag: &str, input: &str) -> Result<Vec<u8>, String> {
+    input.split(',').map(|s| parse_number(flag, s)).collect()
+}
+
+fn split_sequences(input: &str) -> Vec<String> {
+    input
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_no_args_is_help() {
+        let args = Args::parse(Vec::<String>::new()).unwrap();
+        assert_eq!(args.command, CommandKind::Help);
+    }
+
+    #[test]
+    fn test_parse_unknown_command() {
+        assert!(Args::parse(["frobnicate"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_solve_defaults() {
+        let args = Args::parse(["solve", "--scramble", "R U R' U'"]).unwrap();
+        assert_eq!(args.command, CommandKind::Solve);
+        assert_eq!(args.modes, vec![SearchMode::RU]);
+        assert_eq!(args.metric, Metric::Fifth);
+        assert_eq!(args.max_search_depth, DEFAULT_MAX_SEARCH_DEPTH);
+        assert_eq!(args.pruning_depth, DEFAULT_PRUNING_DEPTH);
+        assert_eq!(args.format, OutputFormat::Text);
+    }
+
+    #[test]
+    fn test_parse_solve_options() {
+        let args = Args::parse([
+            "solve",
+            "-m",
+            "RUF",
+            "--metric",
+            "face",
+            "-d",
+            "10",
+            "-p",
+            "9",
+            "--ignore-edge-orientations",
+            "-f",
+            "json",
+            "--scramble",
+            "R U",
+        ])
+        .unwrap();
+        assert_eq!(args.modes, vec![SearchMode::RUF]);
+        assert_eq!(args.metric, Metric::Face);
+        assert_eq!(args.max_search_depth, 10);
+        assert_eq!(args.pruning_depth, 9);
+        assert!(args.ignore_edge_orientations);
+        assert!(!args.ignore_corner_orientations);
+        assert_eq!(args.format, OutputFormat::Json);
+    }
+
+    #[test]
+    fn test_parse_parallel_modes() {
+        let args = Args::parse(["parallel", "--modes", "RU,RUF,RU", "--scramble", "R"]).unwrap();
+        assert_eq!(args.modes, vec![SearchMode::RU, SearchMode::RUF]);
+    }
+
+    #[test]
+    fn test_parse_custom_mode() {
+        let args = Args::parse(["solve", "-m", "RUbRF", "--scramble", "R"]).unwrap();
+        assert_eq!(args.modes[0].to_string(), "RUFbR");
+    }
+
+    #[test]
+    fn test_solve_rejects_multiple_modes() {
+        assert!(Args::parse(["solve", "-m", "RU,RUF", "--scramble", "R"]).is_err());
+    }
+
+    #[test]
+    fn test_batch_requires_scramble() {
+        assert!(Args::parse(["batch"]).is_err());
+        let args = Args::parse([
+            "batch",
+            "--scramble",
+            "[U] R U R'",
+            "--pre-adjust",
+            "U, U'",
+            "--stop-after-first",
+            "--journal",
+            "batch.jsonl",
+            "--verify-solutions",
+            "--known-algs",
+            "olls.txt",
+            "--cache",
+        ])
+        .unwrap();
+        assert!(args.use_cache);
+        assert_eq!(args.pre_adjust, vec!["U".to_string(), "U'".to_string()]);
+        assert!(args.stop_after_first);
+        assert!(args.verify_solutions);
+        assert_eq!(args.known_algs.as_deref(), Some("olls.txt"));
+        assert_eq!(args.journal.as_deref(), Some("batch.jsonl"));
+        assert_eq!(args.depth_slack, None);
+    }
+
+    #[test]
+    fn test_parse_depth_slack() {
+        let args = Args::parse(["solve", "--slack", "2", "--scramble", "R"]).unwrap();
+        assert_eq!(args.depth_slack, Some(2));
+        assert!(Args::parse(["solve", "--slack", "-1", "--scramble", "R"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_filter_options() {
+        let args = Args::parse([
+            "solve",
+            "--no-auf",
+            "--max-face",
+            "F=1",
+            "--forbid",
+            "R U R'",
+            "--reject-regex",
+            "D",
+            "--scramble",
+            "R",
+        ])
+        .unwrap();
+        assert_eq!(args.filters.len(), 4);
+        assert_eq!(args.dedup, DedupMode::Off);
+
+        let args = Args::parse(["solve", "--dedup", "auf", "--scramble", "R"]).unwrap();
+        assert_eq!(args.dedup, DedupMode::CommutingAndAuf);
+        assert!(Args::parse(["solve", "--dedup", "all", "--scramble", "R"]).is_err());
+
+        assert!(Args::parse(["solve", "--max-face", "F", "--scramble", "R"]).is_err());
+        assert!(Args::parse(["solve", "--forbid", "X", "--scramble", "R"]).is_err());
+        assert!(Args::parse(["solve", "--require-regex", "(", "--scramble", "R"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_ranking_options() {
+        let args = Args::parse(["solve", "--scramble", "R"]).unwrap();
+        assert_eq!(args.ranking(), Ok(None));
+
+        let args = Args::parse([
+            "batch",
+            "--scramble",
+            "[U] R",
+            "--top",
+            "3",
+            "--rank-by",
+            "blend:1,0.5,0",
+            "--mcc-preset",
+            "big-cube",
+        ])
+        .unwrap();
+        let ranking = args.ranking().unwrap().unwrap();
+        assert_eq!(ranking.top_k, 3);
+        assert_eq!(
+            ranking.score,
+            RankScore::Blend {
+                mcc: 1.0,
+                ftm: 0.5,
+                fftm: 0.0
+            }
+        );
+        assert_eq!(ranking.mcc_params, MCCPreset::BigCube.params());
+
+        assert!(Args::parse(["solve", "--top", "0", "--scramble", "R"]).is_err());
+        assert!(Args::parse(["solve", "--rank-by", "speed", "--scramble", "R"]).is_err());
+    }
+
+    #[test]
+    fn test_xlsx_requires_output() {
+        assert!(Args::parse(["solve", "-f", "xlsx", "--scramble", "R"]).is_err());
+        assert!(Args::parse(["solve", "-f", "xlsx", "-o", "out.xlsx", "--scramble", "R"]).is_ok());
+    }
+
+    #[test]
+    fn test_parse_mcc_options() {
+        let args = Args::parse(["solve", "--mcc-preset", "one-handed", "--scramble", "R"]).unwrap();
+        assert_eq!(args.mcc_params(), Ok(MCCPreset::OneHanded.params()));
+        assert!(Args::parse(["solve", "--mcc-preset", "thumbs", "--scramble", "R"]).is_err());
+
+        let path = std::env::temp_dir().join(format!("llminx_mcc_{}.json", std::process::id()));
+        std::fs::write(&path, r#"{"rotation": 1.5}"#).unwrap();
+        let args = Args::parse([
+            "solve",
+            "--mcc-params",
+            path.to_str().unwrap(),
+            "--scramble",
+            "R",
+        ])
+        .unwrap();
+        let params = args.mcc_params().unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(params.rotation, 1.5);
+        assert_eq!(params.double, MCCParams::default().double);
+    }
+
+    #[test]
+    fn test_csv_is_batch_only() {
+        assert!(Args::parse(["solve", "-f", "csv", "--scramble", "R"]).is_err());
+        let args = Args::parse(["batch", "-f", "csv", "--per-case", "--scramble", "R"]).unwrap();
+        assert_eq!(args.format, OutputFormat::Csv);
+        assert!(args.per_case);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_values() {
+        assert!(Args::parse(["solve", "--depth", "abc"]).is_err());
+        assert!(Args::parse(["solve", "--pruning-depth", "40"]).is_err());
+        assert!(Args::parse(["solve", "--metric"]).is_err());
+        assert!(Args::parse(["solve", "--bogus"]).is_err());
+    }
+
+    #[test]
+    fn test_start_state_from_scramble() {
+        let args = Args::parse(["solve", "--scramble", "R U R' U'"]).unwrap();
+        let start = args.start_state().unwrap();
+        assert_eq!(start.depth(), 0);
+        assert!(!start.state_equals(&LLMinx::new()));
+    }
+
+    #[test]
+    fn test_start_state_from_alg() {
+        let args = Args::parse(["solve", "--alg", "(R U R' U')2"]).unwrap();
+        let mut start = args.start_state().unwrap();
+        start.apply_alg("(R U R' U')2").unwrap();
+        assert!(start.state_equals(&LLMinx::new()));
+    }
+
+    #[test]
+    fn test_start_state_reports_notation_errors() {
+        let args = Args::parse(["solve", "--scramble", "R Q"]).unwrap();
+        let err = args.start_state().unwrap_err();
+        assert!(err.contains("position 2"));
+    }
+
+    #[test]
+    fn test_start_state_from_explicit_state() {
+        let args = Args::parse(["solve", "--corners", "1,2,0,3,4"]).unwrap();
+        let start = args.start_state().unwrap();
+        assert_eq!(&start.corner_positions()[..5], &[1, 2, 0, 3, 4]);
+
+        let args = Args::parse(["solve", "--corners", "1,0,2,3,4"]).unwrap();
+        assert!(args.start_state().is_err());
+    }
+
+    #[test]
+    fn test_start_state_requires_input() {
+        let args = Args::parse(["solve"]).unwrap();
+        assert!(args.start_state().is_err());
+
+        let args = Args::parse(["solve", "--scramble", "R", "--edges", "0,1,2,3,4"]).unwrap();
+        assert!(args.start_state().is_err());
+
+        let args = Args::parse(["solve", "--scramble", "R", "--alg", "U"]).unwrap();
+        assert!(args.start_state().is_err());
+    }
+}
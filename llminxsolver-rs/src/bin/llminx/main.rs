@@ -0,0 +1,333 @@
+//! `llminx` command-line front end
+//!
+//! Exposes single, multi-mode and batch solving plus pruning table prebuild
+//! without going through the uniffi bindings.
+
+mod args;
+mod output;
+
+use args::{Args, CommandKind, USAGE};
+use llminxsolver_rs::batch_solver::{
+    AdjustHandler, BatchSolverConfig, CaseIndex, CoverageReport, EquivalenceHandler,
+    GeneratedState, GeneratorConfig,
+};
+use llminxsolver_rs::minx::format_alg;
+use llminxsolver_rs::{
+    BatchExportLayout, BatchExportOptions, Move, ParallelSolver, Solver, StatusCallback,
+    StatusEvent, StatusEventType, batch_solver, set_data_directory,
+};
+use output::SolutionRecord;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+
+fn main() {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(e) = run(&args) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    if let Some(ref dir) = args.data_dir {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create data directory '{}': {}", dir, e))?;
+        set_data_directory(dir);
+    }
+
+    match args.command {
+        CommandKind::Help => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+        CommandKind::Solve => run_solve(args),
+        CommandKind::Parallel => run_parallel(args),
+        CommandKind::Batch => run_batch(args),
+        CommandKind::Prebuild => run_prebuild(args),
+    }
+}
+
+/// Progress reporter that writes status events to stderr.
+fn progress_callback(quiet: bool) -> StatusCallback {
+    Arc::new(move |event: StatusEvent| {
+        if quiet || event.event_type == StatusEventType::SolutionFound {
+            return;
+        }
+        match event.search_mode {
+            Some(ref mode) => eprintln!("[{}] {}", mode, event.message),
+            None => eprintln!("{}", event.message),
+        }
+    })
+}
+
+fn run_solve(args: &Args) -> Result<(), String> {
+    let start = args.start_state()?;
+    let mode = args.modes[0];
+
+    let mut solver =
+        Solver::with_parallel_config(mode, args.max_search_depth, args.memory_config());
+    solver.set_metric(args.metric);
+    solver.set_limit_search_depth(true);
+    solver.set_depth_slack(args.depth_slack);
+    solver.set_ranking(args.ranking()?);
+    solver.set_filters(args.filters.clone());
+    solver.set_dedup(args.dedup);
+    solver.set_result_cache(args.result_cache()?);
+    solver.set_pruning_depth(args.pruning_depth);
+    solver.set_start(start);
+    solver.set_ignore_corner_positions(args.ignore_corner_positions);
+    solver.set_ignore_edge_positions(args.ignore_edge_positions);
+    solver.set_ignore_corner_orientations(args.ignore_corner_orientations);
+    solver.set_ignore_edge_orientations(args.ignore_edge_orientations);
+
+    let progress = progress_callback(args.quiet);
+    solver.set_status_callback(move |event| progress(event));
+
+    let mcc_params = args.mcc_params()?;
+    let solutions: Vec<SolutionRecord> = solver
+        .solve()
+        .iter_mut()
+        .map(|solution| {
+            solution.score_mcc(&mcc_params);
+            SolutionRecord::from_solution(solution)
+        })
+        .collect();
+
+    output::write_solutions(&solutions, args.metric, args.format, args.output.as_deref())
+}
+
+fn run_parallel(args: &Args) -> Result<(), String> {
+    let start = args.start_state()?;
+
+    let mut solver = ParallelSolver::with_config(args.modes.clone(), args.memory_config());
+    solver.set_metric(args.metric);
+    solver.set_max_search_depth(args.max_search_depth);
+    solver.set_limit_search_depth(true);
+    solver.set_depth_slack(args.depth_slack);
+    solver.set_ranking(args.ranking()?);
+    solver.set_filters(args.filters.clone());
+    solver.set_dedup(args.dedup);
+    solver.set_result_cache(args.result_cache()?);
+    solver.set_pruning_depth(args.pruning_depth);
+    solver.set_ignore_corner_positions(args.ignore_corner_positions);
+    solver.set_ignore_edge_positions(args.ignore_edge_positions);
+    solver.set_ignore_corner_orientations(args.ignore_corner_orientations);
+    solver.set_ignore_edge_orientations(args.ignore_edge_orientations);
+
+    let progress = progress_callback(args.quiet);
+    solver.set_status_callback(move |event| progress(event));
+
+    let mcc_params = args.mcc_params()?;
+    let solutions: Vec<SolutionRecord> = solver
+        .solve(start)
+        .iter_mut()
+        .map(|solution| {
+            solution.score_mcc(&mcc_params);
+            SolutionRecord::from_solution(solution)
+        })
+        .collect();
+    output::write_solutions(&solutions, args.metric, args.format, args.output.as_deref())
+}
+
+fn run_batch(args: &Args) -> Result<(), String> {
+    let memory_config = args.memory_config();
+    let mcc_params = args.mcc_params()?;
+    let interrupt = Arc::new(AtomicBool::new(false));
+
+    let generator_config = GeneratorConfig {
+        scramble: args.scramble.clone().unwrap_or_default(),
+        equivalences_str: args.equivalences.clone(),
+        pre_adjust: args.pre_adjust.clone(),
+        post_adjust: args.post_adjust.clone(),
+        sort_criteria: Vec::new(),
+        num_threads: memory_config.search_threads,
+    };
+
+    let quiet = args.quiet;
+    let (states, equivalence) = batch_solver::generate_batch_states(
+        &generator_config,
+        Some(Arc::clone(&interrupt)),
+        Some(Arc::new(move |count, msg: &str| {
+            if !quiet {
+                eprintln!("{} ({} states)", msg, count);
+            }
+        })),
+    )
+    .map_err(|e| e.to_string())?;
+
+    if !quiet {
+        eprintln!("Generated {} cases", states.len());
+    }
+
+    let states = match args.known_algs {
+        Some(ref path) => skip_known_cases(args, states, equivalence.clone(), Path::new(path))?,
+        None => states,
+    };
+
+    let config = BatchSolverConfig {
+        search_mode: args.modes[0],
+        metric: args.metric,
+        pruning_depth: args.pruning_depth,
+        max_search_depth: args.max_search_depth,
+        stop_after_first: args.stop_after_first,
+        depth_slack: args.depth_slack,
+        memory_config,
+        ignore_corner_permutation: args.ignore_corner_positions,
+        ignore_edge_permutation: args.ignore_edge_positions,
+        ignore_corner_orientation: args.ignore_corner_orientations,
+        ignore_edge_orientation: args.ignore_edge_orientations,
+        mcc_params: Some(mcc_params),
+        ranking: args.ranking()?,
+        dedup: args.dedup,
+        filters: args.filters.clone(),
+        verify_solutions: args.verify_solutions,
+        result_cache: args.result_cache()?,
+    };
+
+    let results = match args.journal {
+        Some(ref journal) => batch_solver::resume_batch_states(
+            states,
+            &config,
+            equivalence.as_ref(),
+            Path::new(journal),
+            interrupt,
+            Some(progress_callback(args.quiet)),
+            None,
+        )?,
+        None => batch_solver::solve_batch_states(
+            states,
+            &config,
+            equivalence.as_ref(),
+            interrupt,
+            Some(progress_callback(args.quiet)),
+            None,
+        ),
+    };
+
+    let export = BatchExportOptions {
+        metric: args.metric,
+        mcc_params,
+        layout: if args.per_case {
+            BatchExportLayout::PerCase
+        } else {
+            BatchExportLayout::Summary
+        },
+    };
+    output::write_batch_results(&results, args.format, &export, args.output.as_deref())
+}
+
+/// Report which cases the known algs already cover and keep the rest.
+fn skip_known_cases(
+    args: &Args,
+    states: Vec<GeneratedState>,
+    equivalence: Option<Arc<EquivalenceHandler>>,
+    path: &Path,
+) -> Result<Vec<GeneratedState>, String> {
+    let algs = batch_solver::read_alg_file(path).map_err(|e| e.to_string())?;
+    let adjust =
+        AdjustHandler::new(&args.pre_adjust, &args.post_adjust).map_err(|e| e.to_string())?;
+    let index = CaseIndex::new(&algs, Some(&adjust), equivalence).map_err(|e| e.to_string())?;
+    let report = CoverageReport::new(states, &index);
+
+    if !args.quiet {
+        for (first, second) in index.duplicates() {
+            eprintln!("warning: '{}' solves the same case as '{}'", second, first);
+        }
+        for case in &report.covered {
+            let recognition = &case.recognition;
+            eprintln!(
+                "Case {}: covered by {} (pre: {}, post: {})",
+                case.state.case_number,
+                recognition.name,
+                auf_or_none(&recognition.pre_auf),
+                auf_or_none(&recognition.post_auf),
+            );
+        }
+        eprintln!(
+            "Known algs cover {} of {} cases ({:.1}%), solving {}",
+            report.covered.len(),
+            report.total_cases(),
+            report.coverage_rate(),
+            report.missing.len()
+        );
+    }
+    Ok(report.missing)
+}
+
+fn auf_or_none(moves: &[Move]) -> String {
+    if moves.is_empty() {
+        "none".to_string()
+    } else {
+        format_alg(moves)
+    }
+}
+
+fn run_prebuild(args: &Args) -> Result<(), String> {
+    for &mode in &args.modes {
+        let mut solver =
+            Solver::with_parallel_config(mode, args.max_search_depth, args.memory_config());
+        solver.set_metric(args.metric);
+        solver.set_pruning_depth(args.pruning_depth);
+
+        let progress = progress_callback(args.quiet);
+        let mode_name = mode.to_string();
+        solver.set_status_callback(move |event| {
+            progress(StatusEvent::with_context(
+                event.event_type,
+                &event.message,
+                event.progress,
+                Some(mode_name.clone()),
+                event.current_depth,
+            ))
+        });
+
+        solver.prepare_tables();
+        println!(
+            "{}: {} tables ready ({}, depth {})",
+            mode,
+            solver.get_pruners().len(),
+            args.metric,
+            args.pruning_depth
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prebuild_writes_tables_to_data_dir() {
+        // Kept between runs so the tables are only built once
+        let dir = std::env::temp_dir().join("llminx_cli_test_data");
+        let dir_arg = dir.to_string_lossy().to_string();
+        let args = Args::parse([
+            "prebuild",
+            "--mode",
+            "RU",
+            "--pruning-depth",
+            "8",
+            "--quiet",
+            "--data-dir",
+            dir_arg.as_str(),
+        ])
+        .unwrap();
+
+        run(&args).unwrap();
+        let tables = std::fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().ends_with(".prn.lz4"))
+            .count();
+        assert!(tables > 0);
+    }
+}
@@ -0,0 +1,273 @@
+use crate::args::OutputFormat;
+use llminxsolver_rs::batch_export::batch_results_csv;
+use llminxsolver_rs::batch_solver::BatchResults;
+use llminxsolver_rs::{
+    BatchExportOptions, Metric, ScoredSolutionExport, Solution, calculate_mcc, export_batch_xlsx,
+    export_scored_xlsx,
+};
+use serde::Serialize;
+use std::io::Write;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SolutionRecord {
+    pub mode: String,
+    pub algorithm: String,
+    pub ftm: u32,
+    pub fftm: u32,
+    pub mcc: f64,
+    /// Equivalent reorderings merged into this solution.
+    pub merged_variants: usize,
+}
+
+impl SolutionRecord {
+    pub fn from_solution(solution: &Solution) -> Self {
+        let algorithm = solution.algorithm();
+        Self {
+            mode: solution.search_mode.to_string(),
+            mcc: solution.mcc.unwrap_or_else(|| calculate_mcc(&algorithm)),
+            algorithm,
+            ftm: solution.ftm as u32,
+            fftm: solution.fftm as u32,
+            merged_variants: solution.merged_variants,
+        }
+    }
+
+    fn move_count(&self, metric: Metric) -> u32 {
+        match metric {
+            Metric::Fifth => self.fftm,
+            Metric::Face => self.ftm,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct SolveReport<'a> {
+    metric: Metric,
+    solutions: &'a [SolutionRecord],
+}
+
+#[derive(Debug, Serialize)]
+struct BatchCaseReport {
+    case_number: usize,
+    setup_moves: String,
+    solutions: Vec<SolutionRecord>,
+    best_solution: Option<SolutionRecord>,
+    optimal_length: Option<usize>,
+    solve_time: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct BatchReport {
+    total_cases: usize,
+    solved_cases: usize,
+    failed_cases: Vec<usize>,
+    total_time: f64,
+    average_time_per_case: f64,
+    cases: Vec<BatchCaseReport>,
+}
+
+fn write_to(output: Option<&str>, contents: &str) -> Result<(), String> {
+    match output {
+        Some(path) => {
+            std::fs::write(path, contents).map_err(|e| format!("Failed to write '{}': {}", path, e))
+        }
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            stdout
+                .write_all(contents.as_bytes())
+                .and_then(|_| stdout.flush())
+                .map_err(|e| e.to_string())
+        }
+    }
+}
+
+pub fn write_solutions(
+    solutions: &[SolutionRecord],
+    metric: Metric,
+    format: OutputFormat,
+    output: Option<&str>,
+) -> Result<(), String> {
+    match format {
+        OutputFormat::Text => {
+            let mut text = String::new();
+            for solution in solutions {
+                text.push_str(&format!(
+                    "[{}] {} ({},{}) mcc={:.2}",
+                    solution.mode, solution.algorithm, solution.ftm, solution.fftm, solution.mcc
+                ));
+                if solution.merged_variants > 0 {
+                    text.push_str(&format!(" +{} variants", solution.merged_variants));
+                }
+                text.push('\n');
+            }
+            text.push_str(&format!("{} solution(s) found\n", solutions.len()));
+            write_to(output, &text)
+        }
+        OutputFormat::Json => {
+            let report = SolveReport { metric, solutions };
+            let json = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+            write_to(output, &(json + "\n"))
+        }
+        OutputFormat::Xlsx => {
+            let path = output.ok_or("xlsx output requires --output")?;
+            let scored: Vec<ScoredSolutionExport> = solutions
+                .iter()
+                .map(|s| ScoredSolutionExport {
+                    mcc: s.mcc,
+                    move_count: s.move_count(metric),
+                    algorithm: s.algorithm.clone(),
+                })
+                .collect();
+            export_scored_xlsx(path, &scored, None, 0)
+        }
+        OutputFormat::Csv => Err("csv output is only supported by the batch command".to_string()),
+    }
+}
+
+pub fn write_batch_results(
+    results: &BatchResults,
+    format: OutputFormat,
+    export: &BatchExportOptions,
+    output: Option<&str>,
+) -> Result<(), String> {
+    let mut case_results: Vec<_> = results.case_results.iter().collect();
+    case_results.sort_by_key(|r| r.case_number);
+
+    match format {
+        OutputFormat::Text => {
+            let mut text = String::new();
+            for result in &case_results {
+                text.push_str(&format!(
+                    "Case {}: {} ({:.2}s)\n",
+                    result.case_number, result.setup_moves, result.solve_time
+                ));
+                if result.solutions.is_empty() {
+                    text.push_str("  no solution found\n");
+                }
+                for solution in &result.solutions {
+                    text.push_str(&format!("  {}\n", solution.to_string().trim()));
+                }
+            }
+            text.push_str(&format!(
+                "Solved {}/{} cases in {:.2}s\n",
+                results.solved_cases, results.total_cases, results.total_time
+            ));
+            write_to(output, &text)
+        }
+        OutputFormat::Json => {
+            let report = BatchReport {
+                total_cases: results.total_cases,
+                solved_cases: results.solved_cases,
+                failed_cases: results.failed_cases.clone(),
+                total_time: results.total_time,
+                average_time_per_case: results.average_time_per_case,
+                cases: case_results
+                    .iter()
+                    .map(|r| BatchCaseReport {
+                        case_number: r.case_number,
+                        setup_moves: r.setup_moves.clone(),
+                        solutions: r
+                            .solutions
+                            .iter()
+                            .map(SolutionRecord::from_solution)
+                            .collect(),
+                        best_solution: r.best_solution.as_ref().map(SolutionRecord::from_solution),
+                        optimal_length: r.optimal_length,
+                        solve_time: r.solve_time,
+                    })
+                    .collect(),
+            };
+            let json = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+            write_to(output, &(json + "\n"))
+        }
+        OutputFormat::Xlsx => {
+            let path = output.ok_or("xlsx output requires --output")?;
+            export_batch_xlsx(path, results, export)
+        }
+        OutputFormat::Csv => write_to(output, &batch_results_csv(results, export)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use llminxsolver_rs::{SearchMode, parse_alg};
+
+    #[test]
+    fn test_solution_record_from_solution() {
+        let record = SolutionRecord::from_solution(&Solution::from_moves(
+            &parse_alg("R U R' U'").unwrap(),
+            SearchMode::RU,
+        ));
+        assert_eq!(record.mode, "RU");
+        assert_eq!(record.algorithm, "R U R' U'");
+        assert_eq!(record.ftm, 4);
+        assert_eq!(record.fftm, 4);
+        assert!(record.mcc > 0.0);
+    }
+
+    #[test]
+    fn test_solution_record_keeps_existing_mcc() {
+        let mut scored = Solution::from_moves(&parse_alg("R U R' U'").unwrap(), SearchMode::RU);
+        scored.mcc = Some(1.5);
+        assert_eq!(SolutionRecord::from_solution(&scored).mcc, 1.5);
+    }
+
+    #[test]
+    fn test_move_count_follows_metric() {
+        let record = SolutionRecord::from_solution(&Solution::from_moves(
+            &parse_alg("R2 U").unwrap(),
+            SearchMode::RU,
+        ));
+        assert_eq!(record.move_count(Metric::Face), 2);
+        assert_eq!(record.move_count(Metric::Fifth), 3);
+    }
+
+    #[test]
+    fn test_write_solutions_json_to_file() {
+        let path =
+            std::env::temp_dir().join(format!("llminx_cli_test_{}.json", std::process::id()));
+        let path_str = path.to_str().unwrap();
+        let solutions = vec![SolutionRecord::from_solution(&Solution::from_moves(
+            &parse_alg("R U").unwrap(),
+            SearchMode::RU,
+        ))];
+
+        write_solutions(
+            &solutions,
+            Metric::Fifth,
+            OutputFormat::Json,
+            Some(path_str),
+        )
+        .unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(value["solutions"][0]["algorithm"], "R U");
+        assert_eq!(value["metric"], "Fifth");
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_write_solutions_text_ends_with_summary() {
+        let path = std::env::temp_dir().join(format!("llminx_cli_test_{}.txt", std::process::id()));
+        let solutions: Vec<SolutionRecord> = ["R U", "R' U'"]
+            .iter()
+            .map(|alg| {
+                SolutionRecord::from_solution(&Solution::from_moves(
+                    &parse_alg(alg).unwrap(),
+                    SearchMode::RU,
+                ))
+            })
+            .collect();
+
+        write_solutions(&solutions, Metric::Fifth, OutputFormat::Text, path.to_str()).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("[RU] R U (2,2)"));
+        assert_eq!(lines[2], "2 solution(s) found");
+        std::fs::remove_file(&path).ok();
+    }
+}
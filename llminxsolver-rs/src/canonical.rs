@@ -0,0 +1,245 @@
+//! Canonical forms of move sequences
+//!
+//! Turns of two faces that do not share an edge commute, so a sequence can
+//! often be reordered without changing what it does. Of all those orderings
+//! the canonical form is the one that is smallest move by move, built by
+//! repeatedly taking the smallest move that could be brought to the front.
+
+use crate::minx::Move;
+use crate::solution::Solution;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+
+const U_FACE: u8 = 2;
+
+/// Face pairs that do not share an edge, by `Move::face` index.
+const COMMUTING_FACES: [(u8, u8); 9] = [
+    (0, 1), // R L
+    (0, 4), // R bL
+    (1, 5), // L bR
+    (1, 6), // L D
+    (2, 6), // U D
+    (3, 4), // F bL
+    (3, 5), // F bR
+    (4, 6), // bL D
+    (5, 6), // bR D
+];
+
+/// Whether `a` and `b` can swap places. Turns of the same face always can.
+pub fn moves_commute(a: Move, b: Move) -> bool {
+    let (a, b) = (a.face().min(b.face()), a.face().max(b.face()));
+    a == b || COMMUTING_FACES.contains(&(a, b))
+}
+
+/// The smallest reordering of `moves` that only swaps commuting moves.
+pub fn canonical_form(moves: &[Move]) -> Vec<Move> {
+    let mut remaining = moves.to_vec();
+    let mut canonical = Vec::with_capacity(moves.len());
+    while !remaining.is_empty() {
+        let next = (0..remaining.len())
+            .filter(|&i| {
+                remaining[..i]
+                    .iter()
+                    .all(|&m| moves_commute(m, remaining[i]))
+            })
+            .min_by_key(|&i| remaining[i] as u8)
+            .expect("the first remaining move can always lead");
+        canonical.push(remaining.remove(next));
+    }
+    canonical
+}
+
+/// Which solutions count as the same.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DedupMode {
+    /// Every solution is kept.
+    #[default]
+    Off,
+    /// Solutions that only differ in the order of commuting moves.
+    Commuting,
+    /// As `Commuting`, and also ignoring U turns at either end.
+    CommutingAndAuf,
+}
+
+impl DedupMode {
+    /// Key shared by every solution in the same class.
+    pub fn key(&self, moves: &[Move]) -> Vec<Move> {
+        match self {
+            DedupMode::Off => moves.to_vec(),
+            DedupMode::Commuting => canonical_form(moves),
+            DedupMode::CommutingAndAuf => canonical_form(&strip_auf(moves)),
+        }
+    }
+}
+
+impl std::str::FromStr for DedupMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "off" => Ok(DedupMode::Off),
+            "commuting" => Ok(DedupMode::Commuting),
+            "auf" => Ok(DedupMode::CommutingAndAuf),
+            _ => Err(format!("Unknown dedup mode: '{}'", s)),
+        }
+    }
+}
+
+/// Drop U turns that can be brought to the start or the end of `moves`.
+fn strip_auf(moves: &[Move]) -> Vec<Move> {
+    let movable = |before: &[Move], m: Move| before.iter().all(|&b| moves_commute(b, m));
+    let mut kept: Vec<Move> = Vec::with_capacity(moves.len());
+    for (i, &m) in moves.iter().enumerate() {
+        if m.face() == U_FACE && movable(&moves[..i], m) {
+            continue;
+        }
+        kept.push(m);
+    }
+    let mut stripped: Vec<Move> = Vec::with_capacity(kept.len());
+    for (i, &m) in kept.iter().enumerate().rev() {
+        if m.face() == U_FACE && movable(&kept[i + 1..], m) {
+            continue;
+        }
+        stripped.push(m);
+    }
+    stripped.reverse();
+    stripped
+}
+
+/// Keeps the first solution of each class and counts the rest.
+#[derive(Debug, Default)]
+pub struct Deduplicator {
+    mode: DedupMode,
+    merged: HashMap<Vec<Move>, usize>,
+}
+
+impl Deduplicator {
+    pub fn new(mode: DedupMode) -> Self {
+        Self {
+            mode,
+            merged: HashMap::new(),
+        }
+    }
+
+    pub fn mode(&self) -> DedupMode {
+        self.mode
+    }
+
+    /// Record a solution, returning whether it is the first of its class.
+    pub fn insert(&mut self, solution: &Solution) -> bool {
+        if self.mode == DedupMode::Off {
+            return true;
+        }
+        match self.merged.entry(self.mode.key(&solution.moves)) {
+            Entry::Occupied(mut entry) => {
+                *entry.get_mut() += 1;
+                false
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(0);
+                true
+            }
+        }
+    }
+
+    /// Fill in how many variants were merged into a kept solution.
+    pub fn annotate(&self, solution: &mut Solution) {
+        if self.mode == DedupMode::Off {
+            return;
+        }
+        if let Some(&merged) = self.merged.get(&self.mode.key(&solution.moves)) {
+            solution.merged_variants = merged;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minx::{LLMinx, parse_alg};
+    use crate::search_mode::SearchMode;
+
+    fn alg(s: &str) -> Vec<Move> {
+        parse_alg(s).unwrap()
+    }
+
+    fn tracked_state(moves: &[Move]) -> LLMinx {
+        let mut minx = LLMinx::new();
+        for &m in moves {
+            minx.apply_move(m);
+        }
+        minx
+    }
+
+    #[test]
+    fn test_commuting_faces_match_move_model() {
+        let faces = [
+            Move::R,
+            Move::L,
+            Move::U,
+            Move::F,
+            Move::bL,
+            Move::bR,
+            Move::D,
+        ];
+        for &a in &faces {
+            for &b in &faces {
+                let same = tracked_state(&[a, b]) == tracked_state(&[b, a]);
+                assert_eq!(moves_commute(a, b), same, "{:?} {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_canonical_form_merges_commuting_orders() {
+        assert_eq!(canonical_form(&alg("L R U")), alg("R L U"));
+        assert_eq!(canonical_form(&alg("R L U")), alg("R L U"));
+        assert_eq!(canonical_form(&alg("F U bL F'")), alg("F U F' bL"));
+        assert_eq!(
+            canonical_form(&alg("U bL' F R")),
+            canonical_form(&alg("U F bL' R"))
+        );
+        assert_ne!(canonical_form(&alg("R U")), canonical_form(&alg("U R")));
+        assert!(canonical_form(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_canonical_form_keeps_the_same_effect() {
+        let moves = alg("L R' U F bL' U' bR L' R2");
+        let canonical = canonical_form(&moves);
+        assert_eq!(tracked_state(&moves), tracked_state(&canonical));
+    }
+
+    #[test]
+    fn test_auf_key_ignores_outer_u_turns() {
+        let mode = DedupMode::CommutingAndAuf;
+        assert_eq!(mode.key(&alg("U R U R'")), mode.key(&alg("R U R' U2")));
+        assert_eq!(mode.key(&alg("D U R U R'")), alg("D R U R'"));
+        assert_ne!(
+            DedupMode::Commuting.key(&alg("U R U R'")),
+            DedupMode::Commuting.key(&alg("R U R' U2"))
+        );
+    }
+
+    #[test]
+    fn test_deduplicator_counts_merged_variants() {
+        let mut dedup = Deduplicator::new(DedupMode::Commuting);
+        let mut first = Solution::from_alg("R L U", SearchMode::RUFLbL);
+        assert!(dedup.insert(&first));
+        assert!(!dedup.insert(&Solution::from_alg("L R U", SearchMode::RUFLbL)));
+        assert!(dedup.insert(&Solution::from_alg("R U L", SearchMode::RUFLbL)));
+        dedup.annotate(&mut first);
+        assert_eq!(first.merged_variants, 1);
+
+        let mut off = Deduplicator::new(DedupMode::Off);
+        assert!(off.insert(&first));
+        assert!(off.insert(&first));
+    }
+
+    #[test]
+    fn test_dedup_mode_parsing() {
+        assert_eq!("AUF".parse::<DedupMode>(), Ok(DedupMode::CommutingAndAuf));
+        assert_eq!("commuting".parse::<DedupMode>(), Ok(DedupMode::Commuting));
+        assert!("all".parse::<DedupMode>().is_err());
+    }
+}
@@ -80,14 +80,12 @@ impl CoordinateUtil {
         let mut indices = vec![0usize; cubies.len()];
         let mut locations: Vec<u8> = cubies.to_vec();
 
-        let mut factor = 3usize;
         let mut sum = 0usize;
 
-        for i in (0..=(cubies.len().saturating_sub(3))).rev() {
+        for (factor, i) in (3usize..).zip((0..=(cubies.len().saturating_sub(3))).rev()) {
             indices[i] = (coord as usize) % factor;
             sum += indices[i];
             coord /= factor as u32;
-            factor += 1;
         }
 
         if cubies.len() >= 2 {
@@ -12,6 +12,26 @@ pub fn get_data_directory() -> Option<&'static PathBuf> {
     DATA_DIRECTORY.get()
 }
 
+/// Directory solvers read and write pruning tables in unless told
+/// otherwise: the data directory, or else the current directory.
+pub fn table_directory() -> PathBuf {
+    get_data_directory()
+        .cloned()
+        .unwrap_or_else(default_table_directory)
+}
+
+#[cfg(not(test))]
+fn default_table_directory() -> PathBuf {
+    PathBuf::from(".")
+}
+
+/// Unit tests never write tables into the source tree. The directory is kept
+/// between runs so the tables are only built once.
+#[cfg(test)]
+fn default_table_directory() -> PathBuf {
+    std::env::temp_dir().join("llminx_test_tables")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
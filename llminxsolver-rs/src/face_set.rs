@@ -0,0 +1,349 @@
+//! Generator sets for custom search modes
+//!
+//! A `FaceSet` is any combination of the seven tracked faces. The move list,
+//! the pieces the generators can reach and a matching set of pruners are all
+//! derived from the move model, so no per-set tables have to be written by hand.
+
+use crate::coordinate::{FAC, POWERS_OF_THREE, POWERS_OF_TWO};
+use crate::minx::{LLMinx, Move, NUM_CORNERS, NUM_EDGES, parse_alg};
+use crate::pruner::{
+    CompositePruner, CornerOrientationPruner, CornerPermutationPruner, EdgeOrientationPruner,
+    EdgePermutationPruner, Pruner, SeparationPruner,
+};
+use serde::{Deserialize, Serialize};
+
+/// Largest piece count a permutation pruner can index.
+pub const MAX_PERMUTATION_PIECES: usize = 12;
+
+/// Two pruners are merged into one composite table while it stays below this size.
+const MAX_COMPOSITE_TABLE_SIZE: usize = 1 << 26;
+
+/// Face order used for names and move lists, matching the built-in modes.
+const FACE_ORDER: [u8; 7] = [0, 2, 3, 1, 4, 5, 6];
+const FACE_NAMES: [&str; 7] = ["R", "L", "U", "F", "bL", "bR", "D"];
+const U_FACE: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FaceSet(u8);
+
+impl FaceSet {
+    /// Build a set from the faces of `moves`; amounts and directions are ignored.
+    pub fn new(moves: &[Move]) -> Result<Self, String> {
+        let bits = moves.iter().fold(0u8, |bits, m| bits | (1 << m.face()));
+        if bits == 0 {
+            return Err("A face set needs at least one face".to_string());
+        }
+        Ok(Self(bits))
+    }
+
+    /// Every set of one or more of the seven faces.
+    pub fn all() -> impl Iterator<Item = Self> {
+        (1..1u8 << 7).map(Self)
+    }
+
+    pub fn bits(&self) -> u8 {
+        self.0
+    }
+
+    pub fn contains(&self, face: u8) -> bool {
+        face < 7 && self.0 & (1 << face) != 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Faces in the set, in the canonical R, U, F, L, bL, bR, D order.
+    pub fn faces(&self) -> Vec<u8> {
+        FACE_ORDER
+            .iter()
+            .copied()
+            .filter(|&face| self.contains(face))
+            .collect()
+    }
+
+    /// Generator letters, e.g. `RUFbR`.
+    pub fn name(&self) -> String {
+        self.faces()
+            .iter()
+            .map(|&face| FACE_NAMES[face as usize])
+            .collect()
+    }
+
+    /// Prefix shared by the pruning table files of this set.
+    pub fn table_key(&self) -> String {
+        format!("custom{}", self.name().to_lowercase())
+    }
+
+    pub fn possible_moves(&self) -> Vec<Move> {
+        self.faces()
+            .iter()
+            .flat_map(|&face| (0..4).filter_map(move |variant| Move::from_u8(face * 4 + variant)))
+            .collect()
+    }
+
+    /// Corner slots moved by at least one generator.
+    pub fn corners(&self) -> Vec<u8> {
+        (0..NUM_CORNERS as u8)
+            .filter(|&slot| {
+                self.faces()
+                    .iter()
+                    .any(|&f| face_corners(f).contains(&slot))
+            })
+            .collect()
+    }
+
+    /// Edge slots moved by at least one generator.
+    pub fn edges(&self) -> Vec<u8> {
+        (0..NUM_EDGES as u8)
+            .filter(|&slot| self.faces().iter().any(|&f| face_edges(f).contains(&slot)))
+            .collect()
+    }
+
+    /// Pruners for this set: permutation tables where the piece count allows,
+    /// orientation tables, and face separations to cover what permutations cannot.
+    pub fn create_pruners(&self) -> Vec<Box<dyn Pruner>> {
+        let name = self.name();
+        let key = self.table_key();
+        let corners = self.corners();
+        let edges = self.edges();
+
+        // Edge orientation coordinates cover a prefix of the edge slots.
+        let orientation_edges: Vec<u8> = match edges.last() {
+            Some(&last) => (0..=last).collect(),
+            None => Vec::new(),
+        };
+
+        let corner_permutation = (corners.len() <= MAX_PERMUTATION_PIECES).then(|| {
+            CornerPermutationPruner::new(
+                &format!("Corner permutations {}", name),
+                &format!("{}cornerpermutations", key),
+                &corners,
+            )
+        });
+        let edge_permutation = (edges.len() <= MAX_PERMUTATION_PIECES).then(|| {
+            EdgePermutationPruner::new(
+                &format!("Edge permutations {}", name),
+                &format!("{}edgepermutations", key),
+                &edges,
+            )
+        });
+        let corner_orientation = CornerOrientationPruner::new(
+            &format!("Corner orientations {}", name),
+            &format!("{}cornerorientations", key),
+            &corners,
+        );
+        let edge_orientation = EdgeOrientationPruner::new(
+            &format!("Edge orientations {}", name),
+            &format!("{}edgeorientations", key),
+            &orientation_edges,
+        );
+
+        let corner_orientation_size = POWERS_OF_THREE[corners.len() - 1] as usize;
+        let edge_orientation_size = POWERS_OF_TWO[orientation_edges.len() - 1] as usize;
+        let fits = |a: usize, b: usize| a.saturating_mul(b) <= MAX_COMPOSITE_TABLE_SIZE;
+
+        let mut pruners: Vec<Box<dyn Pruner>> = Vec::new();
+        let has_full_permutations = corner_permutation.is_some() && edge_permutation.is_some();
+
+        let mut pending_corner_orientation = Some(corner_orientation);
+        match corner_permutation {
+            Some(cp) if fits(FAC[corners.len()] as usize / 2, corner_orientation_size) => {
+                pruners.push(Box::new(CompositePruner::new(
+                    &format!("Corners {}", name),
+                    &format!("{}corners", key),
+                    Box::new(cp),
+                    Box::new(pending_corner_orientation.take().unwrap()),
+                )));
+            }
+            Some(cp) => pruners.push(Box::new(cp)),
+            None => {}
+        }
+
+        let mut pending_edge_orientation = Some(edge_orientation);
+        match edge_permutation {
+            Some(ep)
+                if pending_corner_orientation.is_none()
+                    && fits(FAC[edges.len()] as usize / 2, edge_orientation_size) =>
+            {
+                pruners.push(Box::new(CompositePruner::new(
+                    &format!("Edges {}", name),
+                    &format!("{}edges", key),
+                    Box::new(ep),
+                    Box::new(pending_edge_orientation.take().unwrap()),
+                )));
+            }
+            Some(ep) => pruners.push(Box::new(ep)),
+            None => {}
+        }
+
+        match (pending_corner_orientation, pending_edge_orientation) {
+            (Some(co), Some(eo)) if fits(corner_orientation_size, edge_orientation_size) => {
+                pruners.push(Box::new(CompositePruner::new(
+                    &format!("Orientations {}", name),
+                    &format!("{}orientations", key),
+                    Box::new(co),
+                    Box::new(eo),
+                )));
+            }
+            (co, eo) => {
+                if let Some(co) = co {
+                    pruners.push(Box::new(co));
+                }
+                if let Some(eo) = eo {
+                    pruners.push(Box::new(eo));
+                }
+            }
+        }
+
+        if !has_full_permutations {
+            for face in self.faces().into_iter().filter(|&f| f != U_FACE) {
+                let face_name = FACE_NAMES[face as usize];
+                pruners.push(Box::new(SeparationPruner::new(
+                    &format!("Separations {} {}", face_name, name),
+                    &format!("{}separations{}", key, face_name.to_lowercase()),
+                    &face_corners(face),
+                    &face_edges(face),
+                )));
+            }
+        }
+
+        pruners
+    }
+}
+
+impl std::fmt::Display for FaceSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for FaceSet {
+    type Err = String;
+
+    /// Accepts generator letters with optional brackets and separators,
+    /// e.g. `RUbR`, `R U F` or `<R, U, bR, F>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let letters = s.trim().trim_start_matches('<').trim_end_matches('>');
+        let moves = parse_alg(letters).map_err(|e| format!("Invalid face set '{}': {}", s, e))?;
+        Self::new(&moves)
+    }
+}
+
+/// Corner slots turned by a single face.
+fn face_corners(face: u8) -> Vec<u8> {
+    let minx = turned(face);
+    (0..NUM_CORNERS as u8)
+        .filter(|&slot| minx.corner_positions()[slot as usize] != slot)
+        .collect()
+}
+
+/// Edge slots turned by a single face.
+fn face_edges(face: u8) -> Vec<u8> {
+    let minx = turned(face);
+    (0..NUM_EDGES as u8)
+        .filter(|&slot| minx.edge_positions()[slot as usize] != slot)
+        .collect()
+}
+
+fn turned(face: u8) -> LLMinx {
+    let mut minx = LLMinx::new();
+    minx.apply_move(Move::from_u8(face * 4).expect("face index is always in range"));
+    minx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minx::{CornerPosition, EdgePosition};
+
+    #[test]
+    fn test_face_set_is_order_independent() {
+        let a = FaceSet::new(&[Move::bR, Move::U, Move::R]).unwrap();
+        let b = FaceSet::new(&[Move::R, Move::Ui, Move::bR2]).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.name(), "RUbR");
+        assert_eq!(a.table_key(), "customrubr");
+        assert!(FaceSet::new(&[]).is_err());
+    }
+
+    #[test]
+    fn test_face_set_from_str() {
+        let set: FaceSet = "<R, U, bR, F>".parse().unwrap();
+        assert_eq!(set.name(), "RUFbR");
+        assert_eq!("ULbL".parse::<FaceSet>().unwrap().name(), "ULbL");
+        assert!("RXU".parse::<FaceSet>().is_err());
+    }
+
+    #[test]
+    fn test_possible_moves_cover_every_variant() {
+        let set = FaceSet::new(&[Move::U, Move::L]).unwrap();
+        let moves = set.possible_moves();
+        assert_eq!(moves.len(), 8);
+        assert_eq!(&moves[..4], &[Move::U, Move::Ui, Move::U2, Move::U2i]);
+        assert!(moves.contains(&Move::L2i));
+    }
+
+    #[test]
+    fn test_affected_pieces_match_move_model() {
+        use CornerPosition::*;
+        use EdgePosition::*;
+
+        let set = FaceSet::new(&[Move::R, Move::U]).unwrap();
+        assert_eq!(
+            set.corners(),
+            vec![
+                UC1 as u8, UC2 as u8, UC3 as u8, UC4 as u8, UC5 as u8, RC1 as u8, RC5 as u8,
+                FC5 as u8
+            ]
+        );
+        assert_eq!(
+            set.edges(),
+            vec![
+                UE1 as u8, UE2 as u8, UE3 as u8, UE4 as u8, UE5 as u8, RE2 as u8, RE3 as u8,
+                RE4 as u8, FE2 as u8
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pruners_for_small_set_use_permutations() {
+        let set = FaceSet::new(&[Move::R, Move::U]).unwrap();
+        let pruners = set.create_pruners();
+        assert_eq!(pruners.len(), 2);
+        assert!(
+            pruners
+                .iter()
+                .all(|p| p.table_path().starts_with("customru"))
+        );
+        assert!(pruners.iter().any(|p| p.uses_corner_permutation()));
+        assert!(pruners.iter().any(|p| p.uses_edge_permutation()));
+        assert!(pruners.iter().any(|p| p.uses_corner_orientation()));
+        assert!(pruners.iter().any(|p| p.uses_edge_orientation()));
+    }
+
+    #[test]
+    fn test_pruners_for_large_set_add_separations() {
+        let set = FaceSet::new(&[Move::R, Move::U, Move::F, Move::L, Move::bR]).unwrap();
+        assert!(set.edges().len() > MAX_PERMUTATION_PIECES);
+        let pruners = set.create_pruners();
+        let separations = pruners
+            .iter()
+            .filter(|p| p.table_path().contains("separations"))
+            .count();
+        assert_eq!(separations, 4);
+    }
+
+    #[test]
+    fn test_pruner_coordinates_in_range() {
+        let set = FaceSet::new(&[Move::U, Move::L, Move::bL]).unwrap();
+        let solved = LLMinx::new();
+        for pruner in set.create_pruners() {
+            assert!(pruner.get_coordinate(&solved) < pruner.table_size());
+        }
+    }
+}
@@ -0,0 +1,217 @@
+//! Dropping unwanted solutions as the search finds them
+//!
+//! Filters see every solution before it is sent on, so rejected solutions
+//! never reach the caller, the status callback or the optimal depth.
+
+use crate::minx::{Move, parse_move};
+use crate::solution::Solution;
+use regex::Regex;
+use std::sync::Arc;
+
+const U_FACE: u8 = 2;
+
+pub trait SolutionFilter: Send + Sync + std::fmt::Debug {
+    /// Whether the solution should be kept.
+    fn accepts(&self, solution: &Solution) -> bool;
+}
+
+pub type SharedFilter = Arc<dyn SolutionFilter>;
+
+/// Whether every filter keeps the solution.
+pub fn accepts_all(filters: &[SharedFilter], solution: &Solution) -> bool {
+    filters.iter().all(|filter| filter.accepts(solution))
+}
+
+/// Rejects solutions that start or end with a U turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoAuf {
+    pub leading: bool,
+    pub trailing: bool,
+}
+
+impl NoAuf {
+    pub fn both() -> Self {
+        Self {
+            leading: true,
+            trailing: true,
+        }
+    }
+}
+
+impl SolutionFilter for NoAuf {
+    fn accepts(&self, solution: &Solution) -> bool {
+        let is_u = |m: Option<&Move>| m.is_some_and(|m| m.face() == U_FACE);
+        !(self.leading && is_u(solution.moves.first())
+            || self.trailing && is_u(solution.moves.last()))
+    }
+}
+
+/// Rejects solutions that turn one face more than `max` times.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaxFaceTurns {
+    face: u8,
+    max: usize,
+}
+
+impl MaxFaceTurns {
+    /// Limit the turns of the face `face` moves.
+    pub fn new(face: Move, max: usize) -> Self {
+        Self {
+            face: face.face(),
+            max,
+        }
+    }
+}
+
+impl std::str::FromStr for MaxFaceTurns {
+    type Err = String;
+
+    /// Parses `FACE=N`, e.g. `F=1`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (face, max) = s
+            .split_once('=')
+            .ok_or_else(|| format!("Expected FACE=N, got '{}'", s))?;
+        let face = parse_move(face.trim()).map_err(|e| e.to_string())?;
+        let max = max
+            .trim()
+            .parse()
+            .map_err(|_| format!("Invalid turn count in '{}'", s))?;
+        Ok(Self::new(face, max))
+    }
+}
+
+impl SolutionFilter for MaxFaceTurns {
+    fn accepts(&self, solution: &Solution) -> bool {
+        solution
+            .moves
+            .iter()
+            .filter(|m| m.face() == self.face)
+            .count()
+            <= self.max
+    }
+}
+
+/// Rejects solutions that contain `moves` as a consecutive run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForbiddenSequence {
+    moves: Vec<Move>,
+}
+
+impl ForbiddenSequence {
+    pub fn new(moves: Vec<Move>) -> Result<Self, String> {
+        if moves.is_empty() {
+            return Err("A forbidden sequence needs at least one move".to_string());
+        }
+        Ok(Self { moves })
+    }
+}
+
+impl SolutionFilter for ForbiddenSequence {
+    fn accepts(&self, solution: &Solution) -> bool {
+        !solution
+            .moves
+            .windows(self.moves.len())
+            .any(|window| window == self.moves.as_slice())
+    }
+}
+
+/// Matches a regex against the solution's notation, e.g. `R U R' U'`.
+#[derive(Debug, Clone)]
+pub struct NotationRegex {
+    regex: Regex,
+    reject_matches: bool,
+}
+
+impl NotationRegex {
+    /// Keep only solutions whose notation matches `pattern`.
+    pub fn require(pattern: &str) -> Result<Self, String> {
+        Self::new(pattern, false)
+    }
+
+    /// Drop solutions whose notation matches `pattern`.
+    pub fn reject(pattern: &str) -> Result<Self, String> {
+        Self::new(pattern, true)
+    }
+
+    fn new(pattern: &str, reject_matches: bool) -> Result<Self, String> {
+        let regex =
+            Regex::new(pattern).map_err(|e| format!("Invalid regex '{}': {}", pattern, e))?;
+        Ok(Self {
+            regex,
+            reject_matches,
+        })
+    }
+}
+
+impl SolutionFilter for NotationRegex {
+    fn accepts(&self, solution: &Solution) -> bool {
+        self.regex.is_match(&solution.algorithm()) != self.reject_matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minx::parse_alg;
+    use crate::search_mode::SearchMode;
+
+    #[test]
+    fn test_no_auf() {
+        let filter = NoAuf::both();
+        assert!(filter.accepts(&Solution::from_alg("R U R'", SearchMode::RUF)));
+        assert!(!filter.accepts(&Solution::from_alg("U R U R'", SearchMode::RUF)));
+        assert!(!filter.accepts(&Solution::from_alg("R U R' U2'", SearchMode::RUF)));
+
+        let leading_only = NoAuf {
+            leading: true,
+            trailing: false,
+        };
+        assert!(leading_only.accepts(&Solution::from_alg("R U R' U2'", SearchMode::RUF)));
+    }
+
+    #[test]
+    fn test_max_face_turns() {
+        let filter: MaxFaceTurns = "F=1".parse().unwrap();
+        assert!(!filter.accepts(&Solution::from_alg("F R U R' U' F'", SearchMode::RUF)));
+        assert!(filter.accepts(&Solution::from_alg("R U F R' U'", SearchMode::RUF)));
+        assert!("F".parse::<MaxFaceTurns>().is_err());
+        assert!("X=1".parse::<MaxFaceTurns>().is_err());
+    }
+
+    #[test]
+    fn test_forbidden_sequence() {
+        let filter = ForbiddenSequence::new(parse_alg("R U R'").unwrap()).unwrap();
+        assert!(!filter.accepts(&Solution::from_alg("U R U R' F", SearchMode::RUF)));
+        assert!(filter.accepts(&Solution::from_alg("R U' R'", SearchMode::RUF)));
+        assert!(ForbiddenSequence::new(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_notation_regex() {
+        let no_doubles = NotationRegex::reject("2").unwrap();
+        assert!(no_doubles.accepts(&Solution::from_alg("R U R'", SearchMode::RUF)));
+        assert!(!no_doubles.accepts(&Solution::from_alg("R U2 R'", SearchMode::RUF)));
+
+        let starts_with_r = NotationRegex::require("^R ").unwrap();
+        assert!(starts_with_r.accepts(&Solution::from_alg("R U R'", SearchMode::RUF)));
+        assert!(!starts_with_r.accepts(&Solution::from_alg("F R U R'", SearchMode::RUF)));
+        assert!(NotationRegex::reject("(").is_err());
+    }
+
+    #[test]
+    fn test_accepts_all() {
+        let filters: Vec<SharedFilter> = vec![
+            Arc::new(NoAuf::both()),
+            Arc::new(NotationRegex::reject("F").unwrap()),
+        ];
+        assert!(accepts_all(
+            &filters,
+            &Solution::from_alg("R U R'", SearchMode::RUF)
+        ));
+        assert!(!accepts_all(
+            &filters,
+            &Solution::from_alg("F R U R'", SearchMode::RUF)
+        ));
+        assert!(accepts_all(&[], &Solution::from_alg("U", SearchMode::RUF)));
+    }
+}
@@ -1,32 +1,67 @@
 pub mod batch_solver;
+pub mod canonical;
 pub mod coordinate;
 pub mod data_directory;
+pub mod face_set;
+pub mod filter;
 pub mod mcc;
 pub mod memory_config;
 pub mod minx;
 pub mod parallel_solver;
 pub mod pruner;
+pub mod ranking;
+pub mod result_cache;
 pub mod search_mode;
+pub mod solution;
 pub mod solver;
+pub mod table_file;
+pub mod table_manager;
+pub mod table_registry;
 pub mod util;
 pub mod validation;
 
+pub use canonical::{DedupMode, Deduplicator, canonical_form, moves_commute};
 pub use coordinate::CoordinateUtil;
 pub use data_directory::{get_data_directory, set_data_directory};
-pub use mcc::{MCCParams, calculate_mcc, get_move_count};
+pub use face_set::FaceSet;
+pub use filter::{
+    ForbiddenSequence, MaxFaceTurns, NoAuf, NotationRegex, SharedFilter, SolutionFilter,
+};
+pub use mcc::{
+    MCCCalibration, MCCExplanation, MCCParams, MCCPreset, MCCTiming, calculate_mcc,
+    calculate_mcc_with_params, calibrate_mcc, explain_mcc, explain_mcc_with_params, get_move_count,
+};
 pub use memory_config::{MemoryConfig, MemoryTracker, get_available_memory_mb};
-pub use minx::{LLMinx, Move, Orientation};
+pub use minx::{
+    Frame, LLMinx, Move, NotationError, Orientation, PieceKind, Rotation, UnsolvedPiece,
+    VerifyError, parse_alg, verify,
+};
 pub use parallel_solver::ParallelSolver;
 pub use pruner::{DEFAULT_PRUNING_DEPTH, MAX_PRUNING_DEPTH, MIN_PRUNING_DEPTH, Pruner};
+pub use ranking::{RankScore, SolutionRanking, TopSolutions};
+pub use result_cache::{CacheKey, CachePolicy, CachedResult, ResultCache};
 pub use search_mode::{Metric, SearchMode};
+pub use solution::{Solution, SolutionStream};
 pub use solver::{Solver, StatusCallback, StatusEvent, StatusEventType};
-pub use util::{tempfile, theme_gen, wallpaper, xlsx_export};
+pub use table_file::{TableError, TableHeader};
+pub use table_manager::{
+    BundleImport, PrebuildHandle, PrebuildRequest, TableCheck, TableFile, TableInfo, TableManager,
+    TableSelection,
+};
+pub use table_registry::PruningTable;
+pub use util::{batch_export, solution_store, tempfile, theme_gen, wallpaper, xlsx_export};
 pub use validation::{
     MegaminxState, ValidationError, validate_full_state, validate_last_layer_state,
 };
 
+pub use batch_export::{
+    BatchExportLayout, BatchExportOptions, export_batch_csv, export_batch_json, export_batch_xlsx,
+};
+pub use solution_store::{SolutionStore, StoreOrder, StoreQuery, plain_text};
 pub use tempfile::{
-    BatchTempFile, TempFile, cleanup_stale_batch_temp_files, cleanup_stale_temp_files,
+    BatchTempFile, CleanupOptions, CleanupReport, TempFile, cleanup_stale_batch_temp_files,
+    cleanup_stale_batch_temp_files_with_options, cleanup_stale_temp_files,
+    cleanup_stale_temp_files_with_options,
 };
 pub use theme_gen::{
     SchemeType, ThemeColors, generate_theme_from_image, generate_theme_from_wallpaper,
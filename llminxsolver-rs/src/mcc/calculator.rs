@@ -1,39 +1,194 @@
-use super::finger_sim::test;
+use super::finger_sim::{MoveTrace, PenaltyBreakdown, trace};
 use super::parser::process_alg;
 use super::types::MCCParams;
 
+/// A grip the simulation started or switched to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GripChange {
+    /// Index of the first move played from this grip.
+    pub before_move: usize,
+    pub l_wrist: i32,
+    pub r_wrist: i32,
+    /// Cost between the end of the previous move and the first move from
+    /// this grip: the regrip itself, waiting for busy fingers, less any
+    /// overlap the simulation allows.
+    pub cost: f64,
+}
+
+/// Why an algorithm got its MCC score.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MCCExplanation {
+    pub score: f64,
+    /// Starting grip followed by every regrip, in order.
+    pub grips: Vec<GripChange>,
+    pub moves: Vec<MoveTrace>,
+}
+
+impl MCCExplanation {
+    /// Penalties summed over all moves.
+    pub fn total_penalties(&self) -> PenaltyBreakdown {
+        self.moves
+            .iter()
+            .fold(PenaltyBreakdown::default(), |total, m| PenaltyBreakdown {
+                destabilize: total.destabilize + m.penalties.destabilize,
+                over_work: total.over_work + m.penalties.over_work,
+                moveblock: total.moveblock + m.penalties.moveblock,
+                double: total.double + m.penalties.double,
+            })
+    }
+}
+
+/// Formats as one line per grip change and move, then the total.
+impl std::fmt::Display for MCCExplanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut grips = self.grips.iter().peekable();
+        for m in &self.moves {
+            while let Some(grip) = grips.next_if(|g| g.before_move <= m.index) {
+                writeln!(
+                    f,
+                    "grip  left {:+} right {:+}  +{:.2}",
+                    grip.l_wrist, grip.r_wrist, grip.cost
+                )?;
+            }
+            let by = if m.executed_by.is_empty() {
+                "-".to_string()
+            } else {
+                m.executed_by.join(", ")
+            };
+            write!(
+                f,
+                "{:>3}. {:<4} {:<28} +{:.2}",
+                m.index + 1,
+                m.notation,
+                by,
+                m.cost
+            )?;
+            let penalties = [
+                ("destabilize", m.penalties.destabilize),
+                ("overwork", m.penalties.over_work),
+                ("moveblock", m.penalties.moveblock),
+                ("double", m.penalties.double),
+            ];
+            for (name, cost) in penalties {
+                if cost.abs() >= 0.005 {
+                    write!(f, "  {} {:+.2}", name, cost)?;
+                }
+            }
+            writeln!(f)?;
+        }
+        write!(f, "total {}", self.score)
+    }
+}
+
 pub fn calculate_mcc(sequence: &str) -> f64 {
     calculate_mcc_with_params(sequence, &MCCParams::default())
 }
 
 pub fn calculate_mcc_with_params(sequence: &str, params: &MCCParams) -> f64 {
-    let mut split_seq = process_alg(sequence, true);
+    search(sequence, params, false).score
+}
 
-    if split_seq.is_empty() {
-        return 0.0;
+pub fn explain_mcc(sequence: &str) -> MCCExplanation {
+    explain_mcc_with_params(sequence, &MCCParams::default())
+}
+
+/// Score an algorithm and keep the grip path and per-move trace the score
+/// came from. `score` always equals `calculate_mcc_with_params`.
+pub fn explain_mcc_with_params(sequence: &str, params: &MCCParams) -> MCCExplanation {
+    search(sequence, params, true)
+}
+
+/// A simulation of the rest of the sequence from one grip.
+struct Attempt {
+    result: [f64; 6],
+    grip: (i32, i32),
+    speed: f64,
+    moves: Vec<MoveTrace>,
+}
+
+fn attempt(
+    split_seq: &[String],
+    offset: usize,
+    grip: (i32, i32),
+    speed: f64,
+    params: &MCCParams,
+    traced: bool,
+) -> Attempt {
+    let (l_wrist, r_wrist) = grip;
+    let (result, mut moves) = if traced {
+        trace(split_seq, l_wrist, r_wrist, speed, params)
+    } else {
+        (
+            super::finger_sim::test(split_seq, l_wrist, r_wrist, speed, params),
+            Vec::new(),
+        )
+    };
+    for m in &mut moves {
+        m.index += offset;
+    }
+    Attempt {
+        result,
+        grip,
+        speed,
+        moves,
     }
+}
+
+fn search(sequence: &str, params: &MCCParams, traced: bool) -> MCCExplanation {
+    let mut split_seq = process_alg(sequence, true);
+    let mut explanation = MCCExplanation {
+        score: 0.0,
+        grips: Vec::new(),
+        moves: Vec::new(),
+    };
 
-    let mut tests: Vec<[f64; 6]> = vec![
-        test(&split_seq, 0, 0, 0.0, params),
-        test(&split_seq, 0, -1, 1.0 + params.add_regrip, params),
-        test(&split_seq, 0, 1, 1.0 + params.add_regrip, params),
-        test(&split_seq, -1, 0, 1.0 + params.add_regrip, params),
-        test(&split_seq, 1, 0, 1.0 + params.add_regrip, params),
-    ];
+    if split_seq.is_empty() {
+        return explanation;
+    }
+
+    let regrip = 1.0 + params.add_regrip;
+    let mut offset = 0;
+    // Speed after the last move of the chosen path so far.
+    let mut path_end = 0.0;
+    let mut tests: Vec<Attempt> = [
+        ((0, 0), 0.0),
+        ((0, -1), regrip),
+        ((0, 1), regrip),
+        ((-1, 0), regrip),
+        ((1, 0), regrip),
+    ]
+    .into_iter()
+    .map(|(grip, speed)| attempt(&split_seq, 0, grip, speed, params, traced))
+    .collect();
 
     loop {
-        let mut best_test = tests[0];
-        for t in tests.iter().skip(1) {
+        let mut best_index = 0;
+        for (i, t) in tests.iter().enumerate().skip(1) {
+            let t = t.result;
+            let best_test = tests[best_index].result;
             let prefer_t = (t[0] == -1.0 && (best_test[0] != -1.0 || best_test[1] > t[1]))
                 || (t[0] > best_test[0] && best_test[0] != -1.0)
                 || (t[0] == best_test[0] && t[1] < best_test[1] && best_test[0] != -1.0);
             if prefer_t {
-                best_test = *t;
+                best_index = i;
             }
         }
+        let best = tests.swap_remove(best_index);
+        let best_test = best.result;
+        if traced {
+            explanation.grips.push(GripChange {
+                before_move: offset,
+                l_wrist: best.grip.0,
+                r_wrist: best.grip.1,
+                cost: best.speed - path_end,
+            });
+            path_end = best.speed + best.moves.iter().map(|m| m.cost).sum::<f64>();
+            explanation.moves.extend(best.moves);
+        }
 
         if best_test[0] == -1.0 {
-            return (best_test[1] * 10.0).round() / 10.0;
+            explanation.score = (best_test[1] * 10.0).round() / 10.0;
+            return explanation;
         }
 
         let idx = best_test[0] as usize;
@@ -53,19 +208,17 @@ pub fn calculate_mcc_with_params(sequence: &str, params: &MCCParams) -> f64 {
         let double_regrip = (best_test[2] > 1.0 || best_test[2] < -1.0)
             && (best_test[3] > 1.0 || best_test[3] < -1.0);
 
+        offset += idx;
+        let rest = &split_seq[idx..];
+
         for left_wrist in -1..=1 {
             for right_wrist in -1..=1 {
                 let left_match = best_test[2] == left_wrist as f64;
                 let right_match = best_test[3] == right_wrist as f64;
+                let grip = (left_wrist, right_wrist);
 
                 if ['X', 'x', 'Y', 'y', 'Z', 'z'].contains(&prev_move_type) {
-                    tests.push(test(
-                        &split_seq[idx..],
-                        left_wrist,
-                        right_wrist,
-                        best_test[1],
-                        params,
-                    ));
+                    tests.push(attempt(rest, offset, grip, best_test[1], params, traced));
                 } else {
                     let r_move_latency = if prev_move_type == 'R'
                         || prev2_type == 'R'
@@ -89,32 +242,35 @@ pub fn calculate_mcc_with_params(sequence: &str, params: &MCCParams) -> f64 {
                     if left_match || double_regrip {
                         let r_hand_latency = (2.0 - (best_test[1] - best_test[5])).max(0.0);
                         let penalty = r_hand_latency.max(r_move_latency).max(l_move_latency * 2.0);
-                        tests.push(test(
-                            &split_seq[idx..],
-                            left_wrist,
-                            right_wrist,
+                        tests.push(attempt(
+                            rest,
+                            offset,
+                            grip,
                             best_test[1] + penalty + params.add_regrip,
                             params,
+                            traced,
                         ));
                     } else if right_match {
                         let l_hand_latency = (2.0 - (best_test[1] - best_test[4])).max(0.0);
                         let penalty = l_hand_latency.max(l_move_latency).max(r_move_latency * 2.0);
-                        tests.push(test(
-                            &split_seq[idx..],
-                            left_wrist,
-                            right_wrist,
+                        tests.push(attempt(
+                            rest,
+                            offset,
+                            grip,
                             best_test[1] + penalty + params.add_regrip,
                             params,
+                            traced,
                         ));
                     }
                 }
             }
         }
 
-        split_seq = split_seq[idx..].to_vec();
+        split_seq = rest.to_vec();
 
         if tests.is_empty() {
-            return f64::NAN;
+            explanation.score = f64::NAN;
+            return explanation;
         }
     }
 }
@@ -131,7 +287,7 @@ mod tests {
 
     #[test]
     fn test_calculate_mcc_only_invalid() {
-        let result = calculate_mcc("invalid xyz");
+        let result = calculate_mcc("invalid qq");
         assert_eq!(result, 0.0);
     }
 
@@ -272,6 +428,62 @@ mod tests {
         assert!(result > 0.0);
     }
 
+    #[test]
+    fn test_explain_mcc_matches_score_and_accounts_for_it() {
+        let params = MCCParams::default();
+        for alg in [
+            "R U R' U R U2 R'",
+            "x R U R' U' x' D",
+            "R R2 U' D' R2 U D",
+            "BL2 F' L D U' BR",
+        ] {
+            let explanation = explain_mcc_with_params(alg, &params);
+            assert_eq!(explanation.score, calculate_mcc_with_params(alg, &params));
+
+            let total: f64 = explanation.grips.iter().map(|g| g.cost).sum::<f64>()
+                + explanation.moves.iter().map(|m| m.cost).sum::<f64>();
+            assert!((total - explanation.score).abs() < 0.051, "{}", alg);
+
+            let indices: Vec<usize> = explanation.moves.iter().map(|m| m.index).collect();
+            assert_eq!(indices, (0..indices.len()).collect::<Vec<_>>());
+            assert_eq!(explanation.grips[0].before_move, 0);
+        }
+    }
+
+    #[test]
+    fn test_explain_mcc_reports_regrips_rotations_and_penalties() {
+        let explanation = explain_mcc("R U R' U' R U R' U' R U R' U' x R2 D");
+        assert!(explanation.grips.len() > 1);
+        assert!(explanation.moves.iter().any(|m| m.is_rotation));
+        assert!(
+            explanation
+                .moves
+                .iter()
+                .filter(|m| !m.is_rotation)
+                .all(|m| !m.executed_by.is_empty())
+        );
+
+        let r2 = explanation
+            .moves
+            .iter()
+            .find(|m| m.notation == "R2")
+            .unwrap();
+        assert!(r2.penalties.double > 0.0);
+        assert!(explanation.total_penalties().double >= r2.penalties.double);
+
+        let text = explanation.to_string();
+        assert!(text.starts_with("grip"));
+        assert!(text.ends_with(&format!("total {}", explanation.score)));
+    }
+
+    #[test]
+    fn test_explain_mcc_empty() {
+        let explanation = explain_mcc("U");
+        assert_eq!(explanation.score, 0.0);
+        assert!(explanation.moves.is_empty());
+        assert!(explanation.grips.is_empty());
+    }
+
     #[test]
     fn test_calculate_mcc_long_algorithm() {
         let result = calculate_mcc("R U R' U' R U R' U' R U R' U'");
@@ -0,0 +1,195 @@
+//! Fitting the MCC cost model to a solver's own execution times
+//!
+//! Times are modelled as `seconds_per_unit * mcc`. The nine multipliers are
+//! tuned by a coordinate search that scales one multiplier at a time and
+//! keeps any change that lowers the RMS error of that fit, shrinking the step
+//! once no single change helps.
+
+use super::calculator::calculate_mcc_with_params;
+use super::types::MCCParams;
+
+const INITIAL_STEP: f64 = 0.5;
+const MIN_STEP: f64 = 0.01;
+const MAX_ROUNDS: usize = 200;
+const MIN_MULTIPLIER: f64 = 0.05;
+const MAX_MULTIPLIER: f64 = 10.0;
+
+/// An algorithm and how long it took to execute.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MCCTiming {
+    pub algorithm: String,
+    pub seconds: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MCCCalibration {
+    pub params: MCCParams,
+    /// Seconds per MCC unit under the fitted params.
+    pub seconds_per_unit: f64,
+    /// Root mean square difference between measured and predicted seconds.
+    pub rms_error: f64,
+}
+
+impl MCCCalibration {
+    /// Predicted execution time of an algorithm.
+    pub fn predict_seconds(&self, algorithm: &str) -> f64 {
+        calculate_mcc_with_params(algorithm, &self.params) * self.seconds_per_unit
+    }
+}
+
+/// Fit the MCC multipliers to measured times, starting from `start`.
+pub fn calibrate_mcc(samples: &[MCCTiming], start: &MCCParams) -> Result<MCCCalibration, String> {
+    if samples.len() < 2 {
+        return Err("Calibration needs at least two timed algorithms".to_string());
+    }
+    for sample in samples {
+        if !sample.seconds.is_finite() || sample.seconds <= 0.0 {
+            return Err(format!(
+                "Invalid time {} for '{}'",
+                sample.seconds, sample.algorithm
+            ));
+        }
+        let mcc = calculate_mcc_with_params(&sample.algorithm, start);
+        if !mcc.is_finite() || mcc <= 0.0 {
+            return Err(format!("Algorithm '{}' has no MCC score", sample.algorithm));
+        }
+    }
+
+    let mut best = fit(samples, *start).ok_or("Starting params do not score every algorithm")?;
+    let mut step = INITIAL_STEP;
+
+    for _ in 0..MAX_ROUNDS {
+        if step < MIN_STEP {
+            break;
+        }
+        let mut improved = false;
+        for index in 0..9 {
+            for factor in [1.0 + step, 1.0 / (1.0 + step)] {
+                let mut params = best.params;
+                let value = multiplier(&mut params, index);
+                *value = (*value * factor).clamp(MIN_MULTIPLIER, MAX_MULTIPLIER);
+                if let Some(candidate) = fit(samples, params)
+                    && candidate.rms_error < best.rms_error
+                {
+                    best = candidate;
+                    improved = true;
+                }
+            }
+        }
+        if !improved {
+            step /= 2.0;
+        }
+    }
+
+    Ok(best)
+}
+
+/// Least-squares scale and error for a fixed set of params, or `None` if
+/// some algorithm cannot be scored with them.
+fn fit(samples: &[MCCTiming], params: MCCParams) -> Option<MCCCalibration> {
+    let scores: Vec<f64> = samples
+        .iter()
+        .map(|sample| calculate_mcc_with_params(&sample.algorithm, &params))
+        .collect();
+    if scores
+        .iter()
+        .any(|score| !score.is_finite() || *score <= 0.0)
+    {
+        return None;
+    }
+
+    let seconds_per_unit = samples
+        .iter()
+        .zip(&scores)
+        .map(|(sample, score)| sample.seconds * score)
+        .sum::<f64>()
+        / scores.iter().map(|score| score * score).sum::<f64>();
+    let squared_error: f64 = samples
+        .iter()
+        .zip(&scores)
+        .map(|(sample, score)| (sample.seconds - seconds_per_unit * score).powi(2))
+        .sum();
+
+    Some(MCCCalibration {
+        params,
+        seconds_per_unit,
+        rms_error: (squared_error / samples.len() as f64).sqrt(),
+    })
+}
+
+fn multiplier(params: &mut MCCParams, index: usize) -> &mut f64 {
+    match index {
+        0 => &mut params.wrist_mult,
+        1 => &mut params.push_mult,
+        2 => &mut params.ring_mult,
+        3 => &mut params.destabilize,
+        4 => &mut params.add_regrip,
+        5 => &mut params.double,
+        6 => &mut params.over_work_mult,
+        7 => &mut params.moveblock,
+        _ => &mut params.rotation,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcc::presets::MCCPreset;
+
+    const ALGS: [&str; 8] = [
+        "R U R' U R U2 R'",
+        "R U2 R' U' R U' R'",
+        "F R U R' U' F'",
+        "R U R' U' R' F R2 U' R' U' R U R' F'",
+        "x R U R' U' x'",
+        "R2 U R U R' U' R' U' R' U R'",
+        "L' U' L U' L' U2 L",
+        "R U R' F' R U R' U' R' F R2 U' R'",
+    ];
+
+    fn timings(params: &MCCParams, seconds_per_unit: f64) -> Vec<MCCTiming> {
+        ALGS.iter()
+            .map(|alg| MCCTiming {
+                algorithm: alg.to_string(),
+                seconds: calculate_mcc_with_params(alg, params) * seconds_per_unit,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_calibration_improves_on_starting_params() {
+        let samples = timings(&MCCPreset::BigCube.params(), 0.1);
+        let start = MCCParams::default();
+        let before = fit(&samples, start).unwrap();
+        let calibration = calibrate_mcc(&samples, &start).unwrap();
+
+        assert!(calibration.rms_error < before.rms_error);
+        assert!(calibration.seconds_per_unit > 0.0);
+        let predicted = calibration.predict_seconds(ALGS[0]);
+        assert!((predicted - samples[0].seconds).abs() < samples[0].seconds * 0.25);
+    }
+
+    #[test]
+    fn test_calibration_keeps_exact_fit() {
+        let params = MCCParams::default();
+        let samples = timings(&params, 0.2);
+        let calibration = calibrate_mcc(&samples, &params).unwrap();
+        assert_eq!(calibration.params, params);
+        assert!(calibration.rms_error < 1e-9);
+        assert!((calibration.seconds_per_unit - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_calibration_rejects_bad_samples() {
+        let params = MCCParams::default();
+        assert!(calibrate_mcc(&timings(&params, 0.1)[..1], &params).is_err());
+
+        let mut samples = timings(&params, 0.1);
+        samples[0].seconds = 0.0;
+        assert!(calibrate_mcc(&samples, &params).is_err());
+
+        let mut samples = timings(&params, 0.1);
+        samples[1].algorithm = "U".to_string();
+        assert!(calibrate_mcc(&samples, &params).is_err());
+    }
+}
@@ -30,6 +30,7 @@ impl Default for FingerState {
     }
 }
 
+#[derive(Clone)]
 pub struct SimulationContext<'a> {
     pub l_thumb: FingerState,
     pub l_index: FingerState,
@@ -110,12 +111,156 @@ pub enum MoveResult {
     EarlyReturn([f64; 6]),
 }
 
+/// Cost each penalty multiplier added to a move, measured as the move's cost
+/// minus its cost from the same state with that multiplier made neutral.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PenaltyBreakdown {
+    pub destabilize: f64,
+    pub over_work: f64,
+    pub moveblock: f64,
+    pub double: f64,
+}
+
+/// One move of a simulated grip segment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveTrace {
+    /// Position of the move in the processed sequence.
+    pub index: usize,
+    pub notation: String,
+    /// Fingers and wrists that moved, e.g. `"right index"`.
+    pub executed_by: Vec<&'static str>,
+    pub is_rotation: bool,
+    /// Wrist positions after the move.
+    pub l_wrist: i32,
+    pub r_wrist: i32,
+    /// Total cost of the move, penalties included.
+    pub cost: f64,
+    pub penalties: PenaltyBreakdown,
+}
+
 pub fn test(
     split_seq: &[String],
     l_grip: i32,
     r_grip: i32,
     initial_speed: f64,
     params: &MCCParams,
+) -> [f64; 6] {
+    simulate(split_seq, l_grip, r_grip, initial_speed, params, None)
+}
+
+/// Same as `test`, also returning a trace of every move the grip completed.
+pub fn trace(
+    split_seq: &[String],
+    l_grip: i32,
+    r_grip: i32,
+    initial_speed: f64,
+    params: &MCCParams,
+) -> ([f64; 6], Vec<MoveTrace>) {
+    let mut moves = Vec::new();
+    let result = simulate(
+        split_seq,
+        l_grip,
+        r_grip,
+        initial_speed,
+        params,
+        Some(&mut moves),
+    );
+    (result, moves)
+}
+
+fn apply_move(
+    ctx: &mut SimulationContext,
+    j: usize,
+    mv: &str,
+    normal_move: &str,
+    prev_move: &str,
+) -> MoveResult {
+    match normal_move {
+        "R'" => r_moves::handle_ri(ctx, j),
+        "R" => r_moves::handle_r(ctx, j),
+        "R2" => r_moves::handle_r2(ctx, j),
+        "U" => u_moves::handle_u(ctx, j, prev_move),
+        "U'" => u_moves::handle_ui(ctx, j, prev_move),
+        "U2" => u_moves::handle_u2(ctx, j),
+        "D" => d_moves::handle_d(ctx, j, prev_move),
+        "D'" => d_moves::handle_di(ctx, j, prev_move),
+        "D2" => d_moves::handle_d2(ctx, j, prev_move),
+        "L" => l_moves::handle_l(ctx, j),
+        "L'" => l_moves::handle_li(ctx, j),
+        "L2" => l_moves::handle_l2(ctx, j),
+        "F" => f_moves::handle_f(ctx, j, mv, prev_move),
+        "F'" => f_moves::handle_fi(ctx, j, mv, prev_move),
+        "F2" => f_moves::handle_f2(ctx, j, prev_move),
+
+        "BL" => bl_moves::handle_bl(ctx, j, prev_move),
+        "BL'" => bl_moves::handle_bli(ctx, j, prev_move),
+        "BL2" => bl_moves::handle_bl2(ctx, j, prev_move),
+        "BR" => br_moves::handle_br(ctx, j, prev_move),
+        "BR'" => br_moves::handle_bri(ctx, j, prev_move),
+        "BR2" => br_moves::handle_br2(ctx, j, prev_move),
+        "X" => rotation_moves::handle_x(ctx, j),
+        "X'" => rotation_moves::handle_xi(ctx, j),
+        "X2" => rotation_moves::handle_x2(ctx, j),
+        "Y" | "Y'" | "Z" | "Z'" => rotation_moves::handle_y_z(ctx, j),
+        "Y2" | "Z2" => rotation_moves::handle_y2_z2(ctx, j),
+        _ => MoveResult::EarlyReturn(ctx.make_early_return(
+            j,
+            ctx.l_wrist as f64,
+            ctx.r_wrist as f64,
+        )),
+    }
+}
+
+/// Cost the move would have had from `before` with `neutral` params, if it
+/// still succeeds.
+fn neutral_cost(
+    before: &SimulationContext,
+    neutral: &MCCParams,
+    j: usize,
+    mv: &str,
+    normal_move: &str,
+    prev_move: &str,
+) -> Option<f64> {
+    let mut probe = before.clone();
+    probe.params = neutral;
+    match apply_move(&mut probe, j, mv, normal_move, prev_move) {
+        MoveResult::Success => Some(probe.speed - before.speed),
+        MoveResult::EarlyReturn(_) => None,
+    }
+}
+
+fn moved_parts(before: &SimulationContext, after: &SimulationContext) -> Vec<&'static str> {
+    let fingers = [
+        ("left thumb", before.l_thumb, after.l_thumb),
+        ("left index", before.l_index, after.l_index),
+        ("left middle", before.l_middle, after.l_middle),
+        ("left ring", before.l_ring, after.l_ring),
+        ("right thumb", before.r_thumb, after.r_thumb),
+        ("right index", before.r_index, after.r_index),
+        ("right middle", before.r_middle, after.r_middle),
+        ("right ring", before.r_ring, after.r_ring),
+    ];
+    let mut parts: Vec<&'static str> = fingers
+        .into_iter()
+        .filter(|(_, b, a)| b.time != a.time || b.location != a.location)
+        .map(|(name, _, _)| name)
+        .collect();
+    if before.l_wrist != after.l_wrist {
+        parts.push("left wrist");
+    }
+    if before.r_wrist != after.r_wrist {
+        parts.push("right wrist");
+    }
+    parts
+}
+
+fn simulate(
+    split_seq: &[String],
+    l_grip: i32,
+    r_grip: i32,
+    initial_speed: f64,
+    params: &MCCParams,
+    mut trace: Option<&mut Vec<MoveTrace>>,
 ) -> [f64; 6] {
     let mut ctx = SimulationContext::new(l_grip, r_grip, initial_speed, params);
     let mut udgrip = -1;
@@ -123,6 +268,7 @@ pub fn test(
     let mut first_move_speed: Option<f64> = None;
 
     for j in 0..split_seq.len() {
+        let move_start = ctx.speed;
         let mv = &split_seq[j];
         let normal_move = mv.to_uppercase();
         let prev_move = if j == 0 {
@@ -145,44 +291,49 @@ pub fn test(
             }
         }
 
-        let result = match normal_move.as_str() {
-            "R'" => r_moves::handle_ri(&mut ctx, j),
-            "R" => r_moves::handle_r(&mut ctx, j),
-            "R2" => r_moves::handle_r2(&mut ctx, j),
-            "U" => u_moves::handle_u(&mut ctx, j, &prev_move),
-            "U'" => u_moves::handle_ui(&mut ctx, j, &prev_move),
-            "U2" => u_moves::handle_u2(&mut ctx, j),
-            "D" => d_moves::handle_d(&mut ctx, j, &prev_move),
-            "D'" => d_moves::handle_di(&mut ctx, j, &prev_move),
-            "D2" => d_moves::handle_d2(&mut ctx, j, &prev_move),
-            "L" => l_moves::handle_l(&mut ctx, j),
-            "L'" => l_moves::handle_li(&mut ctx, j),
-            "L2" => l_moves::handle_l2(&mut ctx, j),
-            "F" => f_moves::handle_f(&mut ctx, j, mv, &prev_move),
-            "F'" => f_moves::handle_fi(&mut ctx, j, mv, &prev_move),
-            "F2" => f_moves::handle_f2(&mut ctx, j, &prev_move),
-
-            "BL" => bl_moves::handle_bl(&mut ctx, j, &prev_move),
-            "BL'" => bl_moves::handle_bli(&mut ctx, j, &prev_move),
-            "BL2" => bl_moves::handle_bl2(&mut ctx, j, &prev_move),
-            "BR" => br_moves::handle_br(&mut ctx, j, &prev_move),
-            "BR'" => br_moves::handle_bri(&mut ctx, j, &prev_move),
-            "BR2" => br_moves::handle_br2(&mut ctx, j, &prev_move),
-            "X" => rotation_moves::handle_x(&mut ctx, j),
-            "X'" => rotation_moves::handle_xi(&mut ctx, j),
-            "X2" => rotation_moves::handle_x2(&mut ctx, j),
-            "Y" | "Y'" | "Z" | "Z'" => rotation_moves::handle_y_z(&mut ctx, j),
-            "Y2" | "Z2" => rotation_moves::handle_y2_z2(&mut ctx, j),
-            _ => MoveResult::EarlyReturn(ctx.make_early_return(
-                j,
-                ctx.l_wrist as f64,
-                ctx.r_wrist as f64,
-            )),
-        };
+        let before = trace.as_ref().map(|_| ctx.clone());
+        let result = apply_move(&mut ctx, j, mv, &normal_move, &prev_move);
+        let entry = before.as_ref().map(|before| {
+            let handler_cost = ctx.speed - before.speed;
+            let share = |neutral: MCCParams| {
+                neutral_cost(before, &neutral, j, mv, &normal_move, &prev_move)
+                    .map_or(0.0, |cost| handler_cost - cost)
+            };
+            MoveTrace {
+                index: j,
+                notation: mv.clone(),
+                executed_by: moved_parts(before, &ctx),
+                is_rotation: mv.starts_with(['x', 'X', 'y', 'Y', 'z', 'Z']),
+                l_wrist: ctx.l_wrist,
+                r_wrist: ctx.r_wrist,
+                cost: 0.0,
+                penalties: PenaltyBreakdown {
+                    destabilize: 0.0,
+                    over_work: share(MCCParams {
+                        over_work_mult: 0.0,
+                        ..*params
+                    }),
+                    moveblock: share(MCCParams {
+                        moveblock: 0.0,
+                        ..*params
+                    }),
+                    double: share(MCCParams {
+                        double: 1.0,
+                        ..*params
+                    }),
+                },
+            }
+        });
 
-        match result {
-            MoveResult::EarlyReturn(arr) => return arr,
-            MoveResult::Success => {}
+        if let MoveResult::EarlyReturn(arr) = result {
+            // Rotations finish before handing over to the next grip.
+            if let (Some(moves), Some(mut entry)) = (trace.as_deref_mut(), entry)
+                && arr[0] as usize > j
+            {
+                entry.cost = ctx.speed - move_start;
+                moves.push(entry);
+            }
+            return arr;
         }
 
         if let Some(fms) = first_move_speed {
@@ -221,14 +372,20 @@ pub fn test(
             }
         }
 
-        if normal_move == "U" && (ctx.l_wrist == -1 || ctx.r_wrist == -1) {
-            ctx.speed += ctx.params.destabilize;
-        }
-        if (normal_move == "BL" || normal_move == "BR") && (ctx.l_wrist == 0 || ctx.r_wrist == 0) {
+        let destabilized = (normal_move == "U" && (ctx.l_wrist == -1 || ctx.r_wrist == -1))
+            || ((normal_move == "BL" || normal_move == "BR")
+                && (ctx.l_wrist == 0 || ctx.r_wrist == 0))
+            || (normal_move == "D" && (ctx.l_wrist == 1 || ctx.r_wrist == 1));
+        if destabilized {
             ctx.speed += ctx.params.destabilize;
         }
-        if normal_move == "D" && (ctx.l_wrist == 1 || ctx.r_wrist == 1) {
-            ctx.speed += ctx.params.destabilize;
+
+        if let (Some(moves), Some(mut entry)) = (trace.as_deref_mut(), entry) {
+            if destabilized {
+                entry.penalties.destabilize = ctx.params.destabilize;
+            }
+            entry.cost = ctx.speed - move_start;
+            moves.push(entry);
         }
     }
 
@@ -546,6 +703,33 @@ mod tests {
         assert_eq!(result_d[0], -1.0);
     }
 
+    #[test]
+    fn test_trace_matches_test_and_names_fingers() {
+        let params = MCCParams::default();
+        let seq: Vec<String> = ["R", "U", "R'", "U'"]
+            .iter()
+            .map(|m| m.to_string())
+            .collect();
+        let (result, moves) = trace(&seq, 0, 0, 0.0, &params);
+        assert_eq!(result, test(&seq, 0, 0, 0.0, &params));
+        assert_eq!(moves.len(), 4);
+        assert_eq!(moves[0].executed_by, vec!["right wrist"]);
+        assert_eq!(moves[0].r_wrist, 1);
+        assert_eq!(moves[0].cost, params.wrist_mult);
+
+        let total: f64 = moves.iter().map(|m| m.cost).sum();
+        assert!((total - result[1]).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_trace_stops_at_failed_move() {
+        let params = MCCParams::default();
+        let seq: Vec<String> = ["R", "R", "R"].iter().map(|m| m.to_string()).collect();
+        let (result, moves) = trace(&seq, 0, 0, 0.0, &params);
+        assert_eq!(result[0], 2.0);
+        assert_eq!(moves.len(), 2);
+    }
+
     #[test]
     fn test_function_lowercase_transitions_cover_grip_and_udgrip_flips() {
         let params = MCCParams::default();
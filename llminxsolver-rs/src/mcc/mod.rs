@@ -1,8 +1,16 @@
 pub mod calculator;
+pub mod calibration;
 pub mod finger_sim;
 pub mod parser;
+pub mod presets;
 pub mod types;
 
-pub use calculator::calculate_mcc;
+pub use calculator::{
+    GripChange, MCCExplanation, calculate_mcc, calculate_mcc_with_params, explain_mcc,
+    explain_mcc_with_params,
+};
+pub use calibration::{MCCCalibration, MCCTiming, calibrate_mcc};
+pub use finger_sim::{MoveTrace, PenaltyBreakdown};
 pub use parser::get_move_count;
+pub use presets::MCCPreset;
 pub use types::MCCParams;
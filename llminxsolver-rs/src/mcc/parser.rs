@@ -1,4 +1,5 @@
 use super::types::VALID_MOVES;
+use crate::minx::{AlgToken, parse_tokens};
 
 pub fn replace_double(input: &str) -> String {
     let segs: Vec<&str> = input.split(' ').collect();
@@ -24,11 +25,48 @@ pub fn is_valid_move(m: &str) -> bool {
     VALID_MOVES.contains(&m.to_lowercase().as_str())
 }
 
+/// MCC spelling of a token: wide moves are lowercase and double turns drop
+/// their direction. Slice moves have no fingertrick model and are skipped.
+fn mcc_move(token: AlgToken) -> Option<String> {
+    let (text, wide) = match token {
+        AlgToken::Turn(m) => (m.to_string(), false),
+        AlgToken::Wide(m) => (m.to_string(), true),
+        AlgToken::Rotate(r) => (r.to_string(), false),
+        AlgToken::Slice(_) => return None,
+    };
+    let text = text.trim_end().replace("2'", "2");
+    Some(if wide { text.to_lowercase() } else { text })
+}
+
+/// Tokens of `input`, dropping the ones the notation parser rejects.
+fn lenient_tokens(input: &str) -> Vec<AlgToken> {
+    let mut input = input.to_string();
+    loop {
+        let err = match parse_tokens(&input) {
+            Ok(tokens) => return tokens,
+            Err(err) => err,
+        };
+        let start = input
+            .char_indices()
+            .nth(err.position)
+            .map_or(input.len(), |(i, _)| i);
+        let end = (start + err.token.len()).min(input.len());
+        if end == start {
+            return Vec::new();
+        }
+        input.replace_range(start..end, " ");
+    }
+}
+
 pub fn process_alg(input: &str, ignore_auf: bool) -> Vec<String> {
-    let alg = replace_double(input).replace("2'", "2");
+    let moves: Vec<String> = lenient_tokens(input)
+        .into_iter()
+        .filter_map(mcc_move)
+        .collect();
+    let alg = replace_double(&moves.join(" "));
     let mut split_seq: Vec<String> = alg
         .split(' ')
-        .filter(|s| !s.is_empty() && is_valid_move(s))
+        .filter(|s| !s.is_empty())
         .map(|s| s.to_string())
         .collect();
 
@@ -159,6 +197,19 @@ mod tests {
         assert_eq!(result, vec!["R", "U"]);
     }
 
+    #[test]
+    fn test_process_alg_uses_shared_notation() {
+        assert_eq!(
+            process_alg("(R U)2 Rw bl2'", false),
+            vec!["R", "U", "R", "U", "r", "bL2"]
+        );
+        assert_eq!(
+            process_alg("RUR'U' // sexy", false),
+            vec!["R", "U", "R'", "U'"]
+        );
+        assert_eq!(process_alg("y' x2' Rs", false), vec!["y'", "x2"]);
+    }
+
     #[test]
     fn test_process_alg_empty() {
         let result = process_alg("", false);
@@ -0,0 +1,97 @@
+use super::types::MCCParams;
+
+/// Named MCC cost models for common solving styles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MCCPreset {
+    /// The standard two-handed model.
+    Default,
+    /// One hand does all the turning, so regrips and double turns cost more
+    /// and wrist turns are the cheap way to move.
+    OneHanded,
+    /// Larger puzzles with heavier turning, where finger tricks and rotations
+    /// are slower and regrips are harder to hide.
+    BigCube,
+}
+
+impl MCCPreset {
+    pub const ALL: [MCCPreset; 3] = [MCCPreset::Default, MCCPreset::OneHanded, MCCPreset::BigCube];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MCCPreset::Default => "default",
+            MCCPreset::OneHanded => "one-handed",
+            MCCPreset::BigCube => "big-cube",
+        }
+    }
+
+    pub fn params(&self) -> MCCParams {
+        match self {
+            MCCPreset::Default => MCCParams::default(),
+            MCCPreset::OneHanded => MCCParams {
+                wrist_mult: 0.6,
+                push_mult: 1.1,
+                ring_mult: 1.8,
+                destabilize: 0.8,
+                add_regrip: 1.6,
+                double: 1.9,
+                over_work_mult: 2.0,
+                moveblock: 1.0,
+                rotation: 2.5,
+            },
+            MCCPreset::BigCube => MCCParams {
+                wrist_mult: 1.0,
+                push_mult: 1.5,
+                ring_mult: 1.6,
+                destabilize: 0.7,
+                add_regrip: 1.4,
+                double: 1.9,
+                over_work_mult: 2.5,
+                moveblock: 1.0,
+                rotation: 4.5,
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for MCCPreset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for MCCPreset {
+    type Err = String;
+
+    /// Accepts preset names case-insensitively, with `-` or `_` separators.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_ascii_lowercase().replace('_', "-");
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.name() == name)
+            .ok_or_else(|| format!("Unknown MCC preset: '{}'", s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcc::calculator::calculate_mcc_with_params;
+
+    #[test]
+    fn test_preset_names_round_trip() {
+        for preset in MCCPreset::ALL {
+            assert_eq!(preset.name().parse::<MCCPreset>(), Ok(preset));
+        }
+        assert_eq!("One_Handed".parse::<MCCPreset>(), Ok(MCCPreset::OneHanded));
+        assert!("two-handed".parse::<MCCPreset>().is_err());
+    }
+
+    #[test]
+    fn test_presets_score_differently() {
+        assert_eq!(MCCPreset::Default.params(), MCCParams::default());
+        let alg = "R U R' U' F R2 U' R' x";
+        let default = calculate_mcc_with_params(alg, &MCCPreset::Default.params());
+        let big_cube = calculate_mcc_with_params(alg, &MCCPreset::BigCube.params());
+        assert!(big_cube > default);
+    }
+}
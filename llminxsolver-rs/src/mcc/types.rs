@@ -1,9 +1,14 @@
+use serde::{Deserialize, Serialize};
+
 pub const VALID_MOVES: [&str; 30] = [
     "bl", "bl2", "bl'", "br", "br2", "br'", "r", "r2", "r'", "u", "u'", "u2", "f", "f2", "f'", "d",
     "d2", "d'", "l", "l2", "l'", "x", "x'", "x2", "y", "y'", "y2", "z", "z'", "z2",
 ];
 
-#[derive(Clone, Copy)]
+/// Multipliers of the MCC cost model. Missing fields deserialize to their
+/// default values, so a stored profile only needs the ones it changes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct MCCParams {
     pub wrist_mult: f64,
     pub push_mult: f64,
@@ -65,6 +70,16 @@ mod tests {
         assert_eq!(params.push_mult, cloned.push_mult);
     }
 
+    #[test]
+    fn test_mcc_params_json_fills_missing_fields() {
+        let params: MCCParams = serde_json::from_str(r#"{"rotation": 2.0}"#).unwrap();
+        assert_eq!(params.rotation, 2.0);
+        assert_eq!(params.wrist_mult, MCCParams::default().wrist_mult);
+
+        let json = serde_json::to_string(&params).unwrap();
+        assert_eq!(serde_json::from_str::<MCCParams>(&json).unwrap(), params);
+    }
+
     #[test]
     fn test_valid_moves_array() {
         assert_eq!(VALID_MOVES.len(), 30);
@@ -62,6 +62,13 @@ impl MemoryConfig {
         }
     }
 
+    /// Whether tables totalling `table_bytes` should be held packed at four
+    /// bits per entry, which is the case whenever they would not fit unpacked.
+    /// Distances above `PACKED_MAX_DISTANCE` are then clamped.
+    pub fn prefers_packed_tables(&self, table_bytes: usize) -> bool {
+        table_bytes > self.total_budget_bytes
+    }
+
     pub fn budget_mb(&self) -> usize {
         self.total_budget_bytes / BYTES_PER_MB
     }
@@ -483,6 +490,13 @@ mod tests {
         assert!(tracker.is_at_warning_threshold());
     }
 
+    #[test]
+    fn test_prefers_packed_tables_over_budget() {
+        let config = MemoryConfig::new(10, 1, 1);
+        assert!(!config.prefers_packed_tables(10 * BYTES_PER_MB));
+        assert!(config.prefers_packed_tables(10 * BYTES_PER_MB + 1));
+    }
+
     #[test]
     fn test_memory_tracker_zero_budget_percentage() {
         let config = MemoryConfig {
@@ -1,8 +1,18 @@
 mod moves;
+mod notation;
 mod position;
+mod rotation;
 mod state;
 mod transformations;
+mod verify;
 
 pub use moves::Move;
+pub use notation::{
+    AlgToken, NotationError, format_alg, invert_alg, parse_alg, parse_move, parse_tokens,
+};
 pub use position::{CornerPosition, EdgePosition, Orientation};
-pub use state::{LLMinx, MAX_SEARCH_DEPTH, NUM_CORNERS, NUM_EDGES};
+pub use rotation::{Frame, Rotation};
+pub use state::{
+    LLMinx, MAX_SEARCH_DEPTH, NUM_CORNERS, NUM_EDGES, NUM_OUTER_CORNERS, NUM_OUTER_EDGES,
+};
+pub use verify::{PieceKind, UnsolvedPiece, VerifyError, unsolved_pieces, verify};
@@ -0,0 +1,715 @@
+//! Algorithm notation parser
+//!
+//! Accepts face turns `R U F L bL bR D` (also `bl`, `br`) with `'`/`i`, `2`
+//! and `2'` suffixes, parenthesised groups with an optional repetition count
+//! or inverse (`(R U)3`, `(R U R')'`), and `//` line or `/* */` block comments.
+//!
+//! Whole-puzzle rotations `x y z`, wide moves such as `Rw`, `r` or `bLw'` and
+//! slice moves such as `Rs` (the layers between R and its opposite face, i.e.
+//! `Rw R'`) are resolved through a [`Frame`]. `LLMinx::apply_alg` can turn
+//! all twelve faces; `parse_alg` produces plain `Move`s and so reports a turn
+//! that lands on one of the five faces the search never turns as an error.
+
+use super::moves::Move;
+use super::rotation::{Frame, NUM_TRACKED_FACES, OPPOSITE, Rotation, STEPS};
+use super::state::LLMinx;
+use std::fmt;
+
+/// Error produced when an algorithm string cannot be parsed.
+///
+/// `position` is the 0-based character offset of the offending token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotationError {
+    pub position: usize,
+    pub token: String,
+    pub message: String,
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.token.is_empty() {
+            write!(f, "{} at position {}", self.message, self.position)
+        } else {
+            write!(
+                f,
+                "{} '{}' at position {}",
+                self.message, self.token, self.position
+            )
+        }
+    }
+}
+
+impl std::error::Error for NotationError {}
+
+/// Parse an algorithm string into a list of moves.
+pub fn parse_alg(input: &str) -> Result<Vec<Move>, NotationError> {
+    let mut parser = AlgParser { input, pos: 0 };
+    let tokens = parser.parse_sequence(None)?;
+    parser.resolve(&tokens)
+}
+
+/// Parse an algorithm string into its tokens, expanding groups but keeping
+/// rotations, wide and slice moves as written.
+pub fn parse_tokens(input: &str) -> Result<Vec<AlgToken>, NotationError> {
+    Ok(tokenize(input)?.into_iter().map(|t| t.kind).collect())
+}
+
+/// Parse exactly one move token such as `R`, `bL2'` or `Ui`.
+pub fn parse_move(input: &str) -> Result<Move, NotationError> {
+    let trimmed = input.trim();
+    let mut parser = AlgParser {
+        input: trimmed,
+        pos: 0,
+    };
+    let token = parser.parse_token()?;
+    match token.kind {
+        AlgToken::Turn(mv) if parser.pos == trimmed.len() => Ok(mv),
+        _ => Err(parser.error_at(0, "Unrecognized move")),
+    }
+}
+
+/// Reverse a move sequence and invert every move.
+pub fn invert_alg(moves: &[Move]) -> Vec<Move> {
+    moves.iter().rev().map(|m| m.inverse()).collect()
+}
+
+/// Format a move sequence using the canonical move strings.
+pub fn format_alg(moves: &[Move]) -> String {
+    moves
+        .iter()
+        .map(|m| m.to_string().trim_end())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// One step of an algorithm as written, before rotations are resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlgToken {
+    Turn(Move),
+    Wide(Move),
+    Slice(Move),
+    Rotate(Rotation),
+}
+
+/// A parsed token together with the byte offset it started at, kept so
+/// that errors found while resolving rotations point at the right place.
+#[derive(Debug, Clone, Copy)]
+struct Token {
+    kind: AlgToken,
+    pos: usize,
+}
+
+impl Token {
+    fn inverse(self) -> Token {
+        let kind = match self.kind {
+            AlgToken::Turn(m) => AlgToken::Turn(m.inverse()),
+            AlgToken::Wide(m) => AlgToken::Wide(m.inverse()),
+            AlgToken::Slice(m) => AlgToken::Slice(m.inverse()),
+            AlgToken::Rotate(r) => AlgToken::Rotate(r.inverse()),
+        };
+        Token {
+            kind,
+            pos: self.pos,
+        }
+    }
+}
+
+struct AlgParser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl AlgParser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.pos += ch.len_utf8();
+        Some(ch)
+    }
+
+    fn char_position(&self, byte_pos: usize) -> usize {
+        self.input[..byte_pos].chars().count()
+    }
+
+    fn token_at(&self, byte_pos: usize) -> &str {
+        let rest = &self.input[byte_pos..];
+        let end = rest
+            .char_indices()
+            .skip(1)
+            .find(|(_, c)| c.is_whitespace() || matches!(c, '(' | ')' | ','))
+            .map(|(i, _)| i)
+            .unwrap_or(rest.len());
+        &rest[..end]
+    }
+
+    fn error_at(&self, byte_pos: usize, message: &str) -> NotationError {
+        NotationError {
+            position: self.char_position(byte_pos),
+            token: self.token_at(byte_pos).to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    fn skip_trivia(&mut self) -> Result<(), NotationError> {
+        loop {
+            let rest = &self.input[self.pos..];
+            if let Some(ch) = self.peek()
+                && (ch.is_whitespace() || ch == ',')
+            {
+                self.bump();
+            } else if rest.starts_with("//") {
+                self.pos += rest.find('\n').unwrap_or(rest.len());
+            } else if let Some(body) = rest.strip_prefix("/*") {
+                match body.find("*/") {
+                    Some(end) => self.pos += end + 4,
+                    None => {
+                        return Err(NotationError {
+                            position: self.char_position(self.pos),
+                            token: "/*".to_string(),
+                            message: "Unterminated comment".to_string(),
+                        });
+                    }
+                }
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_sequence(&mut self, open: Option<usize>) -> Result<Vec<Token>, NotationError> {
+        let mut tokens = Vec::new();
+
+        loop {
+            self.skip_trivia()?;
+            match self.peek() {
+                None => {
+                    return match open {
+                        Some(open_pos) => Err(self.error_at(open_pos, "Unclosed group")),
+                        None => Ok(tokens),
+                    };
+                }
+                Some(')') => {
+                    return match open {
+                        Some(_) => Ok(tokens),
+                        None => Err(self.error_at(self.pos, "Unmatched")),
+                    };
+                }
+                Some('(') => {
+                    let open_pos = self.pos;
+                    self.bump();
+                    let group = self.parse_sequence(Some(open_pos))?;
+                    self.bump();
+                    let (count, inverted) = self.parse_group_suffix()?;
+                    let group: Vec<Token> = if inverted {
+                        group.iter().rev().map(|t| t.inverse()).collect()
+                    } else {
+                        group
+                    };
+                    for _ in 0..count {
+                        tokens.extend_from_slice(&group);
+                    }
+                }
+                Some(_) => tokens.push(self.parse_token()?),
+            }
+        }
+    }
+
+    fn parse_group_suffix(&mut self) -> Result<(usize, bool), NotationError> {
+        let mut inverted = self.parse_prime();
+
+        let digits_start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.bump();
+        }
+        let count = if self.pos > digits_start {
+            self.input[digits_start..self.pos]
+                .parse::<usize>()
+                .map_err(|_| self.error_at(digits_start, "Invalid repetition count"))?
+        } else {
+            1
+        };
+
+        if !inverted {
+            inverted = self.parse_prime();
+        }
+
+        Ok((count, inverted))
+    }
+
+    fn parse_prime(&mut self) -> bool {
+        if matches!(self.peek(), Some('\'') | Some('\u{2019}')) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_token(&mut self) -> Result<Token, NotationError> {
+        let start = self.pos;
+
+        let rotation_axis: Option<u8> = match self.peek() {
+            Some('x') => Some(0),
+            Some('y') => Some(1),
+            Some('z') => Some(2),
+            _ => None,
+        };
+        if let Some(axis) = rotation_axis {
+            self.bump();
+            let variant = self.parse_amount(start)?;
+            let rotation =
+                Rotation::from_u8(axis * 4 + variant).expect("rotation index is always in range");
+            return Ok(Token {
+                kind: AlgToken::Rotate(rotation),
+                pos: start,
+            });
+        }
+
+        // A lowercase single letter is the wide move, as in `r` for `Rw`
+        let (face, lowercase): (u8, bool) = match self.bump() {
+            Some('R') => (0, false),
+            Some('L') => (1, false),
+            Some('U') => (2, false),
+            Some('F') => (3, false),
+            Some('D') => (6, false),
+            Some('r') => (0, true),
+            Some('l') => (1, true),
+            Some('u') => (2, true),
+            Some('f') => (3, true),
+            Some('d') => (6, true),
+            Some('b') | Some('B') => match self.bump() {
+                Some('L') | Some('l') => (4, false),
+                Some('R') | Some('r') => (5, false),
+                _ => return Err(self.error_at(start, "Unrecognized move")),
+            },
+            _ => return Err(self.error_at(start, "Unrecognized move")),
+        };
+
+        let mut layers = self
+            .peek()
+            .filter(|&c| !lowercase && (c == 'w' || c == 's'));
+        if layers.is_some() {
+            self.bump();
+        } else if lowercase {
+            layers = Some('w');
+        }
+
+        let variant = self.parse_amount(start)?;
+        let mv = Move::from_u8(face * 4 + variant).expect("face index is always in range");
+        let kind = match layers {
+            Some('w') => AlgToken::Wide(mv),
+            Some(_) => AlgToken::Slice(mv),
+            None => AlgToken::Turn(mv),
+        };
+        Ok(Token { kind, pos: start })
+    }
+
+    /// Parse the `2` and `'`/`i` suffixes, returning the variant offset
+    /// shared by `Move` and `Rotation` (0 = X, 1 = X', 2 = X2, 3 = X2').
+    fn parse_amount(&mut self, start: usize) -> Result<u8, NotationError> {
+        let amount_start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.bump();
+        }
+        let double = match &self.input[amount_start..self.pos] {
+            "" => false,
+            "2" => true,
+            _ => return Err(self.error_at(start, "Invalid turn amount in")),
+        };
+
+        let inverse = if self.peek() == Some('i') {
+            self.bump();
+            true
+        } else {
+            self.parse_prime()
+        };
+
+        Ok(if double { 2 } else { 0 } + if inverse { 1 } else { 0 })
+    }
+
+    /// Replay rotations and wide moves through a frame, producing the face
+    /// turns they amount to on the tracked puzzle.
+    fn resolve(&self, tokens: &[Token]) -> Result<Vec<Move>, NotationError> {
+        let mut frame = Frame::new();
+        let mut moves = Vec::with_capacity(tokens.len());
+
+        for token in tokens {
+            let untracked = || self.error_at(token.pos, "Untracked face in");
+            match token.kind {
+                AlgToken::Turn(m) => moves.push(frame.map_move(m).ok_or_else(untracked)?),
+                AlgToken::Wide(m) => moves.push(frame.wide_move(m).ok_or_else(untracked)?),
+                AlgToken::Slice(m) => {
+                    moves.push(frame.wide_move(m).ok_or_else(untracked)?);
+                    moves.push(frame.map_move(m.inverse()).ok_or_else(untracked)?);
+                }
+                AlgToken::Rotate(r) => frame.rotate(r),
+            }
+        }
+
+        Ok(moves)
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, NotationError> {
+    AlgParser { input, pos: 0 }.parse_sequence(None)
+}
+
+impl LLMinx {
+    /// Apply every move of an algorithm string to this state.
+    ///
+    /// Letters are read through the state's frame, which rotations and wide
+    /// moves update. Turns of the five faces outside the search are applied to
+    /// the outer pieces but are not recorded in the move history.
+    pub fn apply_alg(&mut self, alg: &str) -> Result<(), NotationError> {
+        for token in tokenize(alg)? {
+            self.apply_token(token.kind);
+        }
+        Ok(())
+    }
+
+    /// State reached by performing `alg` on a solved puzzle, with an empty move history.
+    pub fn from_alg(alg: &str) -> Result<Self, NotationError> {
+        let mut minx = LLMinx::new();
+        minx.apply_alg(alg)?;
+        minx.clear_moves();
+        Ok(minx)
+    }
+
+    /// State solved by `alg`, i.e. a solved puzzle with the inverse of `alg` applied.
+    pub fn from_alg_inverse(alg: &str) -> Result<Self, NotationError> {
+        let mut minx = LLMinx::new();
+        for token in tokenize(alg)?.iter().rev() {
+            minx.apply_token(token.inverse().kind);
+        }
+        minx.clear_moves();
+        Ok(minx)
+    }
+
+    fn apply_token(&mut self, kind: AlgToken) {
+        match kind {
+            AlgToken::Turn(m) => self.turn_physical(self.frame.face(m.face()), m),
+            AlgToken::Wide(m) => {
+                self.turn_physical(self.frame.face(OPPOSITE[m.face() as usize]), m);
+                self.frame.rotate_about(m.face(), m as u8 % 4);
+            }
+            AlgToken::Slice(m) => {
+                self.apply_token(AlgToken::Wide(m));
+                self.apply_token(AlgToken::Turn(m.inverse()));
+            }
+            AlgToken::Rotate(r) => self.frame.rotate(r),
+        }
+    }
+
+    /// Turn physical `face` by the amount of `m`, recording it when it is one
+    /// of the seven search faces.
+    fn turn_physical(&mut self, face: u8, m: Move) {
+        let variant = m as u8 % 4;
+        match Move::from_u8(face * 4 + variant).filter(|_| face < NUM_TRACKED_FACES) {
+            Some(physical) => self.apply_move(physical),
+            None => self.turn_face(face, STEPS[variant as usize]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_simple_moves() {
+        let moves = parse_alg("R U R' U'").unwrap();
+        assert_eq!(moves, vec![Move::R, Move::U, Move::Ri, Move::Ui]);
+    }
+
+    #[test]
+    fn test_parse_all_suffixes() {
+        let moves = parse_alg("R Ri R' R2 R2' R2i bL bR2' D2").unwrap();
+        assert_eq!(
+            moves,
+            vec![
+                Move::R,
+                Move::Ri,
+                Move::Ri,
+                Move::R2,
+                Move::R2i,
+                Move::R2i,
+                Move::bL,
+                Move::bR2i,
+                Move::D2
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_without_spaces() {
+        assert_eq!(
+            parse_alg("RUR'U'").unwrap(),
+            vec![Move::R, Move::U, Move::Ri, Move::Ui]
+        );
+        assert_eq!(parse_alg("BLbR").unwrap(), vec![Move::bL, Move::bR]);
+    }
+
+    #[test]
+    fn test_parse_groups_and_repetition() {
+        let moves = parse_alg("(R U)3 F").unwrap();
+        assert_eq!(
+            moves,
+            vec![
+                Move::R,
+                Move::U,
+                Move::R,
+                Move::U,
+                Move::R,
+                Move::U,
+                Move::F
+            ]
+        );
+
+        let nested = parse_alg("((R U)2 F)2").unwrap();
+        assert_eq!(nested.len(), 10);
+    }
+
+    #[test]
+    fn test_parse_inverted_group() {
+        assert_eq!(
+            parse_alg("(R U F)'").unwrap(),
+            vec![Move::Fi, Move::Ui, Move::Ri]
+        );
+        assert_eq!(parse_alg("(R U)2'").unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_parse_comments() {
+        let moves = parse_alg("R U // setup\nR' /* insert */ U'").unwrap();
+        assert_eq!(moves, vec![Move::R, Move::U, Move::Ri, Move::Ui]);
+    }
+
+    #[test]
+    fn test_parse_empty() {
+        assert!(parse_alg("").unwrap().is_empty());
+        assert!(parse_alg("  // nothing\n").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_error_positions() {
+        let err = parse_alg("R U X R'").unwrap_err();
+        assert_eq!(err.position, 4);
+        assert_eq!(err.token, "X");
+
+        let err = parse_alg("R U3").unwrap_err();
+        assert_eq!(err.position, 2);
+        assert_eq!(err.token, "U3");
+
+        let err = parse_alg("R (U F").unwrap_err();
+        assert_eq!(err.position, 2);
+        assert!(err.message.contains("Unclosed"));
+
+        let err = parse_alg("R U) F").unwrap_err();
+        assert_eq!(err.position, 3);
+
+        let err = parse_alg("R /* open").unwrap_err();
+        assert_eq!(err.position, 2);
+    }
+
+    #[test]
+    fn test_error_position_counts_characters() {
+        let err = parse_alg("R\u{2019} Q").unwrap_err();
+        assert_eq!(err.position, 3);
+    }
+
+    #[test]
+    fn test_parse_move_single() {
+        assert_eq!(parse_move("bL2i").unwrap(), Move::bL2i);
+        assert_eq!(parse_move(" U' ").unwrap(), Move::Ui);
+        assert!(parse_move("R U").is_err());
+        assert!(parse_move("XYZ").is_err());
+    }
+
+    #[test]
+    fn test_parse_rotations() {
+        assert_eq!(parse_alg("y R").unwrap(), vec![Move::bR]);
+        assert_eq!(parse_alg("y' F2").unwrap(), vec![Move::L2]);
+        assert_eq!(parse_alg("(y R)2").unwrap(), vec![Move::bR, Move::bL]);
+        assert_eq!(
+            parse_alg("y R U R' y'").unwrap(),
+            vec![Move::bR, Move::U, Move::bRi]
+        );
+        assert_eq!(parse_alg("y2 y2' R").unwrap(), vec![Move::R]);
+    }
+
+    #[test]
+    fn test_rotated_alg_matches_relabelled_alg() {
+        let rotated = LLMinx::from_alg("y R U R' U'").unwrap();
+        let relabelled = LLMinx::from_alg("bR U bR' U'").unwrap();
+        assert!(rotated.state_equals(&relabelled));
+    }
+
+    #[test]
+    fn test_parse_wide_moves() {
+        assert_eq!(parse_alg("bLw").unwrap(), vec![Move::D]);
+        assert_eq!(parse_alg("Dw2'").unwrap(), vec![Move::bL2i]);
+
+        // A wide move and its inverse leave the frame untouched.
+        assert_eq!(
+            parse_alg("bLw bLw' R").unwrap(),
+            vec![Move::D, Move::Di, Move::R]
+        );
+        assert_eq!(
+            parse_alg("(bLw R)'").unwrap(),
+            parse_alg("R' bLw'").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_wide_moves_on_outer_faces() {
+        for alg in ["Rw", "Uw", "Lw", "Fw", "bRw", "Rw2'", "y D"] {
+            let mut minx = LLMinx::from_alg(alg).unwrap();
+            assert!(!minx.state_equals(&LLMinx::new()), "{}", alg);
+            minx.apply_alg(&format!("({})'", alg)).unwrap();
+            assert!(minx.state_equals(&LLMinx::new()), "{}", alg);
+            assert!(minx.frame().is_identity(), "{}", alg);
+        }
+
+        // parse_alg can only express the seven search faces
+        let err = parse_alg("R Rw").unwrap_err();
+        assert_eq!(err.position, 2);
+        assert!(err.message.contains("Untracked"));
+    }
+
+    #[test]
+    fn test_rw_turns_the_face_opposite_r() {
+        let minx = LLMinx::from_alg("Rw").unwrap();
+        let mut expected = LLMinx::new();
+        expected.turn_face(OPPOSITE[0], 1);
+        assert!(minx.state_equals(&expected));
+        assert!(!minx.frame().is_identity());
+        assert!(!minx.outer_pieces_solved());
+
+        // Everything but the opposite face moved with R, so R still turns R
+        let mut minx = LLMinx::new();
+        minx.apply_alg("Rw R").unwrap();
+        expected.apply_move(Move::R);
+        assert!(minx.state_equals(&expected));
+        assert_eq!(minx.moves(), &[Move::R]);
+
+        minx.apply_alg("R' Rw Rw Rw Rw").unwrap();
+        assert!(minx.state_equals(&LLMinx::new()));
+        assert!(minx.frame().is_identity());
+    }
+
+    #[test]
+    fn test_uw_turns_the_bottom_face() {
+        let minx = LLMinx::from_alg("Uw'").unwrap();
+        let mut expected = LLMinx::new();
+        expected.turn_face(OPPOSITE[2], 4);
+        assert!(minx.state_equals(&expected));
+        assert_eq!(minx.corner_positions()[..5], [0, 1, 2, 3, 4]);
+        assert_eq!(minx.edge_positions()[..5], [0, 1, 2, 3, 4]);
+
+        // The frame turned with U, so R now names the face that was bR
+        let rotated = LLMinx::from_alg("Uw R").unwrap();
+        let relabelled = LLMinx::from_alg("Uw y' bR y").unwrap();
+        assert!(rotated.state_equals(&relabelled));
+    }
+
+    #[test]
+    fn test_slice_moves() {
+        assert_eq!(parse_alg("bLs").unwrap(), vec![Move::D, Move::bLi]);
+
+        let slice = LLMinx::from_alg("Rs").unwrap();
+        assert!(slice.state_equals(&LLMinx::from_alg("Rw R'").unwrap()));
+
+        let mut expected = LLMinx::new();
+        expected.apply_move(Move::Ri);
+        expected.turn_face(OPPOSITE[0], 1);
+        assert!(slice.state_equals(&expected));
+
+        let mut minx = LLMinx::from_alg("Us Fs2 bRs'").unwrap();
+        assert!(!minx.state_equals(&LLMinx::new()));
+        minx.apply_alg("bRs Fs2' Us'").unwrap();
+        assert!(minx.state_equals(&LLMinx::new()));
+        assert!(minx.frame().is_identity());
+    }
+
+    #[test]
+    fn test_rotation_only_changes_frame() {
+        let minx = LLMinx::from_alg("y x'").unwrap();
+        assert!(minx.state_equals(&LLMinx::new()));
+        assert!(!minx.frame().is_identity());
+        assert!(minx.outer_pieces_solved());
+    }
+
+    #[test]
+    fn test_from_alg_inverse_with_outer_faces() {
+        let alg = "Rw U Fs' y D R'";
+        let mut minx = LLMinx::from_alg_inverse(alg).unwrap();
+        assert!(!minx.state_equals(&LLMinx::new()));
+        minx.apply_alg(alg).unwrap();
+        assert!(minx.state_equals(&LLMinx::new()));
+        assert!(minx.frame().is_identity());
+    }
+
+    #[test]
+    fn test_parse_tokens_keeps_notation() {
+        assert_eq!(
+            parse_tokens("(y r)2' bl d2 Rs'").unwrap(),
+            vec![
+                AlgToken::Wide(Move::Ri),
+                AlgToken::Rotate(Rotation::yi),
+                AlgToken::Wide(Move::Ri),
+                AlgToken::Rotate(Rotation::yi),
+                AlgToken::Turn(Move::bL),
+                AlgToken::Wide(Move::D2),
+                AlgToken::Slice(Move::Ri),
+            ]
+        );
+        assert_eq!(parse_alg("d").unwrap(), parse_alg("Dw").unwrap());
+        assert!(parse_tokens("rw").is_err());
+    }
+
+    #[test]
+    fn test_parse_move_rejects_rotations() {
+        assert!(parse_move("y").is_err());
+        assert!(parse_move("bLw").is_err());
+    }
+
+    #[test]
+    fn test_invert_alg() {
+        let moves = parse_alg("R U2 F'").unwrap();
+        assert_eq!(invert_alg(&moves), vec![Move::F, Move::U2i, Move::Ri]);
+    }
+
+    #[test]
+    fn test_format_alg_round_trip() {
+        let moves = parse_alg("R U2' bL' D").unwrap();
+        let text = format_alg(&moves);
+        assert_eq!(text, "R U2' bL' D");
+        assert_eq!(parse_alg(&text).unwrap(), moves);
+    }
+
+    #[test]
+    fn test_from_alg_inverse_is_solved_by_alg() {
+        let alg = "R U R' U R U2' R'";
+        let mut minx = LLMinx::from_alg_inverse(alg).unwrap();
+        assert_eq!(minx.depth(), 0);
+        assert!(!minx.state_equals(&LLMinx::new()));
+
+        minx.apply_alg(alg).unwrap();
+        assert!(minx.state_equals(&LLMinx::new()));
+    }
+
+    #[test]
+    fn test_from_alg_matches_apply_moves() {
+        let from_alg = LLMinx::from_alg("(R U)5").unwrap();
+        let mut manual = LLMinx::new();
+        for _ in 0..5 {
+            manual.apply_move(Move::R);
+            manual.apply_move(Move::U);
+        }
+        assert!(from_alg.state_equals(&manual));
+        assert_eq!(from_alg.depth(), 0);
+    }
+}
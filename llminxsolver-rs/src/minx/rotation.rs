@@ -0,0 +1,327 @@
+//! Whole-puzzle rotations
+//!
+//! A rotation moves no piece relative to the centres, so instead a `Frame`
+//! records which physical face each notation letter currently refers to, and
+//! face turns are re-labelled through it. Faces 0..7 use the `Move::face()`
+//! numbering; faces 7..12 are the four lower faces round the bottom and the
+//! bottom face, which the search never turns but `LLMinx::apply_alg` can.
+
+use super::moves::Move;
+use serde::{Deserialize, Serialize};
+
+pub(crate) const NUM_FACES: usize = 12;
+pub(crate) const NUM_TRACKED_FACES: u8 = 7;
+
+/// Face opposite each face (R↔DBL, L↔DBR, U↔bottom, F↔B, bL↔D, bR↔DL).
+pub(crate) const OPPOSITE: [u8; NUM_FACES] = [8, 10, 11, 9, 6, 7, 4, 5, 0, 3, 1, 2];
+
+/// Where each face's content ends up after a clockwise 72° rotation about
+/// one of the tracked faces.
+const CLOCKWISE: [[u8; NUM_FACES]; NUM_TRACKED_FACES as usize] = [
+    [0, 4, 5, 2, 9, 10, 3, 1, 8, 11, 6, 7],
+    [6, 1, 3, 7, 2, 0, 11, 8, 4, 5, 10, 9],
+    [3, 4, 2, 1, 5, 0, 7, 8, 9, 10, 6, 11],
+    [6, 2, 0, 3, 5, 10, 7, 1, 4, 9, 11, 8],
+    [3, 8, 1, 7, 4, 2, 6, 11, 9, 5, 0, 10],
+    [2, 8, 4, 1, 9, 5, 3, 7, 11, 10, 0, 6],
+    [10, 2, 5, 0, 4, 9, 6, 3, 1, 8, 11, 7],
+];
+
+/// Number of clockwise 72° steps for the `X`, `Xi`, `X2`, `X2i` variants.
+pub(crate) const STEPS: [usize; 4] = [1, 4, 2, 3];
+
+/// Whole-puzzle rotation: `x` follows R, `y` follows U and `z` follows F.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum Rotation {
+    x = 0,
+    xi = 1,
+    x2 = 2,
+    x2i = 3,
+    y = 4,
+    yi = 5,
+    y2 = 6,
+    y2i = 7,
+    z = 8,
+    zi = 9,
+    z2 = 10,
+    z2i = 11,
+}
+
+impl Rotation {
+    pub const ALL: [Rotation; 12] = [
+        Rotation::x,
+        Rotation::xi,
+        Rotation::x2,
+        Rotation::x2i,
+        Rotation::y,
+        Rotation::yi,
+        Rotation::y2,
+        Rotation::y2i,
+        Rotation::z,
+        Rotation::zi,
+        Rotation::z2,
+        Rotation::z2i,
+    ];
+
+    pub const STRINGS: [&'static str; 12] = [
+        "x ", "x' ", "x2 ", "x2' ", "y ", "y' ", "y2 ", "y2' ", "z ", "z' ", "z2 ", "z2' ",
+    ];
+
+    #[inline]
+    pub fn inverse(self) -> Rotation {
+        Rotation::ALL[(self as usize) ^ 1]
+    }
+
+    /// The face whose axis this rotation turns about.
+    #[inline]
+    pub fn axis(self) -> Move {
+        match (self as u8) / 4 {
+            0 => Move::R,
+            1 => Move::U,
+            _ => Move::F,
+        }
+    }
+
+    #[inline]
+    pub fn to_string(self) -> &'static str {
+        Rotation::STRINGS[self as usize]
+    }
+
+    pub fn from_u8(v: u8) -> Option<Rotation> {
+        Rotation::ALL.get(v as usize).copied()
+    }
+}
+
+/// Current orientation of the puzzle relative to the tracked state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Frame {
+    faces: [u8; NUM_FACES],
+}
+
+impl Default for Frame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Frame {
+    pub fn new() -> Self {
+        let mut faces = [0u8; NUM_FACES];
+        for (i, face) in faces.iter_mut().enumerate() {
+            *face = i as u8;
+        }
+        Self { faces }
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Self::new()
+    }
+
+    /// Rotate the whole puzzle.
+    pub fn rotate(&mut self, rotation: Rotation) {
+        self.turn_about(rotation.axis().face(), STEPS[(rotation as usize) % 4]);
+    }
+
+    /// Physical move for a face turn written in the current frame, or `None`
+    /// if the letter currently points at one of the five faces `Move` cannot
+    /// express.
+    pub fn map_move(&self, m: Move) -> Option<Move> {
+        Self::on_face(self.faces[m.face() as usize], m)
+    }
+
+    /// Apply a wide move such as `Rw`, which turns everything but the face
+    /// opposite `m`. That is a rotation about `m` combined with turning the
+    /// opposite face, so the physical move returned is the opposite face turn.
+    /// Returns `None` and leaves the frame untouched if `Move` cannot express
+    /// that face turn.
+    pub fn wide_move(&mut self, m: Move) -> Option<Move> {
+        let opposite = self.faces[OPPOSITE[m.face() as usize] as usize];
+        let physical = Self::on_face(opposite, m)?;
+        self.turn_about(m.face(), STEPS[(m as usize) % 4]);
+        Some(physical)
+    }
+
+    /// Physical face a notation face currently refers to.
+    #[inline]
+    pub(crate) fn face(&self, face: u8) -> u8 {
+        self.faces[face as usize]
+    }
+
+    /// Rotate the whole puzzle about `face` by the amount of `variant`, the
+    /// `Move` variant offset (0 = X, 1 = X', 2 = X2, 3 = X2').
+    pub(crate) fn rotate_about(&mut self, face: u8, variant: u8) {
+        self.turn_about(face, STEPS[variant as usize]);
+    }
+
+    fn on_face(face: u8, m: Move) -> Option<Move> {
+        if face >= NUM_TRACKED_FACES {
+            return None;
+        }
+        Move::from_u8(face * 4 + (m as u8) % 4)
+    }
+
+    fn turn_about(&mut self, axis: u8, steps: usize) {
+        let cycle = &CLOCKWISE[axis as usize];
+        for _ in 0..steps {
+            let mut faces = [0u8; NUM_FACES];
+            for (position, &target) in cycle.iter().enumerate() {
+                faces[target as usize] = self.faces[position];
+            }
+            self.faces = faces;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minx::{LLMinx, NUM_EDGES};
+
+    fn faces_commute(a: Move, b: Move) -> bool {
+        let mut ab = LLMinx::new();
+        ab.apply_move(a);
+        ab.apply_move(b);
+        let mut ba = LLMinx::new();
+        ba.apply_move(b);
+        ba.apply_move(a);
+        ab.state_equals(&ba)
+    }
+
+    #[test]
+    fn test_rotation_inverse() {
+        for rotation in Rotation::ALL {
+            assert_eq!(rotation.inverse().inverse(), rotation);
+            let mut frame = Frame::new();
+            frame.rotate(rotation);
+            frame.rotate(rotation.inverse());
+            assert!(frame.is_identity());
+        }
+    }
+
+    #[test]
+    fn test_five_rotations_are_identity() {
+        for rotation in [Rotation::x, Rotation::y, Rotation::z] {
+            let mut frame = Frame::new();
+            for _ in 0..5 {
+                frame.rotate(rotation);
+            }
+            assert!(frame.is_identity());
+        }
+    }
+
+    #[test]
+    fn test_opposite_is_involution() {
+        for face in 0..NUM_FACES {
+            assert_eq!(OPPOSITE[OPPOSITE[face] as usize] as usize, face);
+            assert_ne!(OPPOSITE[face] as usize, face);
+        }
+    }
+
+    #[test]
+    fn test_clockwise_fixes_axis_and_opposite() {
+        for axis in 0..NUM_TRACKED_FACES as usize {
+            assert_eq!(CLOCKWISE[axis][axis] as usize, axis);
+            let opposite = OPPOSITE[axis] as usize;
+            assert_eq!(CLOCKWISE[axis][opposite] as usize, opposite);
+        }
+    }
+
+    #[test]
+    fn test_adjacency_matches_move_model() {
+        // Faces sharing an edge with each tracked face; turns of adjacent
+        // faces never commute and turns of non-adjacent faces always do.
+        const ADJACENT: [[u8; 5]; NUM_TRACKED_FACES as usize] = [
+            [2, 3, 5, 6, 10],
+            [2, 3, 4, 7, 8],
+            [0, 1, 3, 4, 5],
+            [0, 1, 2, 6, 7],
+            [1, 2, 5, 8, 9],
+            [0, 2, 4, 9, 10],
+            [0, 3, 7, 10, 11],
+        ];
+
+        for a in 0..NUM_TRACKED_FACES {
+            let ring = &ADJACENT[a as usize];
+            for &face in ring {
+                assert!(ring.contains(&CLOCKWISE[a as usize][face as usize]));
+            }
+            for b in 0..NUM_TRACKED_FACES {
+                if a == b {
+                    continue;
+                }
+                let ma = Move::from_u8(a * 4).unwrap();
+                let mb = Move::from_u8(b * 4).unwrap();
+                assert_eq!(
+                    ring.contains(&b),
+                    !faces_commute(ma, mb),
+                    "faces {} and {}",
+                    a,
+                    b
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_y_relabels_upper_faces() {
+        let mut frame = Frame::new();
+        frame.rotate(Rotation::y);
+        assert_eq!(frame.map_move(Move::R), Some(Move::bR));
+        assert_eq!(frame.map_move(Move::F), Some(Move::R));
+        assert_eq!(frame.map_move(Move::L), Some(Move::F));
+        assert_eq!(frame.map_move(Move::bL), Some(Move::L));
+        assert_eq!(frame.map_move(Move::bR), Some(Move::bL));
+        assert_eq!(frame.map_move(Move::Ui), Some(Move::Ui));
+        assert_eq!(frame.map_move(Move::D), None);
+    }
+
+    #[test]
+    fn test_clockwise_matches_turn_direction() {
+        // A clockwise turn of face A carries the edge shared by A and f to the
+        // edge shared by A and CLOCKWISE[A][f].
+        let moved_edges = |m: Move| -> Vec<usize> {
+            let mut minx = LLMinx::new();
+            minx.apply_move(m);
+            (0..NUM_EDGES)
+                .filter(|&slot| minx.edge_positions()[slot] != slot as u8)
+                .collect()
+        };
+        let shared_edge = |a: Move, b: Move| -> Option<usize> {
+            let b_edges = moved_edges(b);
+            moved_edges(a).into_iter().find(|e| b_edges.contains(e))
+        };
+
+        let mut checked = 0;
+        for axis in 0..NUM_TRACKED_FACES {
+            let turn = Move::from_u8(axis * 4).unwrap();
+            for face in 0..NUM_TRACKED_FACES {
+                let target = CLOCKWISE[axis as usize][face as usize];
+                if face == axis || target == face || target >= NUM_TRACKED_FACES {
+                    continue;
+                }
+                let from = shared_edge(turn, Move::from_u8(face * 4).unwrap());
+                let to = shared_edge(turn, Move::from_u8(target * 4).unwrap());
+                if let (Some(from), Some(to)) = (from, to) {
+                    let mut minx = LLMinx::new();
+                    minx.apply_move(turn);
+                    assert_eq!(minx.edge_positions()[to] as usize, from);
+                    checked += 1;
+                }
+            }
+        }
+        assert!(checked > 0);
+    }
+
+    #[test]
+    fn test_wide_move_on_tracked_opposite() {
+        let mut frame = Frame::new();
+        assert_eq!(frame.wide_move(Move::bL), Some(Move::D));
+        assert!(!frame.is_identity());
+
+        let mut frame = Frame::new();
+        assert_eq!(frame.wide_move(Move::R), None);
+        assert!(frame.is_identity());
+    }
+}
@@ -1,9 +1,16 @@
 use super::moves::Move;
+use super::rotation::Frame;
 
 pub const NUM_CORNERS: usize = 17;
 pub const NUM_EDGES: usize = 23;
 pub const MAX_SEARCH_DEPTH: usize = 100;
 
+/// Corners and edges none of the seven search faces move. They are numbered
+/// after the tracked pieces and only leave home under turns of the other five
+/// faces, e.g. from wide moves and rotated algorithms.
+pub const NUM_OUTER_CORNERS: usize = 3;
+pub const NUM_OUTER_EDGES: usize = 7;
+
 #[derive(Clone, Debug)]
 pub struct LLMinx {
     pub(crate) corner_positions: [u8; NUM_CORNERS],
@@ -14,10 +21,18 @@ pub struct LLMinx {
     pub(crate) ignore_edge_positions: [bool; NUM_EDGES],
     pub(crate) ignore_corner_orientations: [bool; NUM_CORNERS],
     pub(crate) ignore_edge_orientations: [bool; NUM_EDGES],
+    pub(crate) outer_corner_positions: [u8; NUM_OUTER_CORNERS],
+    pub(crate) outer_edge_positions: [u8; NUM_OUTER_EDGES],
+    pub(crate) outer_corner_orientations: u8,
+    pub(crate) outer_edge_orientations: u8,
+    pub(crate) frame: Frame,
     pub(crate) moves: Vec<Move>,
     pub(crate) last_move: Option<Move>,
 }
 
+const OUTER_CORNERS_SOLVED: [u8; NUM_OUTER_CORNERS] = [17, 18, 19];
+const OUTER_EDGES_SOLVED: [u8; NUM_OUTER_EDGES] = [23, 24, 25, 26, 27, 28, 29];
+
 impl Default for LLMinx {
     fn default() -> Self {
         Self::new()
@@ -45,6 +60,11 @@ impl LLMinx {
             ignore_edge_positions: [false; NUM_EDGES],
             ignore_corner_orientations: [false; NUM_CORNERS],
             ignore_edge_orientations: [false; NUM_EDGES],
+            outer_corner_positions: OUTER_CORNERS_SOLVED,
+            outer_edge_positions: OUTER_EDGES_SOLVED,
+            outer_corner_orientations: 0,
+            outer_edge_orientations: 0,
+            frame: Frame::new(),
             moves: Vec::with_capacity(MAX_SEARCH_DEPTH),
             last_move: None,
         }
@@ -65,6 +85,11 @@ impl LLMinx {
             ignore_edge_positions: [false; NUM_EDGES],
             ignore_corner_orientations: [false; NUM_CORNERS],
             ignore_edge_orientations: [false; NUM_EDGES],
+            outer_corner_positions: OUTER_CORNERS_SOLVED,
+            outer_edge_positions: OUTER_EDGES_SOLVED,
+            outer_corner_orientations: 0,
+            outer_edge_orientations: 0,
+            frame: Frame::new(),
             moves: Vec::with_capacity(MAX_SEARCH_DEPTH),
             last_move: None,
         }
@@ -143,6 +168,120 @@ impl LLMinx {
         self.edge_orientations = (self.edge_orientations & mask) | ((orientation as u32) << piece);
     }
 
+    pub fn outer_corner_positions(&self) -> &[u8; NUM_OUTER_CORNERS] {
+        &self.outer_corner_positions
+    }
+
+    pub fn outer_edge_positions(&self) -> &[u8; NUM_OUTER_EDGES] {
+        &self.outer_edge_positions
+    }
+
+    /// True when no piece has left or entered the tracked slots and the outer
+    /// pieces are all home, so the state can be searched with the seven faces.
+    pub fn outer_pieces_solved(&self) -> bool {
+        self.outer_corner_positions == OUTER_CORNERS_SOLVED
+            && self.outer_edge_positions == OUTER_EDGES_SOLVED
+            && self.outer_corner_orientations == 0
+            && self.outer_edge_orientations == 0
+    }
+
+    /// Orientation of the puzzle that notation letters are read through.
+    /// Rotations change it without moving any pieces.
+    #[inline]
+    pub fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    /// Piece in corner slot `slot`, counting the outer slots after the tracked ones.
+    #[inline]
+    pub(crate) fn corner_at(&self, slot: usize) -> u8 {
+        match slot.checked_sub(NUM_CORNERS) {
+            None => self.corner_positions[slot],
+            Some(outer) => self.outer_corner_positions[outer],
+        }
+    }
+
+    #[inline]
+    pub(crate) fn corner_orientation_at(&self, slot: usize) -> u8 {
+        match slot.checked_sub(NUM_CORNERS) {
+            None => self.get_corner_orientation(slot as u8),
+            Some(outer) => (self.outer_corner_orientations >> (outer * 2)) & 3,
+        }
+    }
+
+    pub(crate) fn set_corner_at(&mut self, slot: usize, piece: u8, orientation: u8) {
+        match slot.checked_sub(NUM_CORNERS) {
+            None => {
+                self.corner_positions[slot] = piece;
+                self.set_corner_orientation(slot as u8, orientation);
+            }
+            Some(outer) => {
+                self.outer_corner_positions[outer] = piece;
+                self.outer_corner_orientations = (self.outer_corner_orientations
+                    & !(3 << (outer * 2)))
+                    | (orientation << (outer * 2));
+            }
+        }
+    }
+
+    /// Piece in edge slot `slot`, counting the outer slots after the tracked ones.
+    #[inline]
+    pub(crate) fn edge_at(&self, slot: usize) -> u8 {
+        match slot.checked_sub(NUM_EDGES) {
+            None => self.edge_positions[slot],
+            Some(outer) => self.outer_edge_positions[outer],
+        }
+    }
+
+    #[inline]
+    pub(crate) fn edge_orientation_at(&self, slot: usize) -> u8 {
+        match slot.checked_sub(NUM_EDGES) {
+            None => self.get_edge_orientation(slot as u8),
+            Some(outer) => (self.outer_edge_orientations >> outer) & 1,
+        }
+    }
+
+    pub(crate) fn set_edge_at(&mut self, slot: usize, piece: u8, orientation: u8) {
+        match slot.checked_sub(NUM_EDGES) {
+            None => {
+                self.edge_positions[slot] = piece;
+                self.set_edge_orientation(slot as u8, orientation);
+            }
+            Some(outer) => {
+                self.outer_edge_positions[outer] = piece;
+                self.outer_edge_orientations =
+                    (self.outer_edge_orientations & !(1 << outer)) | (orientation << outer);
+            }
+        }
+    }
+
+    /// Outer pieces have no ignore flags, so they always have to match.
+    #[inline]
+    pub(crate) fn ignores_corner(&self, piece: u8) -> (bool, bool) {
+        let i = piece as usize;
+        if i < NUM_CORNERS {
+            (
+                self.ignore_corner_positions[i],
+                self.ignore_corner_orientations[i],
+            )
+        } else {
+            (false, false)
+        }
+    }
+
+    #[inline]
+    pub(crate) fn ignores_edge(&self, piece: u8) -> (bool, bool) {
+        let i = piece as usize;
+        if i < NUM_EDGES {
+            (
+                self.ignore_edge_positions[i],
+                self.ignore_edge_orientations[i],
+            )
+        } else {
+            (false, false)
+        }
+    }
+
     pub fn ignore_corner_positions(&self) -> &[bool; NUM_CORNERS] {
         &self.ignore_corner_positions
     }
@@ -197,14 +336,19 @@ impl LLMinx {
 
     pub fn get_generating_moves(&self) -> String {
         let mut result = String::with_capacity(self.moves.len() * 4);
-        let mut moves = self.moves.clone();
-        while Self::simplify_moves(&mut moves) {}
-        for m in moves {
+        for m in self.generating_moves() {
             result.push_str(m.to_string());
         }
         result
     }
 
+    /// Move history with repeated quarter turns merged into double turns.
+    pub fn generating_moves(&self) -> Vec<Move> {
+        let mut moves = self.moves.clone();
+        while Self::simplify_moves(&mut moves) {}
+        moves
+    }
+
     fn simplify_moves(moves: &mut Vec<Move>) -> bool {
         for i in 1..moves.len() {
             if moves[i] == moves[i - 1] && (moves[i] as u8) % 4 < 2 {
@@ -243,30 +387,28 @@ impl LLMinx {
         length
     }
 
+    /// Compare piece positions and orientations, skipping pieces `self`
+    /// ignores. The frame is not compared: a rotated puzzle is still solved.
     pub fn state_equals(&self, other: &LLMinx) -> bool {
-        for i in 0..NUM_CORNERS {
-            let piece = self.corner_positions[i];
-            if self.corner_positions[i] != other.corner_positions[i]
-                && !self.ignore_corner_positions[piece as usize]
-            {
+        for i in 0..NUM_CORNERS + NUM_OUTER_CORNERS {
+            let piece = self.corner_at(i);
+            let (ignore_position, ignore_orientation) = self.ignores_corner(piece);
+            if piece != other.corner_at(i) && !ignore_position {
                 return false;
             }
-            if self.get_corner_orientation(i as u8) != other.get_corner_orientation(i as u8)
-                && !self.ignore_corner_orientations[piece as usize]
+            if self.corner_orientation_at(i) != other.corner_orientation_at(i)
+                && !ignore_orientation
             {
                 return false;
             }
         }
-        for i in 0..NUM_EDGES {
-            let piece = self.edge_positions[i];
-            if self.edge_positions[i] != other.edge_positions[i]
-                && !self.ignore_edge_positions[piece as usize]
-            {
+        for i in 0..NUM_EDGES + NUM_OUTER_EDGES {
+            let piece = self.edge_at(i);
+            let (ignore_position, ignore_orientation) = self.ignores_edge(piece);
+            if piece != other.edge_at(i) && !ignore_position {
                 return false;
             }
-            if self.get_edge_orientation(i as u8) != other.get_edge_orientation(i as u8)
-                && !self.ignore_edge_orientations[piece as usize]
-            {
+            if self.edge_orientation_at(i) != other.edge_orientation_at(i) && !ignore_orientation {
                 return false;
             }
         }
@@ -0,0 +1,231 @@
+//! Table-driven turns of all twelve faces
+//!
+//! The search only turns the seven faces with hand-written transforms in the
+//! sibling modules. Notation can also reach the other five (the four lower
+//! faces round the bottom and the bottom itself), so every face turn is also
+//! described here as a 5-cycle over the full set of slots: the 17 tracked
+//! corners followed by the 3 outer ones, and the 23 tracked edges followed by
+//! the 7 outer ones.
+//!
+//! The rows for the seven tracked faces repeat the hand-written transforms,
+//! which a test checks. Those transforms do not follow one facelet convention
+//! around every corner (the bR and D rows disagree with the rest), so the
+//! twists and flips of the five outer rows come from reference facelets
+//! fitted to the other tracked faces. Each outer row still has zero net twist
+//! and flip, so every turn has order five and is undone by its inverse.
+
+use crate::minx::rotation::NUM_FACES;
+use crate::minx::state::LLMinx;
+
+/// Clockwise turn of one face: the piece in `corners[i]` moves to
+/// `corners[(i + 1) % 5]` and is twisted clockwise `twists[i]` times on the
+/// way; edges likewise, flipped when `flips[i]` is set.
+struct FaceCycle {
+    corners: [u8; 5],
+    twists: [u8; 5],
+    edges: [u8; 5],
+    flips: [u8; 5],
+}
+
+/// Indexed by face: R, L, U, F, bL, bR, D, then DL, DBL, B, DBR and the bottom.
+const FACE_CYCLES: [FaceCycle; NUM_FACES] = [
+    // R
+    FaceCycle {
+        corners: [1, 6, 5, 7, 2],
+        twists: [1, 0, 1, 0, 1],
+        edges: [4, 5, 6, 7, 8],
+        flips: [0, 0, 0, 0, 0],
+    },
+    // L
+    FaceCycle {
+        corners: [3, 9, 10, 11, 4],
+        twists: [0, 1, 0, 1, 1],
+        edges: [1, 11, 12, 13, 14],
+        flips: [0, 0, 0, 0, 0],
+    },
+    // U
+    FaceCycle {
+        corners: [0, 1, 2, 3, 4],
+        twists: [0, 0, 0, 0, 0],
+        edges: [0, 1, 2, 3, 4],
+        flips: [0, 0, 0, 0, 0],
+    },
+    // F
+    FaceCycle {
+        corners: [2, 7, 8, 9, 3],
+        twists: [1, 0, 0, 1, 1],
+        edges: [0, 8, 9, 10, 11],
+        flips: [1, 0, 0, 0, 1],
+    },
+    // bL
+    FaceCycle {
+        corners: [0, 4, 11, 12, 13],
+        twists: [1, 0, 1, 0, 1],
+        edges: [2, 14, 15, 16, 17],
+        flips: [1, 0, 0, 0, 1],
+    },
+    // bR
+    FaceCycle {
+        corners: [0, 13, 14, 6, 1],
+        twists: [1, 0, 1, 0, 1],
+        edges: [3, 17, 19, 18, 5],
+        flips: [1, 0, 0, 0, 1],
+    },
+    // D
+    FaceCycle {
+        corners: [5, 15, 16, 8, 7],
+        twists: [0, 0, 0, 0, 0],
+        edges: [7, 20, 21, 22, 9],
+        flips: [0, 0, 0, 0, 0],
+    },
+    // DL
+    FaceCycle {
+        corners: [8, 16, 17, 10, 9],
+        twists: [2, 2, 1, 0, 1],
+        edges: [10, 22, 24, 23, 12],
+        flips: [0, 1, 0, 0, 1],
+    },
+    // DBL
+    FaceCycle {
+        corners: [10, 17, 18, 12, 11],
+        twists: [0, 1, 1, 0, 1],
+        edges: [13, 23, 26, 25, 15],
+        flips: [1, 1, 0, 1, 1],
+    },
+    // B
+    FaceCycle {
+        corners: [12, 18, 19, 14, 13],
+        twists: [0, 1, 0, 1, 1],
+        edges: [16, 25, 28, 27, 19],
+        flips: [0, 1, 0, 0, 1],
+    },
+    // DBR
+    FaceCycle {
+        corners: [5, 6, 14, 19, 15],
+        twists: [1, 2, 1, 1, 1],
+        edges: [6, 18, 27, 29, 20],
+        flips: [1, 0, 1, 1, 1],
+    },
+    // bottom
+    FaceCycle {
+        corners: [15, 19, 18, 17, 16],
+        twists: [0, 0, 0, 2, 1],
+        edges: [21, 29, 28, 26, 24],
+        flips: [0, 0, 0, 0, 0],
+    },
+];
+
+impl LLMinx {
+    /// Turn any face `steps` clockwise fifths without recording a move.
+    pub(crate) fn turn_face(&mut self, face: u8, steps: usize) {
+        let cycle = &FACE_CYCLES[face as usize];
+        for _ in 0..steps {
+            let corners = cycle.corners.map(|slot| {
+                (
+                    self.corner_at(slot as usize),
+                    self.corner_orientation_at(slot as usize),
+                )
+            });
+            let edges = cycle.edges.map(|slot| {
+                (
+                    self.edge_at(slot as usize),
+                    self.edge_orientation_at(slot as usize),
+                )
+            });
+
+            for i in 0..5 {
+                let to = (i + 1) % 5;
+                let (piece, mut orientation) = corners[i];
+                for _ in 0..cycle.twists[i] {
+                    orientation = Self::corner_orient_cw(orientation);
+                }
+                self.set_corner_at(cycle.corners[to] as usize, piece, orientation);
+
+                let (piece, orientation) = edges[i];
+                self.set_edge_at(
+                    cycle.edges[to] as usize,
+                    piece,
+                    orientation ^ cycle.flips[i],
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minx::Move;
+    use crate::minx::rotation::NUM_TRACKED_FACES;
+    use crate::minx::state::{NUM_CORNERS, NUM_EDGES, NUM_OUTER_CORNERS, NUM_OUTER_EDGES};
+
+    fn scrambled() -> LLMinx {
+        let mut minx = LLMinx::new();
+        for m in [
+            Move::R,
+            Move::U2,
+            Move::Fi,
+            Move::bL,
+            Move::D2i,
+            Move::L,
+            Move::bR,
+        ] {
+            minx.apply_move(m);
+        }
+        minx
+    }
+
+    #[test]
+    fn test_tracked_rows_match_hand_written_moves() {
+        for face in 0..NUM_TRACKED_FACES {
+            for steps in 1..5 {
+                let mut by_table = scrambled();
+                by_table.turn_face(face, steps);
+                let mut by_move = scrambled();
+                for _ in 0..steps {
+                    by_move.apply_move(Move::from_u8(face * 4).unwrap());
+                }
+                assert_eq!(by_table.corner_positions, by_move.corner_positions);
+                assert_eq!(by_table.edge_positions, by_move.edge_positions);
+                assert_eq!(by_table.corner_orientations, by_move.corner_orientations);
+                assert_eq!(by_table.edge_orientations, by_move.edge_orientations);
+            }
+        }
+    }
+
+    #[test]
+    fn test_outer_turns_have_order_five() {
+        for face in NUM_TRACKED_FACES..NUM_FACES as u8 {
+            let mut minx = scrambled();
+            minx.turn_face(face, 1);
+            assert!(!minx.state_equals(&scrambled()), "face {}", face);
+            minx.turn_face(face, 4);
+            assert!(minx.state_equals(&scrambled()), "face {}", face);
+        }
+    }
+
+    #[test]
+    fn test_outer_turns_move_outer_pieces() {
+        let mut minx = LLMinx::new();
+        minx.turn_face(11, 1);
+        assert!(!minx.outer_pieces_solved());
+        assert_eq!(minx.outer_corner_positions(), &[18, 19, 15]);
+        assert_eq!(minx.corner_positions[16], 17);
+    }
+
+    #[test]
+    fn test_every_slot_is_on_three_or_two_faces() {
+        let mut corner_faces = [0; NUM_CORNERS + NUM_OUTER_CORNERS];
+        let mut edge_faces = [0; NUM_EDGES + NUM_OUTER_EDGES];
+        for cycle in &FACE_CYCLES {
+            for &slot in &cycle.corners {
+                corner_faces[slot as usize] += 1;
+            }
+            for &slot in &cycle.edges {
+                edge_faces[slot as usize] += 1;
+            }
+        }
+        assert!(corner_faces.iter().all(|&n| n == 3));
+        assert!(edge_faces.iter().all(|&n| n == 2));
+    }
+}
@@ -5,6 +5,7 @@ pub mod bl_moves;
 pub mod br_moves;
 pub mod d_moves;
 pub mod f_moves;
+pub mod face_turns;
 pub mod l_moves;
 pub mod r_moves;
 pub mod u_moves;
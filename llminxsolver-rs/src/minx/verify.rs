@@ -0,0 +1,178 @@
+//! Replaying solutions to check they reach the goal
+//!
+//! Comparison follows `LLMinx::state_equals`: the ignore flags of the
+//! replayed state decide which pieces may be left unsolved.
+
+use super::moves::Move;
+use super::state::{LLMinx, NUM_CORNERS, NUM_EDGES, NUM_OUTER_CORNERS, NUM_OUTER_EDGES};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PieceKind {
+    Corner,
+    Edge,
+}
+
+/// A position left wrong after replaying a solution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsolvedPiece {
+    pub kind: PieceKind,
+    pub position: u8,
+    /// Piece found at `position`.
+    pub piece: u8,
+    /// The goal has a different piece at `position`.
+    pub permuted: bool,
+    /// The piece is twisted or flipped relative to the goal.
+    pub misoriented: bool,
+}
+
+impl fmt::Display for UnsolvedPiece {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            PieceKind::Corner => "corner",
+            PieceKind::Edge => "edge",
+        };
+        write!(f, "{} {}", kind, self.position)?;
+        match (self.permuted, self.misoriented) {
+            (true, true) => write!(f, " (holds {}, misoriented)", self.piece),
+            (true, false) => write!(f, " (holds {})", self.piece),
+            _ => write!(f, " (misoriented)"),
+        }
+    }
+}
+
+/// Error returned by `verify` when a solution does not reach the goal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyError {
+    pub unsolved: Vec<UnsolvedPiece>,
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} pieces unsolved: ", self.unsolved.len())?;
+        for (i, piece) in self.unsolved.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", piece)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// Apply `solution` to `start` and check the result matches `goal`.
+pub fn verify(start: &LLMinx, solution: &[Move], goal: &LLMinx) -> Result<(), VerifyError> {
+    let mut minx = start.clone();
+    minx.clear_moves();
+    for &m in solution {
+        minx.apply_move(m);
+    }
+    let unsolved = unsolved_pieces(&minx, goal);
+    if unsolved.is_empty() {
+        Ok(())
+    } else {
+        Err(VerifyError { unsolved })
+    }
+}
+
+/// Positions where `state` differs from `goal`, skipping pieces `state`
+/// ignores. Empty exactly when `state.state_equals(goal)`.
+pub fn unsolved_pieces(state: &LLMinx, goal: &LLMinx) -> Vec<UnsolvedPiece> {
+    let mut unsolved = Vec::new();
+    for i in 0..NUM_CORNERS + NUM_OUTER_CORNERS {
+        let piece = state.corner_at(i);
+        let (ignore_position, ignore_orientation) = state.ignores_corner(piece);
+        let permuted = piece != goal.corner_at(i) && !ignore_position;
+        let misoriented =
+            state.corner_orientation_at(i) != goal.corner_orientation_at(i) && !ignore_orientation;
+        if permuted || misoriented {
+            unsolved.push(UnsolvedPiece {
+                kind: PieceKind::Corner,
+                position: i as u8,
+                piece,
+                permuted,
+                misoriented,
+            });
+        }
+    }
+    for i in 0..NUM_EDGES + NUM_OUTER_EDGES {
+        let piece = state.edge_at(i);
+        let (ignore_position, ignore_orientation) = state.ignores_edge(piece);
+        let permuted = piece != goal.edge_at(i) && !ignore_position;
+        let misoriented =
+            state.edge_orientation_at(i) != goal.edge_orientation_at(i) && !ignore_orientation;
+        if permuted || misoriented {
+            unsolved.push(UnsolvedPiece {
+                kind: PieceKind::Edge,
+                position: i as u8,
+                piece,
+                permuted,
+                misoriented,
+            });
+        }
+    }
+    unsolved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minx::{invert_alg, parse_alg};
+
+    #[test]
+    fn test_verify_accepts_inverse_of_setup() {
+        let setup = parse_alg("R U R' U R U2' R'").unwrap();
+        let start = LLMinx::from_alg("R U R' U R U2' R'").unwrap();
+        assert_eq!(verify(&start, &invert_alg(&setup), &LLMinx::new()), Ok(()));
+        assert!(verify(&start, &setup, &LLMinx::new()).is_err());
+    }
+
+    #[test]
+    fn test_verify_reports_unsolved_pieces() {
+        let start = LLMinx::new();
+        let err = verify(&start, &[Move::U], &LLMinx::new()).unwrap_err();
+        assert_eq!(err.unsolved.len(), 10);
+        assert!(err.unsolved.iter().all(|p| p.permuted));
+        assert_eq!(
+            err.unsolved
+                .iter()
+                .filter(|p| p.kind == PieceKind::Corner)
+                .count(),
+            5
+        );
+        assert!(err.to_string().starts_with("10 pieces unsolved: corner 0"));
+    }
+
+    #[test]
+    fn test_verify_respects_ignore_flags() {
+        let mut start = LLMinx::new();
+        let mut ignore_corners = [false; NUM_CORNERS];
+        let mut ignore_edges = [false; NUM_EDGES];
+        ignore_corners[..5].fill(true);
+        ignore_edges[..5].fill(true);
+        start.set_ignore_corner_positions(ignore_corners);
+        start.set_ignore_edge_positions(ignore_edges);
+        start.set_ignore_corner_orientations(ignore_corners);
+        start.set_ignore_edge_orientations(ignore_edges);
+
+        assert!(verify(&start, &[Move::U], &LLMinx::new()).is_ok());
+        let err = verify(&start, &[Move::R], &LLMinx::new()).unwrap_err();
+        assert!(err.unsolved.iter().all(|p| p.piece >= 5));
+    }
+
+    #[test]
+    fn test_unsolved_pieces_matches_state_equals() {
+        for alg in ["", "R U R'", "U2", "F R U R' U' F'"] {
+            let state = LLMinx::from_alg(alg).unwrap();
+            let goal = LLMinx::new();
+            assert_eq!(
+                unsolved_pieces(&state, &goal).is_empty(),
+                state.state_equals(&goal),
+                "{}",
+                alg
+            );
+        }
+    }
+}
@@ -1,8 +1,13 @@
 use crate::StatusCallback;
+use crate::canonical::{DedupMode, Deduplicator};
+use crate::filter::SharedFilter;
 use crate::memory_config::MemoryConfig;
 use crate::minx::LLMinx;
 use crate::pruner::{DEFAULT_PRUNING_DEPTH, MAX_PRUNING_DEPTH, MIN_PRUNING_DEPTH};
+use crate::ranking::{SolutionRanking, TopSolutions};
+use crate::result_cache::ResultCache;
 use crate::search_mode::{Metric, SearchMode};
+use crate::solution::Solution;
 use crate::solver::{Solver, StatusEvent, StatusEventType};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -12,6 +17,11 @@ pub struct ParallelSolver {
     metric: Metric,
     max_search_depth: usize,
     limit_search_depth: bool,
+    depth_slack: Option<usize>,
+    ranking: Option<SolutionRanking>,
+    dedup: DedupMode,
+    filters: Vec<SharedFilter>,
+    result_cache: Option<Arc<ResultCache>>,
     pruning_depth: u8,
     mode_pruning_depths: std::collections::HashMap<SearchMode, u8>,
     memory_config: MemoryConfig,
@@ -46,6 +56,11 @@ impl ParallelSolver {
             metric: Metric::Fifth,
             max_search_depth: 12,
             limit_search_depth: false,
+            depth_slack: None,
+            ranking: None,
+            dedup: DedupMode::Off,
+            filters: Vec::new(),
+            result_cache: None,
             pruning_depth: DEFAULT_PRUNING_DEPTH,
             mode_pruning_depths: std::collections::HashMap::new(),
             memory_config,
@@ -107,6 +122,51 @@ impl ParallelSolver {
         self.limit_search_depth = limit;
     }
 
+    pub fn depth_slack(&self) -> Option<usize> {
+        self.depth_slack
+    }
+
+    /// Stop each mode's search `slack` moves past its first depth with solutions.
+    pub fn set_depth_slack(&mut self, slack: Option<usize>) {
+        self.depth_slack = slack;
+    }
+
+    pub fn ranking(&self) -> Option<&SolutionRanking> {
+        self.ranking.as_ref()
+    }
+
+    /// Keep only the best `top_k` solutions across all modes.
+    pub fn set_ranking(&mut self, ranking: Option<SolutionRanking>) {
+        self.ranking = ranking;
+    }
+
+    pub fn dedup(&self) -> DedupMode {
+        self.dedup
+    }
+
+    /// Keep one solution per class of equivalent reorderings across all modes.
+    pub fn set_dedup(&mut self, dedup: DedupMode) {
+        self.dedup = dedup;
+    }
+
+    pub fn filters(&self) -> &[SharedFilter] {
+        &self.filters
+    }
+
+    /// Drop solutions that any of `filters` rejects, in every mode.
+    pub fn set_filters(&mut self, filters: Vec<SharedFilter>) {
+        self.filters = filters;
+    }
+
+    pub fn result_cache(&self) -> Option<&Arc<ResultCache>> {
+        self.result_cache.as_ref()
+    }
+
+    /// Share `cache` between the searches of every mode.
+    pub fn set_result_cache(&mut self, cache: Option<Arc<ResultCache>>) {
+        self.result_cache = cache;
+    }
+
     pub fn pruning_depth(&self) -> u8 {
         self.pruning_depth
     }
@@ -176,7 +236,7 @@ impl ParallelSolver {
         self.interrupted.load(Ordering::SeqCst)
     }
 
-    pub fn solve(&mut self, start: LLMinx) -> Vec<String> {
+    pub fn solve(&mut self, start: LLMinx) -> Vec<Solution> {
         let start_time = std::time::Instant::now();
         self.interrupted.store(false, Ordering::SeqCst);
 
@@ -199,6 +259,11 @@ impl ParallelSolver {
         let metric = self.metric;
         let max_search_depth = self.max_search_depth;
         let limit_search_depth = self.limit_search_depth;
+        let depth_slack = self.depth_slack;
+        let ranking = self.ranking;
+        let dedup = self.dedup;
+        let filters = &self.filters;
+        let result_cache = &self.result_cache;
         let memory_config = self.memory_config;
         let ignore_corner_positions = self.ignore_corner_positions;
         let ignore_edge_positions = self.ignore_edge_positions;
@@ -215,7 +280,7 @@ impl ParallelSolver {
             threads_per_mode,
         );
 
-        std::thread::scope(|s| {
+        let solutions = std::thread::scope(|s| {
             let handles: Vec<_> = modes_with_depths
                 .into_iter()
                 .map(|(mode, pruning_depth)| {
@@ -226,13 +291,18 @@ impl ParallelSolver {
 
                     s.spawn(move || {
                         if interrupted_clone.load(Ordering::Relaxed) {
-                            return;
+                            return Vec::new();
                         }
 
                         let mut solver =
                             Solver::with_parallel_config(mode, max_search_depth, mode_config_clone);
                         solver.set_metric(metric);
                         solver.set_limit_search_depth(limit_search_depth);
+                        solver.set_depth_slack(depth_slack);
+                        solver.set_ranking(ranking);
+                        solver.set_dedup(dedup);
+                        solver.set_filters(filters.clone());
+                        solver.set_result_cache(result_cache.clone());
                         solver.set_pruning_depth(pruning_depth);
                         solver.set_start(start_clone);
                         solver.set_ignore_corner_positions(ignore_corner_positions);
@@ -251,27 +321,24 @@ impl ParallelSolver {
 
                         if let Some(ref cb) = callback_clone {
                             let cb_ref = Arc::clone(cb);
-                            let mode_name = format!("{:?}", mode);
+                            let mode_name = mode.to_string();
                             solver.set_status_callback(move |event| {
-                                let event_with_mode = StatusEvent::with_context(
-                                    event.event_type,
-                                    &event.message,
-                                    event.progress,
-                                    Some(mode_name.clone()),
-                                    event.current_depth,
-                                );
-                                cb_ref(event_with_mode);
+                                cb_ref(StatusEvent {
+                                    search_mode: Some(mode_name.clone()),
+                                    ..event
+                                });
                             });
                         }
 
-                        solver.solve();
+                        solver.solve()
                     })
                 })
                 .collect();
 
-            for handle in handles {
-                let _ = handle.join();
-            }
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap_or_default())
+                .collect::<Vec<_>>()
         });
 
         let elapsed = start_time.elapsed();
@@ -286,14 +353,39 @@ impl ParallelSolver {
 
         self.fire_event(StatusEvent::new(StatusEventType::FinishSearch, &msg, 1.0));
 
-        Vec::new()
+        if self.ranking.is_none() && self.dedup == DedupMode::Off {
+            return solutions;
+        }
+        // Each mode has already merged its own variants, so classes found by
+        // several modes add up their counts.
+        let mut dedup = Deduplicator::new(self.dedup);
+        let mut merged = std::collections::HashMap::new();
+        let mut top = TopSolutions::new(self.ranking);
+        for solution in solutions {
+            let key = self.dedup.key(&solution.moves);
+            *merged.entry(key).or_insert(0) += solution.merged_variants;
+            if dedup.insert(&solution) {
+                top.push(solution);
+            }
+        }
+        let mut solutions = top.into_sorted_vec();
+        for solution in &mut solutions {
+            dedup.annotate(solution);
+            solution.merged_variants += merged[&self.dedup.key(&solution.moves)];
+        }
+        solutions
     }
 
-    fn solve_single_mode(&mut self, start: LLMinx, mode: SearchMode) -> Vec<String> {
+    fn solve_single_mode(&mut self, start: LLMinx, mode: SearchMode) -> Vec<Solution> {
         let mut solver =
             Solver::with_parallel_config(mode, self.max_search_depth, self.memory_config);
         solver.set_metric(self.metric);
         solver.set_limit_search_depth(self.limit_search_depth);
+        solver.set_depth_slack(self.depth_slack);
+        solver.set_ranking(self.ranking);
+        solver.set_dedup(self.dedup);
+        solver.set_filters(self.filters.clone());
+        solver.set_result_cache(self.result_cache.clone());
         solver.set_pruning_depth(self.get_pruning_depth_for_mode(mode));
         solver.set_start(start);
         solver.set_ignore_corner_positions(self.ignore_corner_positions);
@@ -374,6 +466,9 @@ mod tests {
         solver.set_metric(Metric::Face);
         solver.set_max_search_depth(7);
         solver.set_limit_search_depth(true);
+        solver.set_depth_slack(Some(1));
+        let ranking = SolutionRanking::new(crate::ranking::RankScore::Mcc, 3);
+        solver.set_ranking(Some(ranking));
         solver.set_pruning_depth(255);
         solver.set_mode_pruning_depth(SearchMode::RU, 1);
 
@@ -383,6 +478,8 @@ mod tests {
         assert_eq!(solver.metric(), Metric::Face);
         assert_eq!(solver.max_search_depth(), 7);
         assert!(solver.limit_search_depth());
+        assert_eq!(solver.depth_slack(), Some(1));
+        assert_eq!(solver.ranking(), Some(&ranking));
         assert_eq!(solver.pruning_depth(), MAX_PRUNING_DEPTH);
         assert_eq!(solver.memory_config().budget_mb(), 128);
     }
@@ -1,17 +1,19 @@
 use crate::coordinate::{CKN, CoordinateUtil, FAC, POWERS_OF_THREE, POWERS_OF_TWO};
-use crate::data_directory::get_data_directory;
 use crate::minx::{LLMinx, NUM_CORNERS, NUM_EDGES};
 use crate::search_mode::Metric;
-use lz4_flex::{compress_prepend_size, decompress_size_prepended};
-use std::fs::{self, File};
-use std::io::{BufReader, BufWriter, Read, Write};
-use std::path::PathBuf;
+use crate::table_file::{
+    TableError, TableHeader, map_table_cache, read_table_file, write_table_cache, write_table_file,
+};
+use crate::table_registry::PruningTable;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 pub const MIN_PRUNING_DEPTH: u8 = 8;
 pub const MAX_PRUNING_DEPTH: u8 = 18;
 pub const DEFAULT_PRUNING_DEPTH: u8 = 12;
 
-const COMPRESSED_EXTENSION: &str = ".prn.lz4";
+pub(crate) const COMPRESSED_EXTENSION: &str = ".prn.lz4";
+pub(crate) const CACHE_EXTENSION: &str = ".prn";
 
 pub trait Pruner: Send + Sync {
     fn name(&self) -> &str;
@@ -24,33 +26,28 @@ pub trait Pruner: Send + Sync {
     fn uses_corner_orientation(&self) -> bool;
     fn uses_edge_orientation(&self) -> bool;
 
-    fn get_table_file(&self, metric: Metric, depth: u8) -> PathBuf {
-        let metric_suffix = match metric {
-            Metric::Fifth => "FIFTH",
-            Metric::Face => "FACE",
-        };
-        let filename = format!(
-            "d{}_{}{}{}",
-            depth,
-            self.table_path(),
-            metric_suffix,
-            COMPRESSED_EXTENSION
-        );
+    /// Path of the compressed table in the table directory `dir`.
+    fn get_table_file(&self, dir: &Path, metric: Metric, depth: u8) -> PathBuf {
+        table_file_path(dir, self.table_path(), metric, depth, COMPRESSED_EXTENSION)
+    }
 
-        if let Some(data_dir) = get_data_directory() {
-            data_dir.join(&filename)
-        } else {
-            PathBuf::from(filename)
-        }
+    /// Path of the uncompressed cache that is memory-mapped at load time.
+    fn get_cache_file(&self, dir: &Path, metric: Metric, depth: u8) -> PathBuf {
+        table_file_path(dir, self.table_path(), metric, depth, CACHE_EXTENSION)
     }
 
-    fn is_precomputed(&self, metric: Metric, depth: u8) -> bool {
-        self.get_table_file(metric, depth).exists()
+    fn is_precomputed(&self, dir: &Path, metric: Metric, depth: u8) -> bool {
+        self.get_table_file(dir, metric, depth).exists()
     }
 
-    fn find_best_existing_table(&self, metric: Metric, max_depth: u8) -> Option<(PathBuf, u8)> {
+    fn find_best_existing_table(
+        &self,
+        dir: &Path,
+        metric: Metric,
+        max_depth: u8,
+    ) -> Option<(PathBuf, u8)> {
         for depth in (MIN_PRUNING_DEPTH..=max_depth).rev() {
-            let path = self.get_table_file(metric, depth);
+            let path = self.get_table_file(dir, metric, depth);
             if path.exists() {
                 return Some((path, depth));
             }
@@ -58,33 +55,69 @@ pub trait Pruner: Send + Sync {
         None
     }
 
-    fn load_table(&self, metric: Metric, depth: u8) -> Option<Vec<u8>> {
-        let path = self.get_table_file(metric, depth);
-        if path.exists() {
-            return self.load_compressed_table(&path);
-        }
-        None
+    /// Load a table from disk, explaining why it cannot be used if it fails
+    /// header or checksum verification.
+    fn read_table(&self, dir: &Path, metric: Metric, depth: u8) -> Result<Vec<u8>, TableError> {
+        let path = self.get_table_file(dir, metric, depth);
+        self.load_compressed_table(&path, metric, depth)
     }
 
-    fn load_compressed_table(&self, path: &PathBuf) -> Option<Vec<u8>> {
-        let file = File::open(path).ok()?;
-        let mut reader = BufReader::with_capacity(1 << 20, file);
-        let mut compressed = Vec::new();
-        reader.read_to_end(&mut compressed).ok()?;
-        decompress_size_prepended(&compressed).ok()
+    fn load_table(&self, dir: &Path, metric: Metric, depth: u8) -> Option<Vec<u8>> {
+        self.read_table(dir, metric, depth).ok()
     }
 
-    fn save_table(&self, table: &[u8], metric: Metric, depth: u8) {
-        let path = self.get_table_file(metric, depth);
-        if let Some(parent) = path.parent() {
-            let _ = fs::create_dir_all(parent);
-        }
-        if let Ok(file) = File::create(&path) {
-            let compressed = compress_prepend_size(table);
-            let mut writer = BufWriter::with_capacity(1 << 22, file);
-            let _ = writer.write_all(&compressed);
-            let _ = writer.flush();
-        }
+    fn load_compressed_table(
+        &self,
+        path: &Path,
+        metric: Metric,
+        depth: u8,
+    ) -> Result<Vec<u8>, TableError> {
+        read_table_file(path, self.table_path(), metric, depth, self.table_size())
+    }
+
+    fn save_table(&self, dir: &Path, table: &[u8], metric: Metric, depth: u8) {
+        let path = self.get_table_file(dir, metric, depth);
+        let _ = fs::create_dir_all(dir);
+        let header = TableHeader::new(self.table_path(), metric, depth, table);
+        let _ = write_table_file(&path, &header, table);
+    }
+
+    /// Map the uncompressed cache for this table.
+    fn map_table(&self, dir: &Path, metric: Metric, depth: u8) -> Result<PruningTable, TableError> {
+        let path = self.get_cache_file(dir, metric, depth);
+        map_table_cache(&path, self.table_path(), metric, depth, self.table_size())
+    }
+
+    /// Write the uncompressed cache so later loads can map the table.
+    fn save_table_cache(&self, dir: &Path, table: &[u8], metric: Metric, depth: u8) -> bool {
+        let path = self.get_cache_file(dir, metric, depth);
+        let _ = fs::create_dir_all(dir);
+        let header = TableHeader::new(self.table_path(), metric, depth, table);
+        write_table_cache(&path, &header, table).is_ok()
+    }
+}
+
+fn table_file_path(
+    dir: &Path,
+    table_path: &str,
+    metric: Metric,
+    depth: u8,
+    extension: &str,
+) -> PathBuf {
+    dir.join(format!(
+        "d{}_{}{}{}",
+        depth,
+        table_path,
+        metric_suffix(metric),
+        extension
+    ))
+}
+
+/// Suffix naming the metric in table file names.
+pub(crate) fn metric_suffix(metric: Metric) -> &'static str {
+    match metric {
+        Metric::Fifth => "FIFTH",
+        Metric::Face => "FACE",
     }
 }
 
@@ -453,6 +486,10 @@ impl Pruner for CompositePruner {
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    fn test_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("llminx_pruner_test_{}", std::process::id()))
+    }
     use crate::minx::CornerPosition;
 
     fn create_test_corner_orientation_pruner() -> CornerOrientationPruner {
@@ -579,7 +616,7 @@ mod tests {
     #[test]
     fn test_get_table_file_fifth() {
         let pruner = create_test_corner_orientation_pruner();
-        let path = pruner.get_table_file(Metric::Fifth, 12);
+        let path = pruner.get_table_file(&test_dir(), Metric::Fifth, 12);
         let filename = path.file_name().unwrap().to_str().unwrap();
         assert!(filename.contains("FIFTH"));
         assert!(filename.contains("d12"));
@@ -588,7 +625,7 @@ mod tests {
     #[test]
     fn test_get_table_file_face() {
         let pruner = create_test_corner_orientation_pruner();
-        let path = pruner.get_table_file(Metric::Face, 10);
+        let path = pruner.get_table_file(&test_dir(), Metric::Face, 10);
         let filename = path.file_name().unwrap().to_str().unwrap();
         assert!(filename.contains("FACE"));
         assert!(filename.contains("d10"));
@@ -598,14 +635,15 @@ mod tests {
     fn test_is_precomputed() {
         let pruner = create_test_corner_orientation_pruner();
         // This should be false unless a table file exists
-        assert!(!pruner.is_precomputed(Metric::Fifth, 99));
+        assert!(!pruner.is_precomputed(&test_dir(), Metric::Fifth, 99));
     }
 
     #[test]
     fn test_find_best_existing_table_none() {
         let pruner = create_test_corner_orientation_pruner();
         // Deterministic None: max depth below MIN_PRUNING_DEPTH means no candidate depths.
-        let result = pruner.find_best_existing_table(Metric::Fifth, MIN_PRUNING_DEPTH - 1);
+        let result =
+            pruner.find_best_existing_table(&test_dir(), Metric::Fifth, MIN_PRUNING_DEPTH - 1);
         assert!(result.is_none());
     }
 
@@ -654,7 +692,7 @@ mod tests {
     #[test]
     fn test_load_table_nonexistent() {
         let pruner = create_test_corner_orientation_pruner();
-        let result = pruner.load_table(Metric::Fifth, 99);
+        let result = pruner.load_table(&test_dir(), Metric::Fifth, 99);
         assert!(result.is_none());
     }
 
@@ -790,7 +828,7 @@ mod tests {
     #[test]
     fn test_get_table_file_extensions() {
         let pruner = create_test_corner_orientation_pruner();
-        let path = pruner.get_table_file(Metric::Fifth, 12);
+        let path = pruner.get_table_file(&test_dir(), Metric::Fifth, 12);
         let path_str = path.to_string_lossy();
         assert!(path_str.ends_with(".prn.lz4"));
     }
@@ -799,8 +837,8 @@ mod tests {
     fn test_load_compressed_table_invalid_path() {
         let pruner = create_test_corner_orientation_pruner();
         let invalid_path = std::path::PathBuf::from("/nonexistent/path/table.prn.lz4");
-        let result = pruner.load_compressed_table(&invalid_path);
-        assert!(result.is_none());
+        let result = pruner.load_compressed_table(&invalid_path, Metric::Fifth, 12);
+        assert_eq!(result, Err(TableError::Missing));
     }
 
     #[test]
@@ -900,8 +938,8 @@ mod tests {
     #[test]
     fn test_get_table_file_both_metrics() {
         let pruner = create_test_edge_orientation_pruner();
-        let face = pruner.get_table_file(Metric::Face, 9);
-        let fifth = pruner.get_table_file(Metric::Fifth, 9);
+        let face = pruner.get_table_file(&test_dir(), Metric::Face, 9);
+        let fifth = pruner.get_table_file(&test_dir(), Metric::Fifth, 9);
 
         let face_name = face.file_name().unwrap().to_string_lossy().to_string();
         let fifth_name = fifth.file_name().unwrap().to_string_lossy().to_string();
@@ -912,37 +950,47 @@ mod tests {
 
     #[test]
     fn test_save_load_and_find_best_existing_table() {
-        use crate::data_directory::set_data_directory;
-
-        let temp_dir = std::env::temp_dir().join("llminx_pruner_test_data");
-        let _ = std::fs::create_dir_all(&temp_dir);
-        set_data_directory(temp_dir.to_string_lossy().as_ref());
-
         let pruner = create_test_corner_orientation_pruner();
         let table_a = vec![0u8, 1, 2, 3, 4, 5, 6, 7, 8];
         let table_b = vec![8u8, 7, 6, 5, 4, 3, 2, 1, 0];
 
-        pruner.save_table(&table_a, Metric::Fifth, MIN_PRUNING_DEPTH);
-        pruner.save_table(&table_b, Metric::Fifth, MIN_PRUNING_DEPTH + 1);
+        pruner.save_table(&test_dir(), &table_a, Metric::Fifth, MIN_PRUNING_DEPTH);
+        pruner.save_table(&test_dir(), &table_b, Metric::Fifth, MIN_PRUNING_DEPTH + 1);
 
-        assert!(pruner.is_precomputed(Metric::Fifth, MIN_PRUNING_DEPTH));
+        assert!(pruner.is_precomputed(&test_dir(), Metric::Fifth, MIN_PRUNING_DEPTH));
 
         let loaded = pruner
-            .load_table(Metric::Fifth, MIN_PRUNING_DEPTH + 1)
+            .load_table(&test_dir(), Metric::Fifth, MIN_PRUNING_DEPTH + 1)
             .expect("expected table");
         assert_eq!(loaded, table_b);
 
         let best = pruner
-            .find_best_existing_table(Metric::Fifth, MIN_PRUNING_DEPTH + 1)
+            .find_best_existing_table(&test_dir(), Metric::Fifth, MIN_PRUNING_DEPTH + 1)
             .expect("best table expected");
         assert_eq!(best.1, MIN_PRUNING_DEPTH + 1);
     }
 
+    #[test]
+    fn test_read_table_rejects_table_saved_for_other_layout() {
+        let pruner = create_test_corner_orientation_pruner();
+        let depth = MIN_PRUNING_DEPTH + 2;
+        pruner.save_table(&test_dir(), &[0u8; 5], Metric::Face, depth);
+
+        let result = pruner.read_table(&test_dir(), Metric::Face, depth);
+        assert!(matches!(result, Err(TableError::Mismatch(_))));
+        assert!(
+            pruner
+                .load_table(&test_dir(), Metric::Face, depth)
+                .is_none()
+        );
+        let _ = std::fs::remove_file(pruner.get_table_file(&test_dir(), Metric::Face, depth));
+    }
+
     #[test]
     fn test_get_table_file_name_format_includes_all_parts() {
         let pruner = create_test_edge_permutation_pruner();
-        let face_path = pruner.get_table_file(Metric::Face, 11);
-        let fifth_path = pruner.get_table_file(Metric::Fifth, 11);
+        let face_path = pruner.get_table_file(&test_dir(), Metric::Face, 11);
+        let fifth_path = pruner.get_table_file(&test_dir(), Metric::Fifth, 11);
 
         let face_name = face_path
             .file_name()
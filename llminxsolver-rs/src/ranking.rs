@@ -0,0 +1,295 @@
+//! Keeping only the best solutions of a search
+//!
+//! A `SolutionRanking` scores solutions, lower being better, and caps how
+//! many are kept. `TopSolutions` holds them in a max-heap keyed on score, so
+//! a new solution only has to beat the current worst to get in and memory
+//! stays bounded however many solutions the search finds.
+
+use crate::mcc::MCCParams;
+use crate::solution::Solution;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// What solutions are ranked by. Lower scores rank first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RankScore {
+    Mcc,
+    Ftm,
+    Fftm,
+    /// Weighted sum of MCC, FTM and FFTM.
+    Blend {
+        mcc: f64,
+        ftm: f64,
+        fftm: f64,
+    },
+}
+
+impl std::str::FromStr for RankScore {
+    type Err = String;
+
+    /// Accepts `mcc`, `ftm`, `fftm` or `blend:MCC,FTM,FFTM` with three weights.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        match s.as_str() {
+            "mcc" => return Ok(RankScore::Mcc),
+            "ftm" => return Ok(RankScore::Ftm),
+            "fftm" => return Ok(RankScore::Fftm),
+            _ => {}
+        }
+        let weights: Vec<f64> = s
+            .strip_prefix("blend:")
+            .ok_or_else(|| format!("Unknown ranking score: '{}'", s))?
+            .split(',')
+            .map(|w| w.trim().parse::<f64>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("Invalid blend weights: '{}'", s))?;
+        match weights[..] {
+            [mcc, ftm, fftm] => Ok(RankScore::Blend { mcc, ftm, fftm }),
+            _ => Err(format!("Blend needs three weights: '{}'", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SolutionRanking {
+    pub score: RankScore,
+    /// Number of solutions kept.
+    pub top_k: usize,
+    /// Params used when the score involves MCC.
+    pub mcc_params: MCCParams,
+}
+
+impl SolutionRanking {
+    pub fn new(score: RankScore, top_k: usize) -> Self {
+        Self {
+            score,
+            top_k,
+            mcc_params: MCCParams::default(),
+        }
+    }
+
+    /// Score a solution, filling in its MCC when the score uses it.
+    pub fn score(&self, solution: &mut Solution) -> f64 {
+        let mut mcc = || match solution.mcc {
+            Some(mcc) => mcc,
+            None => solution.score_mcc(&self.mcc_params),
+        };
+        match self.score {
+            RankScore::Mcc => mcc(),
+            RankScore::Ftm => solution.ftm as f64,
+            RankScore::Fftm => solution.fftm as f64,
+            RankScore::Blend {
+                mcc: mcc_weight,
+                ftm,
+                fftm,
+            } => {
+                let mcc = if mcc_weight != 0.0 {
+                    mcc_weight * mcc()
+                } else {
+                    0.0
+                };
+                mcc + ftm * solution.ftm as f64 + fftm * solution.fftm as f64
+            }
+        }
+    }
+}
+
+struct Ranked {
+    score: f64,
+    /// Order the solution was found in, so ties go to the earlier one.
+    found: u64,
+    solution: Solution,
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Ranked {}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then(self.found.cmp(&other.found))
+    }
+}
+
+/// Solutions of one search, capped at the ranking's `top_k`. Without a
+/// ranking every solution is kept, in the order found.
+pub struct TopSolutions {
+    ranking: Option<SolutionRanking>,
+    heap: BinaryHeap<Ranked>,
+    found: u64,
+}
+
+impl TopSolutions {
+    pub fn new(ranking: Option<SolutionRanking>) -> Self {
+        Self {
+            ranking,
+            heap: BinaryHeap::new(),
+            found: 0,
+        }
+    }
+
+    pub fn ranking(&self) -> Option<&SolutionRanking> {
+        self.ranking.as_ref()
+    }
+
+    /// Offer a solution, returning whether it was kept.
+    pub fn push(&mut self, mut solution: Solution) -> bool {
+        let found = self.found;
+        self.found += 1;
+
+        let Some(ranking) = self.ranking else {
+            self.heap.push(Ranked {
+                score: 0.0,
+                found,
+                solution,
+            });
+            return true;
+        };
+        if ranking.top_k == 0 {
+            return false;
+        }
+
+        let candidate = Ranked {
+            score: ranking.score(&mut solution),
+            found,
+            solution,
+        };
+        if self.heap.len() < ranking.top_k {
+            self.heap.push(candidate);
+            return true;
+        }
+        match self.heap.peek_mut() {
+            Some(mut worst) if candidate < *worst => {
+                *worst = candidate;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Number of solutions offered, kept or not.
+    pub fn found(&self) -> u64 {
+        self.found
+    }
+
+    /// Kept solutions, best first.
+    pub fn to_sorted_vec(&self) -> Vec<Solution> {
+        let mut ranked: Vec<&Ranked> = self.heap.iter().collect();
+        ranked.sort();
+        ranked.into_iter().map(|r| r.solution.clone()).collect()
+    }
+
+    /// Kept solutions, best first.
+    pub fn into_sorted_vec(self) -> Vec<Solution> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|r| r.solution)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minx::Move;
+    use crate::search_mode::SearchMode;
+
+    #[test]
+    fn test_top_solutions_keeps_best_k_by_length() {
+        let mut top = TopSolutions::new(Some(SolutionRanking::new(RankScore::Ftm, 2)));
+        assert!(top.push(Solution::from_moves(
+            &[Move::R, Move::U, Move::R],
+            SearchMode::RU
+        )));
+        assert!(top.push(Solution::from_moves(&[Move::R], SearchMode::RU)));
+        assert!(top.push(Solution::from_moves(&[Move::U, Move::R], SearchMode::RU)));
+        assert!(!top.push(Solution::from_moves(
+            &[Move::R, Move::U, Move::Ri],
+            SearchMode::RU
+        )));
+        // Ties keep the solution found first.
+        assert!(!top.push(Solution::from_moves(&[Move::Ri, Move::U], SearchMode::RU)));
+
+        assert_eq!(top.len(), 2);
+        assert_eq!(top.found(), 5);
+        let kept: Vec<usize> = top.to_sorted_vec().iter().map(|s| s.ftm).collect();
+        assert_eq!(kept, vec![1, 2]);
+        assert_eq!(top.into_sorted_vec()[1].moves, vec![Move::U, Move::R]);
+    }
+
+    #[test]
+    fn test_top_solutions_without_ranking_keeps_found_order() {
+        let mut top = TopSolutions::new(None);
+        top.push(Solution::from_moves(&[Move::R, Move::U], SearchMode::RU));
+        top.push(Solution::from_moves(&[Move::R], SearchMode::RU));
+        let kept: Vec<usize> = top.into_sorted_vec().iter().map(|s| s.ftm).collect();
+        assert_eq!(kept, vec![2, 1]);
+    }
+
+    #[test]
+    fn test_mcc_ranking_scores_solutions() {
+        let ranking = SolutionRanking::new(RankScore::Mcc, 1);
+        let mut top = TopSolutions::new(Some(ranking));
+        top.push(Solution::from_moves(
+            &[Move::R, Move::U, Move::Ri, Move::U],
+            SearchMode::RU,
+        ));
+        top.push(Solution::from_moves(
+            &[Move::R, Move::U, Move::R, Move::U, Move::R],
+            SearchMode::RU,
+        ));
+
+        let best = top.into_sorted_vec().remove(0);
+        let mcc = best.mcc.unwrap();
+        assert_eq!(ranking.score(&mut best.clone()), mcc);
+
+        let blend = SolutionRanking::new(
+            RankScore::Blend {
+                mcc: 0.0,
+                ftm: 1.0,
+                fftm: 0.5,
+            },
+            1,
+        );
+        let mut unscored = Solution::from_moves(&[Move::R, Move::U], SearchMode::RU);
+        assert_eq!(blend.score(&mut unscored), 3.0);
+        assert_eq!(unscored.mcc, None);
+    }
+
+    #[test]
+    fn test_rank_score_parsing() {
+        assert_eq!("MCC".parse::<RankScore>(), Ok(RankScore::Mcc));
+        assert_eq!("fftm".parse::<RankScore>(), Ok(RankScore::Fftm));
+        assert_eq!(
+            "blend:1,0.5,0".parse::<RankScore>(),
+            Ok(RankScore::Blend {
+                mcc: 1.0,
+                ftm: 0.5,
+                fftm: 0.0
+            })
+        );
+        assert!("blend:1,2".parse::<RankScore>().is_err());
+        assert!("speed".parse::<RankScore>().is_err());
+    }
+}
@@ -0,0 +1,469 @@
+//! Persistent cache of search results
+//!
+//! Entries are keyed by the start state with its ignore masks, the search
+//! mode and the metric. Each entry records the depth the state was searched
+//! through and every solution found up to it, before filters, ranking or
+//! deduplication. A search with a limit the entry covers is answered from it
+//! without touching the pruning tables; a deeper one replays the cached
+//! depths and searches only the ones past them.
+//!
+//! Every entry is one JSON file named by the hash of its key. Reading an
+//! entry bumps its modification time, which is what least-recently-used
+//! eviction and the age limit go by.
+
+use crate::data_directory::get_data_directory;
+use crate::filter::{SharedFilter, accepts_all};
+use crate::minx::LLMinx;
+use crate::search_mode::{Metric, SearchMode};
+use crate::solution::Solution;
+use crate::table_file::checksum;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Bump whenever move generation or the entry layout changes, so entries
+/// written by older versions are dropped instead of replayed.
+const CACHE_VERSION: u32 = 1;
+
+const ENTRY_EXTENSION: &str = "json";
+
+/// Size and age limits of a `ResultCache`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CachePolicy {
+    /// Least recently used entries are evicted once the cache grows past this.
+    pub max_bytes: u64,
+    /// Entries unused for this long are dropped.
+    pub max_age: Option<Duration>,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        Self {
+            max_bytes: 64 * 1024 * 1024,
+            max_age: None,
+        }
+    }
+}
+
+/// What a search result is cached under.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey(String);
+
+impl CacheKey {
+    /// Key of a search from `start`, which must already carry the ignore
+    /// masks of the search.
+    pub fn new(start: &LLMinx, search_mode: SearchMode, metric: Metric) -> Self {
+        Self(format!(
+            "{:?}|{:?}|{:?}|{:?}|{:x}|{:x}|{:?}|{:?}|{:?}|{:?}",
+            search_mode,
+            metric,
+            start.corner_positions,
+            start.edge_positions,
+            start.corner_orientations,
+            start.edge_orientations,
+            start.ignore_corner_positions,
+            start.ignore_edge_positions,
+            start.ignore_corner_orientations,
+            start.ignore_edge_orientations,
+        ))
+    }
+
+    fn file_name(&self) -> String {
+        format!("{:016x}.{}", checksum(self.0.as_bytes()), ENTRY_EXTENSION)
+    }
+}
+
+/// Solutions cached for one key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedResult {
+    /// Deepest depth the state was fully searched through.
+    pub searched_depth: usize,
+    /// Every solution up to `searched_depth`, unfiltered.
+    pub solutions: Vec<Solution>,
+}
+
+impl CachedResult {
+    /// Depth of the shortest cached solution that `filters` accept.
+    pub fn optimal_depth(&self, filters: &[SharedFilter]) -> Option<usize> {
+        self.solutions
+            .iter()
+            .filter(|s| accepts_all(filters, s))
+            .map(|s| s.depth)
+            .min()
+    }
+
+    /// Whether a search through `max_depth` with `filters`, stopping `slack`
+    /// moves past its first solutions, needs nothing beyond this entry.
+    pub fn covers(&self, max_depth: usize, slack: Option<usize>, filters: &[SharedFilter]) -> bool {
+        if self.searched_depth >= max_depth {
+            return true;
+        }
+        match (self.optimal_depth(filters), slack) {
+            (Some(optimal), Some(slack)) => optimal + slack <= self.searched_depth,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredEntry {
+    version: u32,
+    key: String,
+    #[serde(flatten)]
+    result: CachedResult,
+}
+
+/// Directory of cached search results shared by every solver.
+#[derive(Debug)]
+pub struct ResultCache {
+    dir: PathBuf,
+    policy: CachePolicy,
+}
+
+impl ResultCache {
+    const DIR_NAME: &'static str = "result_cache";
+
+    /// Open the cache in `dir`, creating the directory if needed.
+    pub fn open(dir: &Path, policy: CachePolicy) -> Result<Self, String> {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create result cache: {}", e))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            policy,
+        })
+    }
+
+    /// Open the cache kept next to the pruning tables, in the data
+    /// directory or else the current directory.
+    pub fn in_data_directory(policy: CachePolicy) -> Result<Self, String> {
+        let dir = match get_data_directory() {
+            Some(data_dir) => data_dir.join(Self::DIR_NAME),
+            None => PathBuf::from(Self::DIR_NAME),
+        };
+        Self::open(&dir, policy)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn policy(&self) -> CachePolicy {
+        self.policy
+    }
+
+    /// Cached result for `key`, dropping it if it has expired.
+    pub fn get(&self, key: &CacheKey) -> Option<CachedResult> {
+        let path = self.dir.join(key.file_name());
+        if self.expired(&path) {
+            let _ = fs::remove_file(&path);
+            return None;
+        }
+
+        let text = fs::read_to_string(&path).ok()?;
+        let Ok(entry) = serde_json::from_str::<StoredEntry>(&text) else {
+            let _ = fs::remove_file(&path);
+            return None;
+        };
+        if entry.version != CACHE_VERSION {
+            let _ = fs::remove_file(&path);
+            return None;
+        }
+        // Another key with the same hash; leave its entry alone
+        if entry.key != key.0 {
+            return None;
+        }
+
+        if let Ok(file) = File::options().write(true).open(&path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        Some(entry.result)
+    }
+
+    /// Store the solutions of a search through `searched_depth`, replacing
+    /// any entry for `key`, then evict entries past the size limit.
+    pub fn put(
+        &self,
+        key: &CacheKey,
+        searched_depth: usize,
+        solutions: &[Solution],
+    ) -> Result<(), String> {
+        let entry = StoredEntry {
+            version: CACHE_VERSION,
+            key: key.0.clone(),
+            result: CachedResult {
+                searched_depth,
+                solutions: solutions.to_vec(),
+            },
+        };
+        let text = serde_json::to_string(&entry)
+            .map_err(|e| format!("Failed to encode cache entry: {}", e))?;
+
+        // Write then rename, so other processes never read half an entry
+        let path = self.dir.join(key.file_name());
+        let partial = path.with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&partial, text).map_err(|e| format!("Failed to write cache entry: {}", e))?;
+        fs::rename(&partial, &path).map_err(|e| {
+            let _ = fs::remove_file(&partial);
+            format!("Failed to write cache entry: {}", e)
+        })?;
+
+        self.evict();
+        Ok(())
+    }
+
+    /// Drop the entry for `key`, returning whether there was one.
+    pub fn invalidate(&self, key: &CacheKey) -> bool {
+        fs::remove_file(self.dir.join(key.file_name())).is_ok()
+    }
+
+    /// Drop every entry, returning how many were removed.
+    pub fn clear(&self) -> usize {
+        self.entries()
+            .into_iter()
+            .filter(|(path, _, _)| fs::remove_file(path).is_ok())
+            .count()
+    }
+
+    /// Total size of the entries in bytes.
+    pub fn size_bytes(&self) -> u64 {
+        self.entries().iter().map(|(_, size, _)| size).sum()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries().is_empty()
+    }
+
+    fn expired(&self, path: &Path) -> bool {
+        let Some(max_age) = self.policy.max_age else {
+            return false;
+        };
+        fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age >= max_age)
+    }
+
+    /// Path, size and last use of every entry.
+    fn entries(&self) -> Vec<(PathBuf, u64, SystemTime)> {
+        let Ok(dir) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        dir.flatten()
+            .filter(|entry| {
+                entry
+                    .path()
+                    .extension()
+                    .is_some_and(|ext| ext == ENTRY_EXTENSION)
+            })
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                Some((entry.path(), metadata.len(), modified))
+            })
+            .collect()
+    }
+
+    /// Drop expired entries, then the least recently used ones until the
+    /// cache fits in `max_bytes`.
+    fn evict(&self) {
+        let mut entries = self.entries();
+        entries.retain(|(path, _, _)| {
+            let expired = self.expired(path);
+            if expired {
+                let _ = fs::remove_file(path);
+            }
+            !expired
+        });
+
+        let mut total: u64 = entries.iter().map(|(_, size, _)| size).sum();
+        if total <= self.policy.max_bytes {
+            return;
+        }
+        entries.sort_by_key(|(_, _, modified)| *modified);
+        for (path, size, _) in entries {
+            if total <= self.policy.max_bytes {
+                break;
+            }
+            if fs::remove_file(&path).is_ok() {
+                total -= size;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::MaxFaceTurns;
+    use crate::minx::Move;
+    use std::sync::Arc;
+
+    fn cache_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "llminx_result_cache_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn key(alg: &str) -> CacheKey {
+        CacheKey::new(
+            &LLMinx::from_alg(alg).unwrap(),
+            SearchMode::RU,
+            Metric::Face,
+        )
+    }
+
+    #[test]
+    fn test_key_covers_state_masks_mode_and_metric() {
+        let state = LLMinx::from_alg("R U R'").unwrap();
+        let base = CacheKey::new(&state, SearchMode::RU, Metric::Face);
+        assert_eq!(base, CacheKey::new(&state, SearchMode::RU, Metric::Face));
+        assert_ne!(base, CacheKey::new(&state, SearchMode::RUF, Metric::Face));
+        assert_ne!(base, CacheKey::new(&state, SearchMode::RU, Metric::Fifth));
+
+        let mut masked = state.clone();
+        let mut ignore = [false; crate::minx::NUM_CORNERS];
+        ignore[0] = true;
+        masked.set_ignore_corner_orientations(ignore);
+        assert_ne!(base, CacheKey::new(&masked, SearchMode::RU, Metric::Face));
+    }
+
+    #[test]
+    fn test_put_get_invalidate() {
+        let dir = cache_dir("roundtrip");
+        let cache = ResultCache::open(&dir, CachePolicy::default()).unwrap();
+        let solutions = vec![Solution::from_moves(&[Move::Ui], SearchMode::RU)];
+
+        assert!(cache.get(&key("U")).is_none());
+        cache.put(&key("U"), 3, &solutions).unwrap();
+
+        let cached = cache.get(&key("U")).unwrap();
+        assert_eq!(cached.searched_depth, 3);
+        assert_eq!(cached.solutions, solutions);
+        assert!(cache.get(&key("U'")).is_none());
+
+        assert!(cache.invalidate(&key("U")));
+        assert!(cache.get(&key("U")).is_none());
+        assert!(!cache.invalidate(&key("U")));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_covers() {
+        let mut cached = CachedResult {
+            searched_depth: 5,
+            solutions: vec![],
+        };
+        assert!(cached.covers(5, None, &[]));
+        assert!(!cached.covers(6, Some(2), &[]));
+
+        let mut found = Solution::from_moves(&[Move::R, Move::U, Move::Ri], SearchMode::RU);
+        found.depth = 3;
+        cached.solutions.push(found);
+        assert!(cached.covers(10, Some(2), &[]));
+        assert!(!cached.covers(10, Some(3), &[]));
+        assert!(!cached.covers(10, None, &[]));
+
+        // A filtered-out solution doesn't end the search
+        let no_r: SharedFilter = Arc::new(MaxFaceTurns::new(Move::R, 0));
+        assert!(!cached.covers(10, Some(2), &[no_r]));
+    }
+
+    #[test]
+    fn test_size_cap_evicts_least_recently_used() {
+        let dir = cache_dir("evict");
+        let solutions = vec![Solution::from_moves(&[Move::R, Move::U], SearchMode::RU)];
+        let unbounded = ResultCache::open(&dir, CachePolicy::default()).unwrap();
+        unbounded.put(&key("U"), 2, &solutions).unwrap();
+        let entry_size = unbounded.size_bytes();
+
+        let cache = ResultCache::open(
+            &dir,
+            CachePolicy {
+                max_bytes: entry_size * 2 + entry_size / 2,
+                max_age: None,
+            },
+        )
+        .unwrap();
+        // Make the first entry clearly older, then use it so it is the newest
+        let old = SystemTime::now() - Duration::from_secs(60);
+        let first = dir.join(key("U").file_name());
+        File::options()
+            .write(true)
+            .open(&first)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        cache.put(&key("U2"), 2, &solutions).unwrap();
+        let second = dir.join(key("U2").file_name());
+        File::options()
+            .write(true)
+            .open(&second)
+            .unwrap()
+            .set_modified(old - Duration::from_secs(60))
+            .unwrap();
+        assert!(cache.get(&key("U")).is_some());
+
+        cache.put(&key("U'"), 2, &solutions).unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&key("U2")).is_none());
+        assert!(cache.get(&key("U")).is_some());
+        assert!(cache.get(&key("U'")).is_some());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_max_age_and_clear() {
+        let dir = cache_dir("age");
+        let cache = ResultCache::open(
+            &dir,
+            CachePolicy {
+                max_age: Some(Duration::from_secs(3600)),
+                ..CachePolicy::default()
+            },
+        )
+        .unwrap();
+        cache.put(&key("U"), 1, &[]).unwrap();
+        cache.put(&key("U'"), 1, &[]).unwrap();
+
+        let stale = dir.join(key("U").file_name());
+        File::options()
+            .write(true)
+            .open(&stale)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(7200))
+            .unwrap();
+        assert!(cache.get(&key("U")).is_none());
+        assert!(!stale.exists());
+
+        assert_eq!(cache.clear(), 1);
+        assert!(cache.is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_entries_of_other_versions_are_dropped() {
+        let dir = cache_dir("version");
+        let cache = ResultCache::open(&dir, CachePolicy::default()).unwrap();
+        cache.put(&key("U"), 1, &[]).unwrap();
+
+        let path = dir.join(key("U").file_name());
+        let text = fs::read_to_string(&path).unwrap().replacen(
+            &format!("\"version\":{}", CACHE_VERSION),
+            "\"version\":0",
+            1,
+        );
+        fs::write(&path, text).unwrap();
+        assert!(cache.get(&key("U")).is_none());
+        assert!(!path.exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,9 +1,11 @@
+use crate::face_set::FaceSet;
 use crate::minx::{CornerPosition, EdgePosition, Move};
 use crate::pruner::{
     CompositePruner, CornerOrientationPruner, CornerPermutationPruner, EdgeOrientationPruner,
     EdgePermutationPruner, Pruner, SeparationPruner,
 };
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Metric {
@@ -26,6 +28,18 @@ impl std::fmt::Display for Metric {
     }
 }
 
+impl std::str::FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "fifth" => Ok(Metric::Fifth),
+            "face" => Ok(Metric::Face),
+            _ => Err(format!("Unknown metric: '{}'", s)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum SearchMode {
     RU,
@@ -36,9 +50,40 @@ pub enum SearchMode {
     RUbL,
     RUbR,
     RUD,
+    /// Any other combination of faces, with pruners derived from the face set.
+    Custom(FaceSet),
 }
 
 impl SearchMode {
+    pub const BUILTIN: [SearchMode; 8] = [
+        SearchMode::RU,
+        SearchMode::RUF,
+        SearchMode::RUL,
+        SearchMode::RUFL,
+        SearchMode::RUFLbL,
+        SearchMode::RUbL,
+        SearchMode::RUbR,
+        SearchMode::RUD,
+    ];
+
+    /// Search mode for an arbitrary generator set. Sets matching one of the
+    /// built-in modes return that mode so its tuned pruners are used.
+    pub fn custom(faces: &[Move]) -> Result<SearchMode, String> {
+        let set = FaceSet::new(faces)?;
+        Ok(Self::BUILTIN
+            .into_iter()
+            .find(|mode| mode.face_set() == set)
+            .unwrap_or(SearchMode::Custom(set)))
+    }
+
+    /// Faces this mode is allowed to turn.
+    pub fn face_set(&self) -> FaceSet {
+        match self {
+            SearchMode::Custom(set) => *set,
+            _ => FaceSet::new(&self.possible_moves()).expect("built-in modes always have moves"),
+        }
+    }
+
     pub fn description(&self) -> &'static str {
         match self {
             SearchMode::RU => "RU",
@@ -49,6 +94,16 @@ impl SearchMode {
             SearchMode::RUbL => "RUbL",
             SearchMode::RUbR => "RUbR",
             SearchMode::RUD => "RUD",
+            SearchMode::Custom(_) => "Custom",
+        }
+    }
+
+    /// Display name: the built-in description, or the generator faces for a
+    /// custom mode (e.g. `RUFbR`).
+    pub fn name(&self) -> Cow<'static, str> {
+        match self {
+            SearchMode::Custom(set) => Cow::Owned(set.name()),
+            _ => Cow::Borrowed(self.description()),
         }
     }
 
@@ -174,6 +229,7 @@ impl SearchMode {
                 Move::D2,
                 Move::D2i,
             ],
+            SearchMode::Custom(set) => set.possible_moves(),
         }
     }
 
@@ -444,13 +500,34 @@ impl SearchMode {
                     ],
                 )),
             ],
+            SearchMode::Custom(set) => set.create_pruners(),
         }
     }
 }
 
 impl std::fmt::Display for SearchMode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.description())
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for SearchMode {
+    type Err = String;
+
+    /// Accepts built-in mode names case-insensitively, or a generator set
+    /// such as `<R, U, bR, F>` for a custom mode.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(mode) = Self::BUILTIN
+            .into_iter()
+            .find(|mode| mode.description().eq_ignore_ascii_case(s))
+        {
+            return Ok(mode);
+        }
+        let set: FaceSet = s
+            .parse()
+            .map_err(|_| format!("Unknown search mode: '{}'", s))?;
+        Self::custom(&set.possible_moves())
     }
 }
 
@@ -698,4 +775,49 @@ mod tests {
         assert_eq!(Metric::Face, Metric::Face);
         assert_ne!(Metric::Fifth, Metric::Face);
     }
+
+    #[test]
+    fn test_metric_from_str() {
+        assert_eq!("fifth".parse::<Metric>(), Ok(Metric::Fifth));
+        assert_eq!("Face".parse::<Metric>(), Ok(Metric::Face));
+        assert!("htm".parse::<Metric>().is_err());
+    }
+
+    #[test]
+    fn test_search_mode_from_str() {
+        assert_eq!("RU".parse::<SearchMode>(), Ok(SearchMode::RU));
+        assert_eq!("rufl".parse::<SearchMode>(), Ok(SearchMode::RUFL));
+        assert_eq!("RUbR".parse::<SearchMode>(), Ok(SearchMode::RUbR));
+        assert_eq!(" RUFLbL ".parse::<SearchMode>(), Ok(SearchMode::RUFLbL));
+        assert!("RUX".parse::<SearchMode>().is_err());
+    }
+
+    #[test]
+    fn test_custom_mode_matching_builtin_returns_builtin() {
+        assert_eq!(
+            SearchMode::custom(&[Move::U, Move::F, Move::R]),
+            Ok(SearchMode::RUF)
+        );
+        for mode in SearchMode::BUILTIN {
+            assert_eq!(SearchMode::custom(&mode.possible_moves()), Ok(mode));
+        }
+    }
+
+    #[test]
+    fn test_custom_mode() {
+        let mode = SearchMode::custom(&[Move::R, Move::U, Move::bR, Move::F]).unwrap();
+        assert!(matches!(mode, SearchMode::Custom(_)));
+        assert_eq!(mode.description(), "Custom");
+        assert_eq!(mode.name(), "RUFbR");
+        assert_eq!(mode.to_string(), "RUFbR");
+        assert_eq!(SearchMode::RUbL.name(), "RUbL");
+        assert_eq!(mode.possible_moves().len(), 16);
+        assert!(!mode.create_pruners().is_empty());
+        assert_eq!("<R, U, bR, F>".parse::<SearchMode>(), Ok(mode));
+        assert_eq!("ULbL".parse::<SearchMode>().unwrap().name(), "ULbL");
+        assert!(SearchMode::custom(&[]).is_err());
+
+        let json = serde_json::to_string(&mode).unwrap();
+        assert_eq!(serde_json::from_str::<SearchMode>(&json).unwrap(), mode);
+    }
 }
@@ -0,0 +1,229 @@
+//! Structured solver output
+//!
+//! `Solution` is what the search produces for every solving sequence it finds.
+//! `SolutionStream` hands those out one at a time while the search is still
+//! running, as an alternative to collecting everything from `Solver::solve`.
+
+use crate::mcc::{MCCParams, calculator::calculate_mcc_with_params};
+use crate::minx::{LLMinx, Move, VerifyError, format_alg, verify};
+use crate::search_mode::SearchMode;
+use crate::solver::Solver;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Solution {
+    /// Solving moves, with repeated quarter turns merged into double turns.
+    pub moves: Vec<Move>,
+    pub ftm: usize,
+    pub fftm: usize,
+    /// Search depth the solution was found at.
+    pub depth: usize,
+    pub search_mode: SearchMode,
+    /// Milliseconds since the Unix epoch when the solution was found.
+    pub timestamp: u64,
+    /// Move comfort score, filled in by `score_mcc`.
+    #[serde(default)]
+    pub mcc: Option<f64>,
+    /// Reorderings of this solution merged into it by deduplication.
+    #[serde(default)]
+    pub merged_variants: usize,
+    /// Whether replaying the solution reached the goal, filled in by `verify`.
+    #[serde(default)]
+    pub verified: Option<bool>,
+}
+
+impl Solution {
+    /// Capture the move history of a solved search state.
+    pub fn from_minx(minx: &LLMinx, search_mode: SearchMode) -> Self {
+        Self {
+            moves: minx.generating_moves(),
+            ftm: minx.get_ftm_length(),
+            fftm: minx.get_fftm_length(),
+            depth: minx.depth(),
+            search_mode,
+            timestamp: now_millis(),
+            mcc: None,
+            merged_variants: 0,
+            verified: None,
+        }
+    }
+
+    /// The solution performing `moves` from the solved state.
+    pub fn from_moves(moves: &[Move], search_mode: SearchMode) -> Self {
+        let mut minx = LLMinx::new();
+        for &m in moves {
+            minx.apply_move(m);
+        }
+        Self::from_minx(&minx, search_mode)
+    }
+
+    /// Score the solution with the MCC calculator and keep the result.
+    pub fn score_mcc(&mut self, params: &MCCParams) -> f64 {
+        let mcc = calculate_mcc_with_params(&self.algorithm(), params);
+        self.mcc = Some(mcc);
+        mcc
+    }
+
+    /// Replay the solution from `start` and keep whether it reached `goal`.
+    pub fn verify(&mut self, start: &LLMinx, goal: &LLMinx) -> Result<(), VerifyError> {
+        let result = verify(start, &self.moves, goal);
+        self.verified = Some(result.is_ok());
+        result
+    }
+
+    /// Moves in standard notation, e.g. `R U R' U'`.
+    pub fn algorithm(&self) -> String {
+        format_alg(&self.moves)
+    }
+}
+
+#[cfg(test)]
+impl Solution {
+    /// The solution performing `alg` from the solved state.
+    pub(crate) fn from_alg(alg: &str, search_mode: SearchMode) -> Self {
+        Self::from_moves(&crate::minx::parse_alg(alg).unwrap(), search_mode)
+    }
+}
+
+/// Formats as the solver's line format, `"R U R' U'  (4,4)"`.
+impl std::fmt::Display for Solution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for m in &self.moves {
+            f.write_str(m.to_string())?;
+        }
+        write!(f, " ({},{})", self.ftm, self.fftm)
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Solutions of a search running on a background thread.
+///
+/// The channel is bounded, so the search pauses while the consumer is behind.
+/// Dropping the stream cancels the search.
+pub struct SolutionStream {
+    receiver: crossbeam_channel::Receiver<Solution>,
+    interrupted: Arc<AtomicBool>,
+    handle: Option<JoinHandle<Solver>>,
+}
+
+impl SolutionStream {
+    pub(crate) fn new(
+        receiver: crossbeam_channel::Receiver<Solution>,
+        interrupted: Arc<AtomicBool>,
+        handle: JoinHandle<Solver>,
+    ) -> Self {
+        Self {
+            receiver,
+            interrupted,
+            handle: Some(handle),
+        }
+    }
+
+    /// Stop the search. Solutions already queued can still be read.
+    pub fn cancel(&self) {
+        self.interrupted.store(true, Ordering::SeqCst);
+    }
+
+    /// Stop the search and get the solver back, keeping its loaded tables.
+    pub fn into_solver(mut self) -> Solver {
+        self.cancel();
+        while self.receiver.recv().is_ok() {}
+        self.handle
+            .take()
+            .expect("stream owns the search thread")
+            .join()
+            .expect("search thread panicked")
+    }
+}
+
+impl Iterator for SolutionStream {
+    type Item = Solution;
+
+    fn next(&mut self) -> Option<Solution> {
+        self.receiver.recv().ok()
+    }
+}
+
+impl Drop for SolutionStream {
+    fn drop(&mut self) {
+        // Blocked senders are released once the receiver is dropped, so the
+        // search thread winds down on its own.
+        self.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solution_from_minx() {
+        let mut minx = LLMinx::new();
+        for m in [Move::R, Move::R, Move::U, Move::Ri] {
+            minx.apply_move(m);
+        }
+        let solution = Solution::from_minx(&minx, SearchMode::RU);
+        assert_eq!(solution.moves, vec![Move::R2, Move::U, Move::Ri]);
+        assert_eq!(solution.depth, 4);
+        assert_eq!(solution.ftm, 3);
+        assert_eq!(solution.fftm, 4);
+        assert_eq!(solution.algorithm(), "R2 U R'");
+        assert!(solution.timestamp > 0);
+        assert_eq!(solution.mcc, None);
+    }
+
+    #[test]
+    fn test_solution_from_moves() {
+        let moves = [Move::R, Move::R, Move::U, Move::Ri];
+        let mut minx = LLMinx::new();
+        for m in moves {
+            minx.apply_move(m);
+        }
+        let expected = Solution::from_minx(&minx, SearchMode::RU);
+        let solution = Solution::from_moves(&moves, SearchMode::RU);
+        assert_eq!(
+            solution,
+            Solution {
+                timestamp: solution.timestamp,
+                ..expected
+            }
+        );
+    }
+
+    #[test]
+    fn test_score_mcc_and_serde_round_trip() {
+        let mut solution =
+            Solution::from_moves(&[Move::R, Move::U, Move::Ri, Move::Ui], SearchMode::RUF);
+        let mcc = solution.score_mcc(&MCCParams::default());
+        assert_eq!(solution.mcc, Some(mcc));
+
+        let json = serde_json::to_string(&solution).unwrap();
+        let parsed: Solution = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, solution);
+    }
+
+    #[test]
+    fn test_display_matches_solver_line_format() {
+        let mut minx = LLMinx::new();
+        minx.apply_move(Move::R);
+        minx.apply_move(Move::U);
+        let solution = Solution::from_minx(&minx, SearchMode::RU);
+        let legacy = format!(
+            "{} ({},{})",
+            minx.get_generating_moves(),
+            minx.get_ftm_length(),
+            minx.get_fftm_length()
+        );
+        assert_eq!(solution.to_string(), legacy);
+    }
+}
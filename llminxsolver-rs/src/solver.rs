@@ -1,11 +1,22 @@
+use crate::canonical::{DedupMode, Deduplicator};
+use crate::data_directory::table_directory;
+use crate::filter::{SharedFilter, accepts_all};
 use crate::memory_config::{MemoryConfig, MemoryTracker};
 use crate::minx::{LLMinx, Move, NUM_CORNERS, NUM_EDGES};
 use crate::pruner::{DEFAULT_PRUNING_DEPTH, MAX_PRUNING_DEPTH, MIN_PRUNING_DEPTH, Pruner};
+use crate::ranking::{SolutionRanking, TopSolutions};
+use crate::result_cache::{CacheKey, ResultCache};
 use crate::search_mode::{Metric, SearchMode};
+use crate::solution::{Solution, SolutionStream};
+use crate::table_file::TableError;
+use crate::table_registry::{
+    self, FullTableBuilder, PACKED_MAX_DISTANCE, PackedTableBuilder, PruningTable, TableBuilder,
+};
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 
 pub(crate) const IGNORE_CORNER_5: [bool; NUM_CORNERS] = [
     true, true, true, true, true, false, false, false, false, false, false, false, false, false,
@@ -26,6 +37,8 @@ pub enum StatusEventType {
     Message,
     FinishSearch,
     SolutionFound,
+    OptimalDepthFound,
+    InvalidTable,
     MemoryWarning,
     MemoryExceeded,
 }
@@ -37,6 +50,9 @@ pub struct StatusEvent {
     pub progress: f64,
     pub search_mode: Option<String>,
     pub current_depth: u32,
+    /// The solution a `SolutionFound` event reports.
+    #[serde(default)]
+    pub solution: Option<Solution>,
 }
 
 impl StatusEvent {
@@ -57,6 +73,14 @@ impl StatusEvent {
             progress,
             search_mode,
             current_depth,
+            solution: None,
+        }
+    }
+
+    pub fn solution_found(message: &str, solution: Solution) -> Self {
+        Self {
+            solution: Some(solution),
+            ..Self::new(StatusEventType::SolutionFound, message, 0.0)
         }
     }
 }
@@ -64,13 +88,21 @@ impl StatusEvent {
 pub type StatusCallback = Arc<dyn Fn(StatusEvent) + Send + Sync>;
 
 struct SearchContext<'a> {
-    tables: &'a [Arc<Vec<u8>>],
+    tables: &'a [Arc<PruningTable>],
     pruners: &'a [&'a dyn Pruner],
     first_moves: &'a [Move],
     next_siblings: &'a [Vec<Option<Move>>],
     interrupted: &'a Arc<AtomicBool>,
-    solution_tx: &'a crossbeam_channel::Sender<String>,
+    solution_tx: &'a crossbeam_channel::Sender<Solution>,
     status_tx: &'a crossbeam_channel::Sender<StatusEvent>,
+    solutions_found: &'a AtomicUsize,
+    search_mode: SearchMode,
+    /// Whether each solution gets a `SolutionFound` event as it is found.
+    /// Ranked searches report only the kept solutions once the search ends.
+    report_solutions: bool,
+    filters: &'a [SharedFilter],
+    /// Every solution found, before filtering, for the result cache.
+    found: Option<&'a Mutex<Vec<Solution>>>,
 }
 
 pub struct Solver {
@@ -78,6 +110,8 @@ pub struct Solver {
     metric: Metric,
     max_search_depth: usize,
     limit_search_depth: bool,
+    depth_slack: Option<usize>,
+    optimal_depth: Option<usize>,
     pruning_depth: u8,
     start: LLMinx,
     ignore_corner_positions: bool,
@@ -87,8 +121,13 @@ pub struct Solver {
     interrupted: Arc<AtomicBool>,
     status_callback: Option<StatusCallback>,
     memory_config: MemoryConfig,
+    ranking: Option<SolutionRanking>,
+    dedup: DedupMode,
+    filters: Vec<SharedFilter>,
+    result_cache: Option<Arc<ResultCache>>,
+    table_dir: Option<PathBuf>,
     pruners: Vec<Box<dyn Pruner>>,
-    tables: Vec<Arc<Vec<u8>>>,
+    tables: Vec<Arc<PruningTable>>,
     moves: Vec<Move>,
     first_moves: Vec<Move>,
     next_siblings: Vec<Vec<Option<Move>>>,
@@ -122,6 +161,8 @@ impl Solver {
             metric: Metric::Fifth,
             max_search_depth,
             limit_search_depth: false,
+            depth_slack: None,
+            optimal_depth: None,
             pruning_depth: DEFAULT_PRUNING_DEPTH,
             start: LLMinx::new(),
             ignore_corner_positions: false,
@@ -131,6 +172,11 @@ impl Solver {
             interrupted: Arc::new(AtomicBool::new(false)),
             status_callback: None,
             memory_config,
+            ranking: None,
+            dedup: DedupMode::Off,
+            filters: Vec::new(),
+            result_cache: None,
+            table_dir: None,
             pruners: Vec::new(),
             tables: Vec::new(),
             moves: Vec::new(),
@@ -182,6 +228,21 @@ impl Solver {
         self.limit_search_depth = limit;
     }
 
+    pub fn depth_slack(&self) -> Option<usize> {
+        self.depth_slack
+    }
+
+    /// Stop the search `slack` moves past the first depth that has solutions.
+    /// `None` keeps searching up to the maximum search depth.
+    pub fn set_depth_slack(&mut self, slack: Option<usize>) {
+        self.depth_slack = slack;
+    }
+
+    /// Shortest solution length found by the last `solve`, if any.
+    pub fn optimal_depth(&self) -> Option<usize> {
+        self.optimal_depth
+    }
+
     pub fn pruning_depth(&self) -> u8 {
         self.pruning_depth
     }
@@ -190,6 +251,60 @@ impl Solver {
         self.pruning_depth = depth.clamp(MIN_PRUNING_DEPTH, MAX_PRUNING_DEPTH);
     }
 
+    /// Directory tables are read from and written to, by default the one
+    /// `data_directory::table_directory` names.
+    pub fn table_directory(&self) -> PathBuf {
+        self.table_dir.clone().unwrap_or_else(table_directory)
+    }
+
+    /// Read and write tables in `dir`. Tables already prepared are reloaded
+    /// from there by the next `prepare_tables`.
+    pub fn set_table_directory(&mut self, dir: impl Into<PathBuf>) {
+        self.table_dir = Some(dir.into());
+        self.last_search_mode = None;
+    }
+
+    pub fn ranking(&self) -> Option<&SolutionRanking> {
+        self.ranking.as_ref()
+    }
+
+    /// Keep only the best `top_k` solutions under the ranking's score. They
+    /// are reported, best first, once the search has finished. `None` reports
+    /// every solution as it is found.
+    pub fn set_ranking(&mut self, ranking: Option<SolutionRanking>) {
+        self.ranking = ranking;
+    }
+
+    pub fn dedup(&self) -> DedupMode {
+        self.dedup
+    }
+
+    /// Keep one solution per class of equivalent reorderings, counting the
+    /// others in its `merged_variants`. Like ranking, this reports solutions
+    /// once the search has finished.
+    pub fn set_dedup(&mut self, dedup: DedupMode) {
+        self.dedup = dedup;
+    }
+
+    pub fn filters(&self) -> &[SharedFilter] {
+        &self.filters
+    }
+
+    /// Drop solutions that any of `filters` rejects before they are reported.
+    pub fn set_filters(&mut self, filters: Vec<SharedFilter>) {
+        self.filters = filters;
+    }
+
+    pub fn result_cache(&self) -> Option<&Arc<ResultCache>> {
+        self.result_cache.as_ref()
+    }
+
+    /// Answer searches from `cache` as far as it has them, and store what
+    /// is searched past that.
+    pub fn set_result_cache(&mut self, cache: Option<Arc<ResultCache>>) {
+        self.result_cache = cache;
+    }
+
     pub fn start(&self) -> &LLMinx {
         &self.start
     }
@@ -263,14 +378,39 @@ impl Solver {
         }
     }
 
-    pub fn solve(&mut self) -> Vec<String> {
+    pub fn solve(&mut self) -> Vec<Solution> {
+        let (solution_tx, solution_rx) = crossbeam_channel::unbounded::<Solution>();
+        self.run_search(solution_tx);
+        solution_rx.try_iter().collect()
+    }
+
+    /// Run the search on a background thread and return its solutions as an
+    /// iterator. At most `buffer` solutions are queued before the search waits
+    /// for the consumer; dropping the stream cancels the search.
+    pub fn into_stream(self, buffer: usize) -> SolutionStream {
+        let (solution_tx, solution_rx) = crossbeam_channel::bounded::<Solution>(buffer.max(1));
+        let interrupted = self.interrupt_handle();
+        let handle = std::thread::spawn(move || {
+            let mut solver = self;
+            solver.run_search(solution_tx);
+            solver
+        });
+        SolutionStream::new(solution_rx, interrupted, handle)
+    }
+
+    fn run_search(&mut self, solution_tx: crossbeam_channel::Sender<Solution>) {
         let num_threads = self.memory_config.search_threads;
         let start_time = std::time::Instant::now();
         self.interrupted.store(false, Ordering::SeqCst);
+        self.optimal_depth = None;
 
-        self.prepare_tables();
-        if self.is_interrupted() {
-            return Vec::new();
+        if !self.start.outer_pieces_solved() {
+            self.fire_event(StatusEvent::new(
+                StatusEventType::FinishSearch,
+                "Start state moves pieces the search faces cannot reach",
+                1.0,
+            ));
+            return;
         }
 
         let mut start = self.start.clone();
@@ -301,10 +441,40 @@ impl Solver {
             goal.set_ignore_edge_orientations(IGNORE_EDGE_5);
         }
 
+        let max_search_depth = if self.limit_search_depth {
+            self.max_search_depth
+        } else {
+            127
+        };
+
+        let cache_key = self
+            .result_cache
+            .as_ref()
+            .map(|_| CacheKey::new(&start, self.search_mode, self.metric));
+        let cached = self
+            .result_cache
+            .as_ref()
+            .zip(cache_key.as_ref())
+            .and_then(|(cache, key)| cache.get(key));
+        let cached_depth = cached
+            .as_ref()
+            .map_or(0, |c| c.searched_depth.min(max_search_depth));
+
+        // Tables are only needed for depths the cache can't answer
+        if !cached
+            .as_ref()
+            .is_some_and(|c| c.covers(max_search_depth, self.depth_slack, &self.filters))
+        {
+            self.prepare_tables();
+            if self.is_interrupted() {
+                return;
+            }
+        }
+
         let used_pruners = self.filter_pruning_tables();
 
         if self.is_interrupted() {
-            return Vec::new();
+            return;
         }
 
         self.fire_event(StatusEvent::new(
@@ -313,13 +483,19 @@ impl Solver {
             0.0,
         ));
 
-        let max_search_depth = if self.limit_search_depth {
-            self.max_search_depth
-        } else {
-            127
-        };
+        if let Some(ref cached) = cached {
+            self.fire_event(StatusEvent::new(
+                StatusEventType::Message,
+                &format!(
+                    "Loaded {} cached solutions through depth {}",
+                    cached.solutions.len(),
+                    cached.searched_depth
+                ),
+                0.0,
+            ));
+        }
 
-        let (solution_tx, solution_rx) = crossbeam_channel::unbounded::<String>();
+        let solutions_found = AtomicUsize::new(0);
         let (status_tx, status_rx) = crossbeam_channel::unbounded::<StatusEvent>();
 
         let status_callback_clone = self.status_callback.clone();
@@ -331,7 +507,8 @@ impl Solver {
             }
         });
 
-        let tables: Vec<Arc<Vec<u8>>> = used_pruners.iter().map(|(t, _)| Arc::clone(t)).collect();
+        let tables: Vec<Arc<PruningTable>> =
+            used_pruners.iter().map(|(t, _)| Arc::clone(t)).collect();
         let pruner_indices: Vec<usize> = self
             .pruners
             .iter()
@@ -353,17 +530,45 @@ impl Solver {
 
         let search_mode = self.search_mode;
 
+        let (search_tx, collector) = if self.ranking.is_some() || self.dedup != DedupMode::Off {
+            let ranking = self.ranking;
+            let mut dedup = Deduplicator::new(self.dedup);
+            let (collected_tx, collected_rx) = crossbeam_channel::unbounded::<Solution>();
+            let collector = std::thread::spawn(move || {
+                let mut top = TopSolutions::new(ranking);
+                for solution in collected_rx.iter() {
+                    if dedup.insert(&solution) {
+                        top.push(solution);
+                    }
+                }
+                let mut solutions = top.into_sorted_vec();
+                for solution in &mut solutions {
+                    dedup.annotate(solution);
+                }
+                solutions
+            });
+            (collected_tx, Some(collector))
+        } else {
+            (solution_tx.clone(), None)
+        };
+        let report_solutions = collector.is_none();
+        let filters = self.filters.clone();
+        let found = Mutex::new(Vec::new());
+        let found_for_cache = cache_key.as_ref().map(|_| &found);
+        let mut searched_through = 0;
+
         for depth in 1..=max_search_depth {
             if interrupted.load(Ordering::SeqCst) {
                 break;
             }
 
-            let depth_start_time = std::time::Instant::now();
+            if let (Some(optimal), Some(slack)) = (self.optimal_depth, self.depth_slack)
+                && depth > optimal + slack
+            {
+                break;
+            }
 
-            let pool = rayon::ThreadPoolBuilder::new()
-                .num_threads(num_threads)
-                .build()
-                .unwrap();
+            let depth_start_time = std::time::Instant::now();
 
             let moves_clone = moves.clone();
             let first_moves_clone = first_moves.clone();
@@ -373,7 +578,7 @@ impl Solver {
             let start_clone = start.clone();
             let goal_clone = goal.clone();
             let interrupted_clone = Arc::clone(&interrupted);
-            let solution_tx_clone = solution_tx.clone();
+            let solution_tx_clone = search_tx.clone();
             let search_mode_clone = search_mode;
 
             let status_tx_clone = status_tx.clone();
@@ -390,61 +595,95 @@ impl Solver {
                 depth as u32,
             ));
 
-            pool.install(|| {
-                moves_clone.par_iter().for_each(|&first_move| {
-                    if interrupted_clone.load(Ordering::Relaxed) {
-                        return;
-                    }
-
-                    let mut minx = start_clone.clone();
-                    minx.apply_move(first_move);
-
-                    let all_pruners = search_mode_clone.create_pruners();
-                    let local_pruners: Vec<&dyn Pruner> = pruner_indices_clone
-                        .iter()
-                        .filter_map(|&i| all_pruners.get(i).map(|p| p.as_ref()))
-                        .collect();
-
-                    let ctx = SearchContext {
-                        tables: &tables_clone,
-                        pruners: &local_pruners,
-                        first_moves: &first_moves_clone,
-                        next_siblings: &next_siblings_clone,
-                        interrupted: &interrupted_clone,
-                        solution_tx: &solution_tx_clone,
-                        status_tx: &status_tx_clone,
-                    };
-
-                    Self::search_branch(&mut minx, &goal_clone, depth, &ctx);
-
-                    let completed = completed_branches.fetch_add(1, Ordering::Relaxed) + 1;
-                    let progress = completed as f64 / total_branches as f64;
-                    let elapsed = depth_start_shared.elapsed().as_secs_f64();
-
-                    let etr_str = if progress > 0.005 && elapsed > 0.5 {
-                        let total_estimated = elapsed / progress;
-                        let remaining = total_estimated - elapsed;
-                        if remaining < 60.0 {
-                            format!("ETR: {:.1}s", remaining)
-                        } else if remaining < 3600.0 {
-                            format!("ETR: {:.1}m", remaining / 60.0)
-                        } else {
-                            format!("ETR: {:.1}h", remaining / 3600.0)
+            if depth <= cached_depth {
+                let ctx = SearchContext {
+                    tables: &tables_clone,
+                    pruners: &[],
+                    first_moves: &first_moves_clone,
+                    next_siblings: &next_siblings_clone,
+                    interrupted: &interrupted_clone,
+                    solution_tx: &solution_tx_clone,
+                    status_tx: &status_tx_clone,
+                    solutions_found: &solutions_found,
+                    search_mode: search_mode_clone,
+                    report_solutions,
+                    filters: &filters,
+                    found: None,
+                };
+                let cached_solutions = cached.iter().flat_map(|c| &c.solutions);
+                for solution in cached_solutions.filter(|s| s.depth == depth) {
+                    Self::deliver(solution.clone(), &ctx);
+                }
+            } else {
+                let pool = rayon::ThreadPoolBuilder::new()
+                    .num_threads(num_threads)
+                    .build()
+                    .unwrap();
+
+                pool.install(|| {
+                    moves_clone.par_iter().for_each(|&first_move| {
+                        if interrupted_clone.load(Ordering::Relaxed) {
+                            return;
                         }
-                    } else {
-                        "ETR: --".to_string()
-                    };
 
-                    let _ = status_tx_clone.send(StatusEvent::with_context(
-                        StatusEventType::Message,
-                        &format!("Searching depth {}... ({})", depth, etr_str),
-                        progress,
-                        None,
-                        depth as u32,
-                    ));
+                        let mut minx = start_clone.clone();
+                        minx.apply_move(first_move);
+
+                        let all_pruners = search_mode_clone.create_pruners();
+                        let local_pruners: Vec<&dyn Pruner> = pruner_indices_clone
+                            .iter()
+                            .filter_map(|&i| all_pruners.get(i).map(|p| p.as_ref()))
+                            .collect();
+
+                        let ctx = SearchContext {
+                            tables: &tables_clone,
+                            pruners: &local_pruners,
+                            first_moves: &first_moves_clone,
+                            next_siblings: &next_siblings_clone,
+                            interrupted: &interrupted_clone,
+                            solution_tx: &solution_tx_clone,
+                            status_tx: &status_tx_clone,
+                            solutions_found: &solutions_found,
+                            search_mode: search_mode_clone,
+                            report_solutions,
+                            filters: &filters,
+                            found: found_for_cache,
+                        };
+
+                        Self::search_branch(&mut minx, &goal_clone, depth, &ctx);
+
+                        let completed = completed_branches.fetch_add(1, Ordering::Relaxed) + 1;
+                        let progress = completed as f64 / total_branches as f64;
+                        let elapsed = depth_start_shared.elapsed().as_secs_f64();
+
+                        let etr_str = if progress > 0.005 && elapsed > 0.5 {
+                            let total_estimated = elapsed / progress;
+                            let remaining = total_estimated - elapsed;
+                            if remaining < 60.0 {
+                                format!("ETR: {:.1}s", remaining)
+                            } else if remaining < 3600.0 {
+                                format!("ETR: {:.1}m", remaining / 60.0)
+                            } else {
+                                format!("ETR: {:.1}h", remaining / 3600.0)
+                            }
+                        } else {
+                            "ETR: --".to_string()
+                        };
+
+                        let _ = status_tx_clone.send(StatusEvent::with_context(
+                            StatusEventType::Message,
+                            &format!("Searching depth {}... ({})", depth, etr_str),
+                            progress,
+                            None,
+                            depth as u32,
+                        ));
+                    });
                 });
-            });
+            }
             let depth_elapsed = depth_start_time.elapsed().as_secs_f64();
+            if !self.is_interrupted() {
+                searched_through = depth;
+            }
 
             self.fire_event(StatusEvent::with_context(
                 StatusEventType::EndDepth,
@@ -453,15 +692,52 @@ impl Solver {
                 None,
                 depth as u32,
             ));
+
+            if self.optimal_depth.is_none()
+                && solutions_found.load(Ordering::SeqCst) > 0
+                && !self.is_interrupted()
+            {
+                self.optimal_depth = Some(depth);
+                self.fire_event(StatusEvent::with_context(
+                    StatusEventType::OptimalDepthFound,
+                    &format!("Optimal solution length: {}", depth),
+                    1.0,
+                    None,
+                    depth as u32,
+                ));
+            }
         }
 
-        drop(solution_tx);
+        drop(search_tx);
         drop(status_tx);
 
         // Wait for the status thread to finish processing all events
         let _ = status_thread.join();
 
-        let solutions: Vec<String> = solution_rx.iter().collect();
+        if let Some((cache, key)) = self.result_cache.as_ref().zip(cache_key.as_ref())
+            && searched_through > cached_depth
+        {
+            let mut solutions = cached.map(|c| c.solutions).unwrap_or_default();
+            solutions.retain(|s| s.depth <= cached_depth);
+            let found = found.into_inner().unwrap_or_default();
+            solutions.extend(found.into_iter().filter(|s| s.depth <= searched_through));
+            if let Err(err) = cache.put(key, searched_through, &solutions) {
+                self.fire_event(StatusEvent::new(StatusEventType::Message, &err, 1.0));
+            }
+        }
+
+        if let Some(solutions) = collector.and_then(|collector| collector.join().ok()) {
+            for solution in solutions {
+                self.fire_event(StatusEvent::solution_found(
+                    &solution.to_string(),
+                    solution.clone(),
+                ));
+                if solution_tx.send(solution).is_err() {
+                    break;
+                }
+            }
+        }
+        drop(solution_tx);
 
         let elapsed = start_time.elapsed();
         let was_interrupted = self.is_interrupted();
@@ -474,8 +750,6 @@ impl Solver {
         };
 
         self.fire_event(StatusEvent::new(StatusEventType::FinishSearch, &msg, 1.0));
-
-        solutions
     }
 
     fn search_branch(minx: &mut LLMinx, goal: &LLMinx, target_depth: usize, ctx: &SearchContext) {
@@ -486,18 +760,11 @@ impl Solver {
 
             if minx.state_equals(goal) {
                 if levels_left == 0 && Self::check_optimal(minx) {
-                    let msg = format!(
-                        "{} ({},{})",
-                        minx.get_generating_moves(),
-                        minx.get_ftm_length(),
-                        minx.get_fftm_length()
-                    );
-                    let _ = ctx.solution_tx.send(msg.clone());
-                    let _ = ctx.status_tx.send(StatusEvent::new(
-                        StatusEventType::SolutionFound,
-                        &msg,
-                        0.0,
-                    ));
+                    let solution = Solution::from_minx(minx, ctx.search_mode);
+                    if let Some(found) = ctx.found {
+                        found.lock().unwrap().push(solution.clone());
+                    }
+                    Self::deliver(solution, ctx);
                 }
                 stop = Self::back_track(minx, ctx.next_siblings);
             } else if levels_left > 0 {
@@ -505,7 +772,7 @@ impl Solver {
                 for (table_idx, pruner) in ctx.pruners.iter().enumerate() {
                     if let Some(table) = ctx.tables.get(table_idx) {
                         let coord = pruner.get_coordinate(minx);
-                        if coord < table.len() && table[coord] as usize > levels_left {
+                        if coord < table.len() && table.get(coord) as usize > levels_left {
                             pruned = true;
                             break;
                         }
@@ -523,6 +790,24 @@ impl Solver {
         }
     }
 
+    /// Hand a solution that passes the filters to the consumer.
+    fn deliver(solution: Solution, ctx: &SearchContext) {
+        if !accepts_all(ctx.filters, &solution) {
+            return;
+        }
+        let event = ctx
+            .report_solutions
+            .then(|| StatusEvent::solution_found(&solution.to_string(), solution.clone()));
+        ctx.solutions_found.fetch_add(1, Ordering::SeqCst);
+        if ctx.solution_tx.send(solution).is_err() {
+            // Nobody is listening any more, e.g. a dropped stream.
+            ctx.interrupted.store(true, Ordering::SeqCst);
+        }
+        if let Some(event) = event {
+            let _ = ctx.status_tx.send(event);
+        }
+    }
+
     pub(crate) fn next_node(
         minx: &mut LLMinx,
         target_depth: usize,
@@ -658,10 +943,44 @@ impl Solver {
     fn build_pruning_tables(&mut self) {
         self.pruners = self.search_mode.create_pruners();
         self.tables = Vec::with_capacity(self.pruners.len());
-        let memory_tracker = MemoryTracker::from_config(&self.memory_config);
         let target_depth = self.pruning_depth;
+        let dir = self.table_directory();
 
-        let total_estimated: usize = self.pruners.iter().map(|p| p.table_size()).sum();
+        // Tables alive in the process already take their share of the budget,
+        // once each, whether or not this solver ends up sharing them. Mapped
+        // tables are paged from disk and stay out of it.
+        let memory_tracker = MemoryTracker::from_config(&self.memory_config);
+        let live = table_registry::live_table_bytes();
+        memory_tracker.allocate(live.heap);
+        if live.heap + live.mapped > 0 {
+            self.fire_event(StatusEvent::new(
+                StatusEventType::Message,
+                &format!(
+                    "Tables already loaded in this process: {} MB in memory, {} MB mapped",
+                    live.heap / (1024 * 1024),
+                    live.mapped / (1024 * 1024)
+                ),
+                0.0,
+            ));
+        }
+
+        let mut total_estimated: usize = self.pruners.iter().map(|p| p.table_size()).sum();
+        let packed = self.memory_config.prefers_packed_tables(total_estimated);
+        if packed {
+            total_estimated = self
+                .pruners
+                .iter()
+                .map(|p| PackedTableBuilder::peak_bytes(p.table_size(), target_depth))
+                .sum();
+            self.fire_event(StatusEvent::new(
+                StatusEventType::Message,
+                &format!(
+                    "Packing pruning tables to 4 bits per entry to fit {} MB budget",
+                    self.memory_config.budget_mb()
+                ),
+                0.0,
+            ));
+        }
         self.fire_event(StatusEvent::new(
             StatusEventType::Message,
             &format!(
@@ -690,9 +1009,28 @@ impl Solver {
                 break;
             }
 
-            let table_size_bytes = pruner.table_size();
+            let table_size_bytes = if packed {
+                PackedTableBuilder::peak_bytes(pruner.table_size(), target_depth)
+            } else {
+                pruner.table_size()
+            };
             let progress = idx as f64 / self.pruners.len() as f64;
 
+            if let Some(table) =
+                table_registry::shared_table(pruner.as_ref(), &dir, self.metric, target_depth)
+            {
+                self.fire_event(StatusEvent::new(
+                    StatusEventType::Message,
+                    &format!(
+                        "Sharing pruning table {} already loaded in this process",
+                        pruner.name()
+                    ),
+                    progress,
+                ));
+                self.tables.push(table);
+                continue;
+            }
+
             if !memory_tracker.can_allocate(table_size_bytes) {
                 self.fire_event(StatusEvent::new(
                     StatusEventType::MemoryExceeded,
@@ -720,7 +1058,33 @@ impl Solver {
                 progress,
             ));
 
-            if pruner.is_precomputed(self.metric, target_depth) {
+            match pruner.map_table(&dir, self.metric, target_depth) {
+                Ok(table) => {
+                    self.fire_event(StatusEvent::new(
+                        StatusEventType::Message,
+                        &format!("Mapped pruning table from disk (depth {})", target_depth),
+                        progress,
+                    ));
+                    let table = if packed { table.into_packed() } else { table };
+                    let table =
+                        self.share_table(pruner.as_ref(), table, target_depth, &memory_tracker);
+                    self.tables.push(table);
+                    continue;
+                }
+                Err(TableError::Missing) => {}
+                Err(e) => self.fire_event(StatusEvent::new(
+                    StatusEventType::InvalidTable,
+                    &format!(
+                        "Discarding pruning table cache {} (depth {}): {}",
+                        pruner.name(),
+                        target_depth,
+                        e
+                    ),
+                    progress,
+                )),
+            }
+
+            if pruner.is_precomputed(&dir, self.metric, target_depth) {
                 self.fire_event(StatusEvent::new(
                     StatusEventType::Message,
                     &format!(
@@ -729,14 +1093,33 @@ impl Solver {
                     ),
                     progress,
                 ));
-                if let Some(table) = pruner.load_table(self.metric, target_depth) {
-                    memory_tracker.allocate(table.len());
-                    self.tables.push(Arc::new(table));
-                    continue;
+                match pruner.read_table(&dir, self.metric, target_depth) {
+                    Ok(table) => {
+                        let table = PruningTable::from(table);
+                        let cached = table.with_entries(|entries| {
+                            pruner.save_table_cache(&dir, entries, self.metric, target_depth)
+                        });
+                        let table =
+                            self.cached_table(pruner.as_ref(), table, target_depth, cached, packed);
+                        let table =
+                            self.share_table(pruner.as_ref(), table, target_depth, &memory_tracker);
+                        self.tables.push(table);
+                        continue;
+                    }
+                    Err(e) => self.fire_event(StatusEvent::new(
+                        StatusEventType::InvalidTable,
+                        &format!(
+                            "Rebuilding pruning table {} (depth {}): {}",
+                            pruner.name(),
+                            target_depth,
+                            e
+                        ),
+                        progress,
+                    )),
                 }
             }
 
-            let base_table = pruner.find_best_existing_table(self.metric, target_depth - 1);
+            let base_table = pruner.find_best_existing_table(&dir, self.metric, target_depth - 1);
 
             self.fire_event(StatusEvent::new(
                 StatusEventType::StartBuildingTable,
@@ -752,32 +1135,48 @@ impl Solver {
                 progress,
             ));
 
-            let table = self.build_pruning_table(
-                pruner.as_ref(),
-                target_depth,
-                base_table.as_ref().map(|(_, d)| *d),
-            );
-            memory_tracker.allocate(table.len());
+            let base_depth = base_table.as_ref().map(|(_, d)| *d);
+            let table = if packed {
+                let builder = PackedTableBuilder::new(pruner.table_size(), target_depth);
+                self.build_pruning_table(pruner.as_ref(), target_depth, base_depth, builder)
+            } else {
+                let builder = FullTableBuilder::new(pruner.table_size());
+                self.build_pruning_table(pruner.as_ref(), target_depth, base_depth, builder)
+            };
 
-            if memory_tracker.is_at_warning_threshold() {
+            if self.is_interrupted() {
+                // A partial table is only good for this search, so it is
+                // neither saved nor shared.
+                memory_tracker.allocate(table.memory_bytes());
+                self.tables.push(Arc::new(table));
+                continue;
+            }
+
+            let table = if table.is_saturated() {
+                // Clamped distances would pass for the exact table on disk
                 self.fire_event(StatusEvent::new(
-                    StatusEventType::MemoryWarning,
+                    StatusEventType::Message,
                     &format!(
-                        "Memory usage at {:.1}% of budget",
-                        memory_tracker.usage_percentage()
+                        "Not writing {} to disk, packing clamped distances above {}",
+                        pruner.name(),
+                        PACKED_MAX_DISTANCE
                     ),
                     progress,
                 ));
-            }
-
-            if !self.is_interrupted() {
+                table
+            } else {
                 self.fire_event(StatusEvent::new(
                     StatusEventType::Message,
                     &format!("Writing table to disk (depth {})...", target_depth),
                     progress,
                 ));
-                pruner.save_table(&table, self.metric, target_depth);
-            }
+                // Packed tables are unpacked only while they are written
+                let cached = table.with_entries(|entries| {
+                    pruner.save_table(&dir, entries, self.metric, target_depth);
+                    pruner.save_table_cache(&dir, entries, self.metric, target_depth)
+                });
+                self.cached_table(pruner.as_ref(), table, target_depth, cached, packed)
+            };
 
             self.fire_event(StatusEvent::new(
                 StatusEventType::EndBuildingTable,
@@ -785,85 +1184,132 @@ impl Solver {
                 progress,
             ));
 
-            self.tables.push(Arc::new(table));
+            let table = self.share_table(pruner.as_ref(), table, target_depth, &memory_tracker);
+            self.tables.push(table);
+
+            if memory_tracker.is_at_warning_threshold() {
+                self.fire_event(StatusEvent::new(
+                    StatusEventType::MemoryWarning,
+                    &format!(
+                        "Memory usage at {:.1}% of budget",
+                        memory_tracker.usage_percentage()
+                    ),
+                    progress,
+                ));
+            }
+        }
+    }
+
+    /// Switch to the mapped copy of a table whose cache was written, keeping
+    /// the table in memory if the cache could not be written. Packed tables
+    /// are always kept in memory.
+    fn cached_table(
+        &self,
+        pruner: &dyn Pruner,
+        table: PruningTable,
+        depth: u8,
+        cached: bool,
+        packed: bool,
+    ) -> PruningTable {
+        if packed {
+            return table.into_packed();
+        }
+        if cached && let Ok(mapped) = pruner.map_table(&self.table_directory(), self.metric, depth)
+        {
+            return mapped;
+        }
+        table
+    }
+
+    /// Register a table for other solvers in the process, charging it to
+    /// this solver's memory budget unless it is mapped.
+    fn share_table(
+        &self,
+        pruner: &dyn Pruner,
+        table: PruningTable,
+        depth: u8,
+        memory_tracker: &MemoryTracker,
+    ) -> Arc<PruningTable> {
+        let table = table_registry::register_table(
+            pruner,
+            &self.table_directory(),
+            self.metric,
+            depth,
+            table,
+        );
+        if !table.is_mapped() {
+            memory_tracker.allocate(table.memory_bytes());
         }
+        table
     }
 
-    fn build_pruning_table(
+    fn build_pruning_table<B: TableBuilder>(
         &self,
         pruner: &dyn Pruner,
         max_depth: u8,
         base_depth: Option<u8>,
-    ) -> Vec<u8> {
+        table: B,
+    ) -> PruningTable {
         let num_threads = self.memory_config.table_generation_threads;
 
-        rayon::ThreadPoolBuilder::new()
+        let table = match rayon::ThreadPoolBuilder::new()
             .num_threads(num_threads)
             .build()
-            .map(|pool| {
-                pool.install(|| self.build_pruning_table_internal(pruner, max_depth, base_depth))
-            })
-            .unwrap_or_else(|_| self.build_pruning_table_internal(pruner, max_depth, base_depth))
+        {
+            Ok(pool) => pool.install(|| {
+                self.build_pruning_table_internal(pruner, max_depth, base_depth, table)
+            }),
+            Err(_) => self.build_pruning_table_internal(pruner, max_depth, base_depth, table),
+        };
+        table.finish()
     }
 
-    fn build_pruning_table_internal(
+    fn build_pruning_table_internal<B: TableBuilder>(
         &self,
         pruner: &dyn Pruner,
         max_depth: u8,
         base_depth: Option<u8>,
-    ) -> Vec<u8> {
-        let table_size = pruner.table_size();
-
-        let (table, start_depth, total_nodes) = if let Some(base) = base_depth {
-            if let Some(base_table) = pruner.load_table(self.metric, base) {
-                let atomic_table: Vec<AtomicU8> =
-                    base_table.into_iter().map(AtomicU8::new).collect();
-
-                let filled: usize = atomic_table
-                    .iter()
-                    .filter(|a| a.load(Ordering::Relaxed) != u8::MAX)
-                    .count();
-
-                // Find the actual maximum depth in the loaded table
-                let actual_max_depth = atomic_table
-                    .iter()
-                    .map(|a| a.load(Ordering::Relaxed))
-                    .filter(|&d| d != u8::MAX)
-                    .max()
-                    .unwrap_or(0);
-
-                self.fire_event(StatusEvent::new(
-                    StatusEventType::Message,
-                    &format!(
-                        "Loaded base table at depth {} ({} positions filled, actual max depth: {})",
-                        base, filled, actual_max_depth
-                    ),
-                    filled as f64 / table_size as f64,
-                ));
-                (atomic_table, actual_max_depth, filled)
-            } else {
-                let atomic_table: Vec<AtomicU8> =
-                    (0..table_size).map(|_| AtomicU8::new(u8::MAX)).collect();
-                let minx = LLMinx::new();
-                let coord = pruner.get_coordinate(&minx);
-                atomic_table[coord].store(0, Ordering::Relaxed);
-                (atomic_table, 0, 1)
+        table: B,
+    ) -> B {
+        let table_size = table.len();
+
+        let base_table = base_depth.and_then(|base| {
+            pruner
+                .load_table(&self.table_directory(), self.metric, base)
+                .map(|base_table| (base, base_table))
+        });
+        let (start_depth, total_nodes) = if let Some((base, base_table)) = base_table {
+            let mut filled = 0;
+            // Find the actual maximum depth in the loaded table
+            let mut actual_max_depth = 0;
+            for (coord, &distance) in base_table.iter().enumerate() {
+                if distance != u8::MAX {
+                    table.reach(coord, distance);
+                    filled += 1;
+                    actual_max_depth = actual_max_depth.max(distance);
+                }
             }
+
+            self.fire_event(StatusEvent::new(
+                StatusEventType::Message,
+                &format!(
+                    "Loaded base table at depth {} ({} positions filled, actual max depth: {})",
+                    base, filled, actual_max_depth
+                ),
+                filled as f64 / table_size as f64,
+            ));
+            (actual_max_depth, filled)
         } else {
-            let atomic_table: Vec<AtomicU8> =
-                (0..table_size).map(|_| AtomicU8::new(u8::MAX)).collect();
             let minx = LLMinx::new();
-            let coord = pruner.get_coordinate(&minx);
-            atomic_table[coord].store(0, Ordering::Relaxed);
-            (atomic_table, 0, 1)
+            table.reach(pruner.get_coordinate(&minx), 0);
+            (0, 1)
         };
 
         let mut total_nodes = total_nodes;
         let mut depth = start_depth;
-
-        let mut prev_depth_count = table
-            .iter()
-            .filter(|a| a.load(Ordering::Relaxed) == depth)
+        let mut prev_depth_count = (0..table_size)
+            .into_par_iter()
+            .filter(|&i| table.is_at(i, depth))
             .count();
 
         while prev_depth_count > 0 && depth < max_depth && !self.is_interrupted() {
@@ -879,6 +1325,7 @@ impl Solver {
             let new_count = if forward_search {
                 let interrupted = &self.interrupted;
                 let moves = &self.moves;
+                let table = &table;
 
                 (0..table_size)
                     .into_par_iter()
@@ -889,21 +1336,13 @@ impl Solver {
                                 return (local_minx, count);
                             }
 
-                            if table[i].load(Ordering::Relaxed) == depth {
+                            if table.is_at(i, depth) {
                                 pruner.set_minx(i, &mut local_minx);
 
                                 for &m in moves {
                                     local_minx.apply_move(m);
                                     let new_coord = pruner.get_coordinate(&local_minx);
-                                    if table[new_coord]
-                                        .compare_exchange(
-                                            u8::MAX,
-                                            next_depth,
-                                            Ordering::Relaxed,
-                                            Ordering::Relaxed,
-                                        )
-                                        .is_ok()
-                                    {
+                                    if table.reach(new_coord, next_depth) {
                                         count += 1;
                                     }
                                     local_minx.undo_move();
@@ -917,6 +1356,7 @@ impl Solver {
             } else {
                 let interrupted = &self.interrupted;
                 let moves = &self.moves;
+                let table = &table;
 
                 (0..table_size)
                     .into_par_iter()
@@ -927,14 +1367,14 @@ impl Solver {
                                 return (local_minx, count);
                             }
 
-                            if table[i].load(Ordering::Relaxed) == u8::MAX {
+                            if !table.is_reached(i) {
                                 pruner.set_minx(i, &mut local_minx);
 
                                 for &m in moves {
                                     local_minx.apply_move(m);
                                     let new_coord = pruner.get_coordinate(&local_minx);
-                                    if table[new_coord].load(Ordering::Relaxed) == depth {
-                                        table[i].store(next_depth, Ordering::Relaxed);
+                                    if table.is_at(new_coord, depth) {
+                                        table.reach(i, next_depth);
                                         count += 1;
                                         break;
                                     }
@@ -954,12 +1394,9 @@ impl Solver {
         }
 
         table
-            .into_iter()
-            .map(|a| a.load(Ordering::Relaxed))
-            .collect()
     }
 
-    fn filter_pruning_tables(&self) -> Vec<(Arc<Vec<u8>>, &dyn Pruner)> {
+    fn filter_pruning_tables(&self) -> Vec<(Arc<PruningTable>, &dyn Pruner)> {
         self.pruners
             .iter()
             .enumerate()
@@ -980,7 +1417,7 @@ impl Solver {
             .collect()
     }
 
-    pub fn get_tables(&self) -> &[Arc<Vec<u8>>] {
+    pub fn get_tables(&self) -> &[Arc<PruningTable>] {
         &self.tables
     }
 
@@ -1032,7 +1469,8 @@ impl Solver {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::PathBuf;
+    use crate::ranking::RankScore;
+    use std::path::Path;
     use std::sync::Mutex;
     use std::sync::atomic::AtomicUsize;
 
@@ -1119,12 +1557,13 @@ mod tests {
             self.use_edge_ori
         }
 
-        fn is_precomputed(&self, _metric: Metric, _depth: u8) -> bool {
+        fn is_precomputed(&self, _dir: &Path, _metric: Metric, _depth: u8) -> bool {
             self.precomputed
         }
 
         fn find_best_existing_table(
             &self,
+            _dir: &Path,
             _metric: Metric,
             max_depth: u8,
         ) -> Option<(PathBuf, u8)> {
@@ -1135,11 +1574,18 @@ mod tests {
             }
         }
 
-        fn load_table(&self, _metric: Metric, _depth: u8) -> Option<Vec<u8>> {
-            self.load_table_result.clone()
+        fn read_table(
+            &self,
+            _dir: &Path,
+            _metric: Metric,
+            _depth: u8,
+        ) -> Result<Vec<u8>, TableError> {
+            self.load_table_result
+                .clone()
+                .ok_or_else(|| TableError::Corrupt("checksum mismatch".to_string()))
         }
 
-        fn save_table(&self, table: &[u8], _metric: Metric, _depth: u8) {
+        fn save_table(&self, _dir: &Path, table: &[u8], _metric: Metric, _depth: u8) {
             self.saved
                 .lock()
                 .expect("mock save lock should not be poisoned")
@@ -1236,6 +1682,7 @@ mod tests {
         solver.set_metric(Metric::Face);
         solver.set_max_search_depth(7);
         solver.set_limit_search_depth(true);
+        solver.set_depth_slack(Some(2));
         solver.set_pruning_depth(200);
         solver.set_memory_config(MemoryConfig::new(128, 1, 1));
 
@@ -1243,6 +1690,8 @@ mod tests {
         assert_eq!(solver.metric(), Metric::Face);
         assert_eq!(solver.max_search_depth(), 7);
         assert!(solver.limit_search_depth());
+        assert_eq!(solver.depth_slack(), Some(2));
+        assert_eq!(solver.optimal_depth(), None);
         assert_eq!(solver.pruning_depth(), MAX_PRUNING_DEPTH);
         assert_eq!(solver.memory_config().budget_mb(), 128);
     }
@@ -1296,6 +1745,95 @@ mod tests {
         assert!(solver.get_tables().len() <= solver.get_pruners().len());
     }
 
+    #[test]
+    fn test_prepare_tables_rebuilds_invalid_table_file() {
+        let dir =
+            std::env::temp_dir().join(format!("llminx_invalid_table_test_{}", std::process::id()));
+        let path = SearchMode::RU.create_pruners()[0].get_table_file(
+            &dir,
+            Metric::Fifth,
+            MAX_PRUNING_DEPTH,
+        );
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&path, b"truncated").unwrap();
+
+        let mut solver =
+            Solver::with_parallel_config(SearchMode::RU, 1, MemoryConfig::new(128, 1, 1));
+        solver.set_table_directory(&dir);
+        solver.set_pruning_depth(MAX_PRUNING_DEPTH);
+        solver.set_start(LLMinx::new());
+
+        let reasons = Arc::new(Mutex::new(Vec::new()));
+        let reasons_clone = Arc::clone(&reasons);
+        let interrupt = solver.interrupt_handle();
+        solver.set_status_callback(move |event| match event.event_type {
+            StatusEventType::InvalidTable => reasons_clone.lock().unwrap().push(event.message),
+            StatusEventType::StartBuildingTable => interrupt.store(true, Ordering::SeqCst),
+            _ => {}
+        });
+
+        solver.prepare_tables();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let reasons = reasons.lock().unwrap();
+        assert_eq!(reasons.len(), 1);
+        assert!(reasons[0].contains("no pruning table header"));
+    }
+
+    #[test]
+    fn test_tight_budget_packs_tables() {
+        let mut solver =
+            Solver::with_parallel_config(SearchMode::RU, 1, MemoryConfig::new(32, 1, 1));
+        solver.set_metric(Metric::Face);
+        solver.set_pruning_depth(MIN_PRUNING_DEPTH);
+        solver.set_start(LLMinx::new());
+
+        let exceeded = Arc::new(AtomicBool::new(false));
+        let exceeded_clone = Arc::clone(&exceeded);
+        solver.set_status_callback(move |event| {
+            if event.event_type == StatusEventType::MemoryExceeded {
+                exceeded_clone.store(true, Ordering::SeqCst);
+            }
+        });
+        solver.prepare_tables();
+
+        assert!(!exceeded.load(Ordering::SeqCst));
+        assert_eq!(solver.get_tables().len(), solver.get_pruners().len());
+        assert!(solver.get_tables().iter().all(|table| table.is_packed()));
+        assert_eq!(solver.get_tables()[0].get(0), 0);
+    }
+
+    #[test]
+    fn test_solvers_share_mapped_tables() {
+        let new_solver = || {
+            let mut solver =
+                Solver::with_parallel_config(SearchMode::RU, 1, MemoryConfig::new(128, 1, 1));
+            solver.set_pruning_depth(MIN_PRUNING_DEPTH);
+            solver.set_start(LLMinx::new());
+            solver
+        };
+
+        let mut first = new_solver();
+        first.prepare_tables();
+        assert!(!first.get_tables().is_empty());
+        assert!(first.get_tables().iter().all(|table| table.is_mapped()));
+
+        let shared = Arc::new(AtomicUsize::new(0));
+        let shared_clone = Arc::clone(&shared);
+        let mut second = new_solver();
+        second.set_status_callback(move |event| {
+            if event.message.starts_with("Sharing pruning table") {
+                shared_clone.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        second.prepare_tables();
+
+        assert_eq!(shared.load(Ordering::SeqCst), first.get_tables().len());
+        for (a, b) in first.get_tables().iter().zip(second.get_tables()) {
+            assert!(Arc::ptr_eq(a, b));
+        }
+    }
+
     #[test]
     fn test_next_node_and_back_track_paths() {
         let mut solver = Solver::new();
@@ -1348,6 +1886,24 @@ mod tests {
         assert!(events.load(Ordering::Relaxed) > 0);
     }
 
+    #[test]
+    fn test_solve_rejects_start_with_outer_pieces_moved() {
+        let mut solver =
+            Solver::with_parallel_config(SearchMode::RU, 0, MemoryConfig::new(0, 1, 1));
+        solver.set_start(LLMinx::from_alg("Rw").unwrap());
+
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let messages_clone = Arc::clone(&messages);
+        solver.set_status_callback(move |event| {
+            messages_clone.lock().unwrap().push(event.message.clone());
+        });
+
+        assert!(solver.solve().is_empty());
+        let messages = messages.lock().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("cannot reach"));
+    }
+
     #[test]
     fn test_build_moves_table_face_vs_fifth() {
         let mut solver = Solver::new();
@@ -1451,7 +2007,7 @@ mod tests {
     fn test_filter_pruning_tables_handles_missing_table_entries() {
         let mut solver = Solver::new();
         solver.pruners = SearchMode::RU.create_pruners();
-        solver.tables = vec![Arc::new(vec![0u8; 1])];
+        solver.tables = vec![Arc::new(PruningTable::from(vec![0u8; 1]))];
 
         let filtered = solver.filter_pruning_tables();
         assert!(filtered.len() <= solver.pruners.len());
@@ -1484,10 +2040,12 @@ mod tests {
         solver.moves = vec![Move::R, Move::U];
 
         let pruner = MockPruner::new("mock", 3);
-        let table = solver.build_pruning_table_internal(&pruner, 2, None);
+        let table = solver
+            .build_pruning_table_internal(&pruner, 2, None, FullTableBuilder::new(3))
+            .finish();
 
         assert_eq!(table.len(), 3);
-        assert!(table.iter().any(|&value| value != u8::MAX));
+        assert!((0..3).any(|i| table.get(i) != u8::MAX));
     }
 
     #[test]
@@ -1497,11 +2055,18 @@ mod tests {
         solver.moves = vec![Move::R];
 
         let pruner = MockPruner::new("loaded", 3).with_loaded_table(vec![0, 1, u8::MAX]);
-        let table = solver.build_pruning_table_internal(&pruner, 3, Some(MIN_PRUNING_DEPTH));
+        let table = solver
+            .build_pruning_table_internal(
+                &pruner,
+                3,
+                Some(MIN_PRUNING_DEPTH),
+                FullTableBuilder::new(3),
+            )
+            .finish();
 
         assert_eq!(table.len(), 3);
-        assert_eq!(table[0], 0);
-        assert_eq!(table[1], 1);
+        assert_eq!(table.get(0), 0);
+        assert_eq!(table.get(1), 1);
     }
 
     #[test]
@@ -1511,10 +2076,17 @@ mod tests {
         solver.moves = vec![Move::R];
 
         let pruner = MockPruner::new("missing", 2);
-        let table = solver.build_pruning_table_internal(&pruner, 1, Some(MIN_PRUNING_DEPTH));
+        let table = solver
+            .build_pruning_table_internal(
+                &pruner,
+                1,
+                Some(MIN_PRUNING_DEPTH),
+                FullTableBuilder::new(2),
+            )
+            .finish();
 
         assert_eq!(table.len(), 2);
-        assert_eq!(table[0], 0);
+        assert_eq!(table.get(0), 0);
     }
 
     #[test]
@@ -1524,7 +2096,7 @@ mod tests {
         solver.moves = vec![Move::R];
 
         let pruner = MockPruner::new("fallback", 2);
-        let table = solver.build_pruning_table(&pruner, 1, None);
+        let table = solver.build_pruning_table(&pruner, 1, None, FullTableBuilder::new(2));
 
         assert_eq!(table.len(), 2);
     }
@@ -1536,11 +2108,71 @@ mod tests {
         solver.moves = vec![Move::R];
 
         let pruner = MockPruner::new("hinted", 3).with_loaded_table(vec![0, 1, u8::MAX]);
-        let table = solver.build_pruning_table(&pruner, 3, Some(MIN_PRUNING_DEPTH));
+        let table = solver.build_pruning_table(
+            &pruner,
+            3,
+            Some(MIN_PRUNING_DEPTH),
+            FullTableBuilder::new(3),
+        );
 
         assert_eq!(table.len(), 3);
-        assert_eq!(table[0], 0);
-        assert!(table[1] <= 2);
+        assert_eq!(table.get(0), 0);
+        assert!(table.get(1) <= 2);
+    }
+
+    #[test]
+    fn test_packed_build_matches_full_build() {
+        let mut solver =
+            Solver::with_parallel_config(SearchMode::RU, 1, MemoryConfig::new(64, 2, 1));
+        solver.moves = SearchMode::RU.possible_moves();
+        let pruner =
+            crate::pruner::CornerPermutationPruner::new("packed", "packed_build", &[0, 1, 2, 3]);
+        let size = pruner.table_size();
+
+        let full = solver.build_pruning_table(&pruner, 10, None, FullTableBuilder::new(size));
+        let packed =
+            solver.build_pruning_table(&pruner, 10, None, PackedTableBuilder::new(size, 10));
+
+        assert!(packed.is_packed());
+        assert_eq!(packed.memory_bytes(), size.div_ceil(2));
+        assert!((0..size).all(|i| full.get(i) == packed.get(i)));
+        assert!((0..size).any(|i| full.get(i) > 1 && full.get(i) != u8::MAX));
+    }
+
+    #[test]
+    fn test_deep_packed_build_clamps_full_build() {
+        use crate::minx::EdgePosition::*;
+
+        // Turning R and U one way only takes this table past sixteen levels
+        let mut solver =
+            Solver::with_parallel_config(SearchMode::RU, 1, MemoryConfig::new(64, 2, 2));
+        let moves = SearchMode::RU.possible_moves();
+        solver.moves = vec![moves[0], moves[4]];
+        let pruner = crate::pruner::EdgePermutationPruner::new(
+            "deep",
+            "deep_packed_build",
+            &[
+                RE2 as u8, UE1 as u8, RE4 as u8, FE2 as u8, UE5 as u8, UE2 as u8, RE3 as u8,
+            ],
+        );
+        let size = pruner.table_size();
+
+        for depth in [PACKED_MAX_DISTANCE, 18, 30] {
+            let full =
+                solver.build_pruning_table(&pruner, depth, None, FullTableBuilder::new(size));
+            let packed = solver.build_pruning_table(
+                &pruner,
+                depth,
+                None,
+                PackedTableBuilder::new(size, depth),
+            );
+            let clamped = PruningTable::packed(&(0..size).map(|i| full.get(i)).collect::<Vec<_>>());
+
+            assert_eq!(packed.is_saturated(), depth > PACKED_MAX_DISTANCE);
+            assert!((0..size).all(|i| packed.get(i) == clamped.get(i)));
+        }
+        let full = solver.build_pruning_table(&pruner, 30, None, FullTableBuilder::new(size));
+        assert!((0..size).any(|i| full.get(i) > 16 && full.get(i) != u8::MAX));
     }
 
     #[test]
@@ -1557,9 +2189,9 @@ mod tests {
             Box::new(MockPruner::new("ori", 2).with_usage_flags(false, false, true, true)),
         ];
         solver.tables = vec![
-            Arc::new(vec![0u8; 2]),
-            Arc::new(vec![0u8; 2]),
-            Arc::new(vec![0u8; 2]),
+            Arc::new(PruningTable::from(vec![0u8; 2])),
+            Arc::new(PruningTable::from(vec![0u8; 2])),
+            Arc::new(PruningTable::from(vec![0u8; 2])),
         ];
 
         let filtered = solver.filter_pruning_tables();
@@ -1571,7 +2203,7 @@ mod tests {
         let mut minx = LLMinx::new();
         let goal = LLMinx::new();
 
-        let pruned_table = Arc::new(vec![2u8, 2u8]);
+        let pruned_table = Arc::new(PruningTable::from(vec![2u8, 2u8]));
         let pruner = MockPruner::new("prune", 2);
         let (solution_tx, solution_rx) = crossbeam_channel::unbounded();
         let (status_tx, status_rx) = crossbeam_channel::unbounded();
@@ -1586,6 +2218,11 @@ mod tests {
             interrupted: &interrupted,
             solution_tx: &solution_tx,
             status_tx: &status_tx,
+            solutions_found: &AtomicUsize::new(0),
+            search_mode: SearchMode::RU,
+            report_solutions: true,
+            filters: &[],
+            found: None,
         };
 
         Solver::search_branch(&mut minx, &goal, 1, &ctx_pruned);
@@ -1595,7 +2232,7 @@ mod tests {
         let mut solved = LLMinx::new();
         solved.apply_move(Move::R);
         solved.apply_move(Move::Ri);
-        let empty_tables: [Arc<Vec<u8>>; 0] = [];
+        let empty_tables: [Arc<PruningTable>; 0] = [];
         let empty_pruners: [&dyn Pruner; 0] = [];
         let (solution_tx2, solution_rx2) = crossbeam_channel::unbounded();
         let (status_tx2, status_rx2) = crossbeam_channel::unbounded();
@@ -1609,6 +2246,11 @@ mod tests {
             interrupted: &interrupted,
             solution_tx: &solution_tx2,
             status_tx: &status_tx2,
+            solutions_found: &AtomicUsize::new(0),
+            search_mode: SearchMode::RU,
+            report_solutions: true,
+            filters: &[],
+            found: None,
         };
 
         let solved_depth = solved.depth();
@@ -1627,7 +2269,7 @@ mod tests {
         solver.build_moves_table();
 
         solver.pruners = vec![Box::new(MockPruner::new("cached", 1))];
-        solver.tables = vec![Arc::new(vec![u8::MAX])];
+        solver.tables = vec![Arc::new(PruningTable::from(vec![u8::MAX]))];
         solver.last_search_mode = Some(solver.search_mode);
         solver.last_metric = Some(solver.metric);
         solver.last_pruning_depth = Some(solver.pruning_depth);
@@ -1649,6 +2291,257 @@ mod tests {
         assert!(depth_events.load(Ordering::Relaxed) >= 3);
     }
 
+    #[test]
+    fn test_result_cache_replays_and_extends_searches() {
+        use crate::result_cache::{CachePolicy, ResultCache};
+
+        let dir =
+            std::env::temp_dir().join(format!("llminx_solver_result_cache_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = Arc::new(ResultCache::open(&dir, CachePolicy::default()).unwrap());
+        let mut start = LLMinx::new();
+        start.apply_move(Move::R);
+        start.clear_moves();
+
+        let solve = |max_depth: usize| {
+            let mut solver = Solver::with_parallel_config(
+                SearchMode::RU,
+                max_depth,
+                MemoryConfig::new(64, 1, 1),
+            );
+            solver.set_limit_search_depth(true);
+            solver.set_start(start.clone());
+            solver.set_result_cache(Some(Arc::clone(&cache)));
+            solver.build_moves_table();
+            solver.pruners = vec![Box::new(MockPruner::new("cached", 1))];
+            solver.tables = vec![Arc::new(PruningTable::from(vec![0]))];
+            solver.last_search_mode = Some(solver.search_mode);
+            solver.last_metric = Some(solver.metric);
+            solver.last_pruning_depth = Some(solver.pruning_depth);
+
+            let loaded = Arc::new(AtomicUsize::new(0));
+            let loaded_clone = Arc::clone(&loaded);
+            solver.set_status_callback(move |event| {
+                if event.message.starts_with("Loaded") {
+                    loaded_clone.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+            let solutions = solver.solve();
+            (solutions, loaded.load(Ordering::Relaxed))
+        };
+        let key = CacheKey::new(&start, SearchMode::RU, Metric::Fifth);
+
+        let (first, loaded) = solve(1);
+        assert!(!first.is_empty());
+        assert_eq!(loaded, 0);
+        assert_eq!(cache.get(&key).unwrap().searched_depth, 1);
+
+        let (replayed, loaded) = solve(1);
+        assert_eq!(loaded, 1);
+        assert_eq!(replayed, first);
+
+        // Depth 1 comes from the cache, the rest is searched and stored
+        let (deeper, loaded) = solve(3);
+        assert_eq!(loaded, 1);
+        assert!(first.iter().all(|s| deeper.contains(s)));
+        assert_eq!(cache.get(&key).unwrap().searched_depth, 3);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_depth_slack_stops_after_optimal_depth() {
+        let mut solver =
+            Solver::with_parallel_config(SearchMode::RU, 6, MemoryConfig::new(64, 1, 1));
+        solver.set_limit_search_depth(true);
+        solver.set_depth_slack(Some(1));
+        let mut start = LLMinx::new();
+        start.apply_move(Move::R);
+        start.clear_moves();
+        solver.set_start(start);
+        solver.build_moves_table();
+
+        solver.pruners = vec![Box::new(MockPruner::new("cached", 1))];
+        solver.tables = vec![Arc::new(PruningTable::from(vec![0]))];
+        solver.last_search_mode = Some(solver.search_mode);
+        solver.last_metric = Some(solver.metric);
+        solver.last_pruning_depth = Some(solver.pruning_depth);
+
+        let finished_depths = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let optimal_events = Arc::new(AtomicUsize::new(0));
+        let finished_clone = Arc::clone(&finished_depths);
+        let optimal_clone = Arc::clone(&optimal_events);
+        solver.set_status_callback(move |event| match event.event_type {
+            StatusEventType::EndDepth => finished_clone.lock().unwrap().push(event.current_depth),
+            StatusEventType::OptimalDepthFound => {
+                assert_eq!(event.current_depth, 1);
+                optimal_clone.fetch_add(1, Ordering::Relaxed);
+            }
+            StatusEventType::SolutionFound => {
+                let solution = event.solution.expect("solution events carry the solution");
+                assert_eq!(event.message, solution.to_string());
+            }
+            _ => {}
+        });
+
+        let solutions = solver.solve();
+        assert!(!solutions.is_empty());
+        assert_eq!(solver.optimal_depth(), Some(1));
+        assert_eq!(*finished_depths.lock().unwrap(), vec![1, 2]);
+        assert_eq!(optimal_events.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_ranked_solve_reports_only_top_solutions() {
+        // Ignoring the whole last layer makes every U turn a solution.
+        let mut solver =
+            Solver::with_parallel_config(SearchMode::RU, 1, MemoryConfig::new(64, 1, 1));
+        solver.set_limit_search_depth(true);
+        solver.set_ranking(Some(SolutionRanking::new(RankScore::Fftm, 2)));
+        solver.set_ignore_corner_positions(true);
+        solver.set_ignore_edge_positions(true);
+        solver.set_ignore_corner_orientations(true);
+        solver.set_ignore_edge_orientations(true);
+        solver.build_moves_table();
+
+        solver.pruners = vec![Box::new(MockPruner::new("cached", 1))];
+        solver.tables = vec![Arc::new(PruningTable::from(vec![0]))];
+        solver.last_search_mode = Some(solver.search_mode);
+        solver.last_metric = Some(solver.metric);
+        solver.last_pruning_depth = Some(solver.pruning_depth);
+
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let events_clone = Arc::clone(&events);
+        solver.set_status_callback(move |event| match event.event_type {
+            StatusEventType::SolutionFound | StatusEventType::FinishSearch => {
+                events_clone.lock().unwrap().push(event)
+            }
+            _ => {}
+        });
+
+        let solutions = solver.solve();
+        assert_eq!(solutions.len(), 2);
+        assert!(solutions.iter().all(|s| s.fftm == 1));
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].solution.as_ref(), Some(&solutions[0]));
+        assert_eq!(events[2].event_type, StatusEventType::FinishSearch);
+    }
+
+    #[test]
+    fn test_dedup_merges_commuting_reorderings() {
+        let mut solver =
+            Solver::with_parallel_config(SearchMode::RUL, 2, MemoryConfig::new(64, 1, 1));
+        solver.set_limit_search_depth(true);
+        solver.set_dedup(DedupMode::Commuting);
+        let mut start = LLMinx::new();
+        start.apply_move(Move::R);
+        start.apply_move(Move::L);
+        start.clear_moves();
+        solver.set_start(start);
+        solver.build_moves_table();
+
+        solver.pruners = vec![Box::new(MockPruner::new("cached", 1))];
+        solver.tables = vec![Arc::new(PruningTable::from(vec![0]))];
+        solver.last_search_mode = Some(solver.search_mode);
+        solver.last_metric = Some(solver.metric);
+        solver.last_pruning_depth = Some(solver.pruning_depth);
+
+        let reported = Arc::new(AtomicUsize::new(0));
+        let reported_clone = Arc::clone(&reported);
+        solver.set_status_callback(move |event| {
+            if event.event_type == StatusEventType::SolutionFound {
+                reported_clone.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+
+        // R' L' and L' R' are the same solution.
+        let solutions = solver.solve();
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].merged_variants, 1);
+        assert_eq!(reported.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_filters_drop_solutions_before_they_are_reported() {
+        // Ignoring the whole last layer makes every U turn a solution.
+        let mut solver =
+            Solver::with_parallel_config(SearchMode::RU, 1, MemoryConfig::new(64, 1, 1));
+        solver.set_limit_search_depth(true);
+        solver.set_filters(vec![Arc::new(
+            crate::filter::NotationRegex::require("'").unwrap(),
+        )]);
+        solver.set_ignore_corner_positions(true);
+        solver.set_ignore_edge_positions(true);
+        solver.set_ignore_corner_orientations(true);
+        solver.set_ignore_edge_orientations(true);
+        solver.build_moves_table();
+
+        solver.pruners = vec![Box::new(MockPruner::new("cached", 1))];
+        solver.tables = vec![Arc::new(PruningTable::from(vec![0]))];
+        solver.last_search_mode = Some(solver.search_mode);
+        solver.last_metric = Some(solver.metric);
+        solver.last_pruning_depth = Some(solver.pruning_depth);
+
+        let reported = Arc::new(AtomicUsize::new(0));
+        let reported_clone = Arc::clone(&reported);
+        solver.set_status_callback(move |event| {
+            if event.event_type == StatusEventType::SolutionFound {
+                reported_clone.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+
+        let solutions = solver.solve();
+        assert!(!solutions.is_empty());
+        assert!(solutions.iter().all(|s| s.algorithm().ends_with('\'')));
+        assert_eq!(reported.load(Ordering::Relaxed), solutions.len());
+    }
+
+    fn cached_stream_solver() -> Solver {
+        let mut solver =
+            Solver::with_parallel_config(SearchMode::RU, 3, MemoryConfig::new(64, 1, 1));
+        solver.set_limit_search_depth(true);
+        let mut start = LLMinx::new();
+        start.apply_move(Move::R);
+        start.clear_moves();
+        solver.set_start(start);
+        solver.build_moves_table();
+
+        solver.pruners = vec![Box::new(MockPruner::new("cached", 1))];
+        solver.tables = vec![Arc::new(PruningTable::from(vec![0]))];
+        solver.last_search_mode = Some(solver.search_mode);
+        solver.last_metric = Some(solver.metric);
+        solver.last_pruning_depth = Some(solver.pruning_depth);
+        solver
+    }
+
+    #[test]
+    fn test_stream_yields_structured_solutions() {
+        let stream = cached_stream_solver().into_stream(1);
+        let solutions: Vec<Solution> = stream.collect();
+        assert!(!solutions.is_empty());
+        assert!(solutions.iter().all(|s| s.search_mode == SearchMode::RU));
+        assert_eq!(solutions[0].moves, vec![Move::Ri]);
+        assert_eq!(solutions[0].to_string(), "R'  (1,1)");
+
+        let collected: Vec<String> = cached_stream_solver()
+            .solve()
+            .iter()
+            .map(Solution::algorithm)
+            .collect();
+        let streamed: Vec<String> = solutions.iter().map(Solution::algorithm).collect();
+        assert_eq!(collected, streamed);
+    }
+
+    #[test]
+    fn test_stream_can_be_cancelled_and_returns_solver() {
+        let mut stream = cached_stream_solver().into_stream(1);
+        assert!(stream.next().is_some());
+        let solver = stream.into_solver();
+        assert!(solver.is_interrupted());
+        assert_eq!(solver.search_mode(), SearchMode::RU);
+    }
+
     #[test]
     fn test_start_getter_returns_configured_state() {
         let mut solver = Solver::new();
@@ -1668,7 +2561,7 @@ mod tests {
         solver.build_moves_table();
 
         solver.pruners = vec![Box::new(MockPruner::new("cached", 1))];
-        solver.tables = vec![Arc::new(vec![u8::MAX])];
+        solver.tables = vec![Arc::new(PruningTable::from(vec![u8::MAX]))];
         solver.last_search_mode = Some(solver.search_mode);
         solver.last_metric = Some(solver.metric);
         solver.last_pruning_depth = Some(solver.pruning_depth);
@@ -1704,7 +2597,7 @@ mod tests {
         solver.build_moves_table();
 
         solver.pruners = vec![Box::new(MockPruner::new("cached", 1))];
-        solver.tables = vec![Arc::new(vec![u8::MAX])];
+        solver.tables = vec![Arc::new(PruningTable::from(vec![u8::MAX]))];
         solver.last_search_mode = Some(solver.search_mode);
         solver.last_metric = Some(solver.metric);
         solver.last_pruning_depth = Some(solver.pruning_depth);
@@ -1761,9 +2654,9 @@ mod tests {
         minx_pruned.apply_move(Move::R);
         let goal = LLMinx::new();
 
-        let (solution_tx, _solution_rx) = crossbeam_channel::unbounded::<String>();
+        let (solution_tx, _solution_rx) = crossbeam_channel::unbounded::<Solution>();
         let (status_tx, _status_rx) = crossbeam_channel::unbounded::<StatusEvent>();
-        let pruned_tables = [Arc::new(vec![3u8; 4])];
+        let pruned_tables = [Arc::new(PruningTable::from(vec![3u8; 4]))];
         let pruned_pruners: [&dyn Pruner; 1] = [&pruner];
 
         let ctx_pruned = SearchContext {
@@ -1774,12 +2667,17 @@ mod tests {
             interrupted: &interrupted,
             solution_tx: &solution_tx,
             status_tx: &status_tx,
+            solutions_found: &AtomicUsize::new(0),
+            search_mode: SearchMode::RU,
+            report_solutions: true,
+            filters: &[],
+            found: None,
         };
         Solver::search_branch(&mut minx_pruned, &goal, 2, &ctx_pruned);
 
         let mut minx_unpruned = LLMinx::new();
         minx_unpruned.apply_move(Move::R);
-        let unpruned_tables = [Arc::new(vec![0u8; 4])];
+        let unpruned_tables = [Arc::new(PruningTable::from(vec![0u8; 4]))];
         let ctx_unpruned = SearchContext {
             tables: &unpruned_tables,
             pruners: &pruned_pruners,
@@ -1788,6 +2686,11 @@ mod tests {
             interrupted: &interrupted,
             solution_tx: &solution_tx,
             status_tx: &status_tx,
+            solutions_found: &AtomicUsize::new(0),
+            search_mode: SearchMode::RU,
+            report_solutions: true,
+            filters: &[],
+            found: None,
         };
         Solver::search_branch(&mut minx_unpruned, &goal, 2, &ctx_unpruned);
     }
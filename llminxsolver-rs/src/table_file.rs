@@ -0,0 +1,532 @@
+//! On-disk format for pruning tables
+//!
+//! Every `.prn.lz4` file starts with a fixed header describing the table it
+//! holds, followed by the lz4 compressed table. Loading checks the header
+//! against the pruner asking for it and the checksum against the decompressed
+//! data, so truncated files and tables from older coordinate layouts are
+//! rejected instead of silently corrupting a search.
+//!
+//! Next to each compressed file an uncompressed `.prn` cache with the same
+//! header can be kept. It is memory-mapped rather than read, so processes
+//! using the same table share one copy through the page cache. Its checksum
+//! is taken from the table when its header is built, not on every map.
+//!
+//! Both files are written under a temporary name and renamed into place, so
+//! an interrupted write never leaves a truncated table behind.
+
+use crate::search_mode::Metric;
+use crate::table_registry::PruningTable;
+use lz4_flex::{compress_prepend_size, decompress_size_prepended};
+use memmap2::Mmap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub const TABLE_MAGIC: [u8; 4] = *b"LLMP";
+
+/// Bump whenever a coordinate layout or the file layout changes.
+pub const TABLE_FORMAT_VERSION: u16 = 1;
+
+/// Magic, version, metric, depth, table size, checksum and name length.
+const FIXED_HEADER_LEN: usize = 4 + 2 + 1 + 1 + 8 + 8 + 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableError {
+    Missing,
+    Io(String),
+    NotATable,
+    UnsupportedVersion(u16),
+    Mismatch(String),
+    Corrupt(String),
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableError::Missing => write!(f, "table file does not exist"),
+            TableError::Io(msg) => write!(f, "failed to read table file: {}", msg),
+            TableError::NotATable => write!(f, "file has no pruning table header"),
+            TableError::UnsupportedVersion(version) => write!(
+                f,
+                "table format version {} does not match current version {}",
+                version, TABLE_FORMAT_VERSION
+            ),
+            TableError::Mismatch(msg) => write!(f, "table header mismatch: {}", msg),
+            TableError::Corrupt(msg) => write!(f, "table data is corrupt: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for TableError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableHeader {
+    pub version: u16,
+    /// Table name of the pruner, as used in the file name.
+    pub name: String,
+    pub metric: Metric,
+    pub depth: u8,
+    pub table_size: u64,
+    /// FNV-1a hash of the decompressed table.
+    pub checksum: u64,
+}
+
+impl TableHeader {
+    pub fn new(name: &str, metric: Metric, depth: u8, table: &[u8]) -> Self {
+        Self {
+            version: TABLE_FORMAT_VERSION,
+            name: name.to_string(),
+            metric,
+            depth,
+            table_size: table.len() as u64,
+            checksum: checksum(table),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let name = self.name.as_bytes();
+        let mut bytes = Vec::with_capacity(FIXED_HEADER_LEN + name.len());
+        bytes.extend_from_slice(&TABLE_MAGIC);
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        bytes.push(metric_byte(self.metric));
+        bytes.push(self.depth);
+        bytes.extend_from_slice(&self.table_size.to_le_bytes());
+        bytes.extend_from_slice(&self.checksum.to_le_bytes());
+        bytes.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(name);
+        bytes
+    }
+
+    /// Parse a header, returning it with the number of bytes it took up.
+    pub fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), TableError> {
+        if bytes.len() < FIXED_HEADER_LEN || bytes[..4] != TABLE_MAGIC {
+            return Err(TableError::NotATable);
+        }
+
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version != TABLE_FORMAT_VERSION {
+            return Err(TableError::UnsupportedVersion(version));
+        }
+
+        let metric = match bytes[6] {
+            0 => Metric::Fifth,
+            1 => Metric::Face,
+            other => return Err(TableError::Corrupt(format!("unknown metric {}", other))),
+        };
+        let depth = bytes[7];
+        let table_size = u64::from_le_bytes(bytes[8..16].try_into().unwrap());
+        let checksum = u64::from_le_bytes(bytes[16..24].try_into().unwrap());
+        let name_len = u16::from_le_bytes([bytes[24], bytes[25]]) as usize;
+
+        let end = FIXED_HEADER_LEN + name_len;
+        let name = bytes
+            .get(FIXED_HEADER_LEN..end)
+            .ok_or_else(|| TableError::Corrupt("header is truncated".to_string()))?;
+        let name = String::from_utf8(name.to_vec())
+            .map_err(|_| TableError::Corrupt("table name is not UTF-8".to_string()))?;
+
+        Ok((
+            Self {
+                version,
+                name,
+                metric,
+                depth,
+                table_size,
+                checksum,
+            },
+            end,
+        ))
+    }
+
+    /// Check that this header describes the table a pruner expects.
+    pub fn matches(
+        &self,
+        name: &str,
+        metric: Metric,
+        depth: u8,
+        table_size: usize,
+    ) -> Result<(), TableError> {
+        if self.name != name {
+            return Err(TableError::Mismatch(format!(
+                "table is for '{}', expected '{}'",
+                self.name, name
+            )));
+        }
+        if self.metric != metric {
+            return Err(TableError::Mismatch(format!(
+                "table uses metric {:?}, expected {:?}",
+                self.metric, metric
+            )));
+        }
+        if self.depth != depth {
+            return Err(TableError::Mismatch(format!(
+                "table has depth {}, expected {}",
+                self.depth, depth
+            )));
+        }
+        if self.table_size != table_size as u64 {
+            return Err(TableError::Mismatch(format!(
+                "table has {} entries, expected {}",
+                self.table_size, table_size
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Read only the header of a table file or cache.
+pub fn read_table_header(path: &Path) -> Result<TableHeader, TableError> {
+    let file = File::open(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => TableError::Missing,
+        _ => TableError::Io(e.to_string()),
+    })?;
+    let mut bytes = Vec::with_capacity(FIXED_HEADER_LEN);
+    file.take((FIXED_HEADER_LEN + u16::MAX as usize) as u64)
+        .read_to_end(&mut bytes)
+        .map_err(|e| TableError::Io(e.to_string()))?;
+    TableHeader::from_bytes(&bytes).map(|(header, _)| header)
+}
+
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Temporary name next to `path`, unique per write so concurrent writers of
+/// the same table in one process never share it.
+fn temp_path_for(path: &Path) -> PathBuf {
+    let count = TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed);
+    path.with_extension(format!("tmp{}_{}", std::process::id(), count))
+}
+
+/// Write `header` and `body` under a temporary name, then rename the file
+/// into place so readers see either the old file or the complete new one.
+fn write_atomically(path: &Path, header: &TableHeader, body: &[u8]) -> std::io::Result<()> {
+    let temp_path = temp_path_for(path);
+    let result = (|| {
+        let file = File::create(&temp_path)?;
+        let mut writer = BufWriter::with_capacity(1 << 22, file);
+        writer.write_all(&header.to_bytes())?;
+        writer.write_all(body)?;
+        writer.flush()?;
+        drop(writer);
+        fs::rename(&temp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Write a header followed by the compressed table.
+pub fn write_table_file(path: &Path, header: &TableHeader, table: &[u8]) -> std::io::Result<()> {
+    write_atomically(path, header, &compress_prepend_size(table))
+}
+
+/// Read a table, verifying its header against the expected name, metric,
+/// depth and size and its contents against the stored checksum.
+pub fn read_table_file(
+    path: &Path,
+    name: &str,
+    metric: Metric,
+    depth: u8,
+    table_size: usize,
+) -> Result<Vec<u8>, TableError> {
+    let file = File::open(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => TableError::Missing,
+        _ => TableError::Io(e.to_string()),
+    })?;
+    let mut reader = BufReader::with_capacity(1 << 20, file);
+    let mut bytes = Vec::new();
+    reader
+        .read_to_end(&mut bytes)
+        .map_err(|e| TableError::Io(e.to_string()))?;
+
+    let (header, offset) = TableHeader::from_bytes(&bytes)?;
+    header.matches(name, metric, depth, table_size)?;
+
+    let table = decompress_size_prepended(&bytes[offset..])
+        .map_err(|e| TableError::Corrupt(e.to_string()))?;
+    if table.len() != table_size {
+        return Err(TableError::Corrupt(format!(
+            "decompressed {} entries, expected {}",
+            table.len(),
+            table_size
+        )));
+    }
+    if checksum(&table) != header.checksum {
+        return Err(TableError::Corrupt("checksum mismatch".to_string()));
+    }
+    Ok(table)
+}
+
+/// Write an uncompressed table cache. The header checksum is trusted, as
+/// `TableHeader::new` just computed it from the table, so only the size is
+/// checked. Renaming into place means a cache another process has mapped is
+/// never modified underneath it.
+pub fn write_table_cache(path: &Path, header: &TableHeader, table: &[u8]) -> std::io::Result<()> {
+    if table.len() as u64 != header.table_size {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "table does not match its header",
+        ));
+    }
+    write_atomically(path, header, table)
+}
+
+/// Map a cache file and check its header and size.
+fn open_table_cache(
+    path: &Path,
+    name: &str,
+    metric: Metric,
+    depth: u8,
+    table_size: usize,
+) -> Result<(Mmap, TableHeader, usize), TableError> {
+    let file = File::open(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => TableError::Missing,
+        _ => TableError::Io(e.to_string()),
+    })?;
+    // Safety: cache files are only ever replaced by rename, never written in place.
+    let map = unsafe { Mmap::map(&file) }.map_err(|e| TableError::Io(e.to_string()))?;
+
+    let (header, offset) = TableHeader::from_bytes(&map)?;
+    header.matches(name, metric, depth, table_size)?;
+    if map.len() - offset != table_size {
+        return Err(TableError::Corrupt(format!(
+            "cache holds {} entries, expected {}",
+            map.len() - offset,
+            table_size
+        )));
+    }
+    Ok((map, header, offset))
+}
+
+/// Memory-map an uncompressed table cache, checking its header and size.
+/// The contents were checksummed when the cache was written; use
+/// `verify_table_cache` to check them again.
+pub fn map_table_cache(
+    path: &Path,
+    name: &str,
+    metric: Metric,
+    depth: u8,
+    table_size: usize,
+) -> Result<PruningTable, TableError> {
+    let (map, _, offset) = open_table_cache(path, name, metric, depth, table_size)?;
+    Ok(PruningTable::mapped(map, offset))
+}
+
+/// Check an uncompressed table cache like `read_table_file`, including the
+/// checksum of its contents.
+pub fn verify_table_cache(
+    path: &Path,
+    name: &str,
+    metric: Metric,
+    depth: u8,
+    table_size: usize,
+) -> Result<(), TableError> {
+    let (map, header, offset) = open_table_cache(path, name, metric, depth, table_size)?;
+    if checksum(&map[offset..]) != header.checksum {
+        return Err(TableError::Corrupt("checksum mismatch".to_string()));
+    }
+    Ok(())
+}
+
+/// 64-bit FNV-1a.
+pub fn checksum(data: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    data.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(PRIME)
+    })
+}
+
+fn metric_byte(metric: Metric) -> u8 {
+    match metric {
+        Metric::Fifth => 0,
+        Metric::Face => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!(
+            "llminx_table_file_{}_{}.prn.lz4",
+            name,
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn test_header_round_trip() {
+        let header = TableHeader::new("ruedges", Metric::Face, 11, &[0, 1, 2, u8::MAX]);
+        let mut bytes = header.to_bytes();
+        bytes.extend_from_slice(&[9, 9, 9]);
+
+        let (parsed, len) = TableHeader::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed, header);
+        assert_eq!(len, bytes.len() - 3);
+    }
+
+    #[test]
+    fn test_header_rejects_foreign_data() {
+        assert_eq!(
+            TableHeader::from_bytes(b"not a pruning table at all"),
+            Err(TableError::NotATable)
+        );
+
+        let mut bytes = TableHeader::new("x", Metric::Fifth, 8, &[0]).to_bytes();
+        bytes[4] = 0xff;
+        assert!(matches!(
+            TableHeader::from_bytes(&bytes),
+            Err(TableError::UnsupportedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn test_header_matches_reports_field() {
+        let header = TableHeader::new("corners", Metric::Fifth, 10, &[0; 6]);
+        assert!(header.matches("corners", Metric::Fifth, 10, 6).is_ok());
+
+        let err = header.matches("corners", Metric::Fifth, 12, 6).unwrap_err();
+        assert!(err.to_string().contains("depth 10, expected 12"));
+        assert!(header.matches("edges", Metric::Fifth, 10, 6).is_err());
+        assert!(header.matches("corners", Metric::Face, 10, 6).is_err());
+        assert!(header.matches("corners", Metric::Fifth, 10, 7).is_err());
+    }
+
+    #[test]
+    fn test_write_and_read_table_file() {
+        let path = temp_path("round_trip");
+        let table: Vec<u8> = (0..200).map(|i| (i % 7) as u8).collect();
+        let header = TableHeader::new("test", Metric::Fifth, 9, &table);
+        write_table_file(&path, &header, &table).unwrap();
+
+        let loaded = read_table_file(&path, "test", Metric::Fifth, 9, table.len()).unwrap();
+        assert_eq!(loaded, table);
+        assert_eq!(read_table_header(&path).unwrap(), header);
+        assert!(matches!(
+            read_table_file(&path, "test", Metric::Fifth, 10, table.len()),
+            Err(TableError::Mismatch(_))
+        ));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_truncated_and_tampered_files_are_rejected() {
+        let path = temp_path("damaged");
+        let table: Vec<u8> = (0..500).map(|i| (i % 13) as u8).collect();
+        let header = TableHeader::new("test", Metric::Face, 9, &table);
+        write_table_file(&path, &header, &table).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 10]).unwrap();
+        assert!(matches!(
+            read_table_file(&path, "test", Metric::Face, 9, table.len()),
+            Err(TableError::Corrupt(_))
+        ));
+
+        let mut tampered = header.clone();
+        tampered.checksum ^= 1;
+        write_table_file(&path, &tampered, &table).unwrap();
+        assert_eq!(
+            read_table_file(&path, "test", Metric::Face, 9, table.len()),
+            Err(TableError::Corrupt("checksum mismatch".to_string()))
+        );
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_table_cache_is_mapped_and_verified() {
+        let path = temp_path("cache").with_extension("prn");
+        let table: Vec<u8> = (0..300).map(|i| (i % 11) as u8).collect();
+        let header = TableHeader::new("test", Metric::Fifth, 10, &table);
+        write_table_cache(&path, &header, &table).unwrap();
+
+        let mapped = map_table_cache(&path, "test", Metric::Fifth, 10, table.len()).unwrap();
+        assert!(mapped.is_mapped());
+        assert!((0..table.len()).all(|i| mapped.get(i) == table[i]));
+        assert!(matches!(
+            map_table_cache(&path, "other", Metric::Fifth, 10, table.len()),
+            Err(TableError::Mismatch(_))
+        ));
+        drop(mapped);
+
+        assert!(verify_table_cache(&path, "test", Metric::Fifth, 10, table.len()).is_ok());
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        std::fs::write(&path, &bytes).unwrap();
+        assert_eq!(
+            verify_table_cache(&path, "test", Metric::Fifth, 10, table.len()),
+            Err(TableError::Corrupt("checksum mismatch".to_string()))
+        );
+        std::fs::write(&path, &bytes[..last]).unwrap();
+        assert!(matches!(
+            map_table_cache(&path, "test", Metric::Fifth, 10, table.len()),
+            Err(TableError::Corrupt(_))
+        ));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_table_cache_write_checks_the_header() {
+        let path = temp_path("cache_header").with_extension("prn");
+        let table: Vec<u8> = (0..64).map(|i| (i % 5) as u8).collect();
+        let header = TableHeader::new("test", Metric::Face, 9, &table[1..]);
+
+        assert!(write_table_cache(&path, &header, &table).is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_table_file_is_replaced_by_rename() {
+        let path = temp_path("replace");
+        let first = vec![1u8; 100];
+        let second = vec![2u8; 100];
+        write_table_file(
+            &path,
+            &TableHeader::new("test", Metric::Face, 9, &first),
+            &first,
+        )
+        .unwrap();
+        write_table_file(
+            &path,
+            &TableHeader::new("test", Metric::Face, 9, &second),
+            &second,
+        )
+        .unwrap();
+
+        let loaded = read_table_file(&path, "test", Metric::Face, 9, second.len()).unwrap();
+        assert_eq!(loaded, second);
+        let stem = path.with_extension("tmp");
+        let stem = stem.file_name().unwrap().to_string_lossy();
+        let leftovers = fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(&*stem))
+            .count();
+        assert_eq!(leftovers, 0);
+        assert_ne!(temp_path_for(&path), temp_path_for(&path));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_missing_and_legacy_files() {
+        let path = temp_path("legacy");
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(
+            read_table_file(&path, "test", Metric::Fifth, 9, 4),
+            Err(TableError::Missing)
+        );
+
+        std::fs::write(&path, compress_prepend_size(&[0, 1, 2, 3])).unwrap();
+        assert_eq!(
+            read_table_file(&path, "test", Metric::Fifth, 9, 4),
+            Err(TableError::NotATable)
+        );
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,863 @@
+//! Management of the pruning tables kept on disk
+//!
+//! Tables are found by scanning the table directory for file names of the
+//! form `d{depth}_{name}{METRIC}.prn.lz4` and their `.prn` caches, so tables
+//! of custom search modes show up too. They can be verified against their
+//! headers and checksums, deleted selectively, built ahead of time in the
+//! background, and copied between machines as bundles.
+//!
+//! A bundle is a plain ustar archive of the compressed files, so it can also
+//! be unpacked with `tar`. Caches are left out, as loading a table recreates
+//! its cache.
+
+use crate::data_directory::table_directory;
+use crate::face_set::FaceSet;
+use crate::memory_config::MemoryConfig;
+use crate::pruner::{CACHE_EXTENSION, COMPRESSED_EXTENSION, metric_suffix};
+use crate::search_mode::{Metric, SearchMode};
+use crate::solver::{Solver, StatusCallback, StatusEvent};
+use crate::table_file::{TableError, read_table_file, read_table_header, verify_table_cache};
+use crate::table_registry::forget_table;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::UNIX_EPOCH;
+
+const TAR_BLOCK: usize = 512;
+const TAR_NAME_LEN: usize = 100;
+
+/// A file belonging to a table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableFile {
+    pub path: PathBuf,
+    pub size_bytes: u64,
+}
+
+/// A pruning table on disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableInfo {
+    /// Table name as used in file names, e.g. `rucorners`.
+    pub name: String,
+    pub metric: Metric,
+    pub depth: u8,
+    /// Built-in search modes that load this table.
+    pub modes: Vec<SearchMode>,
+    /// The compressed `.prn.lz4` table.
+    pub compressed: Option<TableFile>,
+    /// The uncompressed `.prn` cache that is mapped at load time.
+    pub cache: Option<TableFile>,
+}
+
+impl TableInfo {
+    /// Bytes taken up by the table and its cache.
+    pub fn size_bytes(&self) -> u64 {
+        self.files().map(|f| f.size_bytes).sum()
+    }
+
+    fn files(&self) -> impl Iterator<Item = &TableFile> {
+        self.compressed.iter().chain(self.cache.iter())
+    }
+}
+
+/// Which tables `TableManager::delete` removes. Unset fields match any table.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableSelection {
+    /// Tables the mode loads, custom modes included.
+    pub mode: Option<SearchMode>,
+    pub metric: Option<Metric>,
+    pub depth: Option<u8>,
+    /// Remove only the caches and keep the compressed tables.
+    pub caches_only: bool,
+}
+
+impl TableSelection {
+    fn matches(&self, table: &TableInfo) -> bool {
+        self.metric.is_none_or(|m| m == table.metric)
+            && self.depth.is_none_or(|d| d == table.depth)
+            && self.mode.is_none_or(|mode| {
+                mode.create_pruners()
+                    .iter()
+                    .any(|p| p.table_path() == table.name)
+            })
+    }
+}
+
+/// Outcome of verifying one table file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableCheck {
+    pub path: PathBuf,
+    pub result: Result<(), TableError>,
+}
+
+/// Outcome of `TableManager::import_bundle`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BundleImport {
+    /// Tables written to the table directory.
+    pub imported: Vec<PathBuf>,
+    /// Bundle entries that were skipped, with the reason.
+    pub rejected: Vec<(String, String)>,
+}
+
+/// Tables to build with `TableManager::prebuild`.
+#[derive(Debug, Clone)]
+pub struct PrebuildRequest {
+    pub modes: Vec<SearchMode>,
+    pub metric: Metric,
+    pub pruning_depth: u8,
+    pub memory_config: MemoryConfig,
+}
+
+/// A prebuild running in the background.
+pub struct PrebuildHandle {
+    cancelled: Arc<AtomicBool>,
+    current: Arc<Mutex<Option<Arc<AtomicBool>>>>,
+    thread: Option<JoinHandle<Vec<SearchMode>>>,
+}
+
+impl PrebuildHandle {
+    /// Stop after the table being built. Partial tables are not saved.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        if let Some(ref interrupt) = *self.current.lock().unwrap() {
+            interrupt.store(true, Ordering::SeqCst);
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.thread.as_ref().is_none_or(|t| t.is_finished())
+    }
+
+    /// Wait for the build, returning the modes whose tables are all ready.
+    pub fn join(mut self) -> Vec<SearchMode> {
+        self.thread
+            .take()
+            .and_then(|t| t.join().ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Lists, verifies, deletes and bundles the tables in one directory.
+#[derive(Debug, Clone)]
+pub struct TableManager {
+    dir: PathBuf,
+}
+
+impl TableManager {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Manage the directory solvers load tables from: the data directory,
+    /// or else the current directory.
+    pub fn in_data_directory() -> Self {
+        Self::new(table_directory())
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Every table in the directory, ordered by name, metric and depth.
+    pub fn list(&self) -> Vec<TableInfo> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let builtin = builtin_tables();
+
+        let mut tables: HashMap<(String, Metric, u8), TableInfo> = HashMap::new();
+        for entry in entries.flatten() {
+            let file_name = entry.file_name();
+            let Some((name, metric, depth, is_cache)) =
+                file_name.to_str().and_then(parse_file_name)
+            else {
+                continue;
+            };
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let file = TableFile {
+                path: entry.path(),
+                size_bytes: metadata.len(),
+            };
+
+            let table = tables
+                .entry((name.clone(), metric, depth))
+                .or_insert_with(|| TableInfo {
+                    modes: builtin
+                        .get(&name)
+                        .map(|(_, m)| m.clone())
+                        .unwrap_or_default(),
+                    name,
+                    metric,
+                    depth,
+                    compressed: None,
+                    cache: None,
+                });
+            if is_cache {
+                table.cache = Some(file);
+            } else {
+                table.compressed = Some(file);
+            }
+        }
+
+        let mut tables: Vec<TableInfo> = tables.into_values().collect();
+        tables.sort_by(|a, b| {
+            (&a.name, metric_suffix(a.metric), a.depth).cmp(&(
+                &b.name,
+                metric_suffix(b.metric),
+                b.depth,
+            ))
+        });
+        tables
+    }
+
+    /// Total bytes of every table in the directory.
+    pub fn size_bytes(&self) -> u64 {
+        self.list().iter().map(TableInfo::size_bytes).sum()
+    }
+
+    /// Check the header and checksum of each file of `table`.
+    pub fn verify(&self, table: &TableInfo) -> Vec<TableCheck> {
+        let expected = builtin_tables().get(&table.name).map(|(size, _)| *size);
+        let check = |path: &Path, is_cache: bool| {
+            let table_size = match expected {
+                Some(size) => size,
+                None => read_table_header(path)?.table_size as usize,
+            };
+            if is_cache {
+                verify_table_cache(path, &table.name, table.metric, table.depth, table_size)
+            } else {
+                read_table_file(path, &table.name, table.metric, table.depth, table_size)
+                    .map(|_| ())
+            }
+        };
+
+        let compressed = table.compressed.iter().map(|f| (&f.path, false));
+        let cache = table.cache.iter().map(|f| (&f.path, true));
+        compressed
+            .chain(cache)
+            .map(|(path, is_cache)| TableCheck {
+                path: path.clone(),
+                result: check(path, is_cache),
+            })
+            .collect()
+    }
+
+    pub fn verify_all(&self) -> Vec<TableCheck> {
+        self.list().iter().flat_map(|t| self.verify(t)).collect()
+    }
+
+    /// Remove the selected tables, returning the files removed.
+    pub fn delete(&self, selection: &TableSelection) -> Result<Vec<PathBuf>, String> {
+        let mut removed = Vec::new();
+        for table in self.list().iter().filter(|t| selection.matches(t)) {
+            let compressed = table.compressed.iter().filter(|_| !selection.caches_only);
+            for file in compressed.chain(table.cache.iter()) {
+                fs::remove_file(&file.path)
+                    .map_err(|e| format!("Failed to delete {}: {}", file.path.display(), e))?;
+                removed.push(file.path.clone());
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Build the requested tables into this manager's directory on a
+    /// background thread. Solvers find them there when it was opened with
+    /// `in_data_directory`.
+    pub fn prebuild(
+        &self,
+        request: PrebuildRequest,
+        status_callback: Option<StatusCallback>,
+    ) -> PrebuildHandle {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let current: Arc<Mutex<Option<Arc<AtomicBool>>>> = Arc::new(Mutex::new(None));
+        let cancelled_clone = Arc::clone(&cancelled);
+        let current_clone = Arc::clone(&current);
+        let dir = self.dir.clone();
+
+        let thread = std::thread::spawn(move || {
+            let total = request.modes.len();
+            let mut ready = Vec::new();
+            for (idx, &mode) in request.modes.iter().enumerate() {
+                let mut solver = Solver::with_parallel_config(mode, 1, request.memory_config);
+                solver.set_metric(request.metric);
+                solver.set_pruning_depth(request.pruning_depth);
+                solver.set_table_directory(dir.clone());
+                if let Some(ref callback) = status_callback {
+                    let callback = Arc::clone(callback);
+                    let mode_name = mode.to_string();
+                    solver.set_status_callback(move |event| {
+                        callback(StatusEvent::with_context(
+                            event.event_type,
+                            &event.message,
+                            (idx as f64 + event.progress) / total as f64,
+                            Some(mode_name.clone()),
+                            event.current_depth,
+                        ))
+                    });
+                }
+
+                let interrupt = solver.interrupt_handle();
+                *current_clone.lock().unwrap() = Some(Arc::clone(&interrupt));
+                if cancelled_clone.load(Ordering::SeqCst) {
+                    break;
+                }
+                solver.prepare_tables();
+                if interrupt.load(Ordering::SeqCst) {
+                    break;
+                }
+                ready.push(mode);
+            }
+            ready
+        });
+
+        PrebuildHandle {
+            cancelled,
+            current,
+            thread: Some(thread),
+        }
+    }
+
+    /// Write the compressed files of `tables` to a bundle at `path`,
+    /// returning how many were written.
+    pub fn export_bundle(&self, tables: &[TableInfo], path: &Path) -> Result<usize, String> {
+        let write_err = |e: std::io::Error| format!("Failed to write bundle: {}", e);
+        let mut writer = BufWriter::new(File::create(path).map_err(write_err)?);
+
+        let mut written = 0;
+        for file in tables.iter().filter_map(|t| t.compressed.as_ref()) {
+            let name = file
+                .path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .ok_or_else(|| format!("Invalid table path {}", file.path.display()))?;
+            let mut source = File::open(&file.path)
+                .map_err(|e| format!("Failed to read {}: {}", file.path.display(), e))?;
+            let metadata = source.metadata().map_err(write_err)?;
+            let mtime = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs());
+
+            writer
+                .write_all(&tar_header(name, metadata.len(), mtime)?)
+                .map_err(write_err)?;
+            let copied = std::io::copy(&mut source, &mut writer).map_err(write_err)?;
+            if copied != metadata.len() {
+                return Err(format!("{} changed while bundling it", file.path.display()));
+            }
+            writer
+                .write_all(&vec![0; tar_padding(copied)])
+                .map_err(write_err)?;
+            written += 1;
+        }
+
+        // An archive ends with two empty blocks
+        writer
+            .write_all(&[0; 2 * TAR_BLOCK])
+            .and_then(|_| writer.flush())
+            .map_err(write_err)?;
+        Ok(written)
+    }
+
+    /// Unpack the tables in a bundle into the directory. Each table is
+    /// verified before it replaces an existing one, whose cache is removed;
+    /// entries that aren't valid tables of a known mode are skipped and
+    /// reported.
+    pub fn import_bundle(&self, path: &Path) -> Result<BundleImport, String> {
+        let read_err = |e: std::io::Error| format!("Failed to read bundle: {}", e);
+        let file = File::open(path).map_err(read_err)?;
+        let mut reader = BufReader::new(file);
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create table directory: {}", e))?;
+
+        let mut import = BundleImport::default();
+        let mut header = [0u8; TAR_BLOCK];
+        loop {
+            reader.read_exact(&mut header).map_err(read_err)?;
+            if header.iter().all(|&b| b == 0) {
+                break;
+            }
+            let (entry_name, size, is_file) = parse_tar_header(&header)?;
+            let mut data = (&mut reader).take(size);
+
+            // Only the file name is used, so entries can't escape the directory
+            let file_name = Path::new(&entry_name)
+                .file_name()
+                .and_then(|n| n.to_str())
+                .map(str::to_string);
+            let table = file_name
+                .as_deref()
+                .and_then(parse_file_name)
+                .filter(|(_, _, _, is_cache)| !is_cache);
+            match (is_file, file_name, table) {
+                (true, Some(file_name), Some((name, metric, depth, _))) => {
+                    let target = self.dir.join(&file_name);
+                    match self.import_table(&mut data, &target, &name, metric, depth) {
+                        Ok(()) => import.imported.push(target),
+                        Err(reason) => import.rejected.push((entry_name, reason)),
+                    }
+                }
+                (true, _, _) => import
+                    .rejected
+                    .push((entry_name, "not a pruning table file".to_string())),
+                _ => {}
+            }
+
+            std::io::copy(&mut data, &mut std::io::sink()).map_err(read_err)?;
+            let mut padding = vec![0; tar_padding(size)];
+            reader.read_exact(&mut padding).map_err(read_err)?;
+        }
+        Ok(import)
+    }
+
+    fn import_table(
+        &self,
+        data: &mut impl Read,
+        target: &Path,
+        name: &str,
+        metric: Metric,
+        depth: u8,
+    ) -> Result<(), String> {
+        let partial = target.with_extension(format!("import{}", std::process::id()));
+        let result = (|| {
+            let mut file = File::create(&partial).map_err(|e| e.to_string())?;
+            std::io::copy(data, &mut file).map_err(|e| e.to_string())?;
+            drop(file);
+
+            // Sizes come from the search modes, never from the bundle, so a
+            // forged header can't make the table allocate without bound.
+            let table_size = known_table_size(name)
+                .ok_or_else(|| "not a table of any search mode".to_string())?;
+            read_table_file(&partial, name, metric, depth, table_size)
+                .map_err(|e| e.to_string())?;
+            fs::rename(&partial, target).map_err(|e| e.to_string())?;
+
+            // The old cache and any loaded copy belong to the replaced table
+            let cache = self.dir.join(format!(
+                "d{}_{}{}{}",
+                depth,
+                name,
+                metric_suffix(metric),
+                CACHE_EXTENSION
+            ));
+            match fs::remove_file(&cache) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(format!("Failed to remove stale cache: {}", e));
+                }
+                _ => {}
+            }
+            forget_table(&self.dir, name, metric, depth);
+            Ok(())
+        })();
+        if result.is_err() {
+            let _ = fs::remove_file(&partial);
+        }
+        result
+    }
+}
+
+/// Table size and users of every table the built-in modes load.
+fn builtin_tables() -> HashMap<String, (usize, Vec<SearchMode>)> {
+    let mut tables: HashMap<String, (usize, Vec<SearchMode>)> = HashMap::new();
+    for mode in SearchMode::BUILTIN {
+        for pruner in mode.create_pruners() {
+            tables
+                .entry(pruner.table_path().to_string())
+                .or_insert_with(|| (pruner.table_size(), Vec::new()))
+                .1
+                .push(mode);
+        }
+    }
+    tables
+}
+
+/// Entry count of the table called `name` in a built-in or custom mode.
+fn known_table_size(name: &str) -> Option<usize> {
+    if let Some((size, _)) = builtin_tables().get(name) {
+        return Some(*size);
+    }
+    FaceSet::all()
+        .filter(|set| name.starts_with(&set.table_key()))
+        .flat_map(|set| set.create_pruners())
+        .find(|pruner| pruner.table_path() == name)
+        .map(|pruner| pruner.table_size())
+}
+
+/// Name, metric, depth and whether it is a cache, for a table file name.
+fn parse_file_name(file_name: &str) -> Option<(String, Metric, u8, bool)> {
+    let (stem, is_cache) = match file_name.strip_suffix(COMPRESSED_EXTENSION) {
+        Some(stem) => (stem, false),
+        None => (file_name.strip_suffix(CACHE_EXTENSION)?, true),
+    };
+    let (depth, rest) = stem.strip_prefix('d')?.split_once('_')?;
+    let depth = depth.parse().ok()?;
+    let (name, metric) = [Metric::Fifth, Metric::Face]
+        .into_iter()
+        .find_map(|metric| Some((rest.strip_suffix(metric_suffix(metric))?, metric)))?;
+    if name.is_empty() {
+        return None;
+    }
+    Some((name.to_string(), metric, depth, is_cache))
+}
+
+fn tar_padding(size: u64) -> usize {
+    (TAR_BLOCK - (size as usize % TAR_BLOCK)) % TAR_BLOCK
+}
+
+/// Write `value` as zero-padded octal filling all but the last byte of
+/// `field`, which stays NUL.
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = format!("{:0width$o}", value, width = field.len() - 1);
+    field[..digits.len()].copy_from_slice(digits.as_bytes());
+}
+
+fn parse_octal(field: &[u8]) -> Option<u64> {
+    let text = std::str::from_utf8(field).ok()?;
+    let text = text.trim_matches(|c: char| c == '\0' || c == ' ');
+    if text.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(text, 8).ok()
+}
+
+fn tar_checksum(header: &[u8; TAR_BLOCK]) -> u64 {
+    // The checksum field itself counts as spaces
+    header
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| if (148..156).contains(&i) { b' ' } else { b } as u64)
+        .sum()
+}
+
+fn tar_header(name: &str, size: u64, mtime: u64) -> Result<[u8; TAR_BLOCK], String> {
+    if name.len() >= TAR_NAME_LEN {
+        return Err(format!("Table name {} is too long for a bundle", name));
+    }
+    let mut header = [0u8; TAR_BLOCK];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    write_octal(&mut header[100..108], 0o644);
+    write_octal(&mut header[108..116], 0);
+    write_octal(&mut header[116..124], 0);
+    write_octal(&mut header[124..136], size);
+    write_octal(&mut header[136..148], mtime);
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    let checksum = tar_checksum(&header);
+    write_octal(&mut header[148..155], checksum);
+    header[155] = b' ';
+    Ok(header)
+}
+
+/// Entry path, size and whether it is a regular file.
+fn parse_tar_header(header: &[u8; TAR_BLOCK]) -> Result<(String, u64, bool), String> {
+    let invalid = || "Bundle is not a tar archive or is damaged".to_string();
+    let stored = parse_octal(&header[148..156]).ok_or_else(invalid)?;
+    if stored != tar_checksum(header) {
+        return Err(invalid());
+    }
+
+    let text = |field: &[u8]| {
+        let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+        String::from_utf8_lossy(&field[..end]).into_owned()
+    };
+    let name = text(&header[..TAR_NAME_LEN]);
+    let prefix = text(&header[345..500]);
+    let path = if prefix.is_empty() {
+        name
+    } else {
+        format!("{}/{}", prefix, name)
+    };
+    let size = parse_octal(&header[124..136]).ok_or_else(invalid)?;
+    let is_file = matches!(header[156], b'0' | 0);
+    Ok((path, size, is_file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table_file::{TableHeader, write_table_cache, write_table_file};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "llminx_table_manager_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_table(dir: &Path, name: &str, metric: Metric, depth: u8, table: &[u8]) -> PathBuf {
+        let file_name = format!("d{}_{}{}", depth, name, metric_suffix(metric));
+        let header = TableHeader::new(name, metric, depth, table);
+        let path = dir.join(format!("{}{}", file_name, COMPRESSED_EXTENSION));
+        write_table_file(&path, &header, table).unwrap();
+        write_table_cache(
+            &dir.join(format!("{}{}", file_name, CACHE_EXTENSION)),
+            &header,
+            table,
+        )
+        .unwrap();
+        path
+    }
+
+    #[test]
+    fn test_parse_file_name() {
+        assert_eq!(
+            parse_file_name("d12_rucornersFIFTH.prn.lz4"),
+            Some(("rucorners".to_string(), Metric::Fifth, 12, false))
+        );
+        assert_eq!(
+            parse_file_name("d8_ruedgepermutationsFACE.prn"),
+            Some(("ruedgepermutations".to_string(), Metric::Face, 8, true))
+        );
+        assert_eq!(parse_file_name("d8_rucornersFACE.tmp123"), None);
+        assert_eq!(parse_file_name("d8_FIFTH.prn"), None);
+        assert_eq!(parse_file_name("dx_rucornersFIFTH.prn"), None);
+        assert_eq!(parse_file_name("notes.txt"), None);
+    }
+
+    #[test]
+    fn test_list_groups_files_and_finds_modes() {
+        let dir = temp_dir("list");
+        write_table(&dir, "rucorners", Metric::Fifth, 9, &[0, 1, 2]);
+        write_table(&dir, "custom", Metric::Face, 8, &[0; 10]);
+        fs::remove_file(dir.join("d8_customFACE.prn")).unwrap();
+        fs::write(dir.join("unrelated.txt"), "x").unwrap();
+
+        let manager = TableManager::new(&dir);
+        let tables = manager.list();
+        assert_eq!(tables.len(), 2);
+
+        assert_eq!(tables[0].name, "custom");
+        assert!(tables[0].modes.is_empty());
+        assert!(tables[0].cache.is_none());
+        assert_eq!(tables[1].name, "rucorners");
+        assert_eq!(tables[1].depth, 9);
+        assert!(tables[1].modes.contains(&SearchMode::RU));
+        assert!(tables[1].compressed.is_some() && tables[1].cache.is_some());
+        assert_eq!(
+            manager.size_bytes(),
+            tables.iter().map(TableInfo::size_bytes).sum::<u64>()
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_verify_reports_each_file() {
+        let dir = temp_dir("verify");
+        let path = write_table(&dir, "custom", Metric::Fifth, 8, &[0, 1, 2, 3]);
+        // A built-in table name with the wrong number of entries
+        write_table(&dir, "rucorners", Metric::Fifth, 8, &[0, 1]);
+
+        let manager = TableManager::new(&dir);
+        let checks = manager.verify_all();
+        assert_eq!(checks.len(), 4);
+        let custom: Vec<_> = checks.iter().filter(|c| c.path.starts_with(&dir)).collect();
+        assert!(
+            custom
+                .iter()
+                .filter(|c| c.path.to_string_lossy().contains("custom"))
+                .all(|c| c.result.is_ok())
+        );
+        assert!(
+            custom
+                .iter()
+                .filter(|c| c.path.to_string_lossy().contains("rucorners"))
+                .all(|c| matches!(c.result, Err(TableError::Mismatch(_))))
+        );
+
+        let mut bytes = fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        fs::write(&path, bytes).unwrap();
+        let custom = manager
+            .list()
+            .into_iter()
+            .find(|t| t.name == "custom")
+            .unwrap();
+        let checks = manager.verify(&custom);
+        assert!(checks[0].result.is_err());
+        assert!(checks[1].result.is_ok());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_delete_selection() {
+        let dir = temp_dir("delete");
+        write_table(&dir, "rucorners", Metric::Fifth, 8, &[0]);
+        write_table(&dir, "rucorners", Metric::Face, 8, &[0]);
+        write_table(&dir, "ruedgepermutations", Metric::Fifth, 9, &[0]);
+        let manager = TableManager::new(&dir);
+
+        let removed = manager
+            .delete(&TableSelection {
+                caches_only: true,
+                metric: Some(Metric::Face),
+                ..TableSelection::default()
+            })
+            .unwrap();
+        assert_eq!(removed, vec![dir.join("d8_rucornersFACE.prn")]);
+
+        let removed = manager
+            .delete(&TableSelection {
+                mode: Some(SearchMode::RU),
+                depth: Some(8),
+                ..TableSelection::default()
+            })
+            .unwrap();
+        assert_eq!(removed.len(), 3);
+        let left = manager.list();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].name, "ruedgepermutations");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    /// Smallest table of any custom mode.
+    fn small_custom_pruner() -> Box<dyn crate::pruner::Pruner> {
+        FaceSet::all()
+            .flat_map(|set| set.create_pruners())
+            .min_by_key(|pruner| pruner.table_size())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let source = temp_dir("bundle_source");
+        write_table(&source, "rucorners", Metric::Fifth, 8, &[1]);
+        write_table(&source, "unknown", Metric::Face, 8, &[1; 1000]);
+        let pruner = small_custom_pruner();
+        let name = pruner.table_path();
+        let custom: Vec<u8> = (0..pruner.table_size()).map(|i| (i % 9) as u8).collect();
+        write_table(&source, name, Metric::Face, 10, &custom);
+        let bundle = source.join("tables.tar");
+
+        let manager = TableManager::new(&source);
+        // The wrong-sized rucorners table and the table no mode loads export
+        // but are rejected on import
+        assert_eq!(manager.export_bundle(&manager.list(), &bundle).unwrap(), 3);
+        assert_eq!(fs::metadata(&bundle).unwrap().len() % TAR_BLOCK as u64, 0);
+
+        let target = temp_dir("bundle_target");
+        let import = TableManager::new(&target).import_bundle(&bundle).unwrap();
+        let imported = target.join(format!("d10_{}FACE.prn.lz4", name));
+        assert_eq!(import.imported, vec![imported.clone()]);
+        let mut rejected: Vec<&str> = import.rejected.iter().map(|(n, _)| n.as_str()).collect();
+        rejected.sort_unstable();
+        assert_eq!(
+            rejected,
+            vec!["d8_rucornersFIFTH.prn.lz4", "d8_unknownFACE.prn.lz4"]
+        );
+        assert_eq!(
+            read_table_file(&imported, name, Metric::Face, 10, custom.len()).unwrap(),
+            custom
+        );
+        assert_eq!(fs::read_dir(&target).unwrap().count(), 1);
+
+        fs::write(&bundle, [1u8; TAR_BLOCK]).unwrap();
+        assert!(TableManager::new(&target).import_bundle(&bundle).is_err());
+        let _ = fs::remove_dir_all(&source);
+        let _ = fs::remove_dir_all(&target);
+    }
+
+    #[test]
+    fn test_import_replaces_cache_and_shared_table() {
+        use crate::table_registry::{PruningTable, register_table, shared_table};
+
+        let pruner = small_custom_pruner();
+        let name = pruner.table_path();
+        let size = pruner.table_size();
+        let source = temp_dir("replace_source");
+        write_table(&source, name, Metric::Fifth, 11, &vec![2; size]);
+        let bundle = source.join("tables.tar");
+        let manager = TableManager::new(&source);
+        manager.export_bundle(&manager.list(), &bundle).unwrap();
+
+        let target = temp_dir("replace_target");
+        write_table(&target, name, Metric::Fifth, 11, &vec![1; size]);
+        let cache = target.join(format!("d11_{}FIFTH.prn", name));
+        assert!(cache.exists());
+        let loaded = register_table(
+            pruner.as_ref(),
+            &target,
+            Metric::Fifth,
+            11,
+            PruningTable::from(vec![1; size]),
+        );
+        assert!(shared_table(pruner.as_ref(), &target, Metric::Fifth, 11).is_some());
+
+        let import = TableManager::new(&target).import_bundle(&bundle).unwrap();
+        assert_eq!(import.imported.len(), 1);
+        assert!(!cache.exists());
+        assert!(shared_table(pruner.as_ref(), &target, Metric::Fifth, 11).is_none());
+        assert_eq!(loaded.get(0), 1);
+        assert_eq!(
+            read_table_file(&import.imported[0], name, Metric::Fifth, 11, size).unwrap(),
+            vec![2; size]
+        );
+        let _ = fs::remove_dir_all(&source);
+        let _ = fs::remove_dir_all(&target);
+    }
+
+    #[test]
+    fn test_tar_header_round_trip() {
+        let header = tar_header("d8_rucornersFIFTH.prn.lz4", 1234, 5).unwrap();
+        assert_eq!(
+            parse_tar_header(&header).unwrap(),
+            ("d8_rucornersFIFTH.prn.lz4".to_string(), 1234, true)
+        );
+        assert!(tar_header(&"x".repeat(TAR_NAME_LEN), 0, 0).is_err());
+    }
+
+    #[test]
+    fn test_prebuild_and_cancel() {
+        // Kept between runs so the tables are only built once
+        let manager = TableManager::new(std::env::temp_dir().join("llminx_prebuild_test"));
+        let request = PrebuildRequest {
+            modes: vec![SearchMode::RU],
+            metric: Metric::Fifth,
+            pruning_depth: crate::pruner::MIN_PRUNING_DEPTH,
+            memory_config: MemoryConfig::new(256, 1, 1),
+        };
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_clone = Arc::clone(&events);
+        let callback: StatusCallback = Arc::new(move |event: StatusEvent| {
+            events_clone.lock().unwrap().push(event);
+        });
+        let handle = manager.prebuild(request.clone(), Some(callback));
+        assert_eq!(handle.join(), vec![SearchMode::RU]);
+        let built = manager.list();
+        assert!(!built.is_empty());
+        assert!(
+            built
+                .iter()
+                .all(|t| t.compressed.is_some() && t.cache.is_some())
+        );
+        let events = events.lock().unwrap();
+        assert!(!events.is_empty());
+        assert!(
+            events
+                .iter()
+                .all(|e| e.search_mode.as_deref() == Some("RU") && e.progress <= 1.0)
+        );
+
+        let handle = manager.prebuild(
+            PrebuildRequest {
+                modes: vec![SearchMode::RU, SearchMode::RU],
+                ..request
+            },
+            None,
+        );
+        handle.cancel();
+        assert!(handle.join().len() < 2);
+    }
+}
@@ -0,0 +1,598 @@
+//! Process-wide sharing of pruning tables
+//!
+//! Tables are keyed by table directory, pruner table name, metric and depth.
+//! A solver that needs a table another solver in the same process already
+//! holds gets the same `Arc` instead of a second copy. Entries are weak, so a table is freed
+//! as soon as the last solver using it is dropped.
+
+use crate::pruner::Pruner;
+use crate::search_mode::Metric;
+use memmap2::Mmap;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+
+/// Largest distance a packed entry holds exactly. Deeper distances are
+/// stored as `PACKED_MAX_DISTANCE + 1`, which is still a lower bound.
+pub const PACKED_MAX_DISTANCE: u8 = 14;
+
+/// Packed entry for a distance above `PACKED_MAX_DISTANCE`, or for a
+/// coordinate the table never reached.
+const PACKED_OVERFLOW: u8 = 0xF;
+
+/// Reading of a `PACKED_OVERFLOW` entry once distances were clamped.
+const PACKED_SATURATED: u8 = PACKED_MAX_DISTANCE + 1;
+
+/// A pruning table held in memory, as a mapped cache file, or packed two
+/// entries to a byte.
+pub struct PruningTable {
+    storage: Storage,
+}
+
+enum Storage {
+    Owned(Vec<u8>),
+    Mapped {
+        map: Mmap,
+        offset: usize,
+    },
+    /// `overflow` is what a `PACKED_OVERFLOW` entry reads as: `u8::MAX` when
+    /// every reached distance fit, otherwise `PACKED_SATURATED`.
+    Packed {
+        nibbles: Vec<u8>,
+        len: usize,
+        overflow: u8,
+    },
+}
+
+impl PruningTable {
+    pub(crate) fn mapped(map: Mmap, offset: usize) -> Self {
+        Self {
+            storage: Storage::Mapped { map, offset },
+        }
+    }
+
+    /// Pack a table into 4-bit entries, halving its memory. Distances above
+    /// `PACKED_MAX_DISTANCE` are clamped, and unreached coordinates then read
+    /// as `PACKED_MAX_DISTANCE + 1` too.
+    pub fn packed(table: &[u8]) -> Self {
+        let saturated = table
+            .iter()
+            .any(|&d| d != u8::MAX && d > PACKED_MAX_DISTANCE);
+        let nibble = |d: u8| d.min(PACKED_OVERFLOW);
+        let nibbles = table
+            .chunks(2)
+            .map(|pair| {
+                nibble(pair[0]) | (pair.get(1).map_or(PACKED_OVERFLOW, |&d| nibble(d)) << 4)
+            })
+            .collect();
+        Self {
+            storage: Storage::Packed {
+                nibbles,
+                len: table.len(),
+                overflow: overflow_reading(saturated),
+            },
+        }
+    }
+
+    /// Pack this table unless it already is.
+    pub fn into_packed(self) -> Self {
+        match &self.storage {
+            Storage::Owned(table) => Self::packed(table),
+            Storage::Mapped { map, offset } => Self::packed(&map[*offset..]),
+            Storage::Packed { .. } => self,
+        }
+    }
+
+    /// Run `f` on one byte per entry, unpacking a packed table for the call.
+    pub(crate) fn with_entries<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        match &self.storage {
+            Storage::Owned(table) => f(table),
+            Storage::Mapped { map, offset } => f(&map[*offset..]),
+            Storage::Packed { len, .. } => {
+                let entries: Vec<u8> = (0..*len).map(|coord| self.get(coord)).collect();
+                f(&entries)
+            }
+        }
+    }
+
+    /// Distance stored for a coordinate, `u8::MAX` if it was never reached.
+    #[inline]
+    pub fn get(&self, coord: usize) -> u8 {
+        match &self.storage {
+            Storage::Owned(table) => table[coord],
+            Storage::Mapped { map, offset } => map[offset + coord],
+            Storage::Packed {
+                nibbles, overflow, ..
+            } => {
+                let nibble = (nibbles[coord / 2] >> ((coord % 2) * 4)) & 0xF;
+                if nibble == PACKED_OVERFLOW {
+                    *overflow
+                } else {
+                    nibble
+                }
+            }
+        }
+    }
+
+    /// Number of coordinates in the table.
+    pub fn len(&self) -> usize {
+        match &self.storage {
+            Storage::Owned(table) => table.len(),
+            Storage::Mapped { map, offset } => map.len() - offset,
+            Storage::Packed { len, .. } => *len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Bytes the table occupies, which is half its length when packed.
+    pub fn memory_bytes(&self) -> usize {
+        match &self.storage {
+            Storage::Packed { nibbles, .. } => nibbles.len(),
+            _ => self.len(),
+        }
+    }
+
+    /// Whether the table is backed by a mapped file rather than owned memory.
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.storage, Storage::Mapped { .. })
+    }
+
+    pub fn is_packed(&self) -> bool {
+        matches!(self.storage, Storage::Packed { .. })
+    }
+
+    /// Whether packing clamped some distances, so the table no longer holds
+    /// them exactly and must not be written out as the table for its depth.
+    pub fn is_saturated(&self) -> bool {
+        matches!(
+            self.storage,
+            Storage::Packed {
+                overflow: PACKED_SATURATED,
+                ..
+            }
+        )
+    }
+}
+
+/// What a `PACKED_OVERFLOW` entry reads as, depending on whether the table
+/// clamped any distance.
+fn overflow_reading(saturated: bool) -> u8 {
+    if saturated { PACKED_SATURATED } else { u8::MAX }
+}
+
+impl From<Vec<u8>> for PruningTable {
+    fn from(table: Vec<u8>) -> Self {
+        Self {
+            storage: Storage::Owned(table),
+        }
+    }
+}
+
+/// A pruning table being filled in by breadth-first search from several
+/// threads at once.
+pub(crate) trait TableBuilder: Send + Sync {
+    fn len(&self) -> usize;
+
+    fn is_reached(&self, coord: usize) -> bool;
+
+    /// Whether a coordinate was reached at `distance`. Builders that clamp
+    /// deep distances may also answer true for a coordinate reached a
+    /// multiple of three levels earlier, whose neighbours are all reached
+    /// already, so breadth-first search only revisits it without effect.
+    fn is_at(&self, coord: usize, distance: u8) -> bool;
+
+    /// Give an unreached coordinate its distance. Returns false if the
+    /// coordinate was already reached.
+    fn reach(&self, coord: usize, distance: u8) -> bool;
+
+    fn finish(self) -> PruningTable
+    where
+        Self: Sized;
+}
+
+/// Builds a table with one byte per entry.
+pub(crate) struct FullTableBuilder {
+    entries: Vec<AtomicU8>,
+}
+
+impl FullTableBuilder {
+    pub(crate) fn new(len: usize) -> Self {
+        Self {
+            entries: (0..len).map(|_| AtomicU8::new(u8::MAX)).collect(),
+        }
+    }
+}
+
+impl TableBuilder for FullTableBuilder {
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    fn is_reached(&self, coord: usize) -> bool {
+        self.entries[coord].load(Ordering::Relaxed) != u8::MAX
+    }
+
+    #[inline]
+    fn is_at(&self, coord: usize, distance: u8) -> bool {
+        self.entries[coord].load(Ordering::Relaxed) == distance
+    }
+
+    #[inline]
+    fn reach(&self, coord: usize, distance: u8) -> bool {
+        self.entries[coord]
+            .compare_exchange(u8::MAX, distance, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    fn finish(self) -> PruningTable {
+        PruningTable::from(
+            self.entries
+                .into_iter()
+                .map(AtomicU8::into_inner)
+                .collect::<Vec<u8>>(),
+        )
+    }
+}
+
+/// Builds a packed table directly, so the full table is never allocated.
+/// Distances above `PACKED_MAX_DISTANCE` are clamped in the nibbles, while a
+/// side table of two bits per entry keeps them modulo three so the search can
+/// still tell each level's frontier apart. The side table is only allocated
+/// when building that deep.
+pub(crate) struct PackedTableBuilder {
+    nibbles: Vec<AtomicU8>,
+    deep: Vec<AtomicU8>,
+    len: usize,
+}
+
+impl PackedTableBuilder {
+    pub(crate) fn new(len: usize, max_depth: u8) -> Self {
+        let deep_len = if max_depth > PACKED_MAX_DISTANCE {
+            len.div_ceil(4)
+        } else {
+            0
+        };
+        Self {
+            nibbles: (0..len.div_ceil(2))
+                .map(|_| AtomicU8::new(PACKED_OVERFLOW | (PACKED_OVERFLOW << 4)))
+                .collect(),
+            deep: (0..deep_len).map(|_| AtomicU8::new(0)).collect(),
+            len,
+        }
+    }
+
+    /// Peak bytes held while building a table of `len` entries to `max_depth`.
+    pub(crate) fn peak_bytes(len: usize, max_depth: u8) -> usize {
+        let deep = if max_depth > PACKED_MAX_DISTANCE {
+            len.div_ceil(4)
+        } else {
+            0
+        };
+        len.div_ceil(2) + deep
+    }
+
+    #[inline]
+    fn nibble(&self, coord: usize) -> u8 {
+        (self.nibbles[coord / 2].load(Ordering::Relaxed) >> ((coord % 2) * 4)) & 0xF
+    }
+
+    /// Side table state of a clamped entry: 0 while unreached, otherwise one
+    /// more than its distance modulo three.
+    #[inline]
+    fn deep_state(&self, coord: usize) -> u8 {
+        match self.deep.get(coord / 4) {
+            Some(byte) => (byte.load(Ordering::Relaxed) >> ((coord % 4) * 2)) & 0b11,
+            None => 0,
+        }
+    }
+}
+
+impl TableBuilder for PackedTableBuilder {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    fn is_reached(&self, coord: usize) -> bool {
+        self.nibble(coord) != PACKED_OVERFLOW || self.deep_state(coord) != 0
+    }
+
+    #[inline]
+    fn is_at(&self, coord: usize, distance: u8) -> bool {
+        match self.nibble(coord) {
+            PACKED_OVERFLOW => {
+                distance > PACKED_MAX_DISTANCE && self.deep_state(coord) == distance % 3 + 1
+            }
+            nibble => nibble == distance,
+        }
+    }
+
+    #[inline]
+    fn reach(&self, coord: usize, distance: u8) -> bool {
+        if distance > PACKED_MAX_DISTANCE {
+            // The nibble stays clamped, so only an entry no shallower
+            // distance reached may claim the side table
+            if self.nibble(coord) != PACKED_OVERFLOW {
+                return false;
+            }
+            let shift = (coord % 4) * 2;
+            return self.deep[coord / 4]
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |byte| {
+                    ((byte >> shift) & 0b11 == 0).then_some(byte | ((distance % 3 + 1) << shift))
+                })
+                .is_ok();
+        }
+        let shift = (coord % 2) * 4;
+        self.nibbles[coord / 2]
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |byte| {
+                ((byte >> shift) & 0xF == PACKED_OVERFLOW)
+                    .then_some((byte & !(0xF << shift)) | (distance << shift))
+            })
+            .is_ok()
+    }
+
+    fn finish(self) -> PruningTable {
+        let saturated = self.deep.into_iter().any(|byte| byte.into_inner() != 0);
+        PruningTable {
+            storage: Storage::Packed {
+                nibbles: self.nibbles.into_iter().map(AtomicU8::into_inner).collect(),
+                len: self.len,
+                overflow: overflow_reading(saturated),
+            },
+        }
+    }
+}
+
+impl std::fmt::Debug for PruningTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PruningTable")
+            .field("len", &self.len())
+            .field("mapped", &self.is_mapped())
+            .field("packed", &self.is_packed())
+            .finish()
+    }
+}
+
+type TableKey = (PathBuf, String, Metric, u8);
+
+fn registry() -> &'static Mutex<HashMap<TableKey, Weak<PruningTable>>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<TableKey, Weak<PruningTable>>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn table_key(pruner: &dyn Pruner, dir: &Path, metric: Metric, depth: u8) -> TableKey {
+    (
+        dir.to_path_buf(),
+        pruner.table_path().to_string(),
+        metric,
+        depth,
+    )
+}
+
+/// Return the table for this pruner if another solver in the process holds it.
+pub fn shared_table(
+    pruner: &dyn Pruner,
+    dir: &Path,
+    metric: Metric,
+    depth: u8,
+) -> Option<Arc<PruningTable>> {
+    let registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+    registry
+        .get(&table_key(pruner, dir, metric, depth))
+        .and_then(Weak::upgrade)
+        .filter(|table| table.len() == pruner.table_size())
+}
+
+/// Make a table available to other solvers. If an equivalent table was
+/// registered in the meantime, that one is returned and `table` is dropped.
+pub fn register_table(
+    pruner: &dyn Pruner,
+    dir: &Path,
+    metric: Metric,
+    depth: u8,
+    table: PruningTable,
+) -> Arc<PruningTable> {
+    let mut registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+    registry.retain(|_, entry| entry.strong_count() > 0);
+
+    let key = table_key(pruner, dir, metric, depth);
+    if let Some(existing) = registry.get(&key).and_then(Weak::upgrade)
+        && existing.len() == table.len()
+    {
+        return existing;
+    }
+
+    let table = Arc::new(table);
+    registry.insert(key, Arc::downgrade(&table));
+    table
+}
+
+/// Stop sharing a table whose files were replaced, so the next solver loads
+/// the new one. Solvers already holding the old table keep using it.
+pub fn forget_table(dir: &Path, table_path: &str, metric: Metric, depth: u8) {
+    let mut registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+    registry.remove(&(dir.to_path_buf(), table_path.to_string(), metric, depth));
+}
+
+/// Memory held by the tables currently alive in the process, each counted
+/// once however many solvers share it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LiveTableBytes {
+    /// Owned and packed tables on the heap.
+    pub heap: usize,
+    /// Tables mapped from cache files, which the page cache can evict.
+    pub mapped: usize,
+}
+
+pub fn live_table_bytes() -> LiveTableBytes {
+    let registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+    registry.values().filter_map(Weak::upgrade).fold(
+        LiveTableBytes::default(),
+        |mut live, table| {
+            if table.is_mapped() {
+                live.mapped += table.memory_bytes();
+            } else {
+                live.heap += table.memory_bytes();
+            }
+            live
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pruner::CornerOrientationPruner;
+
+    #[test]
+    fn test_registered_table_is_shared_until_dropped() {
+        let dir = Path::new("registry");
+        let pruner = CornerOrientationPruner::new("registry", "registry_test", &[0, 1, 2]);
+        let size = pruner.table_size();
+        assert!(shared_table(&pruner, dir, Metric::Face, 9).is_none());
+
+        let first = register_table(
+            &pruner,
+            dir,
+            Metric::Face,
+            9,
+            PruningTable::from(vec![1; size]),
+        );
+        let second = register_table(
+            &pruner,
+            dir,
+            Metric::Face,
+            9,
+            PruningTable::from(vec![2; size]),
+        );
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(second.get(0), 1);
+
+        let shared = shared_table(&pruner, dir, Metric::Face, 9).unwrap();
+        assert!(Arc::ptr_eq(&first, &shared));
+        assert!(shared_table(&pruner, dir, Metric::Fifth, 9).is_none());
+        assert!(shared_table(&pruner, dir, Metric::Face, 10).is_none());
+        assert!(shared_table(&pruner, Path::new("elsewhere"), Metric::Face, 9).is_none());
+
+        drop((first, second, shared));
+        assert!(shared_table(&pruner, dir, Metric::Face, 9).is_none());
+    }
+
+    #[test]
+    fn test_live_tables_are_counted_once() {
+        let dir = Path::new("registry");
+        let pruner = CornerOrientationPruner::new("live", "registry_live_test", &[0, 1, 2, 3]);
+        let size = pruner.table_size();
+        let first = register_table(
+            &pruner,
+            dir,
+            Metric::Face,
+            9,
+            PruningTable::from(vec![1; size]),
+        );
+        let second = shared_table(&pruner, dir, Metric::Face, 9).unwrap();
+
+        // Other tests register tables concurrently, so only a lower bound holds
+        let live = live_table_bytes();
+        assert!(live.heap >= size);
+        drop((first, second));
+    }
+
+    #[test]
+    fn test_owned_table_reports_contents() {
+        let table = PruningTable::from(vec![4, 5, 6]);
+        assert!(!table.is_mapped());
+        assert_eq!(table.get(2), 6);
+        assert_eq!(table.len(), 3);
+        assert_eq!(table.memory_bytes(), 3);
+    }
+
+    #[test]
+    fn test_packed_table_keeps_distances() {
+        let distances = [0, 1, 7, 13, 14, 2, u8::MAX];
+        let table = PruningTable::packed(&distances);
+        assert!(table.is_packed());
+        assert!(!table.is_saturated());
+        assert_eq!(table.len(), distances.len());
+        assert_eq!(table.memory_bytes(), 4);
+
+        let unpacked: Vec<u8> = (0..table.len()).map(|i| table.get(i)).collect();
+        assert_eq!(unpacked, distances);
+        table.with_entries(|entries| assert_eq!(entries, distances));
+
+        let repacked = PruningTable::from(distances.to_vec()).into_packed();
+        assert!((0..distances.len()).all(|i| repacked.get(i) == table.get(i)));
+    }
+
+    #[test]
+    fn test_packing_clamps_deep_distances() {
+        let distances = vec![0, 14, 15, 18, u8::MAX];
+        let table = PruningTable::from(distances).into_packed();
+        assert!(table.is_packed());
+        assert!(table.is_saturated());
+
+        let unpacked: Vec<u8> = (0..table.len()).map(|i| table.get(i)).collect();
+        assert_eq!(unpacked, [0, 14, 15, 15, 15]);
+    }
+
+    #[test]
+    fn test_builders_match() {
+        let len = 9;
+        let full = FullTableBuilder::new(len);
+        let packed = PackedTableBuilder::new(len, PACKED_MAX_DISTANCE);
+        for builder in [&full as &dyn TableBuilder, &packed] {
+            assert_eq!(builder.len(), len);
+            assert!(builder.reach(0, 0));
+            assert!(builder.reach(3, 5));
+            assert!(builder.reach(8, PACKED_MAX_DISTANCE));
+            assert!(!builder.reach(3, 6));
+            assert!(builder.is_at(3, 5));
+            assert!(!builder.is_at(3, 6));
+            assert!(!builder.is_reached(4));
+        }
+
+        let full = full.finish();
+        let packed = packed.finish();
+        assert!(packed.is_packed());
+        assert!(!packed.is_saturated());
+        assert_eq!(packed.memory_bytes(), len.div_ceil(2));
+        assert!((0..len).all(|i| full.get(i) == packed.get(i)));
+    }
+
+    #[test]
+    fn test_packed_builder_tracks_deep_levels() {
+        let len = 6;
+        assert_eq!(PackedTableBuilder::peak_bytes(len, 14), 3);
+        assert_eq!(PackedTableBuilder::peak_bytes(len, 18), 5);
+
+        let builder = PackedTableBuilder::new(len, 18);
+        assert!(builder.reach(0, 14));
+        assert!(builder.reach(1, 15));
+        assert!(builder.reach(2, 16));
+        assert!(builder.reach(3, 18));
+        assert!(!builder.reach(0, 17));
+        assert!(!builder.reach(1, 17));
+
+        assert!(builder.is_at(0, 14));
+        assert!(!builder.is_at(0, 15));
+        assert!(builder.is_at(1, 15));
+        assert!(!builder.is_at(1, 16));
+        assert!(builder.is_at(2, 16));
+        assert!(!builder.is_at(2, 15));
+        // A level three earlier shares the frontier marker
+        assert!(builder.is_at(1, 18));
+        assert!(builder.is_at(3, 18));
+        assert!(!builder.is_reached(4));
+        assert!(!builder.is_at(4, 15));
+
+        let table = builder.finish();
+        assert!(table.is_saturated());
+        let unpacked: Vec<u8> = (0..len).map(|i| table.get(i)).collect();
+        assert_eq!(unpacked, [14, 15, 15, 15, 15, 15]);
+    }
+}
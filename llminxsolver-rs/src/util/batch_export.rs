@@ -0,0 +1,420 @@
+use crate::batch_solver::{BatchCaseResult, BatchResults};
+use crate::mcc::{MCCParams, calculator::calculate_mcc_with_params};
+use crate::search_mode::Metric;
+use crate::solution::Solution;
+use rust_xlsxwriter::{Format, FormatAlign, Workbook, Worksheet, XlsxError};
+use serde::Serialize;
+
+/// How much of each case an export contains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BatchExportLayout {
+    /// One row per case with its best solution.
+    #[default]
+    Summary,
+    /// The summary, plus every solution of every case. In xlsx each case
+    /// gets its own sheet, in CSV each solution gets its own row.
+    PerCase,
+}
+
+#[derive(Clone, Copy)]
+pub struct BatchExportOptions {
+    /// Metric the move counts are given in.
+    pub metric: Metric,
+    /// Used for solutions that were not scored during the search.
+    pub mcc_params: MCCParams,
+    pub layout: BatchExportLayout,
+}
+
+impl Default for BatchExportOptions {
+    fn default() -> Self {
+        Self {
+            metric: Metric::Fifth,
+            mcc_params: MCCParams::default(),
+            layout: BatchExportLayout::Summary,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BatchExportSolution {
+    pub algorithm: String,
+    pub mcc: f64,
+    pub move_count: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BatchExportCase {
+    pub case_number: usize,
+    pub setup_moves: String,
+    pub best_solution: Option<BatchExportSolution>,
+    pub optimal_length: Option<usize>,
+    pub solution_count: usize,
+    pub solve_time: f64,
+    /// Every solution, only filled for `BatchExportLayout::PerCase`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub solutions: Vec<BatchExportSolution>,
+}
+
+#[derive(Debug, Serialize)]
+struct BatchExportReport<'a> {
+    metric: Metric,
+    total_cases: usize,
+    solved_cases: usize,
+    failed_cases: &'a [usize],
+    total_time: f64,
+    cases: Vec<BatchExportCase>,
+}
+
+impl BatchExportSolution {
+    fn new(solution: &Solution, options: &BatchExportOptions) -> Self {
+        let algorithm = solution.algorithm();
+        let mcc = solution
+            .mcc
+            .unwrap_or_else(|| calculate_mcc_with_params(&algorithm, &options.mcc_params));
+        let move_count = match options.metric {
+            Metric::Fifth => solution.fftm,
+            Metric::Face => solution.ftm,
+        };
+        Self {
+            algorithm,
+            mcc: (mcc * 10.0).round() / 10.0,
+            move_count: move_count as u32,
+        }
+    }
+}
+
+impl BatchExportCase {
+    fn new(result: &BatchCaseResult, options: &BatchExportOptions) -> Self {
+        let solutions = match options.layout {
+            BatchExportLayout::Summary => Vec::new(),
+            BatchExportLayout::PerCase => result
+                .solutions
+                .iter()
+                .map(|s| BatchExportSolution::new(s, options))
+                .collect(),
+        };
+        Self {
+            case_number: result.case_number,
+            setup_moves: result.setup_moves.clone(),
+            best_solution: result
+                .best_solution
+                .as_ref()
+                .or(result.solutions.first())
+                .map(|s| BatchExportSolution::new(s, options)),
+            optimal_length: result.optimal_length,
+            solution_count: result.solutions.len(),
+            solve_time: result.solve_time,
+            solutions,
+        }
+    }
+}
+
+/// Flatten batch results into export rows, ordered by case number.
+pub fn batch_export_cases(
+    results: &BatchResults,
+    options: &BatchExportOptions,
+) -> Vec<BatchExportCase> {
+    let mut cases: Vec<BatchExportCase> = results
+        .case_results
+        .iter()
+        .map(|r| BatchExportCase::new(r, options))
+        .collect();
+    cases.sort_by_key(|c| c.case_number);
+    cases
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// Render batch results as CSV with a header row.
+pub fn batch_results_csv(results: &BatchResults, options: &BatchExportOptions) -> String {
+    let mut csv = String::new();
+    match options.layout {
+        BatchExportLayout::Summary => {
+            csv.push_str(
+                "case,setup_moves,best_solution,mcc,move_count,optimal_length,solutions,solve_time\n",
+            );
+            for case in batch_export_cases(results, options) {
+                let best = case.best_solution.as_ref();
+                csv.push_str(&format!(
+                    "{},{},{},{},{},{},{},{:.3}\n",
+                    case.case_number,
+                    csv_field(&case.setup_moves),
+                    csv_field(&optional(best.map(|s| &s.algorithm))),
+                    optional(best.map(|s| s.mcc)),
+                    optional(best.map(|s| s.move_count)),
+                    optional(case.optimal_length),
+                    case.solution_count,
+                    case.solve_time
+                ));
+            }
+        }
+        BatchExportLayout::PerCase => {
+            csv.push_str("case,setup_moves,rank,algorithm,mcc,move_count\n");
+            for case in batch_export_cases(results, options) {
+                for (rank, solution) in case.solutions.iter().enumerate() {
+                    csv.push_str(&format!(
+                        "{},{},{},{},{},{}\n",
+                        case.case_number,
+                        csv_field(&case.setup_moves),
+                        rank + 1,
+                        csv_field(&solution.algorithm),
+                        solution.mcc,
+                        solution.move_count
+                    ));
+                }
+            }
+        }
+    }
+    csv
+}
+
+/// Render batch results as pretty-printed JSON.
+pub fn batch_results_json(
+    results: &BatchResults,
+    options: &BatchExportOptions,
+) -> Result<String, String> {
+    let report = BatchExportReport {
+        metric: options.metric,
+        total_cases: results.total_cases,
+        solved_cases: results.solved_cases,
+        failed_cases: &results.failed_cases,
+        total_time: results.total_time,
+        cases: batch_export_cases(results, options),
+    };
+    serde_json::to_string_pretty(&report).map_err(|e| e.to_string())
+}
+
+pub fn export_batch_csv(
+    output_path: &str,
+    results: &BatchResults,
+    options: &BatchExportOptions,
+) -> Result<(), String> {
+    std::fs::write(output_path, batch_results_csv(results, options))
+        .map_err(|e| format!("Failed to write '{}': {}", output_path, e))
+}
+
+pub fn export_batch_json(
+    output_path: &str,
+    results: &BatchResults,
+    options: &BatchExportOptions,
+) -> Result<(), String> {
+    std::fs::write(output_path, batch_results_json(results, options)? + "\n")
+        .map_err(|e| format!("Failed to write '{}': {}", output_path, e))
+}
+
+fn write_header(worksheet: &mut Worksheet, headers: &[&str]) -> Result<(), XlsxError> {
+    let header_format = Format::new()
+        .set_bold()
+        .set_align(FormatAlign::Center)
+        .set_align(FormatAlign::VerticalCenter);
+    for (idx, header) in headers.iter().enumerate() {
+        worksheet.write_string_with_format(0, idx as u16, *header, &header_format)?;
+    }
+    Ok(())
+}
+
+fn write_summary_sheet(
+    worksheet: &mut Worksheet,
+    cases: &[BatchExportCase],
+) -> Result<(), XlsxError> {
+    worksheet.set_name("Summary")?;
+    write_header(
+        worksheet,
+        &[
+            "Case",
+            "Setup",
+            "Best Solution",
+            "MCC",
+            "Movecount",
+            "Optimal",
+            "Solutions",
+            "Time (s)",
+        ],
+    )?;
+
+    let cell_format = Format::new()
+        .set_align(FormatAlign::Center)
+        .set_align(FormatAlign::VerticalCenter);
+    let max_setup_len = cases.iter().map(|c| c.setup_moves.len()).max().unwrap_or(0);
+    let max_algo_len = cases
+        .iter()
+        .filter_map(|c| c.best_solution.as_ref().map(|s| s.algorithm.len()))
+        .max()
+        .unwrap_or(0);
+    worksheet.set_column_width(1, (max_setup_len as f64 + 2.0).max(15.0))?;
+    worksheet.set_column_width(2, (max_algo_len as f64 + 2.0).max(15.0))?;
+
+    for (row_idx, case) in cases.iter().enumerate() {
+        let row = (row_idx + 1) as u32;
+        worksheet.write_number_with_format(row, 0, case.case_number as f64, &cell_format)?;
+        worksheet.write_string_with_format(row, 1, &case.setup_moves, &cell_format)?;
+        if let Some(ref best) = case.best_solution {
+            worksheet.write_string_with_format(row, 2, &best.algorithm, &cell_format)?;
+            worksheet.write_number_with_format(row, 3, best.mcc, &cell_format)?;
+            worksheet.write_number_with_format(row, 4, best.move_count as f64, &cell_format)?;
+        }
+        if let Some(optimal) = case.optimal_length {
+            worksheet.write_number_with_format(row, 5, optimal as f64, &cell_format)?;
+        }
+        worksheet.write_number_with_format(row, 6, case.solution_count as f64, &cell_format)?;
+        let time = (case.solve_time * 100.0).round() / 100.0;
+        worksheet.write_number_with_format(row, 7, time, &cell_format)?;
+    }
+    Ok(())
+}
+
+fn write_case_sheet(worksheet: &mut Worksheet, case: &BatchExportCase) -> Result<(), XlsxError> {
+    worksheet.set_name(format!("Case {}", case.case_number))?;
+    write_header(worksheet, &["MCC", "Movecount", "Algorithm"])?;
+
+    let cell_format = Format::new()
+        .set_align(FormatAlign::Center)
+        .set_align(FormatAlign::VerticalCenter);
+    let max_algo_len = case
+        .solutions
+        .iter()
+        .map(|s| s.algorithm.len())
+        .max()
+        .unwrap_or(30);
+    worksheet.set_column_width(0, 10.0)?;
+    worksheet.set_column_width(1, 10.0)?;
+    worksheet.set_column_width(2, (max_algo_len as f64 + 2.0).max(15.0))?;
+
+    for (row_idx, solution) in case.solutions.iter().enumerate() {
+        let row = (row_idx + 1) as u32;
+        worksheet.write_number_with_format(row, 0, solution.mcc, &cell_format)?;
+        worksheet.write_number_with_format(row, 1, solution.move_count as f64, &cell_format)?;
+        worksheet.write_string_with_format(row, 2, &solution.algorithm, &cell_format)?;
+    }
+    Ok(())
+}
+
+/// Write a workbook with a summary sheet and, for `BatchExportLayout::PerCase`,
+/// one sheet per case listing all of its solutions.
+pub fn export_batch_xlsx(
+    output_path: &str,
+    results: &BatchResults,
+    options: &BatchExportOptions,
+) -> Result<(), String> {
+    let cases = batch_export_cases(results, options);
+    let mut workbook = Workbook::new();
+
+    write_summary_sheet(workbook.add_worksheet(), &cases).map_err(|e| e.to_string())?;
+    if options.layout == BatchExportLayout::PerCase {
+        for case in &cases {
+            write_case_sheet(workbook.add_worksheet(), case).map_err(|e| e.to_string())?;
+        }
+    }
+
+    workbook.save(output_path).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search_mode::SearchMode;
+
+    fn results() -> BatchResults {
+        let mut results = BatchResults::new(2);
+        let mut solved = BatchCaseResult::new(2, "R U, R'".to_string());
+        solved.solutions = vec![
+            Solution::from_alg("R U R'", SearchMode::RU),
+            Solution::from_alg("R2 U", SearchMode::RU),
+        ];
+        solved.best_solution = solved.solutions.first().cloned();
+        solved.optimal_length = Some(3);
+        solved.solve_time = 1.25;
+        results.add_result(solved);
+        results.add_result(BatchCaseResult::new(1, "U".to_string()));
+        results
+    }
+
+    #[test]
+    fn test_export_cases_are_sorted_and_scored() {
+        let options = BatchExportOptions {
+            metric: Metric::Fifth,
+            ..Default::default()
+        };
+        let cases = batch_export_cases(&results(), &options);
+        assert_eq!(cases[0].case_number, 1);
+        assert!(cases[0].best_solution.is_none());
+
+        let best = cases[1].best_solution.as_ref().unwrap();
+        assert_eq!(best.algorithm, "R U R'");
+        assert_eq!(best.move_count, 3);
+        assert!(best.mcc > 0.0);
+        assert_eq!(cases[1].solution_count, 2);
+        assert!(cases[1].solutions.is_empty());
+    }
+
+    #[test]
+    fn test_batch_results_csv_layouts() {
+        let summary = batch_results_csv(&results(), &BatchExportOptions::default());
+        let lines: Vec<&str> = summary.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("case,setup_moves,best_solution"));
+        assert_eq!(lines[1], "1,U,,,,,0,0.000");
+        assert!(lines[2].starts_with("2,\"R U, R'\",R U R',"));
+
+        let per_case = batch_results_csv(
+            &results(),
+            &BatchExportOptions {
+                layout: BatchExportLayout::PerCase,
+                ..Default::default()
+            },
+        );
+        let lines: Vec<&str> = per_case.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[2].starts_with("2,\"R U, R'\",2,R2 U,"));
+    }
+
+    #[test]
+    fn test_batch_results_json_per_case() {
+        let options = BatchExportOptions {
+            layout: BatchExportLayout::PerCase,
+            metric: Metric::Face,
+            ..Default::default()
+        };
+        let json = batch_results_json(&results(), &options).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["metric"], "Face");
+        assert_eq!(value["solved_cases"], 1);
+        assert_eq!(value["cases"][1]["solutions"][1]["move_count"], 2);
+        assert!(value["cases"][0].get("solutions").is_none());
+    }
+
+    #[test]
+    fn test_export_batch_files() {
+        let dir = std::env::temp_dir();
+        let options = BatchExportOptions {
+            layout: BatchExportLayout::PerCase,
+            ..Default::default()
+        };
+        for extension in ["xlsx", "csv", "json"] {
+            let path = dir.join(format!(
+                "llminx_batch_export_{}.{}",
+                std::process::id(),
+                extension
+            ));
+            let path = path.to_str().unwrap();
+            let result = match extension {
+                "xlsx" => export_batch_xlsx(path, &results(), &options),
+                "csv" => export_batch_csv(path, &results(), &options),
+                _ => export_batch_json(path, &results(), &options),
+            };
+            assert!(result.is_ok());
+            assert!(std::path::Path::new(path).exists());
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
@@ -1,3 +1,5 @@
+pub mod batch_export;
+pub mod solution_store;
 pub mod tempfile;
 pub mod theme_gen;
 pub mod wallpaper;
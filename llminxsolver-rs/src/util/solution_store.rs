@@ -0,0 +1,473 @@
+//! Binary solution store with an in-memory offset index
+//!
+//! After an 8 byte magic header, every record is a little endian `u32` byte
+//! length followed by that many bytes of UTF-8 text. The offset of each
+//! record is kept in memory, so a page deep into the file costs one seek
+//! instead of a scan, and sorted or filtered views are just lists of record
+//! ids into the same file.
+//!
+//! Records hold either a plain solution line such as `"R U R' U'  (4,4)"` or
+//! a JSON encoded `Solution`; `plain_text` turns both into the line format
+//! for export.
+
+use crate::mcc::{calculate_mcc, get_move_count};
+use crate::minx::{Move, parse_alg};
+use crate::solution::Solution;
+use std::cell::RefCell;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+const MAGIC: &[u8; 8] = b"LLMXSOL1";
+const LENGTH_PREFIX: u64 = 4;
+
+/// Order of the records in a view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StoreOrder {
+    /// The order the records were appended in.
+    #[default]
+    Inserted,
+    /// Shortest first in FTM, ties in insertion order.
+    Length,
+    /// Lowest MCC first, ties in insertion order.
+    Mcc,
+}
+
+/// Which records a view holds and in what order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StoreQuery {
+    pub order: StoreOrder,
+    /// Keep records whose notation contains this text.
+    pub contains: Option<String>,
+    /// Keep records that perform these moves in a row.
+    pub moves: Vec<Move>,
+}
+
+impl StoreQuery {
+    fn filters(&self) -> bool {
+        self.contains.is_some() || !self.moves.is_empty()
+    }
+
+    fn accepts(&self, record: &str) -> bool {
+        let algorithm = record_algorithm(record);
+        if let Some(ref text) = self.contains
+            && !algorithm.contains(text.as_str())
+        {
+            return false;
+        }
+        if self.moves.is_empty() {
+            return true;
+        }
+        parse_alg(&algorithm).is_ok_and(|moves| {
+            moves
+                .windows(self.moves.len())
+                .any(|window| window == self.moves.as_slice())
+        })
+    }
+}
+
+struct RecordMeta {
+    offset: u64,
+    /// FTM length of the solution.
+    length: u32,
+    /// Filled in from the record or on the first MCC sorted view.
+    mcc: Option<f64>,
+}
+
+pub struct SolutionStore {
+    path: PathBuf,
+    writer: RefCell<Option<BufWriter<File>>>,
+    records: Vec<RecordMeta>,
+    end: u64,
+}
+
+impl SolutionStore {
+    /// Create an empty store at `path`, replacing any existing file.
+    pub fn create(path: &Path) -> Result<Self, String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(path)
+            .map_err(|e| format!("Failed to create solution store: {}", e))?;
+        let mut writer = BufWriter::new(file);
+        writer
+            .write_all(MAGIC)
+            .map_err(|e| format!("Failed to write solution store header: {}", e))?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer: RefCell::new(Some(writer)),
+            records: Vec::new(),
+            end: MAGIC.len() as u64,
+        })
+    }
+
+    /// Open an existing store read-only, rebuilding its index.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("Failed to open solution store: {}", e))?;
+        let mut reader = BufReader::new(file);
+        let mut magic = [0u8; MAGIC.len()];
+        if reader.read_exact(&mut magic).is_err() || &magic != MAGIC {
+            return Err(format!("{} is not a solution store", path.display()));
+        }
+
+        let mut store = Self {
+            path: path.to_path_buf(),
+            writer: RefCell::new(None),
+            records: Vec::new(),
+            end: MAGIC.len() as u64,
+        };
+        while let Some(record) = read_record(&mut reader)? {
+            let (length, mcc) = record_meta(&record);
+            store.push_meta(record.len() as u32, length, mcc);
+        }
+        Ok(store)
+    }
+
+    /// Whether `path` starts with the store header.
+    pub fn is_store(path: &Path) -> bool {
+        let mut magic = [0u8; MAGIC.len()];
+        File::open(path)
+            .and_then(|mut file| file.read_exact(&mut magic))
+            .is_ok_and(|_| &magic == MAGIC)
+    }
+
+    /// Append a plain solution line, returning its record id.
+    pub fn append(&mut self, record: &str) -> Result<u32, String> {
+        let (length, mcc) = record_meta(record);
+        self.write_record(record, length, mcc)
+    }
+
+    /// Append a solution as JSON, returning its record id.
+    pub fn append_solution(&mut self, solution: &Solution) -> Result<u32, String> {
+        let record = serde_json::to_string(solution)
+            .map_err(|e| format!("Failed to encode solution: {}", e))?;
+        self.write_record(&record, solution.ftm as u32, solution.mcc)
+    }
+
+    fn write_record(&mut self, record: &str, length: u32, mcc: Option<f64>) -> Result<u32, String> {
+        let mut writer_ref = self.writer.borrow_mut();
+        let Some(ref mut writer) = *writer_ref else {
+            return Err("Writer is closed".to_string());
+        };
+        let bytes = record.as_bytes();
+        writer
+            .write_all(&(bytes.len() as u32).to_le_bytes())
+            .and_then(|_| writer.write_all(bytes))
+            .map_err(|e| format!("Failed to write solution: {}", e))?;
+        drop(writer_ref);
+
+        Ok(self.push_meta(bytes.len() as u32, length, mcc))
+    }
+
+    fn push_meta(&mut self, len: u32, length: u32, mcc: Option<f64>) -> u32 {
+        self.records.push(RecordMeta {
+            offset: self.end,
+            length,
+            mcc,
+        });
+        self.end += LENGTH_PREFIX + len as u64;
+        (self.records.len() - 1) as u32
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Ids of every record, in insertion order.
+    pub fn all_ids(&self) -> Vec<u32> {
+        (0..self.records.len() as u32).collect()
+    }
+
+    pub fn flush(&self) {
+        if let Some(ref mut writer) = *self.writer.borrow_mut() {
+            let _ = writer.flush();
+        }
+    }
+
+    /// Flush and stop accepting records. Reading still works.
+    pub fn close(&mut self) {
+        if let Some(mut writer) = self.writer.borrow_mut().take() {
+            let _ = writer.flush();
+        }
+    }
+
+    fn reader(&self) -> Result<BufReader<File>, String> {
+        self.flush();
+        File::open(&self.path)
+            .map(BufReader::new)
+            .map_err(|e| format!("Failed to open solution store: {}", e))
+    }
+
+    /// Up to `limit` records starting at record `start`, in insertion order.
+    pub fn read_range(&self, start: usize, limit: usize) -> Result<Vec<String>, String> {
+        let end = start.saturating_add(limit).min(self.records.len());
+        if start >= end {
+            return Ok(Vec::new());
+        }
+
+        let mut reader = self.reader()?;
+        reader
+            .seek(SeekFrom::Start(self.records[start].offset))
+            .map_err(|e| format!("Failed to seek solution store: {}", e))?;
+        (start..end)
+            .map(|_| read_record(&mut reader)?.ok_or_else(truncated))
+            .collect()
+    }
+
+    /// The records with the given ids, in the order given.
+    pub fn read_ids(&self, ids: &[u32]) -> Result<Vec<String>, String> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut reader = self.reader()?;
+        ids.iter()
+            .map(|&id| {
+                let meta = self
+                    .records
+                    .get(id as usize)
+                    .ok_or_else(|| format!("No solution record {}", id))?;
+                reader
+                    .seek(SeekFrom::Start(meta.offset))
+                    .map_err(|e| format!("Failed to seek solution store: {}", e))?;
+                read_record(&mut reader)?.ok_or_else(truncated)
+            })
+            .collect()
+    }
+
+    /// The records of `ids` that `query` keeps, in its order.
+    pub fn view(&mut self, ids: &[u32], query: &StoreQuery) -> Result<Vec<u32>, String> {
+        let mut view = ids.to_vec();
+        if query.filters() {
+            let records = self.read_ids(&view)?;
+            view = view
+                .into_iter()
+                .zip(&records)
+                .filter(|(_, record)| query.accepts(record))
+                .map(|(id, _)| id)
+                .collect();
+        }
+
+        match query.order {
+            StoreOrder::Inserted => {}
+            StoreOrder::Length => view.sort_by_key(|&id| self.records[id as usize].length),
+            StoreOrder::Mcc => {
+                self.score_missing_mcc(&view)?;
+                view.sort_by(|&a, &b| {
+                    let mcc = |id: u32| self.records[id as usize].mcc.unwrap_or(f64::INFINITY);
+                    mcc(a).total_cmp(&mcc(b))
+                });
+            }
+        }
+        Ok(view)
+    }
+
+    fn score_missing_mcc(&mut self, ids: &[u32]) -> Result<(), String> {
+        let missing: Vec<u32> = ids
+            .iter()
+            .copied()
+            .filter(|&id| self.records[id as usize].mcc.is_none())
+            .collect();
+        let records = self.read_ids(&missing)?;
+        for (id, record) in missing.into_iter().zip(records) {
+            self.records[id as usize].mcc = Some(calculate_mcc(&record_algorithm(&record)));
+        }
+        Ok(())
+    }
+
+    /// Write the records of `ids` in the plain one-line-per-solution format.
+    pub fn write_text<W: Write>(&self, ids: &[u32], out: &mut W) -> Result<(), String> {
+        const CHUNK: usize = 4096;
+        for chunk in ids.chunks(CHUNK) {
+            for record in self.read_ids(chunk)? {
+                writeln!(out, "{}", plain_text(&record))
+                    .map_err(|e| format!("Failed to write solutions: {}", e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_record<R: Read>(reader: &mut R) -> Result<Option<String>, String> {
+    let mut len = [0u8; LENGTH_PREFIX as usize];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(format!("Failed to read solution store: {}", e)),
+    }
+    let mut bytes = vec![0u8; u32::from_le_bytes(len) as usize];
+    reader.read_exact(&mut bytes).map_err(|_| truncated())?;
+    String::from_utf8(bytes)
+        .map(Some)
+        .map_err(|e| format!("Invalid solution record: {}", e))
+}
+
+fn truncated() -> String {
+    "Solution store is truncated".to_string()
+}
+
+fn decode_record(record: &str) -> Option<Solution> {
+    record
+        .starts_with('{')
+        .then(|| serde_json::from_str(record).ok())
+        .flatten()
+}
+
+fn record_meta(record: &str) -> (u32, Option<f64>) {
+    match decode_record(record) {
+        Some(solution) => (solution.ftm as u32, solution.mcc),
+        None => (get_move_count(&record_algorithm(record), "FTM"), None),
+    }
+}
+
+/// The notation of a record, without the `(ftm,fftm)` suffix of plain lines.
+pub fn record_algorithm(record: &str) -> String {
+    if let Some(solution) = decode_record(record) {
+        return solution.algorithm();
+    }
+    let line = record.trim();
+    let suffix = line
+        .rfind(" (")
+        .filter(|_| line.ends_with(')'))
+        .filter(|&i| {
+            line[i + 2..line.len() - 1]
+                .chars()
+                .all(|c| c.is_ascii_digit() || c == ',')
+        });
+    match suffix {
+        Some(i) => line[..i].trim_end().to_string(),
+        None => line.to_string(),
+    }
+}
+
+/// A record in the plain solution line format.
+pub fn plain_text(record: &str) -> String {
+    match decode_record(record) {
+        Some(solution) => solution.to_string(),
+        None => record.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search_mode::SearchMode;
+
+    fn store_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "llminx_store_test_{}_{}.bin",
+            name,
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn test_pages_read_from_record_offsets() {
+        let path = store_path("pages");
+        let mut store = SolutionStore::create(&path).unwrap();
+        for i in 0..1000 {
+            store.append(&format!("line {}", i)).unwrap();
+        }
+
+        assert_eq!(store.len(), 1000);
+        assert_eq!(
+            store.read_range(998, 10).unwrap(),
+            vec!["line 998", "line 999"]
+        );
+        assert_eq!(store.read_range(0, 2).unwrap(), vec!["line 0", "line 1"]);
+        assert!(store.read_range(1000, 10).unwrap().is_empty());
+        assert_eq!(store.read_ids(&[7, 3]).unwrap(), vec!["line 7", "line 3"]);
+        assert!(store.read_ids(&[1000]).is_err());
+
+        store.close();
+        assert!(store.append("late").is_err());
+        let reopened = SolutionStore::open(&path).unwrap();
+        assert_eq!(reopened.len(), 1000);
+        assert_eq!(reopened.read_range(500, 1).unwrap(), vec!["line 500"]);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_sorted_and_filtered_views() {
+        let path = store_path("views");
+        let mut store = SolutionStore::create(&path).unwrap();
+        store.append("R U R' U R U2' R'  (7,8)").unwrap();
+        store.append("R U R'  (3,3)").unwrap();
+        store
+            .append_solution(&Solution::from_alg("U R U' R'", SearchMode::RU))
+            .unwrap();
+
+        let ids = store.all_ids();
+        let by_length = StoreQuery {
+            order: StoreOrder::Length,
+            ..StoreQuery::default()
+        };
+        assert_eq!(store.view(&ids, &by_length).unwrap(), vec![1, 2, 0]);
+
+        let by_mcc = StoreQuery {
+            order: StoreOrder::Mcc,
+            ..StoreQuery::default()
+        };
+        let view = store.view(&ids, &by_mcc).unwrap();
+        assert_eq!(view.len(), 3);
+        assert!(store.records.iter().all(|r| r.mcc.is_some()));
+
+        let contains = StoreQuery {
+            contains: Some("U2'".to_string()),
+            ..StoreQuery::default()
+        };
+        assert_eq!(store.view(&ids, &contains).unwrap(), vec![0]);
+
+        let moves = StoreQuery {
+            moves: parse_alg("U R").unwrap(),
+            ..StoreQuery::default()
+        };
+        assert_eq!(store.view(&ids, &moves).unwrap(), vec![0, 2]);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_write_text_emits_plain_lines() {
+        let path = store_path("text");
+        let mut store = SolutionStore::create(&path).unwrap();
+        store.append("R U R'  (3,3)").unwrap();
+        store
+            .append_solution(&Solution::from_alg("U R", SearchMode::RU))
+            .unwrap();
+
+        let mut out = Vec::new();
+        store.write_text(&store.all_ids(), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "R U R'  (3,3)\nU R  (2,2)\n"
+        );
+        assert!(SolutionStore::is_store(&path));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_record_algorithm() {
+        assert_eq!(record_algorithm("R U R'  (3,3)"), "R U R'");
+        assert_eq!(record_algorithm("R U (R U)"), "R U (R U)");
+        assert_eq!(record_algorithm("R U"), "R U");
+    }
+
+    #[test]
+    fn test_open_rejects_other_files() {
+        let path = store_path("plain");
+        std::fs::write(&path, "R U R'\n").unwrap();
+        assert!(!SolutionStore::is_store(&path));
+        assert!(SolutionStore::open(&path).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -1,9 +1,12 @@
-use std::cell::RefCell;
-use std::fs::{self, File, OpenOptions};
-use std::io::{BufRead, BufReader, BufWriter, Write};
+use super::solution_store::{SolutionStore, StoreOrder, StoreQuery};
+use crate::solution::Solution;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
 use std::process;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
 
 static TEMPFILE_COUNTER: AtomicU64 = AtomicU64::new(0);
 
@@ -13,10 +16,103 @@ fn unique_temp_suffix() -> String {
     format!("{}_{}", pid, count)
 }
 
-pub struct TempFile {
+/// Exclusive lock on `<name>.lock` next to a temp file or directory, held
+/// for as long as its owner has it open. The OS releases the lock however the
+/// process exits, so cleanup can tell live temp files from orphaned ones.
+struct OwnerLock {
     path: PathBuf,
-    writer: RefCell<Option<BufWriter<File>>>,
-    count: usize,
+    file: Option<File>,
+}
+
+impl OwnerLock {
+    /// Lock the lock file of `data`. Call before creating `data`, so cleanup
+    /// never sees the data without its lock.
+    fn acquire(data: &Path) -> Result<Self, String> {
+        let path = lock_path(data);
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to create lock file: {}", e))?;
+        file.lock()
+            .map_err(|e| format!("Failed to lock temp file: {}", e))?;
+        // For anyone looking at the temp dir; cleanup only checks the lock
+        let _ = writeln!(file, "{}", process::id());
+
+        Ok(Self {
+            path,
+            file: Some(file),
+        })
+    }
+
+    fn release(&mut self) {
+        if let Some(file) = self.file.take() {
+            drop(file);
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+impl Drop for OwnerLock {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+fn lock_path(data: &Path) -> PathBuf {
+    data.with_extension("lock")
+}
+
+/// The last sorted or filtered view, rebuilt when the query or the number of
+/// stored solutions changes.
+struct CachedView {
+    case_number: Option<usize>,
+    query: StoreQuery,
+    len: usize,
+    ids: Vec<u32>,
+}
+
+fn cached_view<'a>(
+    cache: &'a mut Option<CachedView>,
+    store: &mut SolutionStore,
+    case_number: Option<usize>,
+    ids: &[u32],
+    query: &StoreQuery,
+) -> Result<&'a [u32], String> {
+    let stale = cache.as_ref().is_none_or(|view| {
+        view.case_number != case_number || view.query != *query || view.len != ids.len()
+    });
+    if stale {
+        *cache = Some(CachedView {
+            case_number,
+            query: query.clone(),
+            len: ids.len(),
+            ids: store.view(ids, query)?,
+        });
+    }
+    Ok(&cache.as_ref().expect("view was just cached").ids)
+}
+
+fn is_unfiltered(query: &StoreQuery) -> bool {
+    query.order == StoreOrder::Inserted && query.contains.is_none() && query.moves.is_empty()
+}
+
+fn page(ids: &[u32], offset: usize, limit: usize) -> &[u32] {
+    let start = offset.min(ids.len());
+    &ids[start..offset.saturating_add(limit).min(ids.len())]
+}
+
+fn create_text_file(path: &Path) -> Result<BufWriter<File>, String> {
+    File::create(path)
+        .map(BufWriter::new)
+        .map_err(|e| format!("Failed to create export file: {}", e))
+}
+
+pub struct TempFile {
+    store: SolutionStore,
+    view: Option<CachedView>,
+    lock: OwnerLock,
 }
 
 impl TempFile {
@@ -25,185 +121,332 @@ impl TempFile {
     pub fn new() -> Result<Self, String> {
         let temp_dir = std::env::temp_dir();
         let path = temp_dir.join(format!(
-            "{}_{}.txt",
+            "{}_{}.bin",
             Self::FILE_PREFIX,
             unique_temp_suffix()
         ));
 
-        let file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&path)
+        let lock = OwnerLock::acquire(&path)?;
+        let store = SolutionStore::create(&path)
             .map_err(|e| format!("Failed to create temp file: {}", e))?;
 
         Ok(Self {
-            path,
-            writer: RefCell::new(Some(BufWriter::new(file))),
-            count: 0,
+            store,
+            view: None,
+            lock,
         })
     }
 
     pub fn append(&mut self, solution: &str) -> Result<(), String> {
-        let mut writer_ref = self.writer.borrow_mut();
-        if let Some(ref mut writer) = *writer_ref {
-            writeln!(writer, "{}", solution)
-                .map_err(|e| format!("Failed to write solution: {}", e))?;
-            self.count += 1;
-
-            if self.count.is_multiple_of(100) {
-                let _ = writer.flush();
-            }
-            Ok(())
-        } else {
-            Err("Writer is closed".to_string())
-        }
+        self.store.append(solution).map(|_| ())
+    }
+
+    /// Append a solution as JSON, readable with `read_solution_page`.
+    pub fn append_solution(&mut self, solution: &Solution) -> Result<(), String> {
+        self.store.append_solution(solution).map(|_| ())
     }
 
     pub fn get_path(&self) -> &Path {
-        &self.path
+        self.store.path()
     }
 
     pub fn count(&self) -> usize {
-        self.count
+        self.store.len()
     }
 
     pub fn close(&mut self) {
-        let mut writer_ref = self.writer.borrow_mut();
-        if let Some(mut writer) = writer_ref.take() {
-            let _ = writer.flush();
-        }
+        self.store.close();
     }
 
     pub fn flush_file(&self) {
-        let mut writer_ref = self.writer.borrow_mut();
-        if let Some(ref mut writer) = *writer_ref {
-            let _ = writer.flush();
-        }
+        self.store.flush();
     }
 
     pub fn delete_file(&mut self) {
         self.close();
-        let _ = fs::remove_file(&self.path);
+        let _ = fs::remove_file(self.store.path());
+        self.lock.release();
     }
 
     pub fn read_page(&self, offset: usize, limit: usize) -> Result<Vec<String>, String> {
-        self.flush_file();
-
-        let file =
-            File::open(&self.path).map_err(|e| format!("Failed to open solutions file: {}", e))?;
-        let reader = BufReader::new(file);
-        let solutions: Vec<String> = reader
-            .lines()
-            .skip(offset)
-            .take(limit)
-            .filter_map(Result::ok)
-            .collect();
-        Ok(solutions)
+        self.store.read_range(offset, limit)
+    }
+
+    pub fn read_solution_page(&self, offset: usize, limit: usize) -> Result<Vec<Solution>, String> {
+        self.read_page(offset, limit)?
+            .iter()
+            .map(|line| decode_solution(line))
+            .collect()
+    }
+
+    /// A page of the solutions `query` keeps, in its order.
+    pub fn read_view_page(
+        &mut self,
+        query: &StoreQuery,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<String>, String> {
+        if is_unfiltered(query) {
+            return self.read_page(offset, limit);
+        }
+        let ids = self.store.all_ids();
+        let view = cached_view(&mut self.view, &mut self.store, None, &ids, query)?;
+        self.store.read_ids(page(view, offset, limit))
+    }
+
+    /// Number of solutions `query` keeps.
+    pub fn view_count(&mut self, query: &StoreQuery) -> Result<usize, String> {
+        if query.contains.is_none() && query.moves.is_empty() {
+            return Ok(self.count());
+        }
+        let ids = self.store.all_ids();
+        cached_view(&mut self.view, &mut self.store, None, &ids, query).map(<[u32]>::len)
+    }
+
+    /// Write every solution to `path` in the plain line format.
+    pub fn export_text(&self, path: &Path) -> Result<(), String> {
+        let mut out = create_text_file(path)?;
+        self.store.write_text(&self.store.all_ids(), &mut out)?;
+        out.flush()
+            .map_err(|e| format!("Failed to write export file: {}", e))
     }
 }
 
+fn decode_solution(line: &str) -> Result<Solution, String> {
+    serde_json::from_str(line).map_err(|e| format!("Failed to decode solution: {}", e))
+}
+
 impl Drop for TempFile {
     fn drop(&mut self) {
         self.delete_file();
     }
 }
 
-pub fn cleanup_stale_temp_files() {
-    let temp_dir = std::env::temp_dir();
-    if let Ok(entries) = fs::read_dir(temp_dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.is_file()
-                && path
-                    .file_name()
-                    .and_then(|name| name.to_str())
-                    .is_some_and(|name| {
-                        name.starts_with(TempFile::FILE_PREFIX) && name.ends_with(".txt")
-                    })
-            {
-                let _ = fs::remove_file(path);
-            }
+/// Which temp files `cleanup_stale_temp_files_with_options` may remove.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CleanupOptions {
+    /// Also remove temp files of running processes once they have not been
+    /// written to for this long. `None` never removes live temp files.
+    pub max_age: Option<Duration>,
+}
+
+/// What a stale temp file cleanup did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CleanupReport {
+    pub removed: Vec<PathBuf>,
+    /// Temp files kept because their owning process is still running.
+    pub in_use: Vec<PathBuf>,
+}
+
+/// Whether the process that created `data` still holds its lock. Files
+/// without a lock file predate owner locks and count as orphaned.
+fn owner_alive(data: &Path) -> bool {
+    let Ok(file) = File::open(lock_path(data)) else {
+        return false;
+    };
+    match file.try_lock() {
+        Ok(()) => false,
+        Err(TryLockError::WouldBlock) => true,
+        // Can't tell, so leave it alone
+        Err(TryLockError::Error(_)) => true,
+    }
+}
+
+/// Last write to `path`, or to the newest entry in it for a directory.
+fn last_modified(path: &Path) -> Option<SystemTime> {
+    let own = fs::metadata(path).and_then(|m| m.modified()).ok();
+    if !path.is_dir() {
+        return own;
+    }
+    fs::read_dir(path)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| entry.metadata().and_then(|m| m.modified()).ok())
+        .chain(own)
+        .max()
+}
+
+fn cleanup_stale(options: &CleanupOptions, is_temp: impl Fn(&Path, &str) -> bool) -> CleanupReport {
+    let mut report = CleanupReport::default();
+    let Ok(entries) = fs::read_dir(std::env::temp_dir()) else {
+        return report;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_match = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| is_temp(&path, name));
+        if !is_match {
+            continue;
+        }
+
+        let expired = options.max_age.is_some_and(|max_age| {
+            last_modified(&path)
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|age| age >= max_age)
+        });
+        if !expired && owner_alive(&path) {
+            report.in_use.push(path);
+            continue;
+        }
+
+        let removed = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        if removed.is_ok() {
+            let _ = fs::remove_file(lock_path(&path));
+            report.removed.push(path);
         }
     }
+    report
+}
+
+/// Remove solution temp files left behind by processes that have exited.
+pub fn cleanup_stale_temp_files() -> CleanupReport {
+    cleanup_stale_temp_files_with_options(&CleanupOptions::default())
+}
+
+pub fn cleanup_stale_temp_files_with_options(options: &CleanupOptions) -> CleanupReport {
+    cleanup_stale(options, |path, name| {
+        path.is_file()
+            && name.starts_with(TempFile::FILE_PREFIX)
+            && (name.ends_with(".bin") || name.ends_with(".txt"))
+    })
 }
 
 pub struct BatchTempFile {
     dir: PathBuf,
-    counts: std::collections::HashMap<usize, usize>,
+    store: SolutionStore,
+    case_ids: HashMap<usize, Vec<u32>>,
+    view: Option<CachedView>,
     total_count: usize,
+    lock: OwnerLock,
 }
 
 impl BatchTempFile {
     const DIR_PREFIX: &'static str = "llminx_batch_solutions";
-    const CASES_PER_SEGMENT: usize = 100;
+    const STORE_NAME: &'static str = "batch_solutions.bin";
 
     pub fn new() -> Result<Self, String> {
         let temp_dir = std::env::temp_dir();
         let dir = temp_dir.join(format!("{}_{}", Self::DIR_PREFIX, unique_temp_suffix()));
 
+        let lock = OwnerLock::acquire(&dir)?;
         fs::create_dir_all(&dir)
             .map_err(|e| format!("Failed to create batch temp directory: {}", e))?;
+        let store = SolutionStore::create(&dir.join(Self::STORE_NAME))?;
 
         Ok(Self {
             dir,
-            counts: std::collections::HashMap::new(),
+            store,
+            case_ids: HashMap::new(),
+            view: None,
             total_count: 0,
+            lock,
         })
     }
 
-    fn segment_path(&self, case_number: usize) -> PathBuf {
-        let segment_index = case_number / Self::CASES_PER_SEGMENT;
-        self.dir.join(format!("batch_seg_{}.txt", segment_index))
+    fn record(&mut self, case_number: usize, id: u32) {
+        self.case_ids.entry(case_number).or_default().push(id);
+        self.total_count += 1;
     }
 
     pub fn append(&mut self, case_number: usize, solution: &str) -> Result<(), String> {
-        let path = self.segment_path(case_number);
-
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&path)
-            .map_err(|e| format!("Failed to open segment file: {}", e))?;
-
-        writeln!(file, "{}\t{}", case_number, solution)
-            .map_err(|e| format!("Failed to write solution: {}", e))?;
+        let id = self.store.append(solution)?;
+        self.record(case_number, id);
+        Ok(())
+    }
 
-        *self.counts.entry(case_number).or_insert(0) += 1;
-        self.total_count += 1;
+    pub fn append_solution(
+        &mut self,
+        case_number: usize,
+        solution: &Solution,
+    ) -> Result<(), String> {
+        let id = self.store.append_solution(solution)?;
+        self.record(case_number, id);
         Ok(())
     }
 
+    fn ids(&self, case_number: usize) -> &[u32] {
+        self.case_ids.get(&case_number).map_or(&[], Vec::as_slice)
+    }
+
     pub fn read_case_page(
         &self,
         case_number: usize,
         offset: usize,
         limit: usize,
     ) -> Result<Vec<String>, String> {
-        let path = self.segment_path(case_number);
-
-        if !path.exists() {
-            return Ok(vec![]);
-        }
-
-        let file = File::open(&path).map_err(|e| format!("Failed to open segment file: {}", e))?;
-        let reader = BufReader::new(file);
+        self.store
+            .read_ids(page(self.ids(case_number), offset, limit))
+    }
 
-        let prefix = format!("{}\t", case_number);
-        let solutions: Vec<String> = reader
-            .lines()
-            .map_while(Result::ok)
-            .filter(|line| line.starts_with(&prefix))
-            .map(|line| line[prefix.len()..].to_string())
-            .skip(offset)
-            .take(limit)
-            .collect();
+    pub fn read_case_solutions(
+        &self,
+        case_number: usize,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<Solution>, String> {
+        self.read_case_page(case_number, offset, limit)?
+            .iter()
+            .map(|line| decode_solution(line))
+            .collect()
+    }
 
-        Ok(solutions)
+    /// A page of the solutions of one case that `query` keeps, in its order.
+    pub fn read_case_view_page(
+        &mut self,
+        case_number: usize,
+        query: &StoreQuery,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<String>, String> {
+        if is_unfiltered(query) {
+            return self.read_case_page(case_number, offset, limit);
+        }
+        let ids = self
+            .case_ids
+            .get(&case_number)
+            .map_or(&[][..], Vec::as_slice);
+        let view = cached_view(
+            &mut self.view,
+            &mut self.store,
+            Some(case_number),
+            ids,
+            query,
+        )?;
+        self.store.read_ids(page(view, offset, limit))
+    }
+
+    /// Number of solutions of one case that `query` keeps.
+    pub fn case_view_count(
+        &mut self,
+        case_number: usize,
+        query: &StoreQuery,
+    ) -> Result<usize, String> {
+        if query.contains.is_none() && query.moves.is_empty() {
+            return Ok(self.case_count(case_number));
+        }
+        let ids = self
+            .case_ids
+            .get(&case_number)
+            .map_or(&[][..], Vec::as_slice);
+        cached_view(
+            &mut self.view,
+            &mut self.store,
+            Some(case_number),
+            ids,
+            query,
+        )
+        .map(<[u32]>::len)
     }
 
     pub fn case_count(&self, case_number: usize) -> usize {
-        self.counts.get(&case_number).copied().unwrap_or(0)
+        self.ids(case_number).len()
     }
 
     pub fn count(&self) -> usize {
@@ -214,15 +457,39 @@ impl BatchTempFile {
         &self.dir
     }
 
-    pub fn flush(&self) {}
+    /// Write every solution to `path` as `case<TAB>solution` lines, by case.
+    pub fn export_text(&self, path: &Path) -> Result<(), String> {
+        let mut out = create_text_file(path)?;
+        let mut cases: Vec<usize> = self.case_ids.keys().copied().collect();
+        cases.sort_unstable();
+        for case_number in cases {
+            let mut lines = Vec::new();
+            self.store.write_text(self.ids(case_number), &mut lines)?;
+            for line in String::from_utf8_lossy(&lines).lines() {
+                writeln!(out, "{}\t{}", case_number, line)
+                    .map_err(|e| format!("Failed to write export file: {}", e))?;
+            }
+        }
+        out.flush()
+            .map_err(|e| format!("Failed to write export file: {}", e))
+    }
 
-    pub fn close(&mut self) {}
+    pub fn flush(&self) {
+        self.store.flush();
+    }
+
+    pub fn close(&mut self) {
+        self.store.close();
+    }
 
     pub fn delete(&mut self) {
+        self.store.close();
         if self.dir.exists() {
             let _ = fs::remove_dir_all(&self.dir);
         }
-        self.counts.clear();
+        self.lock.release();
+        self.case_ids.clear();
+        self.view = None;
         self.total_count = 0;
     }
 }
@@ -233,26 +500,22 @@ impl Drop for BatchTempFile {
     }
 }
 
-pub fn cleanup_stale_batch_temp_files() {
-    let temp_dir = std::env::temp_dir();
-    if let Ok(entries) = fs::read_dir(temp_dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.is_dir()
-                && path
-                    .file_name()
-                    .and_then(|name| name.to_str())
-                    .is_some_and(|name| name.starts_with(BatchTempFile::DIR_PREFIX))
-            {
-                let _ = fs::remove_dir_all(path);
-            }
-        }
-    }
+/// Remove batch temp directories left behind by processes that have exited.
+pub fn cleanup_stale_batch_temp_files() -> CleanupReport {
+    cleanup_stale_batch_temp_files_with_options(&CleanupOptions::default())
+}
+
+pub fn cleanup_stale_batch_temp_files_with_options(options: &CleanupOptions) -> CleanupReport {
+    cleanup_stale(options, |path, name| {
+        path.is_dir() && name.starts_with(BatchTempFile::DIR_PREFIX)
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::minx::Move;
+    use crate::search_mode::SearchMode;
     use std::sync::{Mutex, OnceLock};
 
     fn test_lock() -> &'static Mutex<()> {
@@ -410,33 +673,116 @@ mod tests {
     }
 
     #[test]
-    fn test_batch_temp_file_segmentation() {
+    fn test_temp_file_solution_round_trip() {
+        let _guard = test_lock().lock().unwrap();
+        let mut temp_file = TempFile::new().unwrap();
+        let first = Solution::from_moves(&[Move::R, Move::U], SearchMode::RU);
+        let second = Solution::from_moves(&[Move::Ui], SearchMode::RU);
+        temp_file.append_solution(&first).unwrap();
+        temp_file.append_solution(&second).unwrap();
+
+        assert_eq!(temp_file.read_solution_page(0, 1).unwrap(), vec![first]);
+        assert_eq!(temp_file.read_solution_page(1, 10).unwrap(), vec![second]);
+
+        temp_file.append("R U (2,2)").unwrap();
+        assert!(temp_file.read_solution_page(2, 1).is_err());
+    }
+
+    #[test]
+    fn test_batch_temp_file_solution_round_trip() {
         let _guard = test_lock().lock().unwrap();
         let mut btf = BatchTempFile::new().unwrap();
-        btf.append(0, "case_0_sol").unwrap();
-        btf.append(99, "case_99_sol").unwrap();
-        btf.append(100, "case_100_sol").unwrap();
-        btf.append(250, "case_250_sol").unwrap();
-
-        // Cases 0 and 99 share segment 0, case 100 is segment 1, case 250 is segment 2
-        let seg0 = btf.dir.join("batch_seg_0.txt");
-        let seg1 = btf.dir.join("batch_seg_1.txt");
-        let seg2 = btf.dir.join("batch_seg_2.txt");
-        assert!(seg0.exists());
-        assert!(seg1.exists());
-        assert!(seg2.exists());
-
-        let page = btf.read_case_page(0, 0, 100).unwrap();
-        assert_eq!(page, vec!["case_0_sol"]);
+        let first = Solution::from_moves(&[Move::R], SearchMode::RU);
+        let second = Solution::from_moves(&[Move::U, Move::Ri], SearchMode::RU);
+        btf.append_solution(3, &first).unwrap();
+        btf.append_solution(4, &second).unwrap();
 
-        let page = btf.read_case_page(99, 0, 100).unwrap();
-        assert_eq!(page, vec!["case_99_sol"]);
+        assert_eq!(btf.read_case_solutions(3, 0, 10).unwrap(), vec![first]);
+        assert_eq!(btf.read_case_solutions(4, 0, 10).unwrap(), vec![second]);
+        assert!(btf.read_case_solutions(5, 0, 10).unwrap().is_empty());
+    }
 
-        let page = btf.read_case_page(100, 0, 100).unwrap();
-        assert_eq!(page, vec!["case_100_sol"]);
+    #[test]
+    fn test_batch_temp_file_interleaved_cases() {
+        let _guard = test_lock().lock().unwrap();
+        let mut btf = BatchTempFile::new().unwrap();
+        for i in 0..3 {
+            for case_number in [0, 99, 100, 250] {
+                btf.append(case_number, &format!("case_{}_sol_{}", case_number, i))
+                    .unwrap();
+            }
+        }
 
-        let page = btf.read_case_page(250, 0, 100).unwrap();
-        assert_eq!(page, vec!["case_250_sol"]);
+        // All cases share one store file
+        assert!(btf.dir.join(BatchTempFile::STORE_NAME).exists());
+
+        let page = btf.read_case_page(99, 1, 100).unwrap();
+        assert_eq!(page, vec!["case_99_sol_1", "case_99_sol_2"]);
+
+        let page = btf.read_case_page(250, 0, 1).unwrap();
+        assert_eq!(page, vec!["case_250_sol_0"]);
+
+        assert!(btf.read_case_page(100, 3, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_temp_file_view_pages() {
+        let _guard = test_lock().lock().unwrap();
+        let mut temp_file = TempFile::new().unwrap();
+        temp_file.append("R U R' U R U2' R'  (7,8)").unwrap();
+        temp_file.append("R U R'  (3,3)").unwrap();
+        temp_file.append("U R U' R'  (4,4)").unwrap();
+
+        let by_length = StoreQuery {
+            order: StoreOrder::Length,
+            ..StoreQuery::default()
+        };
+        assert_eq!(
+            temp_file.read_view_page(&by_length, 0, 2).unwrap(),
+            vec!["R U R'  (3,3)", "U R U' R'  (4,4)"]
+        );
+
+        let filtered = StoreQuery {
+            contains: Some("R U'".to_string()),
+            ..by_length
+        };
+        assert_eq!(temp_file.view_count(&filtered).unwrap(), 1);
+
+        // The cached view picks up solutions appended after it was built
+        temp_file.append("R U'  (2,2)").unwrap();
+        assert_eq!(temp_file.view_count(&filtered).unwrap(), 2);
+        assert_eq!(
+            temp_file.read_view_page(&filtered, 0, 10).unwrap(),
+            vec!["R U'  (2,2)", "U R U' R'  (4,4)"]
+        );
+    }
+
+    #[test]
+    fn test_export_text() {
+        let _guard = test_lock().lock().unwrap();
+        let export =
+            std::env::temp_dir().join(format!("llminx_export_test_{}.txt", std::process::id()));
+
+        let mut temp_file = TempFile::new().unwrap();
+        temp_file.append("R U R'  (3,3)").unwrap();
+        temp_file
+            .append_solution(&Solution::from_moves(&[Move::U], SearchMode::RU))
+            .unwrap();
+        temp_file.export_text(&export).unwrap();
+        assert_eq!(
+            fs::read_to_string(&export).unwrap(),
+            "R U R'  (3,3)\nU  (1,1)\n"
+        );
+
+        let mut btf = BatchTempFile::new().unwrap();
+        btf.append(2, "R  (1,1)").unwrap();
+        btf.append(1, "U  (1,1)").unwrap();
+        btf.export_text(&export).unwrap();
+        assert_eq!(
+            fs::read_to_string(&export).unwrap(),
+            "1\tU  (1,1)\n2\tR  (1,1)\n"
+        );
+        let _ = fs::remove_file(&export);
     }
 
     #[test]
@@ -457,12 +803,57 @@ mod tests {
         let _guard = test_lock().lock().unwrap();
         let btf = BatchTempFile::new().unwrap();
         let dir = btf.get_path().to_path_buf();
-        // Prevent Drop from cleaning up before we test
+        // Prevent Drop from cleaning up before we test; the lock stays held
         std::mem::forget(btf);
 
         assert!(dir.exists());
-        cleanup_stale_batch_temp_files();
+        let report = cleanup_stale_batch_temp_files();
+        assert!(dir.exists());
+        assert!(report.in_use.contains(&dir));
+
+        let report = cleanup_stale_batch_temp_files_with_options(&CleanupOptions {
+            max_age: Some(Duration::ZERO),
+        });
         assert!(!dir.exists());
+        assert!(!lock_path(&dir).exists());
+        assert!(report.removed.contains(&dir));
+    }
+
+    #[test]
+    fn test_cleanup_keeps_files_of_live_owners() {
+        let _guard = test_lock().lock().unwrap();
+        let temp_file = TempFile::new().unwrap();
+        let path = temp_file.get_path().to_path_buf();
+        assert!(lock_path(&path).exists());
+
+        let report = cleanup_stale_temp_files();
+        assert!(path.exists());
+        assert!(report.in_use.contains(&path));
+        assert!(!report.removed.contains(&path));
+
+        // An old but live file is only removed once it passes the age limit
+        let report = cleanup_stale_temp_files_with_options(&CleanupOptions {
+            max_age: Some(Duration::from_secs(3600)),
+        });
+        assert!(report.in_use.contains(&path));
+
+        drop(temp_file);
+        assert!(!path.exists());
+        assert!(!lock_path(&path).exists());
+    }
+
+    #[test]
+    fn test_cleanup_removes_files_with_released_lock() {
+        let _guard = test_lock().lock().unwrap();
+        let path = std::env::temp_dir().join(format!("{}_exited_owner.bin", TempFile::FILE_PREFIX));
+        // What a crashed process leaves: data plus an unlocked lock file
+        std::fs::write(&path, "stale").unwrap();
+        std::fs::write(lock_path(&path), "0").unwrap();
+
+        let report = cleanup_stale_temp_files();
+        assert_eq!(report.removed, vec![path.clone()]);
+        assert!(!path.exists());
+        assert!(!lock_path(&path).exists());
     }
 
     #[test]
@@ -482,8 +873,15 @@ mod tests {
         std::fs::write(&path, "stale").unwrap();
         assert!(path.exists());
 
-        cleanup_stale_temp_files();
+        let store =
+            std::env::temp_dir().join(format!("{}_manual_stale.bin", TempFile::FILE_PREFIX));
+        std::fs::write(&store, "stale").unwrap();
+
+        let report = cleanup_stale_temp_files();
         assert!(!path.exists());
+        assert!(!store.exists());
+        assert!(report.removed.contains(&path));
+        assert!(report.removed.contains(&store));
     }
 
     #[test]
@@ -45,10 +45,11 @@ pub fn detect_wallpaper_path() -> Option<String> {
     None
 }
 
+#[cfg(any(target_os = "linux", test))]
+mod linux;
+
 #[cfg(target_os = "linux")]
-pub fn detect_wallpaper_path() -> Option<String> {
-    None
-}
+pub use linux::detect_wallpaper_path;
 
 #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
 pub fn detect_wallpaper_path() -> Option<String> {
@@ -0,0 +1,603 @@
+//! Wallpaper detection for Linux desktops
+//!
+//! Each desktop keeps its wallpaper in its own settings store, so detection
+//! tries the stores of the desktops named in `XDG_CURRENT_DESKTOP` first and
+//! then the rest, ending with `~/.fehbg` for plain window managers. The
+//! parsers only look at file contents or command output so they can be tested
+//! against fixtures.
+
+#![cfg_attr(not(target_os = "linux"), allow(dead_code))]
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Where a desktop keeps its wallpaper setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    /// GNOME and its derivatives, through gsettings or dconf
+    Gsettings,
+    Plasma,
+    Xfconf,
+    Sway,
+    Hyprpaper,
+    Feh,
+}
+
+const ALL_SOURCES: [Source; 6] = [
+    Source::Gsettings,
+    Source::Plasma,
+    Source::Xfconf,
+    Source::Sway,
+    Source::Hyprpaper,
+    Source::Feh,
+];
+
+/// The parts of the process environment detection depends on
+#[derive(Debug, Clone)]
+struct Environment {
+    home: PathBuf,
+    config_home: PathBuf,
+    /// Lowercased entries of `XDG_CURRENT_DESKTOP`
+    desktops: Vec<String>,
+}
+
+impl Environment {
+    fn from_env() -> Option<Self> {
+        let home = PathBuf::from(std::env::var_os("HOME")?);
+        let config_home = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|p| p.is_absolute())
+            .unwrap_or_else(|| home.join(".config"));
+        let desktops = std::env::var("XDG_CURRENT_DESKTOP")
+            .map(|d| parse_desktops(&d))
+            .unwrap_or_default();
+        Some(Self {
+            home,
+            config_home,
+            desktops,
+        })
+    }
+
+    fn has_desktop(&self, name: &str) -> bool {
+        self.desktops.iter().any(|d| d == name)
+    }
+}
+
+pub fn detect_wallpaper_path() -> Option<String> {
+    let env = Environment::from_env()?;
+    detect(&env, &run_command)
+}
+
+fn run_command(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn detect(env: &Environment, run: &dyn Fn(&str, &[&str]) -> Option<String>) -> Option<String> {
+    sources_for(&env.desktops)
+        .into_iter()
+        .filter_map(|source| read_source(source, env, run))
+        .map(|value| resolve_path(&value, &env.home))
+        .find(|path| path.is_file())
+        .map(|path| path.to_string_lossy().into_owned())
+}
+
+fn parse_desktops(value: &str) -> Vec<String> {
+    value
+        .split(':')
+        .map(|d| d.trim().to_ascii_lowercase())
+        .filter(|d| !d.is_empty())
+        .collect()
+}
+
+/// Sources of the current desktops first, then every other source
+fn sources_for(desktops: &[String]) -> Vec<Source> {
+    let mut sources: Vec<Source> = desktops
+        .iter()
+        .filter_map(|desktop| match desktop.as_str() {
+            "gnome" | "gnome-classic" | "gnome-flashback" | "ubuntu" | "unity" | "pantheon"
+            | "budgie" | "budgie-desktop" | "cinnamon" | "x-cinnamon" | "mate" => {
+                Some(Source::Gsettings)
+            }
+            "kde" => Some(Source::Plasma),
+            "xfce" => Some(Source::Xfconf),
+            "sway" => Some(Source::Sway),
+            "hyprland" => Some(Source::Hyprpaper),
+            _ => None,
+        })
+        .collect();
+    sources.extend(ALL_SOURCES);
+
+    let mut seen = Vec::new();
+    sources.retain(|s| {
+        let first = !seen.contains(s);
+        seen.push(*s);
+        first
+    });
+    sources
+}
+
+fn read_source(
+    source: Source,
+    env: &Environment,
+    run: &dyn Fn(&str, &[&str]) -> Option<String>,
+) -> Option<String> {
+    let config = |path: &str| std::fs::read_to_string(env.config_home.join(path)).ok();
+    match source {
+        Source::Gsettings => gsettings_keys(env, run)
+            .into_iter()
+            .filter_map(|(schema, key)| read_setting(schema, key, run))
+            .find(|value| resolve_path(value, &env.home).is_file()),
+        Source::Plasma => {
+            parse_plasma_appletsrc(&config("plasma-org.kde.plasma.desktop-appletsrc")?)
+        }
+        Source::Xfconf => parse_xfconf_desktop(&config(
+            "xfce4/xfconf/xfce-perchannel-xml/xfce4-desktop.xml",
+        )?),
+        Source::Sway => config("sway/config")
+            .or_else(|| std::fs::read_to_string(env.home.join(".sway/config")).ok())
+            .as_deref()
+            .and_then(parse_sway_config),
+        Source::Hyprpaper => parse_hyprpaper_config(&config("hypr/hyprpaper.conf")?),
+        Source::Feh => parse_fehbg(&std::fs::read_to_string(env.home.join(".fehbg")).ok()?),
+    }
+}
+
+/// Schema and key pairs holding the wallpaper, most specific first
+fn gsettings_keys(
+    env: &Environment,
+    run: &dyn Fn(&str, &[&str]) -> Option<String>,
+) -> Vec<(&'static str, &'static str)> {
+    let mut keys = Vec::new();
+    if env.has_desktop("cinnamon") || env.has_desktop("x-cinnamon") {
+        keys.push(("org.cinnamon.desktop.background", "picture-uri"));
+    }
+    if env.has_desktop("mate") {
+        keys.push(("org.mate.background", "picture-filename"));
+    }
+    let dark = read_setting("org.gnome.desktop.interface", "color-scheme", run)
+        .is_some_and(|scheme| scheme == "prefer-dark");
+    if dark {
+        keys.push(("org.gnome.desktop.background", "picture-uri-dark"));
+    }
+    keys.push(("org.gnome.desktop.background", "picture-uri"));
+    keys
+}
+
+/// Read a key with gsettings, or straight from dconf when gsettings is missing
+fn read_setting(
+    schema: &str,
+    key: &str,
+    run: &dyn Fn(&str, &[&str]) -> Option<String>,
+) -> Option<String> {
+    let output = run("gsettings", &["get", schema, key]).or_else(|| {
+        let path = format!("/{}/{}", schema.replace('.', "/"), key);
+        run("dconf", &["read", &path])
+    })?;
+    parse_gsettings_value(&output)
+}
+
+/// Unquote a string printed by `gsettings get` or `dconf read`
+fn parse_gsettings_value(output: &str) -> Option<String> {
+    let value = output.trim();
+    let quoted = ['\'', '"']
+        .into_iter()
+        .find_map(|q| value.strip_prefix(q)?.strip_suffix(q));
+    let value = match quoted {
+        Some(inner) => {
+            let mut unescaped = String::with_capacity(inner.len());
+            let mut chars = inner.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => unescaped.extend(chars.next()),
+                    c => unescaped.push(c),
+                }
+            }
+            unescaped
+        }
+        None => value.to_string(),
+    };
+    (!value.is_empty()).then_some(value)
+}
+
+/// `Image` of the first containment using the image wallpaper plugin
+fn parse_plasma_appletsrc(text: &str) -> Option<String> {
+    let mut in_image_group = false;
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_image_group = line.ends_with("[Wallpaper][org.kde.image][General]");
+        } else if in_image_group
+            && let Some((key, value)) = line.split_once('=')
+            && key.trim() == "Image"
+            && !value.trim().is_empty()
+        {
+            return Some(value.trim().to_string());
+        }
+    }
+    None
+}
+
+/// `last-image` of the first backdrop, falling back to the older `image-path`
+fn parse_xfconf_desktop(text: &str) -> Option<String> {
+    let mut image_path = None;
+    for tag in text.split('<').filter(|t| t.starts_with("property")) {
+        let tag = tag.split('>').next().unwrap_or(tag);
+        let (Some(name), Some(value)) = (xml_attribute(tag, "name"), xml_attribute(tag, "value"))
+        else {
+            continue;
+        };
+        if value.is_empty() {
+            continue;
+        }
+        match name.as_str() {
+            "last-image" => return Some(value),
+            "image-path" if image_path.is_none() => image_path = Some(value),
+            _ => {}
+        }
+    }
+    image_path
+}
+
+fn xml_attribute(tag: &str, name: &str) -> Option<String> {
+    let pattern = format!(" {}=", name);
+    let start = tag.find(&pattern)? + pattern.len();
+    let quote = tag[start..]
+        .chars()
+        .next()
+        .filter(|&q| q == '"' || q == '\'')?;
+    let rest = &tag[start + 1..];
+    let value = &rest[..rest.find(quote)?];
+    Some(
+        value
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&"),
+    )
+}
+
+/// Image of the first `output ... bg` command, or of a `swaybg` started from
+/// the config
+fn parse_sway_config(text: &str) -> Option<String> {
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('#') {
+            continue;
+        }
+        let words = shell_words(line);
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        match words.as_slice() {
+            ["output", _, "bg" | "background", image, ..] => return Some(image.to_string()),
+            ["exec" | "exec_always", rest @ ..] => {
+                let swaybg = rest.iter().position(|w| w.ends_with("swaybg"));
+                if let Some(image) = swaybg.and_then(|i| {
+                    rest[i..]
+                        .windows(2)
+                        .find(|pair| pair[0] == "-i" || pair[0] == "--image")
+                        .map(|pair| pair[1])
+                }) {
+                    return Some(image.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// First `wallpaper` of a hyprpaper config, in either the `monitor,path` or
+/// the block syntax, falling back to the first preloaded image
+fn parse_hyprpaper_config(text: &str) -> Option<String> {
+    let mut in_block = false;
+    let mut preload = None;
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with("wallpaper") && line.ends_with('{') {
+            in_block = true;
+            continue;
+        }
+        if line == "}" {
+            in_block = false;
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "path" if in_block && !value.is_empty() => return Some(value.to_string()),
+            "wallpaper" if !in_block => {
+                let image = value.split_once(',').map_or(value, |(_, path)| path).trim();
+                if !image.is_empty() {
+                    return Some(image.to_string());
+                }
+            }
+            "preload" if preload.is_none() && !value.is_empty() => {
+                preload = Some(value.to_string())
+            }
+            _ => {}
+        }
+    }
+    preload
+}
+
+/// First image of the `feh --bg-*` command in `~/.fehbg`
+fn parse_fehbg(text: &str) -> Option<String> {
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('#') {
+            continue;
+        }
+        let words = shell_words(line);
+        let Some(feh) = words.iter().position(|w| w.ends_with("feh")) else {
+            continue;
+        };
+        let mut args = words[feh + 1..].iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                // Options of .fehbg that take a value
+                "--image-bg" | "--geometry" | "-g" => {
+                    args.next();
+                }
+                arg if arg.starts_with('-') => {}
+                image => return Some(image.to_string()),
+            }
+        }
+    }
+    None
+}
+
+/// Split a line into words following POSIX shell quoting
+fn shell_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let current = word.get_or_insert_with(String::new);
+                current.extend(chars.by_ref().take_while(|&c| c != '\''));
+            }
+            '"' => {
+                let current = word.get_or_insert_with(String::new);
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => current.extend(chars.next()),
+                        c => current.push(c),
+                    }
+                }
+            }
+            '\\' => word.get_or_insert_with(String::new).extend(chars.next()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    words
+}
+
+/// Turn a setting value into a path: decode `file://` URIs and expand a
+/// leading `~` or `$HOME`
+fn resolve_path(value: &str, home: &Path) -> PathBuf {
+    if let Some(path) = value.strip_prefix("file://") {
+        return PathBuf::from(percent_decode(path));
+    }
+    for prefix in ["~/", "$HOME/", "${HOME}/"] {
+        if let Some(rest) = value.strip_prefix(prefix) {
+            return home.join(rest);
+        }
+    }
+    PathBuf::from(value)
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn no_commands(_: &str, _: &[&str]) -> Option<String> {
+        None
+    }
+
+    fn temp_home(name: &str) -> Environment {
+        let home =
+            std::env::temp_dir().join(format!("llminx_wallpaper_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&home);
+        fs::create_dir_all(&home).unwrap();
+        Environment {
+            config_home: home.join(".config"),
+            home,
+            desktops: Vec::new(),
+        }
+    }
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_parse_gsettings_fixture() {
+        let value = parse_gsettings_value(include_str!("fixtures/gsettings-picture-uri.txt"));
+        assert_eq!(
+            value.as_deref(),
+            Some("file:///home/user/Pictures/Mountain%20Lake.jpg")
+        );
+        assert_eq!(
+            resolve_path(&value.unwrap(), Path::new("/home/user")),
+            PathBuf::from("/home/user/Pictures/Mountain Lake.jpg")
+        );
+        assert_eq!(parse_gsettings_value("''\n"), None);
+        assert_eq!(
+            parse_gsettings_value("'/tmp/it\\'s.png'").as_deref(),
+            Some("/tmp/it's.png")
+        );
+    }
+
+    #[test]
+    fn test_parse_plasma_fixture() {
+        assert_eq!(
+            parse_plasma_appletsrc(include_str!(
+                "fixtures/plasma-org.kde.plasma.desktop-appletsrc"
+            ))
+            .as_deref(),
+            Some("file:///home/user/Pictures/plasma.png")
+        );
+        assert_eq!(parse_plasma_appletsrc("[Containments][1]\nImage=x"), None);
+    }
+
+    #[test]
+    fn test_parse_xfconf_fixture() {
+        assert_eq!(
+            parse_xfconf_desktop(include_str!("fixtures/xfce4-desktop.xml")).as_deref(),
+            Some("/home/user/Pictures/Rock & Roll.jpg")
+        );
+        assert_eq!(
+            parse_xfconf_desktop(r#"<property name="image-path" type="string" value="/old.png"/>"#)
+                .as_deref(),
+            Some("/old.png")
+        );
+    }
+
+    #[test]
+    fn test_parse_sway_fixture() {
+        assert_eq!(
+            parse_sway_config(include_str!("fixtures/sway-config")).as_deref(),
+            Some("~/Pictures/sway wall.png")
+        );
+        assert_eq!(
+            parse_sway_config("exec swaybg -m fill -i /tmp/bg.png").as_deref(),
+            Some("/tmp/bg.png")
+        );
+        assert_eq!(parse_sway_config("output * resolution 1920x1080"), None);
+    }
+
+    #[test]
+    fn test_parse_hyprpaper_fixtures() {
+        assert_eq!(
+            parse_hyprpaper_config(include_str!("fixtures/hyprpaper.conf")).as_deref(),
+            Some("~/Pictures/hypr.png")
+        );
+        assert_eq!(
+            parse_hyprpaper_config(include_str!("fixtures/hyprpaper-block.conf")).as_deref(),
+            Some("$HOME/Pictures/hypr block.png")
+        );
+        assert_eq!(
+            parse_hyprpaper_config("preload = /tmp/a.png\n").as_deref(),
+            Some("/tmp/a.png")
+        );
+    }
+
+    #[test]
+    fn test_parse_fehbg_fixture() {
+        assert_eq!(
+            parse_fehbg(include_str!("fixtures/fehbg")).as_deref(),
+            Some("/home/user/Pictures/it's feh.jpg")
+        );
+        assert_eq!(
+            parse_fehbg("feh --image-bg black --bg-center '/tmp/a.png'").as_deref(),
+            Some("/tmp/a.png")
+        );
+    }
+
+    #[test]
+    fn test_resolve_path() {
+        let home = Path::new("/home/user");
+        assert_eq!(
+            resolve_path("~/a.png", home),
+            PathBuf::from("/home/user/a.png")
+        );
+        assert_eq!(
+            resolve_path("${HOME}/a.png", home),
+            PathBuf::from("/home/user/a.png")
+        );
+        assert_eq!(
+            resolve_path("file:///a%2Fb%zz.png", home),
+            PathBuf::from("/a/b%zz.png")
+        );
+        assert_eq!(resolve_path("/a.png", home), PathBuf::from("/a.png"));
+    }
+
+    #[test]
+    fn test_sources_follow_current_desktop() {
+        assert_eq!(sources_for(&[]), ALL_SOURCES);
+        let sources = sources_for(&parse_desktops("Hyprland:KDE"));
+        assert_eq!(
+            sources[..3],
+            [Source::Hyprpaper, Source::Plasma, Source::Gsettings]
+        );
+        assert_eq!(sources.len(), ALL_SOURCES.len());
+    }
+
+    #[test]
+    fn test_detect_prefers_current_desktop_and_skips_missing_images() {
+        let mut env = temp_home("detect");
+        let sway_image = env.home.join("sway.png");
+        let feh_image = env.home.join("feh.png");
+        fs::write(&sway_image, b"").unwrap();
+        fs::write(&feh_image, b"").unwrap();
+        write(
+            &env.config_home.join("sway/config"),
+            "output * bg ~/sway.png fill\n",
+        );
+        write(
+            &env.home.join(".fehbg"),
+            &format!("feh --bg-fill '{}'\n", feh_image.display()),
+        );
+        write(
+            &env.config_home.join("hypr/hyprpaper.conf"),
+            "wallpaper = ,~/missing.png\n",
+        );
+
+        env.desktops = parse_desktops("Hyprland");
+        let sway = sway_image.to_string_lossy().into_owned();
+        assert_eq!(detect(&env, &no_commands), Some(sway.clone()));
+
+        fs::remove_file(&sway_image).unwrap();
+        let feh = feh_image.to_string_lossy().into_owned();
+        assert_eq!(detect(&env, &no_commands), Some(feh));
+
+        let gnome_image = env.home.join("gnome dark.png");
+        fs::write(&gnome_image, b"").unwrap();
+        let uri = format!("'file://{}'", gnome_image.display()).replace(' ', "%20");
+        let settings = move |program: &str, args: &[&str]| match (program, args) {
+            ("gsettings", ["get", _, "color-scheme"]) => Some("'prefer-dark'\n".to_string()),
+            ("gsettings", ["get", _, "picture-uri-dark"]) => Some(uri.clone()),
+            _ => None,
+        };
+        env.desktops = parse_desktops("ubuntu:GNOME");
+        assert_eq!(
+            detect(&env, &settings),
+            Some(gnome_image.to_string_lossy().into_owned())
+        );
+        let _ = fs::remove_dir_all(&env.home);
+    }
+}
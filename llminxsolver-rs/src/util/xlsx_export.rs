@@ -1,3 +1,4 @@
+use super::solution_store::{SolutionStore, plain_text};
 use rust_xlsxwriter::{Format, FormatAlign, Image, Workbook, Worksheet, XlsxError};
 
 #[derive(Debug, Clone)]
@@ -176,9 +177,20 @@ pub fn export_raw_xlsx_from_file(
 ) -> Result<(), String> {
     use std::fs::File;
     use std::io::{BufRead, BufReader};
+    use std::path::Path;
+
+    let path = Path::new(solutions_file_path);
+    if SolutionStore::is_store(path) {
+        let store = SolutionStore::open(path)?;
+        let algorithms: Vec<String> = store
+            .read_range(0, store.len())?
+            .iter()
+            .map(|record| plain_text(record))
+            .collect();
+        return export_raw_xlsx(output_path, &algorithms, image_png_bytes, image_size);
+    }
 
-    let file = File::open(solutions_file_path)
-        .map_err(|e| format!("Failed to open solutions file: {}", e))?;
+    let file = File::open(path).map_err(|e| format!("Failed to open solutions file: {}", e))?;
     let reader = BufReader::new(file);
     let algorithms: Vec<String> = reader.lines().map_while(Result::ok).collect();
 
@@ -291,6 +303,22 @@ mod tests {
         let _ = fs::remove_file(output_path);
     }
 
+    #[test]
+    fn test_export_raw_xlsx_from_solution_store() {
+        let solutions_path = std::path::Path::new("/tmp/test_solutions_store.bin");
+        let mut store = SolutionStore::create(solutions_path).unwrap();
+        store.append("R U R'  (3,3)").unwrap();
+        store.close();
+
+        let output_path = "/tmp/test_raw_from_store.xlsx";
+        let result =
+            export_raw_xlsx_from_file(output_path, solutions_path.to_str().unwrap(), None, 100);
+        assert!(result.is_ok());
+
+        let _ = fs::remove_file(solutions_path);
+        let _ = fs::remove_file(output_path);
+    }
+
     #[test]
     fn test_export_scored_xlsx_with_image_bytes() {
         let temp_path = "/tmp/test_scored_with_image.xlsx";
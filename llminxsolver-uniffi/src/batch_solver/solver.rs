@@ -2,7 +2,9 @@ use crate::batch_solver::types::{
     BatchCaseResult, BatchSolveResults, BatchSolverConfig, BatchSolverError, GeneratedBatchState,
 };
 use crate::dedicated_solver::ProgressEvent;
+use crate::util::result_cache;
 use llminxsolver_rs::StatusEvent;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
 
@@ -118,6 +120,16 @@ impl BatchSolverHandle {
     }
 
     pub fn start(&self) {
+        self.start_with_journal(None);
+    }
+
+    /// Start solving while checkpointing to `journal_path`, continuing from
+    /// the journal if it already exists.
+    pub fn start_resumable(&self, journal_path: String) {
+        self.start_with_journal(Some(PathBuf::from(journal_path)));
+    }
+
+    fn start_with_journal(&self, journal_path: Option<PathBuf>) {
         if self.running.swap(true, Ordering::SeqCst) {
             return;
         }
@@ -138,20 +150,7 @@ impl BatchSolverHandle {
         let interrupt = Arc::clone(&self.interrupt);
         let running = Arc::clone(&self.running);
 
-        let config = self.config.read().unwrap();
-        let solver_config = llminxsolver_rs::batch_solver::BatchSolverConfig {
-            search_mode: config.search_mode.into(),
-            metric: config.metric.into(),
-            pruning_depth: config.pruning_depth,
-            max_search_depth: config.search_depth as usize,
-            stop_after_first: config.stop_after_first,
-            memory_config: config.parallel_config.clone().into(),
-            ignore_corner_permutation: config.ignore_corner_permutation,
-            ignore_edge_permutation: config.ignore_edge_permutation,
-            ignore_corner_orientation: config.ignore_corner_orientation,
-            ignore_edge_orientation: config.ignore_edge_orientation,
-        };
-        drop(config);
+        let solver_config = solver_config(&self.config.read().unwrap());
 
         std::thread::spawn(move || {
             let status_callback: Option<llminxsolver_rs::solver::StatusCallback> =
@@ -182,14 +181,38 @@ impl BatchSolverHandle {
                     None
                 };
 
-            let results = llminxsolver_rs::batch_solver::solve_batch_states(
-                states,
-                &solver_config,
-                equivalence.as_ref(),
-                interrupt,
-                status_callback,
-                case_solved_callback,
-            );
+            let total_cases = states.len();
+            let results = match journal_path {
+                Some(path) => llminxsolver_rs::batch_solver::resume_batch_states(
+                    states,
+                    &solver_config,
+                    equivalence.as_ref(),
+                    &path,
+                    interrupt,
+                    status_callback,
+                    case_solved_callback,
+                )
+                .unwrap_or_else(|e| {
+                    if let Some(ref cb) = callback {
+                        cb.on_progress(ProgressEvent {
+                            event_type: "Error".to_string(),
+                            message: e,
+                            progress: 0.0,
+                            search_mode: None,
+                            current_depth: 0,
+                        });
+                    }
+                    llminxsolver_rs::batch_solver::BatchResults::new(total_cases)
+                }),
+                None => llminxsolver_rs::batch_solver::solve_batch_states(
+                    states,
+                    &solver_config,
+                    equivalence.as_ref(),
+                    interrupt,
+                    status_callback,
+                    case_solved_callback,
+                ),
+            };
 
             if let Some(ref cb) = callback {
                 cb.on_complete(results.into());
@@ -212,6 +235,29 @@ impl BatchSolverHandle {
     }
 }
 
+/// Library batch config for the options set through the bindings.
+fn solver_config(config: &BatchSolverConfig) -> llminxsolver_rs::batch_solver::BatchSolverConfig {
+    llminxsolver_rs::batch_solver::BatchSolverConfig {
+        search_mode: config.search_mode.into(),
+        metric: config.metric.into(),
+        pruning_depth: config.pruning_depth,
+        max_search_depth: config.search_depth as usize,
+        stop_after_first: config.stop_after_first,
+        depth_slack: config.depth_slack.map(|slack| slack as usize),
+        memory_config: config.parallel_config.clone().into(),
+        ignore_corner_permutation: config.ignore_corner_permutation,
+        ignore_edge_permutation: config.ignore_edge_permutation,
+        ignore_corner_orientation: config.ignore_corner_orientation,
+        ignore_edge_orientation: config.ignore_edge_orientation,
+        mcc_params: config.mcc_params.map(Into::into),
+        ranking: config.ranking.map(Into::into),
+        dedup: Default::default(),
+        filters: Vec::new(),
+        verify_solutions: config.verify_solutions,
+        result_cache: result_cache(config.use_result_cache),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -240,6 +286,8 @@ mod tests {
     }
 
     fn base_config() -> BatchSolverConfig {
+        // Every test using it may build tables
+        crate::util::use_test_data_directory();
         BatchSolverConfig {
             scramble: "R".to_string(),
             equivalences: "".to_string(),
@@ -254,6 +302,7 @@ mod tests {
             pruning_depth: 6,
             search_depth: 1,
             stop_after_first: true,
+            depth_slack: None,
             parallel_config: ParallelConfig {
                 memory_budget_mb: 64,
                 table_gen_threads: 1,
@@ -263,9 +312,24 @@ mod tests {
             ignore_edge_permutation: false,
             ignore_corner_orientation: false,
             ignore_edge_orientation: false,
+            mcc_params: None,
+            ranking: None,
+            verify_solutions: false,
+            use_result_cache: false,
         }
     }
 
+    #[test]
+    fn test_solver_config_passes_depth_slack() {
+        assert_eq!(solver_config(&base_config()).depth_slack, None);
+
+        let mut config = base_config();
+        config.depth_slack = Some(2);
+        let solver_config = solver_config(&config);
+        assert_eq!(solver_config.depth_slack, Some(2));
+        assert_eq!(solver_config.max_search_depth, 1);
+    }
+
     #[test]
     fn test_batch_handle_create_update_and_cancel() {
         let handle = BatchSolverHandle::new(base_config()).unwrap();
@@ -415,4 +479,34 @@ mod tests {
         let _ = solved.load(Ordering::Relaxed);
         let _ = complete.load(Ordering::Relaxed);
     }
+
+    #[test]
+    fn test_start_resumable_writes_journal() {
+        let handle = BatchSolverHandle::new(base_config()).unwrap();
+        *handle.generated_states.write().unwrap() =
+            vec![llminxsolver_rs::batch_solver::GeneratedState {
+                state: llminxsolver_rs::LLMinx::new(),
+                setup_moves: "".to_string(),
+                case_number: 1,
+            }];
+        let path = std::env::temp_dir().join(format!(
+            "llminx_uniffi_batch_journal_{}.jsonl",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        handle.start_resumable(path.to_string_lossy().to_string());
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(3);
+        while handle.is_running() && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        if handle.is_running() {
+            handle.cancel();
+            std::thread::sleep(std::time::Duration::from_millis(120));
+        }
+
+        assert!(path.exists());
+        let _ = std::fs::remove_file(&path);
+    }
 }
@@ -1,4 +1,5 @@
-use crate::dedicated_solver::{Metric, ParallelConfig, SearchMode};
+use crate::dedicated_solver::{Metric, ParallelConfig, SearchMode, Solution};
+use crate::util::{MCCParams, SolutionRanking};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SortingType {
@@ -72,11 +73,17 @@ pub struct BatchSolverConfig {
     pub pruning_depth: u8,
     pub search_depth: u32,
     pub stop_after_first: bool,
+    /// Stop each case this many moves past its shortest solution.
+    pub depth_slack: Option<u32>,
     pub parallel_config: ParallelConfig,
     pub ignore_corner_permutation: bool,
     pub ignore_edge_permutation: bool,
     pub ignore_corner_orientation: bool,
     pub ignore_edge_orientation: bool,
+    pub mcc_params: Option<MCCParams>,
+    pub ranking: Option<SolutionRanking>,
+    pub verify_solutions: bool,
+    pub use_result_cache: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -95,6 +102,7 @@ pub struct BatchCaseResult {
     pub setup_moves: String,
     pub solutions: Vec<String>,
     pub best_solution: Option<String>,
+    pub solution_records: Vec<Solution>,
     pub solve_time: f64,
 }
 
@@ -103,8 +111,9 @@ impl From<llminxsolver_rs::batch_solver::BatchCaseResult> for BatchCaseResult {
         Self {
             case_number: r.case_number as u32,
             setup_moves: r.setup_moves,
-            solutions: r.solutions,
-            best_solution: r.best_solution,
+            solutions: r.solutions.iter().map(ToString::to_string).collect(),
+            best_solution: r.best_solution.as_ref().map(ToString::to_string),
+            solution_records: r.solutions.into_iter().map(Into::into).collect(),
             solve_time: r.solve_time,
         }
     }
@@ -221,8 +230,17 @@ mod tests {
     fn test_batch_result_conversions() {
         let mut rs_results = llminxsolver_rs::batch_solver::BatchResults::new(2);
         let mut rs_case = llminxsolver_rs::batch_solver::BatchCaseResult::new(1, "R U".to_string());
-        rs_case.solutions = vec!["R U R'".to_string()];
-        rs_case.best_solution = Some("R U R'".to_string());
+        let mut minx = llminxsolver_rs::LLMinx::new();
+        for m in [
+            llminxsolver_rs::Move::R,
+            llminxsolver_rs::Move::U,
+            llminxsolver_rs::Move::Ri,
+        ] {
+            minx.apply_move(m);
+        }
+        let solution = llminxsolver_rs::Solution::from_minx(&minx, llminxsolver_rs::SearchMode::RU);
+        rs_case.solutions = vec![solution.clone()];
+        rs_case.best_solution = Some(solution);
         rs_case.solve_time = 0.2;
         rs_results.add_result(rs_case);
 
@@ -231,7 +249,12 @@ mod tests {
         assert_eq!(out.solved_cases, 1);
         assert_eq!(out.case_results.len(), 1);
         assert_eq!(out.case_results[0].case_number, 1);
-        assert_eq!(out.case_results[0].best_solution.as_deref(), Some("R U R'"));
+        assert_eq!(
+            out.case_results[0].best_solution.as_deref(),
+            Some("R U R'  (3,3)")
+        );
+        assert_eq!(out.case_results[0].solution_records.len(), 1);
+        assert_eq!(out.case_results[0].solution_records[0].algorithm, "R U R'");
     }
 
     #[test]
@@ -285,6 +308,7 @@ mod tests {
             pruning_depth: 6,
             search_depth: 8,
             stop_after_first: true,
+            depth_slack: None,
             parallel_config: ParallelConfig {
                 memory_budget_mb: 64,
                 table_gen_threads: 1,
@@ -294,6 +318,10 @@ mod tests {
             ignore_edge_permutation: false,
             ignore_corner_orientation: true,
             ignore_edge_orientation: false,
+            mcc_params: None,
+            ranking: None,
+            verify_solutions: false,
+            use_result_cache: false,
         };
         assert!(cfg.stop_after_first);
         assert_eq!(cfg.search_depth, 8);
@@ -1,8 +1,9 @@
 mod solver;
 mod types;
 
+pub(crate) use solver::build_llminx;
 pub use solver::{ParallelSolverHandle, SolverCallback, SolverHandle};
 pub use types::{
     MegaminxState, Metric, ModePruningDepth, ParallelConfig, ParallelSolverConfig, ProgressEvent,
-    SearchMode, SolverConfig,
+    SearchMode, Solution, SolverConfig,
 };
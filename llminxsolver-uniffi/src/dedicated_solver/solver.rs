@@ -1,13 +1,14 @@
 use crate::dedicated_solver::types::{
-    MegaminxState, ParallelSolverConfig, ProgressEvent, SolverConfig,
+    MegaminxState, ParallelSolverConfig, ProgressEvent, Solution, SolverConfig,
 };
+use crate::util::result_cache;
 use llminxsolver_rs::{LLMinx, MemoryConfig, ParallelSolver, Solver, StatusEvent, StatusEventType};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
 
 pub trait SolverCallback: Send + Sync {
     fn on_progress(&self, event: ProgressEvent);
-    fn on_solution_found(&self, solution: String);
+    fn on_solution_found(&self, solution: Solution);
     fn on_complete(&self);
 }
 
@@ -89,6 +90,8 @@ impl SolverHandle {
                 Solver::with_parallel_config(search_mode, max_search_depth, memory_config);
             solver.set_metric(metric);
             solver.set_limit_search_depth(config.limit_search_depth);
+            solver.set_ranking(config.ranking.map(Into::into));
+            solver.set_result_cache(result_cache(config.use_result_cache));
             solver.set_pruning_depth(config.pruning_depth);
             solver.set_start(start_state);
             solver.set_ignore_corner_positions(config.ignore_corner_positions);
@@ -112,7 +115,9 @@ impl SolverHandle {
                 let cb_clone = Arc::clone(cb);
                 solver.set_status_callback(move |event: StatusEvent| match event.event_type {
                     StatusEventType::SolutionFound => {
-                        cb_clone.on_solution_found(event.message.clone());
+                        if let Some(solution) = event.solution {
+                            cb_clone.on_solution_found(solution.into());
+                        }
                     }
                     StatusEventType::FinishSearch => {
                         cb_clone.on_complete();
@@ -198,6 +203,8 @@ impl ParallelSolverHandle {
             parallel_solver.set_metric(metric);
             parallel_solver.set_max_search_depth(max_search_depth);
             parallel_solver.set_limit_search_depth(config.limit_search_depth);
+            parallel_solver.set_ranking(config.ranking.map(Into::into));
+            parallel_solver.set_result_cache(result_cache(config.use_result_cache));
             parallel_solver.set_pruning_depth(config.pruning_depth);
             for mode_depth in config.mode_pruning_depths {
                 parallel_solver.set_mode_pruning_depth(mode_depth.mode.into(), mode_depth.depth);
@@ -224,7 +231,9 @@ impl ParallelSolverHandle {
                 parallel_solver.set_status_callback(move |event: StatusEvent| {
                     match event.event_type {
                         StatusEventType::SolutionFound => {
-                            cb_clone.on_solution_found(event.message.clone());
+                            if let Some(solution) = event.solution {
+                                cb_clone.on_solution_found(solution.into());
+                            }
                         }
                         StatusEventType::FinishSearch => {
                             cb_clone.on_complete();
@@ -276,7 +285,7 @@ mod tests {
             self.progress.fetch_add(1, Ordering::Relaxed);
         }
 
-        fn on_solution_found(&self, _solution: String) {
+        fn on_solution_found(&self, _solution: Solution) {
             self.found.fetch_add(1, Ordering::Relaxed);
         }
 
@@ -286,6 +295,8 @@ mod tests {
     }
 
     fn sample_state() -> MegaminxState {
+        // Every test using it builds tables
+        crate::util::use_test_data_directory();
         MegaminxState {
             corner_positions: vec![0, 1, 2, 3, 4],
             corner_orientations: vec![0, 1, 2, 0, 1],
@@ -323,6 +334,8 @@ mod tests {
                 table_gen_threads: 1,
                 search_threads: 1,
             }),
+            ranking: None,
+            use_result_cache: false,
         };
 
         let handle = SolverHandle::new(config, sample_state());
@@ -365,6 +378,8 @@ mod tests {
                 table_gen_threads: 1,
                 search_threads: 2,
             },
+            ranking: None,
+            use_result_cache: false,
         };
 
         let handle = ParallelSolverHandle::new(config, sample_state());
@@ -402,6 +417,8 @@ mod tests {
             ignore_corner_orientations: false,
             ignore_edge_orientations: false,
             parallel_config: None,
+            ranking: None,
+            use_result_cache: false,
         };
 
         let handle = SolverHandle::new(config, sample_state());
@@ -430,6 +447,8 @@ mod tests {
                 table_gen_threads: 1,
                 search_threads: 1,
             },
+            ranking: None,
+            use_result_cache: false,
         };
 
         let handle = ParallelSolverHandle::new(config, sample_state());
@@ -457,6 +476,8 @@ mod tests {
                 table_gen_threads: 1,
                 search_threads: 1,
             }),
+            ranking: None,
+            use_result_cache: false,
         };
 
         let handle = SolverHandle::new(config, sample_state());
@@ -507,6 +528,8 @@ mod tests {
                 table_gen_threads: 1,
                 search_threads: 1,
             },
+            ranking: None,
+            use_result_cache: false,
         };
 
         let handle = ParallelSolverHandle::new(config, sample_state());
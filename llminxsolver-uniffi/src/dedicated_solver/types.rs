@@ -1,3 +1,4 @@
+use crate::util::SolutionRanking;
 use llminxsolver_rs::MemoryConfig;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -27,6 +28,26 @@ impl From<SearchMode> for llminxsolver_rs::SearchMode {
     }
 }
 
+impl TryFrom<llminxsolver_rs::SearchMode> for SearchMode {
+    type Error = String;
+
+    fn try_from(mode: llminxsolver_rs::SearchMode) -> Result<Self, Self::Error> {
+        match mode {
+            llminxsolver_rs::SearchMode::RU => Ok(SearchMode::RU),
+            llminxsolver_rs::SearchMode::RUF => Ok(SearchMode::RUF),
+            llminxsolver_rs::SearchMode::RUL => Ok(SearchMode::RUL),
+            llminxsolver_rs::SearchMode::RUFL => Ok(SearchMode::RUFL),
+            llminxsolver_rs::SearchMode::RUFLbL => Ok(SearchMode::RUFLbL),
+            llminxsolver_rs::SearchMode::RUbL => Ok(SearchMode::RUbL),
+            llminxsolver_rs::SearchMode::RUbR => Ok(SearchMode::RUbR),
+            llminxsolver_rs::SearchMode::RUD => Ok(SearchMode::RUD),
+            llminxsolver_rs::SearchMode::Custom(_) => {
+                Err(format!("Search mode {} has no binding", mode))
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Metric {
     Face,
@@ -42,6 +63,15 @@ impl From<Metric> for llminxsolver_rs::Metric {
     }
 }
 
+impl From<llminxsolver_rs::Metric> for Metric {
+    fn from(metric: llminxsolver_rs::Metric) -> Self {
+        match metric {
+            llminxsolver_rs::Metric::Face => Metric::Face,
+            llminxsolver_rs::Metric::Fifth => Metric::Fifth,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ParallelConfig {
     pub memory_budget_mb: u32,
@@ -82,6 +112,8 @@ pub struct SolverConfig {
     pub ignore_corner_orientations: bool,
     pub ignore_edge_orientations: bool,
     pub parallel_config: Option<ParallelConfig>,
+    pub ranking: Option<SolutionRanking>,
+    pub use_result_cache: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -103,6 +135,8 @@ pub struct ParallelSolverConfig {
     pub ignore_corner_orientations: bool,
     pub ignore_edge_orientations: bool,
     pub parallel_config: ParallelConfig,
+    pub ranking: Option<SolutionRanking>,
+    pub use_result_cache: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -122,6 +156,54 @@ pub struct ProgressEvent {
     pub current_depth: u32,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    pub algorithm: String,
+    pub ftm: u32,
+    pub fftm: u32,
+    pub depth: u32,
+    pub search_mode: String,
+    pub timestamp: u64,
+    pub mcc: Option<f64>,
+    pub merged_variants: u32,
+    pub verified: Option<bool>,
+}
+
+impl From<llminxsolver_rs::Solution> for Solution {
+    fn from(solution: llminxsolver_rs::Solution) -> Self {
+        Self {
+            algorithm: solution.algorithm(),
+            ftm: solution.ftm as u32,
+            fftm: solution.fftm as u32,
+            depth: solution.depth as u32,
+            search_mode: solution.search_mode.to_string(),
+            timestamp: solution.timestamp,
+            mcc: solution.mcc,
+            merged_variants: solution.merged_variants as u32,
+            verified: solution.verified,
+        }
+    }
+}
+
+impl TryFrom<Solution> for llminxsolver_rs::Solution {
+    type Error = String;
+
+    fn try_from(solution: Solution) -> Result<Self, Self::Error> {
+        let moves = llminxsolver_rs::parse_alg(&solution.algorithm).map_err(|e| e.to_string())?;
+        Ok(Self {
+            moves,
+            ftm: solution.ftm as usize,
+            fftm: solution.fftm as usize,
+            depth: solution.depth as usize,
+            search_mode: solution.search_mode.parse()?,
+            timestamp: solution.timestamp,
+            mcc: solution.mcc,
+            merged_variants: solution.merged_variants as usize,
+            verified: solution.verified,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -192,6 +274,8 @@ mod tests {
             ignore_corner_orientations: true,
             ignore_edge_orientations: false,
             parallel_config: None,
+            ranking: None,
+            use_result_cache: false,
         };
         assert!(solver_cfg.limit_search_depth);
         assert!(solver_cfg.parallel_config.is_none());
@@ -218,6 +302,8 @@ mod tests {
                 table_gen_threads: 1,
                 search_threads: 1,
             },
+            ranking: None,
+            use_result_cache: false,
         };
         assert_eq!(parallel_cfg.mode_pruning_depths.len(), 1);
 
@@ -238,4 +324,32 @@ mod tests {
         };
         assert_eq!(progress.search_mode.as_deref(), Some("RU"));
     }
+
+    #[test]
+    fn test_solution_conversion() {
+        let mut minx = llminxsolver_rs::LLMinx::new();
+        minx.apply_move(llminxsolver_rs::Move::R2);
+        minx.apply_move(llminxsolver_rs::Move::U);
+        let mut rs_solution =
+            llminxsolver_rs::Solution::from_minx(&minx, llminxsolver_rs::SearchMode::RUF);
+        rs_solution.mcc = Some(2.5);
+
+        let solution: Solution = rs_solution.clone().into();
+        assert_eq!(solution.algorithm, "R2 U");
+        assert_eq!(solution.ftm, 2);
+        assert_eq!(solution.fftm, 3);
+        assert_eq!(solution.depth, rs_solution.depth as u32);
+        assert_eq!(solution.search_mode, "RUF");
+        assert_eq!(solution.timestamp, rs_solution.timestamp);
+        assert_eq!(solution.mcc, Some(2.5));
+
+        let back: llminxsolver_rs::Solution = solution.clone().try_into().unwrap();
+        assert_eq!(back, rs_solution);
+
+        let bad = Solution {
+            algorithm: "Q".to_string(),
+            ..solution
+        };
+        assert!(llminxsolver_rs::Solution::try_from(bad).is_err());
+    }
 }
@@ -1,9 +1,11 @@
 mod batch_solver;
 mod dedicated_solver;
+mod table_manager;
 mod util;
 
 pub use batch_solver::*;
 pub use dedicated_solver::*;
+pub use table_manager::*;
 pub use util::*;
 
 uniffi::include_scaffolding!("llminxsolver");
@@ -0,0 +1,262 @@
+use crate::dedicated_solver::{Metric, ParallelConfig, ProgressEvent, SearchMode, SolverCallback};
+use llminxsolver_rs::{PrebuildRequest, StatusCallback, StatusEvent, TableManager};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PruningTableInfo {
+    pub name: String,
+    pub metric: Metric,
+    pub depth: u8,
+    pub modes: Vec<SearchMode>,
+    pub compressed_path: Option<String>,
+    pub compressed_bytes: u64,
+    pub cache_path: Option<String>,
+    pub cache_bytes: u64,
+}
+
+impl From<&llminxsolver_rs::TableInfo> for PruningTableInfo {
+    fn from(table: &llminxsolver_rs::TableInfo) -> Self {
+        let path = |file: &Option<llminxsolver_rs::TableFile>| {
+            file.as_ref().map(|f| f.path.to_string_lossy().to_string())
+        };
+        let bytes =
+            |file: &Option<llminxsolver_rs::TableFile>| file.as_ref().map_or(0, |f| f.size_bytes);
+        Self {
+            name: table.name.clone(),
+            metric: table.metric.into(),
+            depth: table.depth,
+            modes: table
+                .modes
+                .iter()
+                .filter_map(|&m| SearchMode::try_from(m).ok())
+                .collect(),
+            compressed_path: path(&table.compressed),
+            compressed_bytes: bytes(&table.compressed),
+            cache_path: path(&table.cache),
+            cache_bytes: bytes(&table.cache),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PruningTableCheck {
+    pub path: String,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PruningTableSelection {
+    pub mode: Option<SearchMode>,
+    pub metric: Option<Metric>,
+    pub depth: Option<u8>,
+    pub caches_only: bool,
+}
+
+impl From<PruningTableSelection> for llminxsolver_rs::TableSelection {
+    fn from(selection: PruningTableSelection) -> Self {
+        Self {
+            mode: selection.mode.map(Into::into),
+            metric: selection.metric.map(Into::into),
+            depth: selection.depth,
+            caches_only: selection.caches_only,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PruningBundleImport {
+    pub imported: Vec<String>,
+    pub rejected: Vec<String>,
+    pub error: Option<String>,
+}
+
+pub fn list_pruning_tables() -> Vec<PruningTableInfo> {
+    TableManager::in_data_directory()
+        .list()
+        .iter()
+        .map(Into::into)
+        .collect()
+}
+
+pub fn verify_pruning_tables() -> Vec<PruningTableCheck> {
+    TableManager::in_data_directory()
+        .verify_all()
+        .into_iter()
+        .map(|check| PruningTableCheck {
+            path: check.path.to_string_lossy().to_string(),
+            error: check.result.err().map(|e| e.to_string()),
+        })
+        .collect()
+}
+
+pub fn delete_pruning_tables(selection: PruningTableSelection) -> Option<String> {
+    TableManager::in_data_directory()
+        .delete(&selection.into())
+        .err()
+}
+
+pub fn export_pruning_table_bundle(path: String) -> Option<String> {
+    let manager = TableManager::in_data_directory();
+    manager
+        .export_bundle(&manager.list(), Path::new(&path))
+        .err()
+}
+
+pub fn import_pruning_table_bundle(path: String) -> PruningBundleImport {
+    match TableManager::in_data_directory().import_bundle(Path::new(&path)) {
+        Ok(import) => PruningBundleImport {
+            imported: import
+                .imported
+                .iter()
+                .map(|p| p.to_string_lossy().to_string())
+                .collect(),
+            rejected: import
+                .rejected
+                .into_iter()
+                .map(|(entry, reason)| format!("{}: {}", entry, reason))
+                .collect(),
+            error: None,
+        },
+        Err(e) => PruningBundleImport {
+            error: Some(e),
+            ..PruningBundleImport::default()
+        },
+    }
+}
+
+pub struct TablePrebuildHandle {
+    request: PrebuildRequest,
+    callback: RwLock<Option<Arc<dyn SolverCallback>>>,
+    running: Arc<AtomicBool>,
+    interrupt: Arc<AtomicBool>,
+}
+
+impl TablePrebuildHandle {
+    pub fn new(
+        modes: Vec<SearchMode>,
+        metric: Metric,
+        pruning_depth: u8,
+        parallel_config: ParallelConfig,
+    ) -> Self {
+        Self {
+            request: PrebuildRequest {
+                modes: modes.into_iter().map(Into::into).collect(),
+                metric: metric.into(),
+                pruning_depth,
+                memory_config: parallel_config.into(),
+            },
+            callback: RwLock::new(None),
+            running: Arc::new(AtomicBool::new(false)),
+            interrupt: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn set_callback(&self, callback: Box<dyn SolverCallback>) {
+        let mut cb = self.callback.write().unwrap();
+        *cb = Some(Arc::from(callback));
+    }
+
+    pub fn start(&self) {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        self.interrupt.store(false, Ordering::SeqCst);
+
+        let request = self.request.clone();
+        let callback = self.callback.read().unwrap().clone();
+        let interrupt = Arc::clone(&self.interrupt);
+        let running = Arc::clone(&self.running);
+
+        std::thread::spawn(move || {
+            let status_callback = callback.clone().map(|cb| -> StatusCallback {
+                Arc::new(move |event: StatusEvent| {
+                    cb.on_progress(ProgressEvent {
+                        event_type: format!("{:?}", event.event_type),
+                        message: event.message.clone(),
+                        progress: event.progress,
+                        search_mode: event.search_mode.clone(),
+                        current_depth: event.current_depth,
+                    })
+                })
+            });
+
+            let handle = TableManager::in_data_directory().prebuild(request, status_callback);
+            while !handle.is_finished() {
+                if interrupt.load(Ordering::SeqCst) {
+                    handle.cancel();
+                }
+                std::thread::sleep(std::time::Duration::from_millis(50));
+            }
+            handle.join();
+
+            if let Some(ref cb) = callback {
+                cb.on_complete();
+            }
+            running.store(false, Ordering::SeqCst);
+        });
+    }
+
+    pub fn cancel(&self) {
+        self.interrupt.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selection_converts_to_rs() {
+        let selection: llminxsolver_rs::TableSelection = PruningTableSelection {
+            mode: Some(SearchMode::RUF),
+            metric: Some(Metric::Face),
+            depth: Some(9),
+            caches_only: true,
+        }
+        .into();
+        assert_eq!(selection.mode, Some(llminxsolver_rs::SearchMode::RUF));
+        assert_eq!(selection.metric, Some(llminxsolver_rs::Metric::Face));
+        assert_eq!(selection.depth, Some(9));
+        assert!(selection.caches_only);
+    }
+
+    #[test]
+    fn test_table_info_skips_modes_without_binding() {
+        let table = llminxsolver_rs::TableInfo {
+            name: "rucorners".to_string(),
+            metric: llminxsolver_rs::Metric::Fifth,
+            depth: 8,
+            modes: vec![llminxsolver_rs::SearchMode::RU],
+            compressed: Some(llminxsolver_rs::TableFile {
+                path: "d8_rucornersFIFTH.prn.lz4".into(),
+                size_bytes: 10,
+            }),
+            cache: None,
+        };
+        let info = PruningTableInfo::from(&table);
+        assert_eq!(info.modes, vec![SearchMode::RU]);
+        assert_eq!(info.metric, Metric::Fifth);
+        assert_eq!(
+            info.compressed_path.as_deref(),
+            Some("d8_rucornersFIFTH.prn.lz4")
+        );
+        assert_eq!(info.compressed_bytes, 10);
+        assert_eq!(info.cache_path, None);
+        assert_eq!(info.cache_bytes, 0);
+    }
+
+    #[test]
+    fn test_import_missing_bundle_reports_error() {
+        let path = std::env::temp_dir().join("llminx_no_such_bundle.tar");
+        let import = import_pruning_table_bundle(path.to_string_lossy().to_string());
+        assert!(import.error.is_some());
+        assert!(import.imported.is_empty());
+    }
+}
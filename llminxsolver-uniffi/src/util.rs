@@ -1,4 +1,4 @@
-use crate::dedicated_solver::MegaminxState;
+use crate::dedicated_solver::{MegaminxState, Solution, build_llminx};
 use llminxsolver_rs::MemoryConfig;
 
 #[derive(Debug, Clone)]
@@ -132,6 +132,108 @@ pub fn detect_wallpaper_path() -> Option<String> {
     llminxsolver_rs::detect_wallpaper_path()
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MCCParams {
+    pub wrist_mult: f64,
+    pub push_mult: f64,
+    pub ring_mult: f64,
+    pub destabilize: f64,
+    pub add_regrip: f64,
+    pub double: f64,
+    pub over_work_mult: f64,
+    pub moveblock: f64,
+    pub rotation: f64,
+}
+
+impl From<MCCParams> for llminxsolver_rs::MCCParams {
+    fn from(p: MCCParams) -> Self {
+        Self {
+            wrist_mult: p.wrist_mult,
+            push_mult: p.push_mult,
+            ring_mult: p.ring_mult,
+            destabilize: p.destabilize,
+            add_regrip: p.add_regrip,
+            double: p.double,
+            over_work_mult: p.over_work_mult,
+            moveblock: p.moveblock,
+            rotation: p.rotation,
+        }
+    }
+}
+
+impl From<llminxsolver_rs::MCCParams> for MCCParams {
+    fn from(p: llminxsolver_rs::MCCParams) -> Self {
+        Self {
+            wrist_mult: p.wrist_mult,
+            push_mult: p.push_mult,
+            ring_mult: p.ring_mult,
+            destabilize: p.destabilize,
+            add_regrip: p.add_regrip,
+            double: p.double,
+            over_work_mult: p.over_work_mult,
+            moveblock: p.moveblock,
+            rotation: p.rotation,
+        }
+    }
+}
+
+/// Keep the `top_k` solutions with the lowest weighted sum of MCC, FTM and
+/// FFTM.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SolutionRanking {
+    pub top_k: u32,
+    pub mcc_weight: f64,
+    pub ftm_weight: f64,
+    pub fftm_weight: f64,
+    pub mcc_params: Option<MCCParams>,
+}
+
+impl From<SolutionRanking> for llminxsolver_rs::SolutionRanking {
+    fn from(r: SolutionRanking) -> Self {
+        Self {
+            score: llminxsolver_rs::RankScore::Blend {
+                mcc: r.mcc_weight,
+                ftm: r.ftm_weight,
+                fftm: r.fftm_weight,
+            },
+            top_k: r.top_k as usize,
+            mcc_params: r.mcc_params.map(Into::into).unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MCCTiming {
+    pub algorithm: String,
+    pub seconds: f64,
+}
+
+impl From<MCCTiming> for llminxsolver_rs::MCCTiming {
+    fn from(t: MCCTiming) -> Self {
+        Self {
+            algorithm: t.algorithm,
+            seconds: t.seconds,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MCCCalibration {
+    pub params: MCCParams,
+    pub seconds_per_unit: f64,
+    pub rms_error: f64,
+}
+
+impl From<llminxsolver_rs::MCCCalibration> for MCCCalibration {
+    fn from(c: llminxsolver_rs::MCCCalibration) -> Self {
+        Self {
+            params: c.params.into(),
+            seconds_per_unit: c.seconds_per_unit,
+            rms_error: c.rms_error,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ScoredSolutionExport {
     pub mcc: f64,
@@ -197,6 +299,49 @@ pub fn export_raw_xlsx_from_file(
     .err()
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SolutionOrder {
+    #[default]
+    Inserted,
+    Length,
+    Mcc,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SolutionQuery {
+    pub order: SolutionOrder,
+    pub contains: Option<String>,
+    pub moves: Option<String>,
+}
+
+impl TryFrom<SolutionQuery> for llminxsolver_rs::StoreQuery {
+    type Error = String;
+
+    fn try_from(query: SolutionQuery) -> Result<Self, Self::Error> {
+        let moves = match query.moves {
+            Some(ref moves) => llminxsolver_rs::parse_alg(moves).map_err(|e| e.to_string())?,
+            None => Vec::new(),
+        };
+        Ok(Self {
+            order: match query.order {
+                SolutionOrder::Inserted => llminxsolver_rs::StoreOrder::Inserted,
+                SolutionOrder::Length => llminxsolver_rs::StoreOrder::Length,
+                SolutionOrder::Mcc => llminxsolver_rs::StoreOrder::Mcc,
+            },
+            contains: query.contains.filter(|text| !text.is_empty()),
+            moves,
+        })
+    }
+}
+
+/// Stored records in the plain solution line format, for display.
+fn plain_lines(records: Vec<String>) -> Vec<String> {
+    records
+        .iter()
+        .map(|record| llminxsolver_rs::plain_text(record))
+        .collect()
+}
+
 pub struct TempFile {
     inner: std::sync::Mutex<llminxsolver_rs::TempFile>,
 }
@@ -216,12 +361,15 @@ impl TempFile {
         }
     }
 
-    pub fn append(&self, solution: String) -> Option<String> {
+    pub fn append(&self, solution: Solution) -> Option<String> {
         let mut file = match self.inner.lock() {
             Ok(f) => f,
             Err(e) => return Some(e.to_string()),
         };
-        file.append(&solution).err()
+        solution
+            .try_into()
+            .and_then(|solution| file.append_solution(&solution))
+            .err()
     }
 
     pub fn get_path(&self) -> String {
@@ -247,13 +395,73 @@ impl TempFile {
     pub fn read_page(&self, offset: u64, limit: u64) -> Vec<String> {
         self.flush_file();
         let file = self.inner.lock().unwrap();
-        file.read_page(offset as usize, limit as usize)
+        plain_lines(
+            file.read_page(offset as usize, limit as usize)
+                .unwrap_or_default(),
+        )
+    }
+
+    pub fn read_solution_page(&self, offset: u64, limit: u64) -> Vec<Solution> {
+        let file = self.inner.lock().unwrap();
+        file.read_solution_page(offset as usize, limit as usize)
             .unwrap_or_default()
+            .into_iter()
+            .map(Into::into)
+            .collect()
+    }
+
+    pub fn read_view_page(&self, query: SolutionQuery, offset: u64, limit: u64) -> Vec<String> {
+        let Ok(query) = query.try_into() else {
+            return Vec::new();
+        };
+        let mut file = self.inner.lock().unwrap();
+        plain_lines(
+            file.read_view_page(&query, offset as usize, limit as usize)
+                .unwrap_or_default(),
+        )
+    }
+
+    pub fn view_count(&self, query: SolutionQuery) -> u64 {
+        let Ok(query) = query.try_into() else {
+            return 0;
+        };
+        let mut file = self.inner.lock().unwrap();
+        file.view_count(&query).unwrap_or(0) as u64
+    }
+
+    pub fn export_text(&self, path: String) -> Option<String> {
+        let file = self.inner.lock().unwrap();
+        file.export_text(std::path::Path::new(&path)).err()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TempCleanupReport {
+    pub removed: Vec<String>,
+    pub in_use: u32,
+}
+
+impl From<llminxsolver_rs::CleanupReport> for TempCleanupReport {
+    fn from(report: llminxsolver_rs::CleanupReport) -> Self {
+        Self {
+            removed: report
+                .removed
+                .iter()
+                .map(|path| path.to_string_lossy().to_string())
+                .collect(),
+            in_use: report.in_use.len() as u32,
+        }
+    }
+}
+
+fn cleanup_options(max_age_seconds: Option<u64>) -> llminxsolver_rs::CleanupOptions {
+    llminxsolver_rs::CleanupOptions {
+        max_age: max_age_seconds.map(std::time::Duration::from_secs),
     }
 }
 
-pub fn cleanup_stale_temp_files() {
-    llminxsolver_rs::cleanup_stale_temp_files();
+pub fn cleanup_stale_temp_files(max_age_seconds: Option<u64>) -> TempCleanupReport {
+    llminxsolver_rs::cleanup_stale_temp_files_with_options(&cleanup_options(max_age_seconds)).into()
 }
 
 pub struct BatchTempFile {
@@ -275,12 +483,15 @@ impl BatchTempFile {
         }
     }
 
-    pub fn append(&self, case_number: u32, solution: String) -> Option<String> {
+    pub fn append(&self, case_number: u32, solution: Solution) -> Option<String> {
         let mut file = match self.inner.lock() {
             Ok(f) => f,
             Err(e) => return Some(e.to_string()),
         };
-        file.append(case_number as usize, &solution).err()
+        solution
+            .try_into()
+            .and_then(|solution| file.append_solution(case_number as usize, &solution))
+            .err()
     }
 
     pub fn get_path(&self) -> String {
@@ -310,13 +521,76 @@ impl BatchTempFile {
 
     pub fn read_case_page(&self, case_number: u32, offset: u64, limit: u64) -> Vec<String> {
         let file = self.inner.lock().unwrap();
-        file.read_case_page(case_number as usize, offset as usize, limit as usize)
+        plain_lines(
+            file.read_case_page(case_number as usize, offset as usize, limit as usize)
+                .unwrap_or_default(),
+        )
+    }
+
+    pub fn read_case_solutions(&self, case_number: u32, offset: u64, limit: u64) -> Vec<Solution> {
+        let file = self.inner.lock().unwrap();
+        file.read_case_solutions(case_number as usize, offset as usize, limit as usize)
             .unwrap_or_default()
+            .into_iter()
+            .map(Into::into)
+            .collect()
+    }
+
+    pub fn read_case_view_page(
+        &self,
+        case_number: u32,
+        query: SolutionQuery,
+        offset: u64,
+        limit: u64,
+    ) -> Vec<String> {
+        let Ok(query) = query.try_into() else {
+            return Vec::new();
+        };
+        let mut file = self.inner.lock().unwrap();
+        plain_lines(
+            file.read_case_view_page(
+                case_number as usize,
+                &query,
+                offset as usize,
+                limit as usize,
+            )
+            .unwrap_or_default(),
+        )
+    }
+
+    pub fn case_view_count(&self, case_number: u32, query: SolutionQuery) -> u64 {
+        let Ok(query) = query.try_into() else {
+            return 0;
+        };
+        let mut file = self.inner.lock().unwrap();
+        file.case_view_count(case_number as usize, &query)
+            .unwrap_or(0) as u64
+    }
+
+    pub fn export_text(&self, path: String) -> Option<String> {
+        let file = self.inner.lock().unwrap();
+        file.export_text(std::path::Path::new(&path)).err()
     }
 }
 
-pub fn cleanup_stale_batch_temp_files() {
-    llminxsolver_rs::cleanup_stale_batch_temp_files();
+pub fn cleanup_stale_batch_temp_files(max_age_seconds: Option<u64>) -> TempCleanupReport {
+    llminxsolver_rs::cleanup_stale_batch_temp_files_with_options(&cleanup_options(max_age_seconds))
+        .into()
+}
+
+/// The shared result cache, if a config asks for it and it can be opened.
+pub(crate) fn result_cache(enabled: bool) -> Option<std::sync::Arc<llminxsolver_rs::ResultCache>> {
+    if !enabled {
+        return None;
+    }
+    llminxsolver_rs::ResultCache::in_data_directory(llminxsolver_rs::CachePolicy::default())
+        .ok()
+        .map(std::sync::Arc::new)
+}
+
+pub fn clear_result_cache() -> u64 {
+    llminxsolver_rs::ResultCache::in_data_directory(llminxsolver_rs::CachePolicy::default())
+        .map_or(0, |cache| cache.clear() as u64)
 }
 
 pub fn get_move_count(algorithm: String, metric: String) -> u32 {
@@ -327,10 +601,56 @@ pub fn calculate_mcc(sequence: String) -> f64 {
     llminxsolver_rs::calculate_mcc(&sequence)
 }
 
+pub fn calculate_mcc_with_params(sequence: String, params: MCCParams) -> f64 {
+    llminxsolver_rs::calculate_mcc_with_params(&sequence, &params.into())
+}
+
+pub fn get_mcc_preset_names() -> Vec<String> {
+    llminxsolver_rs::MCCPreset::ALL
+        .iter()
+        .map(|preset| preset.name().to_string())
+        .collect()
+}
+
+pub fn get_mcc_preset(name: String) -> Option<MCCParams> {
+    name.parse::<llminxsolver_rs::MCCPreset>()
+        .ok()
+        .map(|preset| preset.params().into())
+}
+
+pub fn mcc_params_to_json(params: MCCParams) -> String {
+    let params: llminxsolver_rs::MCCParams = params.into();
+    serde_json::to_string(&params).unwrap_or_default()
+}
+
+pub fn mcc_params_from_json(json: String) -> Option<MCCParams> {
+    serde_json::from_str::<llminxsolver_rs::MCCParams>(&json)
+        .ok()
+        .map(Into::into)
+}
+
+pub fn calibrate_mcc(samples: Vec<MCCTiming>, start: MCCParams) -> Option<MCCCalibration> {
+    let samples: Vec<llminxsolver_rs::MCCTiming> = samples.into_iter().map(Into::into).collect();
+    llminxsolver_rs::calibrate_mcc(&samples, &start.into())
+        .ok()
+        .map(Into::into)
+}
+
 pub fn set_data_directory(path: String) {
     llminxsolver_rs::set_data_directory(&path);
 }
 
+/// Point the data directory at a temp directory before a test builds tables,
+/// so they never land in the working directory. It is kept between runs so
+/// the tables are only built once.
+#[cfg(test)]
+pub(crate) fn use_test_data_directory() -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join("llminx_uniffi_test_data");
+    let _ = std::fs::create_dir_all(&dir);
+    llminxsolver_rs::set_data_directory(&dir.to_string_lossy());
+    dir
+}
+
 pub fn get_available_cpus() -> u32 {
     MemoryConfig::available_cpus() as u32
 }
@@ -365,12 +685,118 @@ pub fn validate_megaminx_state(state: MegaminxState) -> Option<String> {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PieceKind {
+    Corner,
+    Edge,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsolvedPiece {
+    pub kind: PieceKind,
+    pub position: u8,
+    pub piece: u8,
+    pub permuted: bool,
+    pub misoriented: bool,
+}
+
+impl From<llminxsolver_rs::UnsolvedPiece> for UnsolvedPiece {
+    fn from(p: llminxsolver_rs::UnsolvedPiece) -> Self {
+        Self {
+            kind: match p.kind {
+                llminxsolver_rs::PieceKind::Corner => PieceKind::Corner,
+                llminxsolver_rs::PieceKind::Edge => PieceKind::Edge,
+            },
+            position: p.position,
+            piece: p.piece,
+            permuted: p.permuted,
+            misoriented: p.misoriented,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolutionVerification {
+    pub solved: bool,
+    pub unsolved: Vec<UnsolvedPiece>,
+    /// Set when the algorithm could not be parsed.
+    pub error: Option<String>,
+}
+
+pub fn verify_solution(
+    state: MegaminxState,
+    algorithm: String,
+    ignore_corner_positions: bool,
+    ignore_edge_positions: bool,
+    ignore_corner_orientations: bool,
+    ignore_edge_orientations: bool,
+) -> SolutionVerification {
+    let moves = match llminxsolver_rs::parse_alg(&algorithm) {
+        Ok(moves) => moves,
+        Err(e) => {
+            return SolutionVerification {
+                solved: false,
+                unsolved: Vec::new(),
+                error: Some(e.to_string()),
+            };
+        }
+    };
+
+    let mut corners = [false; llminxsolver_rs::minx::NUM_CORNERS];
+    let mut edges = [false; llminxsolver_rs::minx::NUM_EDGES];
+    corners[..5].fill(true);
+    edges[..5].fill(true);
+    let mut start = build_llminx(&state);
+    let mut goal = llminxsolver_rs::LLMinx::new();
+    for minx in [&mut start, &mut goal] {
+        if ignore_corner_positions {
+            minx.set_ignore_corner_positions(corners);
+        }
+        if ignore_edge_positions {
+            minx.set_ignore_edge_positions(edges);
+        }
+        if ignore_corner_orientations {
+            minx.set_ignore_corner_orientations(corners);
+        }
+        if ignore_edge_orientations {
+            minx.set_ignore_edge_orientations(edges);
+        }
+    }
+
+    match llminxsolver_rs::verify(&start, &moves, &goal) {
+        Ok(()) => SolutionVerification {
+            solved: true,
+            unsolved: Vec::new(),
+            error: None,
+        },
+        Err(e) => SolutionVerification {
+            solved: false,
+            unsolved: e.unsolved.into_iter().map(Into::into).collect(),
+            error: None,
+        },
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs;
     use std::sync::{Mutex, OnceLock};
 
+    fn solution(algorithm: &str) -> Solution {
+        let moves = llminxsolver_rs::parse_alg(algorithm).unwrap();
+        Solution {
+            algorithm: algorithm.to_string(),
+            ftm: moves.len() as u32,
+            fftm: moves.len() as u32,
+            depth: moves.len() as u32,
+            search_mode: "RU".to_string(),
+            timestamp: 0,
+            mcc: None,
+            merged_variants: 0,
+            verified: None,
+        }
+    }
+
     fn lock() -> &'static Mutex<()> {
         static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
         LOCK.get_or_init(|| Mutex::new(()))
@@ -412,11 +838,24 @@ mod tests {
     fn test_temp_file_wrapper_basic_flow() {
         let _guard = lock().lock().unwrap();
         let file = TempFile::new();
-        assert!(file.append("R U R' U'".to_string()).is_none());
+        assert!(file.append(solution("R U R' U'")).is_none());
+        let invalid = Solution {
+            algorithm: "Q".to_string(),
+            ..solution("R")
+        };
+        assert!(file.append(invalid).is_some());
         assert_eq!(file.count(), 1);
 
         let page = file.read_page(0, 10);
-        assert_eq!(page, vec!["R U R' U'".to_string()]);
+        assert_eq!(page, vec!["R U R' U'  (4,4)".to_string()]);
+        assert_eq!(file.read_solution_page(0, 10), vec![solution("R U R' U'")]);
+
+        let bad_moves = SolutionQuery {
+            moves: Some("Q".to_string()),
+            ..SolutionQuery::default()
+        };
+        assert!(file.read_view_page(bad_moves.clone(), 0, 10).is_empty());
+        assert_eq!(file.view_count(bad_moves), 0);
 
         let path = file.get_path();
         assert!(path.contains("llminx_solutions"));
@@ -427,16 +866,30 @@ mod tests {
     fn test_batch_temp_file_wrapper_basic_flow() {
         let _guard = lock().lock().unwrap();
         let file = BatchTempFile::new();
-        assert!(file.append(2, "alg1".to_string()).is_none());
-        assert!(file.append(2, "alg2".to_string()).is_none());
-        assert!(file.append(3, "alg3".to_string()).is_none());
+        assert!(file.append(2, solution("R U")).is_none());
+        assert!(file.append(2, solution("R U2")).is_none());
+        assert!(file.append(3, solution("U")).is_none());
 
         assert_eq!(file.count(), 3);
         assert_eq!(file.case_count(2), 2);
         assert_eq!(file.case_count(3), 1);
 
         let page = file.read_case_page(2, 0, 10);
-        assert_eq!(page, vec!["alg1".to_string(), "alg2".to_string()]);
+        assert_eq!(
+            page,
+            vec!["R U  (2,2)".to_string(), "R U2  (2,2)".to_string()]
+        );
+        assert_eq!(file.read_case_solutions(3, 0, 10), vec![solution("U")]);
+
+        let query = SolutionQuery {
+            contains: Some("2".to_string()),
+            ..SolutionQuery::default()
+        };
+        assert_eq!(file.case_view_count(2, query.clone()), 1);
+        assert_eq!(
+            file.read_case_view_page(2, query, 0, 10),
+            vec!["R U2  (2,2)"]
+        );
 
         let path = file.get_path();
         assert!(path.contains("llminx_batch_solutions"));
@@ -477,6 +930,59 @@ mod tests {
         assert!(get_max_pruning_depth() >= get_default_pruning_depth());
     }
 
+    #[test]
+    fn test_solution_ranking_converts_to_blend() {
+        let params = get_mcc_preset("one-handed".to_string()).unwrap();
+        let ranking: llminxsolver_rs::SolutionRanking = SolutionRanking {
+            top_k: 5,
+            mcc_weight: 1.0,
+            ftm_weight: 0.5,
+            fftm_weight: 0.0,
+            mcc_params: Some(params),
+        }
+        .into();
+        assert_eq!(ranking.top_k, 5);
+        assert_eq!(
+            ranking.score,
+            llminxsolver_rs::RankScore::Blend {
+                mcc: 1.0,
+                ftm: 0.5,
+                fftm: 0.0
+            }
+        );
+        assert_eq!(ranking.mcc_params, params.into());
+    }
+
+    #[test]
+    fn test_mcc_presets_json_and_calibration_wrappers() {
+        let names = get_mcc_preset_names();
+        assert!(names.contains(&"one-handed".to_string()));
+        assert!(get_mcc_preset("no-such-preset".to_string()).is_none());
+
+        let params = get_mcc_preset("big-cube".to_string()).unwrap();
+        let json = mcc_params_to_json(params);
+        assert_eq!(mcc_params_from_json(json), Some(params));
+        assert!(mcc_params_from_json("not json".to_string()).is_none());
+
+        let default = get_mcc_preset("default".to_string()).unwrap();
+        let alg = "R U R' U' x".to_string();
+        assert_eq!(
+            calculate_mcc_with_params(alg.clone(), default),
+            calculate_mcc(alg.clone())
+        );
+
+        let samples = ["R U R' U'", "R U2 R'", "F R U R' U' F'"]
+            .iter()
+            .map(|alg| MCCTiming {
+                algorithm: alg.to_string(),
+                seconds: calculate_mcc_with_params(alg.to_string(), params) * 0.1,
+            })
+            .collect::<Vec<_>>();
+        let calibration = calibrate_mcc(samples.clone(), default).unwrap();
+        assert!(calibration.seconds_per_unit > 0.0);
+        assert!(calibrate_mcc(samples[..1].to_vec(), default).is_none());
+    }
+
     #[test]
     fn test_theme_colors_conversion_and_theme_wrappers() {
         let _guard = lock().lock().unwrap();
@@ -534,15 +1040,18 @@ mod tests {
     #[test]
     fn test_cleanup_temp_wrappers() {
         let _guard = lock().lock().unwrap();
-        cleanup_stale_temp_files();
-        cleanup_stale_batch_temp_files();
+        let file = TempFile::new();
+        let report = cleanup_stale_temp_files(None);
+        assert!(report.in_use >= 1);
+        assert!(!report.removed.contains(&file.get_path()));
+        cleanup_stale_batch_temp_files(None);
+        file.delete_file();
     }
 
     #[test]
     fn test_set_data_dir_and_validate_state_wrapper() {
         let _guard = lock().lock().unwrap();
-        let temp = std::env::temp_dir().join("llminx_uniffi_data_dir_test");
-        let _ = fs::create_dir_all(&temp);
+        let temp = use_test_data_directory();
         set_data_directory(temp.to_string_lossy().to_string());
 
         let valid = MegaminxState {
@@ -562,6 +1071,33 @@ mod tests {
         assert!(validate_megaminx_state(invalid).is_some());
     }
 
+    #[test]
+    fn test_verify_solution_reports_unsolved_pieces() {
+        let minx = llminxsolver_rs::LLMinx::from_alg("U").unwrap();
+        let state = MegaminxState {
+            corner_positions: minx.corner_positions()[..5].to_vec(),
+            corner_orientations: (0..5).map(|i| minx.get_corner_orientation(i)).collect(),
+            edge_positions: minx.edge_positions()[..5].to_vec(),
+            edge_orientations: (0..5).map(|i| minx.get_edge_orientation(i)).collect(),
+        };
+
+        let solved = verify_solution(state.clone(), "U'".to_string(), false, false, false, false);
+        assert!(solved.solved);
+        assert!(solved.unsolved.is_empty());
+
+        let wrong = verify_solution(state.clone(), "U".to_string(), false, false, false, false);
+        assert!(!wrong.solved);
+        assert_eq!(wrong.unsolved.len(), 10);
+        assert!(wrong.unsolved.iter().all(|p| p.permuted));
+
+        let ignored = verify_solution(state.clone(), String::new(), true, true, true, true);
+        assert!(ignored.solved);
+
+        let invalid = verify_solution(state, "Q".to_string(), false, false, false, false);
+        assert!(!invalid.solved);
+        assert!(invalid.error.is_some());
+    }
+
     #[test]
     fn test_scored_solution_export_into_rs() {
         let input = ScoredSolutionExport {
@@ -584,7 +1120,7 @@ mod tests {
             panic!("poison");
         }));
 
-        let err = file.append("R U".to_string());
+        let err = file.append(solution("R U"));
         assert!(err.is_some());
     }
 
@@ -597,7 +1133,7 @@ mod tests {
             panic!("poison");
         }));
 
-        let err = file.append(1, "R U".to_string());
+        let err = file.append(1, solution("R U"));
         assert!(err.is_some());
     }
 
@@ -606,13 +1142,13 @@ mod tests {
         let _guard = lock().lock().unwrap();
 
         let temp = TempFile::default();
-        assert!(temp.append("A".to_string()).is_none());
+        assert!(temp.append(solution("R")).is_none());
         temp.flush_file();
         assert_eq!(temp.count(), 1);
         temp.delete_file();
 
         let batch = BatchTempFile::default();
-        assert!(batch.append(9, "B".to_string()).is_none());
+        assert!(batch.append(9, solution("U")).is_none());
         batch.flush();
         assert_eq!(batch.case_count(9), 1);
         batch.delete_file();